@@ -12,6 +12,8 @@ fuzz_target!(|actions: Vec<Action>| {
             FuzzTarget::List,
             FuzzTarget::Text,
             FuzzTarget::Tree,
+            FuzzTarget::MovableList,
+            FuzzTarget::Counter,
         ],
         &mut actions.clone(),
     )
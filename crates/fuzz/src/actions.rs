@@ -2,7 +2,7 @@ use std::fmt::Debug;
 
 use super::{
     actor::ActionExecutor,
-    container::{ListAction, MapAction, TextAction, TreeAction},
+    container::{CounterAction, ListAction, MapAction, MovableListAction, TextAction, TreeAction},
     crdt_fuzzer::FuzzValue,
 };
 use arbitrary::Arbitrary;
@@ -18,6 +18,8 @@ pub enum ActionInner {
     List(ListAction),
     Text(TextAction),
     Tree(TreeAction),
+    MovableList(MovableListAction),
+    Counter(CounterAction),
 }
 
 impl ActionInner {
@@ -27,6 +29,10 @@ impl ActionInner {
             ContainerType::List => Self::List(ListAction::from_generic_action(action)),
             ContainerType::Text => Self::Text(TextAction::from_generic_action(action)),
             ContainerType::Tree => Self::Tree(TreeAction::from_generic_action(action)),
+            ContainerType::MovableList => {
+                Self::MovableList(MovableListAction::from_generic_action(action))
+            }
+            ContainerType::Counter => Self::Counter(CounterAction::from_generic_action(action)),
         }
     }
 }
@@ -6,7 +6,7 @@ use fxhash::FxHashMap;
 use loro::{Container, ContainerType, Frontiers, LoroDoc, LoroValue, PeerID, ID};
 
 use crate::{
-    container::{ListActor, TextActor, TreeActor},
+    container::{CounterActor, ListActor, MovableListActor, TextActor, TreeActor},
     value::{ApplyDiff, ContainerTracker, MapTracker, Value},
 };
 
@@ -51,6 +51,8 @@ impl Actor {
             ActionExecutor::ListActor(actor) => actor.add_new_container(container),
             ActionExecutor::TextActor(actor) => actor.add_new_container(container),
             ActionExecutor::TreeActor(actor) => actor.add_new_container(container),
+            ActionExecutor::MovableListActor(actor) => actor.add_new_container(container),
+            ActionExecutor::CounterActor(actor) => actor.add_new_container(container),
         }
     }
 
@@ -153,6 +155,26 @@ impl Actor {
                     ActionExecutor::TextActor(TextActor::new(self.loro.clone())),
                 );
             }
+            ContainerType::MovableList => {
+                self.tracker.lock().unwrap().as_map_mut().unwrap().insert(
+                    "movable_list".to_string(),
+                    Value::empty_container(ContainerType::MovableList),
+                );
+                self.targets.insert(
+                    target,
+                    ActionExecutor::MovableListActor(MovableListActor::new(self.loro.clone())),
+                );
+            }
+            ContainerType::Counter => {
+                self.tracker.lock().unwrap().as_map_mut().unwrap().insert(
+                    "counter".to_string(),
+                    Value::empty_container(ContainerType::Counter),
+                );
+                self.targets.insert(
+                    target,
+                    ActionExecutor::CounterActor(CounterActor::new(self.loro.clone())),
+                );
+            }
             ContainerType::Tree => {
                 self.tracker.lock().unwrap().as_map_mut().unwrap().insert(
                     "tree".to_string(),
@@ -174,6 +196,8 @@ pub enum ActionExecutor {
     ListActor(ListActor),
     TextActor(TextActor),
     TreeActor(TreeActor),
+    MovableListActor(MovableListActor),
+    CounterActor(CounterActor),
 }
 
 #[enum_dispatch]
@@ -193,6 +217,8 @@ fn assert_value_eq(a: &LoroValue, b: &LoroValue) {
                     LoroValue::String(s) => s.is_empty(),
                     LoroValue::List(l) => l.is_empty(),
                     LoroValue::Map(m) => m.is_empty(),
+                    // an untouched counter
+                    LoroValue::Double(d) => *d == 0.,
                     _ => false,
                 };
                 if is_empty {
@@ -206,6 +232,8 @@ fn assert_value_eq(a: &LoroValue, b: &LoroValue) {
                     LoroValue::String(s) => s.is_empty(),
                     LoroValue::List(l) => l.is_empty(),
                     LoroValue::Map(m) => m.is_empty(),
+                    // an untouched counter
+                    LoroValue::Double(d) => *d == 0.,
                     _ => false,
                 };
                 if is_empty {
@@ -0,0 +1,109 @@
+use std::sync::{Arc, Mutex};
+
+use loro::{Container, ContainerID, ContainerType, LoroCounter, LoroDoc, LoroValue};
+
+use crate::{
+    actions::{Actionable, FromGenericAction, GenericAction},
+    actor::{ActionExecutor, ActorTrait},
+    value::{ApplyDiff, ContainerTracker, MapTracker, Value},
+};
+
+#[derive(Debug, Clone)]
+pub struct CounterAction(i32);
+
+pub struct CounterActor {
+    loro: Arc<LoroDoc>,
+    containers: Vec<LoroCounter>,
+    tracker: Arc<Mutex<ContainerTracker>>,
+}
+
+impl CounterActor {
+    pub fn new(loro: Arc<LoroDoc>) -> Self {
+        let mut tracker = MapTracker::empty();
+        tracker.insert(
+            "counter".to_string(),
+            Value::empty_container(ContainerType::Counter),
+        );
+        let tracker = Arc::new(Mutex::new(ContainerTracker::Map(tracker)));
+        let counter = tracker.clone();
+
+        loro.subscribe(
+            &ContainerID::new_root("counter", ContainerType::Counter),
+            Arc::new(move |event| {
+                let mut counter = counter.lock().unwrap();
+                counter.apply_diff(event);
+            }),
+        );
+
+        let root = loro.get_counter("counter");
+        Self {
+            loro,
+            containers: vec![root],
+            tracker,
+        }
+    }
+
+    pub fn get_create_container_mut(&mut self, container_idx: usize) -> &mut LoroCounter {
+        if self.containers.is_empty() {
+            let handler = self.loro.get_counter("counter");
+            self.containers.push(handler);
+            self.containers.last_mut().unwrap()
+        } else {
+            self.containers.get_mut(container_idx).unwrap()
+        }
+    }
+}
+
+impl ActorTrait for CounterActor {
+    fn container_len(&self) -> u8 {
+        self.containers.len() as u8
+    }
+
+    fn check_tracker(&self) {
+        let counter = self.loro.get_counter("counter");
+        let value = counter.get_value();
+        let tracker = self.tracker.lock().unwrap().to_value();
+        assert_eq!(
+            &LoroValue::from(value),
+            tracker.into_map().unwrap().get("counter").unwrap()
+        );
+    }
+
+    fn add_new_container(&mut self, container: Container) {
+        self.containers.push(container.into_counter().unwrap());
+    }
+}
+
+impl Actionable for CounterAction {
+    fn pre_process(&mut self, _actor: &mut ActionExecutor, _container: usize) {}
+
+    fn apply(&self, actor: &mut ActionExecutor, container: usize) -> Option<Container> {
+        let actor = actor.as_counter_actor_mut().unwrap();
+        let counter = actor.get_create_container_mut(container);
+        counter.increment(self.0 as f64).unwrap();
+        None
+    }
+
+    fn ty(&self) -> ContainerType {
+        ContainerType::Counter
+    }
+
+    fn table_fields(&self) -> [std::borrow::Cow<'_, str>; 2] {
+        ["increment".into(), self.0.to_string().into()]
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Counter"
+    }
+
+    fn pre_process_container_value(&mut self) -> Option<&mut ContainerType> {
+        None
+    }
+}
+
+impl FromGenericAction for CounterAction {
+    fn from_generic_action(action: &GenericAction) -> Self {
+        // small integers keep the sum exact regardless of the order of the increments
+        CounterAction((action.key % 256) as i32 - 128)
+    }
+}
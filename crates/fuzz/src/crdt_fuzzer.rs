@@ -185,6 +185,8 @@ pub enum FuzzTarget {
     List,
     Text,
     Tree,
+    MovableList,
+    Counter,
     All,
 }
 
@@ -197,6 +199,8 @@ impl FuzzTarget {
                 set.insert(ContainerType::List);
                 set.insert(ContainerType::Text);
                 set.insert(ContainerType::Tree);
+                set.insert(ContainerType::MovableList);
+                set.insert(ContainerType::Counter);
             }
             FuzzTarget::Map => {
                 set.insert(ContainerType::Map);
@@ -211,6 +215,12 @@ impl FuzzTarget {
                 set.insert(ContainerType::Tree);
                 set.insert(ContainerType::Map);
             }
+            FuzzTarget::MovableList => {
+                set.insert(ContainerType::MovableList);
+            }
+            FuzzTarget::Counter => {
+                set.insert(ContainerType::Counter);
+            }
         }
         set
     }
@@ -18,13 +18,16 @@ impl Value {
     pub fn empty_container(ty: ContainerType) -> Self {
         match ty {
             ContainerType::Map => Value::Container(ContainerTracker::Map(MapTracker::empty())),
-            ContainerType::List => {
+            ContainerType::List | ContainerType::MovableList => {
                 Value::Container(ContainerTracker::List(ListTracker(Vec::new())))
             }
             ContainerType::Text => Value::Container(ContainerTracker::Text(TextTracker::empty())),
             ContainerType::Tree => {
                 Value::Container(ContainerTracker::Tree(TreeTracker(Vec::new())))
             }
+            ContainerType::Counter => {
+                Value::Container(ContainerTracker::Counter(CounterTracker::empty()))
+            }
         }
     }
 }
@@ -47,6 +50,7 @@ pub enum ContainerTracker {
     List(ListTracker),
     Text(TextTracker),
     Tree(TreeTracker),
+    Counter(CounterTracker),
 }
 
 impl ContainerTracker {
@@ -56,6 +60,7 @@ impl ContainerTracker {
             ContainerTracker::List(list) => list.to_value(),
             ContainerTracker::Text(text) => text.to_value(),
             ContainerTracker::Tree(tree) => tree.to_value(),
+            ContainerTracker::Counter(counter) => counter.to_value(),
         }
     }
 }
@@ -195,6 +200,23 @@ impl ApplyDiff for TextTracker {
         self.text.to_string().into()
     }
 }
+#[derive(Debug)]
+pub struct CounterTracker(f64);
+
+impl ApplyDiff for CounterTracker {
+    fn empty() -> Self {
+        CounterTracker(0.)
+    }
+
+    fn apply_diff(&mut self, diff: Diff) {
+        self.0 += diff.as_counter().unwrap();
+    }
+
+    fn to_value(&self) -> LoroValue {
+        self.0.into()
+    }
+}
+
 #[derive(Debug)]
 pub struct TreeTracker(Vec<TreeNode>);
 
@@ -208,14 +230,14 @@ impl ApplyDiff for TreeTracker {
         for diff in &diff.diff {
             let target = diff.target;
             match &diff.action {
-                TreeExternalDiff::Create(parent) => {
+                TreeExternalDiff::Create { parent, .. } => {
                     let node = TreeNode::new(target, *parent);
                     self.push(node);
                 }
                 TreeExternalDiff::Delete => {
                     self.retain(|node| node.id != target && node.parent != Some(target));
                 }
-                TreeExternalDiff::Move(parent) => {
+                TreeExternalDiff::Move { parent, .. } => {
                     let node = self.iter_mut().find(|node| node.id == target).unwrap();
                     node.parent = *parent;
                 }
@@ -325,7 +347,7 @@ impl ContainerTracker {
                 ContainerType::Map => {
                     value.as_map_mut().unwrap().apply_diff(diff);
                 }
-                ContainerType::List => {
+                ContainerType::List | ContainerType::MovableList => {
                     value.as_list_mut().unwrap().apply_diff(diff);
                 }
                 ContainerType::Text => {
@@ -334,6 +356,9 @@ impl ContainerTracker {
                 ContainerType::Tree => {
                     value.as_tree_mut().unwrap().apply_diff(diff);
                 }
+                ContainerType::Counter => {
+                    value.as_counter_mut().unwrap().apply_diff(diff);
+                }
             }
         }
     }
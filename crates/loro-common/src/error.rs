@@ -1,7 +1,7 @@
 use serde_columnar::ColumnarError;
 use thiserror::Error;
 
-use crate::{InternalString, PeerID, TreeID, ID};
+use crate::{ContainerID, InternalString, PeerID, TreeID, ID};
 
 pub type LoroResult<T> = Result<T, LoroError>;
 
@@ -51,6 +51,23 @@ pub enum LoroError {
     NotImplemented(&'static str),
     #[error("Reattach a container that is already attached")]
     ReattachAttachedContainer,
+    #[error("Cannot switch to a version before the shallow root. The history before it has been trimmed.")]
+    SwitchToVersionBeforeShallowRoot,
+    #[error("Cannot import the updates because they depend on the history before the shallow root. The history before it has been trimmed.")]
+    ImportUpdatesThatDependsOnTrimmedHistory,
+    #[error("The import is rejected by the validator because of the change {id}: {reason}")]
+    ImportRejected { id: ID, reason: Box<str> },
+    #[error(
+        "Cannot merge the updates because they depend on the changes that are not included: {0:?}"
+    )]
+    MergeWithMissingDeps(Vec<ID>),
+    #[error("The sync stalls because the remote peer doesn't send the missing changes: {0:?}")]
+    SyncStalled(Vec<ID>),
+    #[error("The edit of {container} violates the schema: {reason}")]
+    SchemaViolation {
+        container: ContainerID,
+        reason: Box<str>,
+    },
 }
 
 #[derive(Error, Debug)]
@@ -126,14 +126,18 @@ pub enum ContainerType {
     Map,
     List,
     Tree,
+    MovableList,
+    Counter,
 }
 
 impl ContainerType {
-    pub const ALL_TYPES: [ContainerType; 4] = [
+    pub const ALL_TYPES: [ContainerType; 6] = [
         ContainerType::Map,
         ContainerType::List,
         ContainerType::Text,
         ContainerType::Tree,
+        ContainerType::MovableList,
+        ContainerType::Counter,
     ];
 
     pub fn default_value(&self) -> LoroValue {
@@ -142,6 +146,8 @@ impl ContainerType {
             ContainerType::List => LoroValue::List(Arc::new(Default::default())),
             ContainerType::Text => LoroValue::String(Arc::new(Default::default())),
             ContainerType::Tree => LoroValue::List(Arc::new(Default::default())),
+            ContainerType::MovableList => LoroValue::List(Arc::new(Default::default())),
+            ContainerType::Counter => LoroValue::Double(0.),
         }
     }
 
@@ -151,6 +157,8 @@ impl ContainerType {
             ContainerType::List => 2,
             ContainerType::Text => 3,
             ContainerType::Tree => 4,
+            ContainerType::MovableList => 5,
+            ContainerType::Counter => 6,
         }
     }
 
@@ -160,6 +168,8 @@ impl ContainerType {
             2 => ContainerType::List,
             3 => ContainerType::Text,
             4 => ContainerType::Tree,
+            5 => ContainerType::MovableList,
+            6 => ContainerType::Counter,
             _ => unreachable!(),
         }
     }
@@ -170,6 +180,8 @@ impl ContainerType {
             2 => Ok(ContainerType::List),
             3 => Ok(ContainerType::Text),
             4 => Ok(ContainerType::Tree),
+            5 => Ok(ContainerType::MovableList),
+            6 => Ok(ContainerType::Counter),
             _ => Err(LoroError::DecodeError(
                 format!("Unknown container type {v}").into_boxed_str(),
             )),
@@ -189,6 +201,8 @@ mod container {
                 ContainerType::List => "List",
                 ContainerType::Text => "Text",
                 ContainerType::Tree => "Tree",
+                ContainerType::MovableList => "MovableList",
+                ContainerType::Counter => "Counter",
             })
         }
     }
@@ -299,8 +313,10 @@ mod container {
                 "List" | "list" => Ok(ContainerType::List),
                 "Text" | "text" => Ok(ContainerType::Text),
                 "Tree" | "tree" => Ok(ContainerType::Tree),
+                "MovableList" | "movableList" => Ok(ContainerType::MovableList),
+                "Counter" | "counter" => Ok(ContainerType::Counter),
                 _ => Err(LoroError::DecodeError(
-                    format!("Unknown container type \"{}\". The valid options are Map|List|Text|Tree|MovableList.", value).into(),
+                    format!("Unknown container type \"{}\". The valid options are Map|List|Text|Tree|MovableList|Counter.", value).into(),
                 )),
             }
         }
@@ -310,12 +310,27 @@ impl SharedArena {
                     container,
                     content: InnerContent::List(InnerListOp::StyleEnd),
                 },
+                ListOp::Move { from, to, elem_id } => Op {
+                    counter,
+                    container,
+                    content: InnerContent::List(InnerListOp::Move { from, to, elem_id }),
+                },
+                ListOp::Set { elem_id, value } => Op {
+                    counter,
+                    container,
+                    content: InnerContent::List(InnerListOp::Set { elem_id, value }),
+                },
             },
             crate::op::RawOpContent::Tree(tree) => Op {
                 counter,
                 container,
                 content: crate::op::InnerContent::Tree(tree),
             },
+            crate::op::RawOpContent::Counter(x) => Op {
+                counter,
+                container,
+                content: crate::op::InnerContent::Counter(x),
+            },
         }
     }
 
@@ -378,6 +393,31 @@ impl SharedArena {
         }
         None
     }
+
+    /// Create an independent copy of this arena.
+    ///
+    /// The container indexes and the value and string positions stay the same,
+    /// so the ops that refer to this arena are still valid in the new one.
+    pub(crate) fn fork(&self) -> Self {
+        let mut str = StrArena::default();
+        let bytes = self.inner.str.lock().unwrap().slice_bytes(..);
+        str.alloc(std::str::from_utf8(&bytes).unwrap());
+        Self {
+            inner: Arc::new(InnerSharedArena {
+                container_idx_to_id: Mutex::new(
+                    self.inner.container_idx_to_id.lock().unwrap().clone(),
+                ),
+                depth: Mutex::new(self.inner.depth.lock().unwrap().clone()),
+                container_id_to_idx: Mutex::new(
+                    self.inner.container_id_to_idx.lock().unwrap().clone(),
+                ),
+                parents: Mutex::new(self.inner.parents.lock().unwrap().clone()),
+                values: Mutex::new(self.inner.values.lock().unwrap().clone()),
+                root_c_idx: Mutex::new(self.inner.root_c_idx.lock().unwrap().clone()),
+                str: Mutex::new(str),
+            }),
+        }
+    }
 }
 
 fn _alloc_str_with_slice(
@@ -0,0 +1,316 @@
+//! The ephemeral presence of the peers, such as their cursors, selections and names.
+//!
+//! The states are not stored in the [OpLog](crate::OpLog), so they are not part of the
+//! history of the doc. Each peer owns its own state, and every local update increases
+//! the clock of the state. A remote state is only replaced by the one with a greater
+//! clock, and it expires if it's not updated within the timeout.
+//!
+//! The [Cursor]s can be stored in the states by converting them into [LoroValue]s,
+//! so the selections are shared as stable positions.
+
+use fxhash::FxHashMap;
+use loro_common::{LoroError, LoroResult, LoroValue, PeerID};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    change::{get_sys_timestamp, Timestamp},
+    cursor::Cursor,
+    obs::SubID,
+};
+
+pub type AwarenessSubscriber = Box<dyn Fn(&AwarenessEvent) + Send + Sync>;
+
+/// The presence states of the peers.
+///
+/// # Example
+///
+/// ```
+/// use loro_internal::{awareness::Awareness, loro_value};
+///
+/// let mut a = Awareness::new(1, 30_000);
+/// let mut b = Awareness::new(2, 30_000);
+/// a.set_local_state(loro_value!({"name": "Alice"}));
+/// b.apply(&a.encode(&[a.peer()])).unwrap();
+/// assert_eq!(b.get_all_states()[&1].state, loro_value!({"name": "Alice"}));
+///
+/// a.remove_local_state();
+/// b.apply(&a.encode_all()).unwrap();
+/// assert!(b.get_all_states().is_empty());
+/// ```
+pub struct Awareness {
+    peer: PeerID,
+    peers: FxHashMap<PeerID, PeerInfo>,
+    /// The time in milliseconds after which a remote state expires
+    timeout: i64,
+    subscribers: FxHashMap<SubID, AwarenessSubscriber>,
+    next_sub_id: u32,
+}
+
+/// The presence state of a peer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerInfo {
+    pub state: LoroValue,
+    /// The clock of the state. It's increased by the owner of the state on each update.
+    pub counter: i32,
+    /// The local time in milliseconds when the state was last updated
+    pub timestamp: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AwarenessEventTrigger {
+    Local,
+    Import,
+    Timeout,
+}
+
+/// The peers whose states are changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AwarenessEvent {
+    pub by: AwarenessEventTrigger,
+    pub added: Vec<PeerID>,
+    pub updated: Vec<PeerID>,
+    pub removed: Vec<PeerID>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncodedPeerInfo {
+    peer: PeerID,
+    counter: i32,
+    state: LoroValue,
+}
+
+impl std::fmt::Debug for Awareness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Awareness")
+            .field("peer", &self.peer)
+            .field("peers", &self.peers)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl Awareness {
+    /// Create the presence store of the local peer.
+    ///
+    /// The remote states that are not updated within `timeout` milliseconds are
+    /// removed by [Awareness::remove_outdated].
+    pub fn new(peer: PeerID, timeout: i64) -> Self {
+        Self {
+            peer,
+            peers: FxHashMap::default(),
+            timeout,
+            subscribers: FxHashMap::default(),
+            next_sub_id: 0,
+        }
+    }
+
+    #[inline]
+    pub fn peer(&self) -> PeerID {
+        self.peer
+    }
+
+    pub fn get_local_state(&self) -> Option<LoroValue> {
+        self.peers
+            .get(&self.peer)
+            .map(|x| x.state.clone())
+            .filter(|x| !x.is_null())
+    }
+
+    /// Set the state of the local peer and increase its clock.
+    pub fn set_local_state(&mut self, value: impl Into<LoroValue>) {
+        let state = value.into();
+        let removed = state.is_null();
+        let now = get_sys_timestamp();
+        let existed = match self.peers.get_mut(&self.peer) {
+            Some(info) => {
+                let existed = !info.state.is_null();
+                info.state = state;
+                info.counter += 1;
+                info.timestamp = now;
+                existed
+            }
+            None => {
+                self.peers.insert(
+                    self.peer,
+                    PeerInfo {
+                        state,
+                        counter: 0,
+                        timestamp: now,
+                    },
+                );
+                false
+            }
+        };
+
+        let mut event = AwarenessEvent::new(AwarenessEventTrigger::Local);
+        match (existed, removed) {
+            (false, false) => event.added.push(self.peer),
+            (true, false) => event.updated.push(self.peer),
+            (true, true) => event.removed.push(self.peer),
+            (false, true) => {}
+        }
+        self.emit(event);
+    }
+
+    /// Remove the state of the local peer.
+    ///
+    /// The removal is synced to the remote peers like a normal update, so it should
+    /// be encoded and sent as well.
+    pub fn remove_local_state(&mut self) {
+        if self.peers.contains_key(&self.peer) {
+            self.set_local_state(LoroValue::Null);
+        }
+    }
+
+    /// Get the states of all the peers, including the local one.
+    pub fn get_all_states(&self) -> FxHashMap<PeerID, PeerInfo> {
+        self.peers
+            .iter()
+            .filter(|(_, info)| !info.state.is_null())
+            .map(|(peer, info)| (*peer, info.clone()))
+            .collect()
+    }
+
+    /// Encode the states of the given peers. The unknown peers are skipped.
+    pub fn encode(&self, peers: &[PeerID]) -> Vec<u8> {
+        let infos: Vec<_> = peers
+            .iter()
+            .filter_map(|peer| {
+                self.peers.get(peer).map(|info| EncodedPeerInfo {
+                    peer: *peer,
+                    counter: info.counter,
+                    state: info.state.clone(),
+                })
+            })
+            .collect();
+        postcard::to_allocvec(&infos).unwrap()
+    }
+
+    /// Encode the states of all the known peers.
+    pub fn encode_all(&self) -> Vec<u8> {
+        let peers: Vec<_> = self.peers.keys().copied().collect();
+        self.encode(&peers)
+    }
+
+    /// Apply the states encoded by [Awareness::encode].
+    ///
+    /// A state is only applied if its clock is greater than the known one. The state of
+    /// this peer is owned by the local side, so the received one is ignored.
+    pub fn apply(&mut self, encoded: &[u8]) -> LoroResult<()> {
+        let infos: Vec<EncodedPeerInfo> = postcard::from_bytes(encoded)
+            .map_err(|_| LoroError::DecodeError("Decode awareness error".into()))?;
+        let now = get_sys_timestamp();
+        let mut event = AwarenessEvent::new(AwarenessEventTrigger::Import);
+        for EncodedPeerInfo {
+            peer,
+            counter,
+            state,
+        } in infos
+        {
+            if peer == self.peer {
+                continue;
+            }
+
+            let existed = match self.peers.get(&peer) {
+                Some(info) if info.counter >= counter => continue,
+                Some(info) => !info.state.is_null(),
+                None => false,
+            };
+
+            match (existed, state.is_null()) {
+                (false, false) => event.added.push(peer),
+                (true, false) => event.updated.push(peer),
+                (true, true) => event.removed.push(peer),
+                (false, true) => {}
+            }
+            self.peers.insert(
+                peer,
+                PeerInfo {
+                    state,
+                    counter,
+                    timestamp: now,
+                },
+            );
+        }
+
+        self.emit(event);
+        Ok(())
+    }
+
+    /// Remove the remote states that are not updated within the timeout.
+    ///
+    /// It returns the removed peers.
+    pub fn remove_outdated(&mut self) -> Vec<PeerID> {
+        let now = get_sys_timestamp();
+        let mut removed = Vec::new();
+        self.peers.retain(|peer, info| {
+            if *peer != self.peer && now - info.timestamp > self.timeout {
+                if !info.state.is_null() {
+                    removed.push(*peer);
+                }
+                return false;
+            }
+
+            true
+        });
+
+        let mut event = AwarenessEvent::new(AwarenessEventTrigger::Timeout);
+        event.removed.clone_from(&removed);
+        self.emit(event);
+        removed
+    }
+
+    /// Subscribe the changes of the states. The callback is not called if no state changes.
+    pub fn subscribe(&mut self, callback: AwarenessSubscriber) -> SubID {
+        let id = SubID::from_u32(self.next_sub_id);
+        self.next_sub_id += 1;
+        self.subscribers.insert(id, callback);
+        id
+    }
+
+    pub fn unsubscribe(&mut self, id: SubID) {
+        self.subscribers.remove(&id);
+    }
+
+    fn emit(&self, event: AwarenessEvent) {
+        if event.added.is_empty() && event.updated.is_empty() && event.removed.is_empty() {
+            return;
+        }
+
+        for callback in self.subscribers.values() {
+            callback(&event);
+        }
+    }
+}
+
+impl AwarenessEvent {
+    fn new(by: AwarenessEventTrigger) -> Self {
+        Self {
+            by,
+            added: Vec::new(),
+            updated: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+}
+
+impl From<&Cursor> for LoroValue {
+    /// Encode the cursor as a binary value, so it can be stored in the presence state.
+    fn from(cursor: &Cursor) -> Self {
+        LoroValue::Binary(cursor.encode().into())
+    }
+}
+
+impl TryFrom<&LoroValue> for Cursor {
+    type Error = LoroError;
+
+    fn try_from(value: &LoroValue) -> Result<Self, Self::Error> {
+        match value {
+            LoroValue::Binary(bytes) => Cursor::decode(bytes)
+                .map_err(|_| LoroError::DecodeError("Decode cursor error".into())),
+            _ => Err(LoroError::DecodeError(
+                "A cursor should be stored as a binary value".into(),
+            )),
+        }
+    }
+}
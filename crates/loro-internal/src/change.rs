@@ -5,16 +5,18 @@
 //! Note: `dep` can only point to the end of the other [Change]. This is the invariant of [Change]s.
 
 use crate::{
+    container::list::list_op::ListOp,
     dag::DagNode,
     id::{Counter, ID},
-    op::Op,
+    op::{ListSlice, Op, RawOpContent, RemoteOp},
     span::{HasId, HasLamport},
     version::Frontiers,
 };
-use loro_common::{HasCounter, HasCounterSpan, PeerID};
+use loro_common::{ContainerID, HasCounter, HasCounterSpan, LoroValue, PeerID, TreeID};
 use num::traits::AsPrimitive;
 use rle::{HasIndex, HasLength, Mergable, RleVec, Sliceable};
 use smallvec::SmallVec;
+use std::{ops::Range, sync::Arc};
 
 pub type Timestamp = i64;
 pub type Lamport = u32;
@@ -34,6 +36,8 @@ pub struct Change<O = Op> {
     /// [Unix time](https://en.wikipedia.org/wiki/Unix_time)
     /// It is the number of seconds that have elapsed since 00:00:00 UTC on 1 January 1970.
     pub(crate) timestamp: Timestamp,
+    /// The commit message of the change
+    pub(crate) message: Option<Arc<str>>,
     /// if it has dependents, it cannot merge with new changes
     pub(crate) has_dependents: bool,
 }
@@ -52,6 +56,7 @@ impl<O> Change<O> {
             id,
             lamport,
             timestamp,
+            message: None,
             has_dependents: false,
         }
     }
@@ -86,6 +91,11 @@ impl<O> Change<O> {
         self.id
     }
 
+    #[inline]
+    pub fn message(&self) -> Option<&Arc<str>> {
+        self.message.as_ref()
+    }
+
     #[inline]
     pub fn deps_on_self(&self) -> bool {
         self.deps.len() == 1 && self.deps[0].peer == self.id.peer
@@ -190,6 +200,7 @@ impl<O: Mergable + HasLength + HasIndex + Sliceable + HasCounter + Debug> Slicea
             id: self.id.inc(from as Counter),
             lamport: self.lamport + from as Lamport,
             timestamp: self.timestamp,
+            message: self.message.clone(),
             has_dependents: self.has_dependents,
         }
     }
@@ -210,6 +221,191 @@ impl Change {
     }
 }
 
+/// The metadata of a [Change].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeMeta {
+    /// The id of the first op in the change
+    pub id: ID,
+    /// The lamport of the first op in the change
+    pub lamport: Lamport,
+    pub timestamp: Timestamp,
+    pub deps: Frontiers,
+    /// The number of atom ops in the change
+    pub len: usize,
+    pub message: Option<Arc<str>>,
+}
+
+impl ChangeMeta {
+    /// Get the metadata of the part of `change` within the counter range.
+    pub(crate) fn from_change_in_range(change: &Change, range: Range<Counter>) -> Self {
+        let start = range.start.max(change.id.counter);
+        let end = range.end.min(change.ctr_end());
+        let offset = start - change.id.counter;
+        ChangeMeta {
+            id: change.id.inc(offset),
+            lamport: change.lamport + offset as Lamport,
+            timestamp: change.timestamp,
+            deps: if offset > 0 {
+                Frontiers::from_id(change.id.inc(offset - 1))
+            } else {
+                change.deps.clone()
+            },
+            len: (end - start) as usize,
+            message: change.message.clone(),
+        }
+    }
+
+    /// The id of the last op in the change
+    pub fn id_last(&self) -> ID {
+        self.id.inc(self.len as Counter - 1)
+    }
+}
+
+/// An op of a [Change] with the decoded container id and content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeOp {
+    /// The id of the first atom op
+    pub id: ID,
+    /// The lamport of the first atom op
+    pub lamport: Lamport,
+    pub container: ContainerID,
+    pub content: ChangeOpContent,
+}
+
+/// The content of a [ChangeOp].
+///
+/// The positions are the indexes at the version before the op. The positions of the
+/// text ops are entity indexes rather than unicode indexes: besides the unicode chars,
+/// the text contains two style anchors for every mark, one before the first char of
+/// the range and one after the last char. The anchors are invisible in the value of
+/// the text, but each of them takes one entity index.
+///
+/// To get the unicode index, subtract the number of the anchors before the entity
+/// index. For example, after `mark(1..3, ..)` on `"abcdef"` the text has the entities
+/// `a [ b c ] d e f`, so `insert(5, "X")` is recorded as `TextInsert { pos: 7, .. }`.
+/// The anchors are recorded by [ChangeOpContent::Mark] and [ChangeOpContent::MarkEnd].
+/// A text without marks has the same entity and unicode indexes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeOpContent {
+    /// Insert the values into a list or a movable list
+    ListInsert {
+        pos: usize,
+        values: Vec<LoroValue>,
+    },
+    /// Insert the string into a text
+    TextInsert {
+        pos: usize,
+        text: String,
+    },
+    /// Delete the elements of a list, a movable list or a text
+    Delete {
+        pos: usize,
+        len: usize,
+    },
+    /// The start of a style on the text. It's followed by a [ChangeOpContent::MarkEnd]
+    ///
+    /// `start` and `end` are entity indexes. The start anchor is inserted at `start`,
+    /// and the end anchor of the following [ChangeOpContent::MarkEnd] at `end + 1`.
+    Mark {
+        start: usize,
+        end: usize,
+        key: String,
+        value: LoroValue,
+    },
+    MarkEnd,
+    /// Move the element `elem_id` of a movable list
+    ListMove {
+        from: usize,
+        to: usize,
+        elem_id: ID,
+    },
+    /// Set the value of the element `elem_id` of a movable list
+    ListSet {
+        elem_id: ID,
+        value: LoroValue,
+    },
+    /// Set the value of the key. The key is deleted if the value is `None`
+    MapSet {
+        key: String,
+        value: Option<LoroValue>,
+    },
+    /// Create or move a tree node
+    TreeMove {
+        target: TreeID,
+        parent: Option<TreeID>,
+    },
+    TreeDelete {
+        target: TreeID,
+    },
+    CounterIncrement(f64),
+}
+
+impl ChangeOp {
+    pub(crate) fn from_remote_op(op: RemoteOp, lamport: Lamport, peer: PeerID) -> Self {
+        let content = match op.content {
+            RawOpContent::Map(map) => ChangeOpContent::MapSet {
+                key: map.key.to_string(),
+                value: map.value,
+            },
+            RawOpContent::List(list) => match list {
+                ListOp::Insert { slice, pos } => match slice {
+                    ListSlice::RawData(values) => ChangeOpContent::ListInsert {
+                        pos,
+                        values: values.into_owned(),
+                    },
+                    ListSlice::RawStr { str, .. } => ChangeOpContent::TextInsert {
+                        pos,
+                        text: str.into_owned(),
+                    },
+                },
+                ListOp::Delete(span) => {
+                    let range = span.span.to_urange();
+                    ChangeOpContent::Delete {
+                        pos: range.start,
+                        len: range.len(),
+                    }
+                }
+                ListOp::StyleStart {
+                    start,
+                    end,
+                    key,
+                    value,
+                    ..
+                } => ChangeOpContent::Mark {
+                    start: start as usize,
+                    end: end as usize,
+                    key: key.to_string(),
+                    value,
+                },
+                ListOp::StyleEnd => ChangeOpContent::MarkEnd,
+                ListOp::Move { from, to, elem_id } => ChangeOpContent::ListMove {
+                    from: from as usize,
+                    to: to as usize,
+                    elem_id,
+                },
+                ListOp::Set { elem_id, value } => ChangeOpContent::ListSet { elem_id, value },
+            },
+            RawOpContent::Tree(tree) => match tree.parent {
+                Some(parent) if TreeID::is_deleted_root(&parent) => ChangeOpContent::TreeDelete {
+                    target: tree.target,
+                },
+                parent => ChangeOpContent::TreeMove {
+                    target: tree.target,
+                    parent,
+                },
+            },
+            RawOpContent::Counter(x) => ChangeOpContent::CounterIncrement(x),
+        };
+
+        ChangeOp {
+            id: ID::new(peer, op.counter),
+            lamport,
+            container: op.container,
+            content,
+        }
+    }
+}
+
 #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
 pub(crate) fn get_sys_timestamp() -> Timestamp {
     use std::time::{SystemTime, UNIX_EPOCH};
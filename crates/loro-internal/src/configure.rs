@@ -1,10 +1,12 @@
 pub use crate::container::richtext::config::{StyleConfig, StyleConfigMap};
+use crate::schema::Schema;
 
 #[derive(Clone)]
 pub struct Configure {
     pub(crate) text_style_config: Arc<RwLock<StyleConfigMap>>,
     record_timestamp: Arc<AtomicBool>,
     merge_interval: Arc<AtomicI64>,
+    schema: Arc<RwLock<Option<Arc<Schema>>>>,
 }
 
 impl Default for Configure {
@@ -13,6 +15,7 @@ impl Default for Configure {
             text_style_config: Arc::new(RwLock::new(StyleConfigMap::default_rich_text_config())),
             record_timestamp: Arc::new(AtomicBool::new(false)),
             merge_interval: Arc::new(AtomicI64::new(1000 * 1000)),
+            schema: Arc::new(RwLock::new(None)),
         }
     }
 }
@@ -41,6 +44,26 @@ impl Configure {
         self.merge_interval
             .store(interval, std::sync::atomic::Ordering::Relaxed);
     }
+
+    pub fn schema(&self) -> Option<Arc<Schema>> {
+        self.schema.read().unwrap().clone()
+    }
+
+    pub fn set_schema(&self, schema: Option<Schema>) {
+        *self.schema.write().unwrap() = schema.map(Arc::new);
+    }
+
+    /// Create a copy that doesn't share the settings with this one.
+    pub fn fork(&self) -> Self {
+        Self {
+            text_style_config: Arc::new(RwLock::new(
+                self.text_style_config.read().unwrap().clone(),
+            )),
+            record_timestamp: Arc::new(AtomicBool::new(self.record_timestamp())),
+            merge_interval: Arc::new(AtomicI64::new(self.merge_interval())),
+            schema: Arc::new(RwLock::new(self.schema())),
+        }
+    }
 }
 
 pub struct DefaultRandom;
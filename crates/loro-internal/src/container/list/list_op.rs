@@ -31,6 +31,18 @@ pub enum ListOp<'a> {
         value: LoroValue,
     },
     StyleEnd,
+    /// Move the element `elem_id` of a movable list. `from` is the op index of the
+    /// element before the move, `to` is the op index where the element is inserted.
+    Move {
+        from: u32,
+        to: u32,
+        elem_id: ID,
+    },
+    /// Set the value of the element `elem_id` of a movable list.
+    Set {
+        elem_id: ID,
+        value: LoroValue,
+    },
 }
 
 #[derive(EnumAsInner, Debug, Clone)]
@@ -58,6 +70,15 @@ pub enum InnerListOp {
         info: TextStyleInfoFlag,
     },
     StyleEnd,
+    Move {
+        from: u32,
+        to: u32,
+        elem_id: ID,
+    },
+    Set {
+        elem_id: ID,
+        value: LoroValue,
+    },
 }
 
 impl<'a> ListOp<'a> {
@@ -340,7 +361,10 @@ impl<'a> Mergable for ListOp<'a> {
                 ListOp::Delete(other_span) => span.is_mergable(other_span, &()),
                 _ => false,
             },
-            ListOp::StyleStart { .. } | ListOp::StyleEnd { .. } => false,
+            ListOp::StyleStart { .. }
+            | ListOp::StyleEnd { .. }
+            | ListOp::Move { .. }
+            | ListOp::Set { .. } => false,
         }
     }
 
@@ -361,7 +385,10 @@ impl<'a> Mergable for ListOp<'a> {
                 ListOp::Delete(other_span) => span.merge(other_span, &()),
                 _ => unreachable!(),
             },
-            ListOp::StyleStart { .. } | ListOp::StyleEnd { .. } => unreachable!(),
+            ListOp::StyleStart { .. }
+            | ListOp::StyleEnd { .. }
+            | ListOp::Move { .. }
+            | ListOp::Set { .. } => unreachable!(),
         }
     }
 }
@@ -371,7 +398,10 @@ impl<'a> HasLength for ListOp<'a> {
         match self {
             ListOp::Insert { slice, .. } => slice.content_len(),
             ListOp::Delete(span) => span.atom_len(),
-            ListOp::StyleStart { .. } | ListOp::StyleEnd { .. } => 1,
+            ListOp::StyleStart { .. }
+            | ListOp::StyleEnd { .. }
+            | ListOp::Move { .. }
+            | ListOp::Set { .. } => 1,
         }
     }
 }
@@ -384,7 +414,10 @@ impl<'a> Sliceable for ListOp<'a> {
                 pos: *pos + from,
             },
             ListOp::Delete(span) => ListOp::Delete(span.slice(from, to)),
-            a @ (ListOp::StyleStart { .. } | ListOp::StyleEnd { .. }) => a.clone(),
+            a @ (ListOp::StyleStart { .. }
+            | ListOp::StyleEnd { .. }
+            | ListOp::Move { .. }
+            | ListOp::Set { .. }) => a.clone(),
         }
     }
 }
@@ -472,7 +505,10 @@ impl HasLength for InnerListOp {
                 unicode_len: len, ..
             } => *len as usize,
             InnerListOp::Delete(span) => span.atom_len(),
-            InnerListOp::StyleStart { .. } | InnerListOp::StyleEnd { .. } => 1,
+            InnerListOp::StyleStart { .. }
+            | InnerListOp::StyleEnd { .. }
+            | InnerListOp::Move { .. }
+            | InnerListOp::Set { .. } => 1,
         }
     }
 }
@@ -504,7 +540,10 @@ impl Sliceable for InnerListOp {
                 pos: *pos + from as u32,
             },
             InnerListOp::Delete(span) => InnerListOp::Delete(span.slice(from, to)),
-            InnerListOp::StyleStart { .. } | InnerListOp::StyleEnd { .. } => self.clone(),
+            InnerListOp::StyleStart { .. }
+            | InnerListOp::StyleEnd { .. }
+            | InnerListOp::Move { .. }
+            | InnerListOp::Set { .. } => self.clone(),
         }
     }
 }
@@ -104,6 +104,11 @@ mod text_chunk {
             self.id.id()
         }
 
+        #[inline]
+        pub fn id_full(&self) -> IdFull {
+            self.id
+        }
+
         #[inline]
         pub fn bytes(&self) -> &BytesSlice {
             &self.bytes
@@ -2228,6 +2233,42 @@ impl RichtextState {
         })
     }
 
+    /// Get the id of each char in the given event range, with the event length of the char
+    pub(crate) fn get_char_ids_in_event_range(&self, range: Range<usize>) -> Vec<(IdFull, usize)> {
+        if range.is_empty() {
+            return Vec::new();
+        }
+
+        let start = self.tree.query::<EventIndexQuery>(&range.start).unwrap();
+        let end = self.tree.query::<EventIndexQuery>(&range.end).unwrap();
+        let mut ans = Vec::new();
+        for span in self.tree.iter_range(start.cursor..end.cursor) {
+            let RichtextStateChunk::Text(s) = span.elem else {
+                continue;
+            };
+
+            let start = span.start.unwrap_or(0);
+            let end = span.end.unwrap_or(span.elem.rle_len());
+            let mut id = s.id_full().inc(start as Counter);
+            for c in s
+                .as_str()
+                .chars()
+                .skip(start)
+                .take(end.saturating_sub(start))
+            {
+                let event_len = if cfg!(feature = "wasm") {
+                    c.len_utf16()
+                } else {
+                    1
+                };
+                ans.push((id, event_len));
+                id = id.inc(1);
+            }
+        }
+
+        ans
+    }
+
     pub(crate) fn get_stable_position_at_event_index(
         &self,
         pos: usize,
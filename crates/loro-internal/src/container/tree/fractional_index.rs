@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+/// The position of a tree node among its siblings.
+///
+/// It's a fraction in `(0, 1)` represented by its base-256 digits. The last digit is
+/// never zero, so comparing the bytes lexicographically is the same as comparing
+/// the fractions. There is always a new index between any two different indexes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FractionalIndex(Arc<Vec<u8>>);
+
+const MID: u8 = 128;
+
+impl Default for FractionalIndex {
+    fn default() -> Self {
+        FractionalIndex(Arc::new(vec![MID]))
+    }
+}
+
+impl Serialize for FractionalIndex {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.as_slice().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FractionalIndex {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        Self::from_bytes(bytes).ok_or_else(|| serde::de::Error::custom("invalid fractional index"))
+    }
+}
+
+impl FractionalIndex {
+    /// Returns `None` if the bytes are not a valid fractional index
+    pub fn from_bytes(bytes: Vec<u8>) -> Option<Self> {
+        match bytes.last() {
+            Some(&last) if last != 0 => Some(FractionalIndex(Arc::new(bytes))),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Generate a new index between `lower` and `upper`.
+    ///
+    /// `None` means unbounded on that side. Returns `None` if `lower >= upper`.
+    pub fn new_between(lower: Option<&Self>, upper: Option<&Self>) -> Option<Self> {
+        let lower = lower.map(|x| x.as_bytes()).unwrap_or(&[]);
+        let mut upper = upper.map(|x| x.as_bytes());
+        if let Some(upper) = upper {
+            if lower >= upper {
+                return None;
+            }
+        }
+
+        let mut ans = Vec::new();
+        for i in 0.. {
+            let l = lower.get(i).copied().unwrap_or(0) as u16;
+            // once the result is known to be less than `upper`, the rest of `upper` doesn't matter
+            let u = match upper {
+                Some(upper) => upper.get(i).copied().unwrap_or(0) as u16,
+                None => 256,
+            };
+            if u > l + 1 {
+                ans.push(((l + u) / 2) as u8);
+                break;
+            }
+
+            ans.push(l as u8);
+            if u == l + 1 {
+                upper = None;
+            }
+        }
+
+        Some(FractionalIndex(Arc::new(ans)))
+    }
+
+    /// Generate `n` ascending indexes between `lower` and `upper`.
+    pub fn generate_n_between(
+        lower: Option<&Self>,
+        upper: Option<&Self>,
+        n: usize,
+    ) -> Option<Vec<Self>> {
+        let mut ans: Vec<Self> = Vec::with_capacity(n);
+        for _ in 0..n {
+            let next = Self::new_between(ans.last().or(lower), upper)?;
+            ans.push(next);
+        }
+
+        Some(ans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn between() {
+        let a = FractionalIndex::default();
+        let b = FractionalIndex::new_between(Some(&a), None).unwrap();
+        assert!(a < b);
+        let c = FractionalIndex::new_between(None, Some(&a)).unwrap();
+        assert!(c < a);
+        let d = FractionalIndex::new_between(Some(&c), Some(&a)).unwrap();
+        assert!(c < d && d < a);
+        assert!(FractionalIndex::new_between(Some(&a), Some(&a)).is_none());
+        assert!(FractionalIndex::new_between(Some(&b), Some(&a)).is_none());
+    }
+
+    #[test]
+    fn between_adjacent_bytes() {
+        let a = FractionalIndex::from_bytes(vec![1]).unwrap();
+        let b = FractionalIndex::from_bytes(vec![2]).unwrap();
+        let c = FractionalIndex::new_between(Some(&a), Some(&b)).unwrap();
+        assert!(a < c && c < b);
+        let d = FractionalIndex::from_bytes(vec![1, 255, 255]).unwrap();
+        let e = FractionalIndex::new_between(Some(&d), Some(&b)).unwrap();
+        assert!(d < e && e < b);
+        assert_ne!(*e.as_bytes().last().unwrap(), 0);
+    }
+
+    #[test]
+    fn repeated_insertion_keeps_order() {
+        let mut lower = None;
+        let upper = FractionalIndex::default();
+        let mut all = vec![];
+        for _ in 0..1000 {
+            let next = FractionalIndex::new_between(lower.as_ref(), Some(&upper)).unwrap();
+            if let Some(lower) = &lower {
+                assert!(lower < &next);
+            }
+            assert!(next < upper);
+            all.push(next.clone());
+            lower = Some(next);
+        }
+
+        let n = FractionalIndex::generate_n_between(all.first(), all.get(1), 10).unwrap();
+        assert!(n.windows(2).all(|w| w[0] < w[1]));
+        assert!(all[0] < n[0] && n[9] < all[1]);
+    }
+}
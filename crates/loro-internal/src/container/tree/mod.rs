@@ -1 +1,2 @@
+pub(crate) mod fractional_index;
 pub(crate) mod tree_op;
@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::state::TreeParentId;
 
+use super::fractional_index::FractionalIndex;
+
 /// The operation of movable tree.
 ///
 /// In the movable tree, there are three actions:
@@ -11,10 +13,13 @@ use crate::state::TreeParentId;
 /// - **Move**: move target tree node a child node of the specified parent node.
 /// - **Delete**: move target tree node to [`loro_common::DELETED_TREE_ROOT`].
 ///
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+/// Create and move ops carry the position of the target among its new siblings.
+/// Delete ops don't have a position.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TreeOp {
     pub(crate) target: TreeID,
     pub(crate) parent: Option<TreeID>,
+    pub(crate) position: Option<FractionalIndex>,
 }
 
 impl TreeOp {
@@ -42,7 +47,7 @@ impl HasLength for TreeOp {
 impl Sliceable for TreeOp {
     fn slice(&self, from: usize, to: usize) -> Self {
         assert!(from == 0 && to == 1);
-        *self
+        self.clone()
     }
 }
 
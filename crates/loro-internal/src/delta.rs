@@ -4,6 +4,8 @@ mod map;
 pub use map::{MapDiff, ValuePair};
 mod map_delta;
 pub use map_delta::{MapDelta, MapValue, ResolvedMapDelta, ResolvedMapValue};
+mod movable_list;
+pub(crate) use movable_list::{MovableListElement, MovableListInnerDelta, MovableListSlot};
 mod text;
 pub use text::{StyleMeta, StyleMetaItem};
 mod tree;
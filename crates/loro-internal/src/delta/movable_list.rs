@@ -0,0 +1,41 @@
+use fxhash::FxHashMap;
+use loro_common::{IdFull, LoroValue, ID};
+
+use super::Delta;
+
+/// The internal diff of a movable list.
+///
+/// `slots` describes how the positions of the list changed, including the positions
+/// that no element points to anymore. `elements` contains the latest position and
+/// value of every element that may have been changed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MovableListInnerDelta {
+    pub slots: Delta<Vec<MovableListSlot>>,
+    pub elements: FxHashMap<ID, MovableListElement>,
+}
+
+/// A position in the movable list. It's created by an insert op or a move op.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MovableListSlot {
+    pub id: IdFull,
+    pub elem_id: ID,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MovableListElement {
+    /// The id of the slot the element is at
+    pub pos: ID,
+    pub value: LoroValue,
+}
+
+impl MovableListInnerDelta {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.slots.is_empty() && self.elements.is_empty()
+    }
+
+    pub(crate) fn compose(mut self, other: Self) -> Self {
+        self.slots = self.slots.compose(other.slots);
+        self.elements.extend(other.elements);
+        self
+    }
+}
@@ -603,6 +603,46 @@ impl<Value: DeltaValue, M: Meta> Delta<Value, M> {
         delta.chop()
     }
 
+    /// Transform `other` so that it can be applied after `self`, given that both
+    /// of them are based on the same state.
+    ///
+    /// If `priority` is true, the insertions of `self` are placed before the
+    /// insertions of `other` at the same position.
+    ///
+    /// Reference: [Quill Delta](https://github.com/quilljs/delta)
+    pub fn transform(&self, other: &Self, priority: bool) -> Self
+    where
+        Value: Clone,
+    {
+        let mut this_iter = self.clone().into_op_iter();
+        let mut other_iter = other.clone().into_op_iter();
+        let mut delta = Delta::new();
+        while this_iter.has_next() || other_iter.has_next() {
+            if this_iter.peek_is_insert() && (priority || !other_iter.peek_is_insert()) {
+                let len = this_iter.next(None).length();
+                delta = delta.retain(len);
+            } else if other_iter.peek_is_insert() {
+                delta.push(other_iter.next(None));
+            } else {
+                let len = this_iter.peek_length().min(other_iter.peek_length());
+                let this_op = this_iter.next(len);
+                let other_op = other_iter.next(len);
+                if this_op.is_delete() {
+                    // the target has been deleted by `self`
+                    continue;
+                }
+
+                if other_op.is_delete() {
+                    delta.push(other_op);
+                } else {
+                    delta = delta.retain_with_meta(len, other_op.meta().clone());
+                }
+            }
+        }
+
+        delta.chop()
+    }
+
     pub(crate) fn concat(mut self, mut other: Self) -> Self {
         if !other.vec.is_empty() {
             let other_first = other.vec.remove(0);
@@ -948,4 +988,30 @@ mod test {
             .insert("f");
         assert_eq!(a.compose(b), expect);
     }
+
+    #[test]
+    fn transform_insert_insert() {
+        let a = TestDelta::new().insert("a");
+        let b = TestDelta::new().insert("b");
+        assert_eq!(
+            a.transform(&b, true),
+            TestDelta::new().retain(1).insert("b")
+        );
+        assert_eq!(a.transform(&b, false), TestDelta::new().insert("b"));
+    }
+
+    #[test]
+    fn transform_delete() {
+        let a = TestDelta::new().retain(1).delete(2);
+        let b = TestDelta::new().retain(2).insert("b").delete(2);
+        assert_eq!(
+            a.transform(&b, true),
+            TestDelta::new().retain(1).insert("b").delete(1)
+        );
+        let c = TestDelta::new().retain(4).insert("c");
+        assert_eq!(
+            a.transform(&c, true),
+            TestDelta::new().retain(2).insert("c")
+        );
+    }
 }
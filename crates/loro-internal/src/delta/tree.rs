@@ -7,7 +7,7 @@ use fxhash::{FxHashMap, FxHashSet};
 use loro_common::{ContainerType, IdFull, LoroValue, TreeID};
 use serde::Serialize;
 
-use crate::state::TreeParentId;
+use crate::{container::tree::fractional_index::FractionalIndex, state::TreeParentId};
 
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct TreeDiff {
@@ -20,24 +20,45 @@ pub struct TreeDiffItem {
     pub action: TreeExternalDiff,
 }
 
+/// `index` is the position of the target among the children of its new parent,
+/// right after this action is applied.
 #[derive(Debug, Clone, Copy, Serialize)]
 pub enum TreeExternalDiff {
-    Create(Option<TreeID>),
-    Move(Option<TreeID>),
+    Create {
+        parent: Option<TreeID>,
+        index: usize,
+    },
+    Move {
+        parent: Option<TreeID>,
+        index: usize,
+    },
     Delete,
 }
 
 impl TreeDiffItem {
-    pub(crate) fn from_delta_item(item: TreeDeltaItem) -> Option<TreeDiffItem> {
+    /// Convert the internal diff item to the external one.
+    ///
+    /// `get_index` returns the index of the target under its new parent. It should be
+    /// called after the item is applied to the state.
+    pub(crate) fn from_delta_item(
+        item: &TreeDeltaItem,
+        get_index: impl FnOnce(&TreeID) -> usize,
+    ) -> Option<TreeDiffItem> {
         let target = item.target;
         match item.action {
             TreeInternalDiff::Create(p) => Some(TreeDiffItem {
                 target,
-                action: TreeExternalDiff::Create(p.into_node().ok()),
+                action: TreeExternalDiff::Create {
+                    parent: p.into_node().ok(),
+                    index: get_index(&target),
+                },
             }),
             TreeInternalDiff::Move(p) => Some(TreeDiffItem {
                 target,
-                action: TreeExternalDiff::Move(p.into_node().ok()),
+                action: TreeExternalDiff::Move {
+                    parent: p.into_node().ok(),
+                    index: get_index(&target),
+                },
             }),
             TreeInternalDiff::Delete(_) | TreeInternalDiff::UnCreate => Some(TreeDiffItem {
                 target,
@@ -49,8 +70,10 @@ impl TreeDiffItem {
 }
 
 impl TreeDiff {
-    pub(crate) fn compose(self, _other: Self) -> Self {
-        unreachable!("tree compose")
+    /// The items are applied one by one, and the index of each item is the one
+    /// right after it's applied, so composing is just concatenating.
+    pub(crate) fn compose(self, other: Self) -> Self {
+        self.extend(other.diff)
     }
 
     pub(crate) fn extend<I: IntoIterator<Item = TreeDiffItem>>(mut self, other: I) -> Self {
@@ -66,10 +89,12 @@ pub struct TreeDelta {
 }
 
 /// The semantic action in movable tree.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct TreeDeltaItem {
     pub target: TreeID,
     pub action: TreeInternalDiff,
+    /// The position of the target among its siblings after this action
+    pub position: Option<FractionalIndex>,
     pub last_effective_move_op_id: IdFull,
 }
 
@@ -97,6 +122,7 @@ impl TreeDeltaItem {
         target: TreeID,
         parent: TreeParentId,
         old_parent: TreeParentId,
+        position: Option<FractionalIndex>,
         op_id: IdFull,
         is_new_parent_deleted: bool,
         is_old_parent_deleted: bool,
@@ -118,6 +144,7 @@ impl TreeDeltaItem {
         TreeDeltaItem {
             target,
             action,
+            position,
             last_effective_move_op_id: op_id,
         }
     }
@@ -147,12 +174,12 @@ impl<'a> TreeValue<'a> {
         for d in diff.diff.iter() {
             let target = d.target;
             match d.action {
-                TreeExternalDiff::Create(parent) => {
+                TreeExternalDiff::Create { parent, .. } => {
                     self.create_target(target);
                     self.mov(target, parent);
                 }
                 TreeExternalDiff::Delete => self.delete_target(target),
-                TreeExternalDiff::Move(parent) => self.mov(target, parent),
+                TreeExternalDiff::Move { parent, .. } => self.mov(target, parent),
             }
         }
     }
@@ -1,4 +1,4 @@
-use std::{num::NonZeroU16, sync::Arc};
+use std::{collections::BTreeMap, num::NonZeroU16, sync::Arc};
 
 pub(super) mod tree;
 use itertools::Itertools;
@@ -19,7 +19,9 @@ use crate::{
         },
     },
     cursor::AbsolutePosition,
-    delta::{Delta, MapDelta, MapValue},
+    delta::{
+        Delta, MapDelta, MapValue, MovableListElement, MovableListInnerDelta, MovableListSlot,
+    },
     event::InternalDiff,
     op::{RichOp, SliceRange, SliceRanges},
     span::{HasId, HasLamport},
@@ -308,6 +310,14 @@ impl DiffCalculator {
                     depth,
                     ContainerDiffCalculator::Tree(TreeDiffCalculator::new(idx)),
                 ),
+                crate::ContainerType::MovableList => (
+                    depth,
+                    ContainerDiffCalculator::MovableList(MovableListDiffCalculator::new(idx)),
+                ),
+                crate::ContainerType::Counter => (
+                    depth,
+                    ContainerDiffCalculator::Counter(CounterDiffCalculator::default()),
+                ),
             })
     }
 }
@@ -345,6 +355,8 @@ pub(crate) enum ContainerDiffCalculator {
     List(ListDiffCalculator),
     Richtext(RichtextDiffCalculator),
     Tree(TreeDiffCalculator),
+    MovableList(MovableListDiffCalculator),
+    Counter(CounterDiffCalculator),
 }
 
 #[derive(Debug)]
@@ -517,6 +529,7 @@ impl DiffCalculatorTrait for ListDiffCalculator {
             },
             crate::op::InnerContent::Map(_) => unreachable!(),
             crate::op::InnerContent::Tree(_) => unreachable!(),
+            crate::op::InnerContent::Counter(_) => unreachable!(),
         }
     }
 
@@ -685,9 +698,12 @@ impl DiffCalculatorTrait for RichtextDiffCalculator {
                     );
                 }
                 crate::container::list::list_op::InnerListOp::StyleEnd => {}
+                crate::container::list::list_op::InnerListOp::Move { .. }
+                | crate::container::list::list_op::InnerListOp::Set { .. } => unreachable!(),
             },
             crate::op::InnerContent::Map(_) => unreachable!(),
             crate::op::InnerContent::Tree(_) => unreachable!(),
+            crate::op::InnerContent::Counter(_) => unreachable!(),
         }
     }
 
@@ -767,3 +783,284 @@ impl DiffCalculatorTrait for RichtextDiffCalculator {
         InternalDiff::RichtextRaw(delta)
     }
 }
+
+/// The diff calculator of movable list.
+///
+/// The positions of the list are tracked by [RichtextTracker], where both insert ops
+/// and move ops create new positions. The position and the value of each element are
+/// resolved by LWW with the help of [crate::oplog::OpLog::op_groups].
+pub(crate) struct MovableListDiffCalculator {
+    container_idx: ContainerIdx,
+    start_vv: VersionVector,
+    tracker: Box<RichtextTracker>,
+}
+
+impl MovableListDiffCalculator {
+    pub(crate) fn new(container_idx: ContainerIdx) -> Self {
+        Self {
+            container_idx,
+            start_vv: Default::default(),
+            tracker: Box::new(RichtextTracker::new_with_unknown()),
+        }
+    }
+}
+
+impl std::fmt::Debug for MovableListDiffCalculator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MovableListDiffCalculator")
+            .field("container_idx", &self.container_idx)
+            .finish()
+    }
+}
+
+impl DiffCalculatorTrait for MovableListDiffCalculator {
+    fn start_tracking(&mut self, _oplog: &OpLog, vv: &crate::VersionVector) {
+        if !vv.includes_vv(&self.start_vv) || !self.tracker.all_vv().includes_vv(vv) {
+            *self.tracker = RichtextTracker::new_with_unknown();
+            self.start_vv = vv.clone();
+        }
+
+        self.tracker.checkout(vv);
+    }
+
+    fn apply_change(
+        &mut self,
+        _oplog: &OpLog,
+        op: crate::op::RichOp,
+        vv: Option<&crate::VersionVector>,
+    ) {
+        if let Some(vv) = vv {
+            self.tracker.checkout(vv);
+        }
+
+        match op.op().content.as_list().unwrap() {
+            crate::container::list::list_op::InnerListOp::Insert { slice, pos } => {
+                self.tracker
+                    .insert(op.id_full(), *pos, RichtextChunk::new_text(slice.0.clone()));
+            }
+            crate::container::list::list_op::InnerListOp::Delete(del) => {
+                self.tracker.delete(
+                    op.id_start(),
+                    del.id_start,
+                    del.start() as usize,
+                    del.atom_len(),
+                    del.is_reversed(),
+                );
+            }
+            crate::container::list::list_op::InnerListOp::Move { to, .. } => {
+                self.tracker
+                    .insert(op.id_full(), *to as usize, RichtextChunk::new_unknown(1));
+            }
+            crate::container::list::list_op::InnerListOp::Set { .. } => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn stop_tracking(&mut self, _oplog: &OpLog, _vv: &crate::VersionVector) {}
+
+    fn calculate_diff(
+        &mut self,
+        oplog: &OpLog,
+        from: &crate::VersionVector,
+        to: &crate::VersionVector,
+        mut on_new_container: impl FnMut(&ContainerID),
+    ) -> InternalDiff {
+        let mut slots = Delta::new();
+        // The elements whose slots are inserted should always be updated, because
+        // their states may be removed or outdated.
+        let mut inserted_elements = FxHashSet::default();
+        for item in self.tracker.diff(from, to) {
+            match item {
+                CrdtRopeDelta::Retain(len) => {
+                    slots = slots.retain(len);
+                }
+                CrdtRopeDelta::Insert { chunk, id, .. } => {
+                    assert_ne!(id.peer, PeerID::MAX);
+                    let mut arr = Vec::with_capacity(chunk.len());
+                    for rich_op in oplog.iter_ops(IdSpan::new(
+                        id.peer,
+                        id.counter,
+                        id.counter + chunk.len() as Counter,
+                    )) {
+                        let op = rich_op.op();
+                        let lamport = rich_op.lamport();
+                        match op.content.as_list().unwrap() {
+                            crate::container::list::list_op::InnerListOp::Insert {
+                                slice, ..
+                            } => {
+                                for i in 0..slice.atom_len() {
+                                    let id = IdFull::new(
+                                        id.peer,
+                                        op.counter + i as Counter,
+                                        lamport + i as Lamport,
+                                    );
+                                    inserted_elements.insert(id.id());
+                                    arr.push(MovableListSlot {
+                                        id,
+                                        elem_id: id.id(),
+                                    });
+                                }
+                            }
+                            crate::container::list::list_op::InnerListOp::Move {
+                                elem_id, ..
+                            } => {
+                                inserted_elements.insert(*elem_id);
+                                arr.push(MovableListSlot {
+                                    id: IdFull::new(id.peer, op.counter, lamport),
+                                    elem_id: *elem_id,
+                                });
+                            }
+                            _ => unreachable!(),
+                        }
+                    }
+
+                    debug_assert_eq!(arr.len(), chunk.len());
+                    slots = slots.insert(arr);
+                }
+                CrdtRopeDelta::Delete(len) => {
+                    slots = slots.delete(len);
+                }
+            }
+        }
+
+        // The elements that are moved, set or deleted between the two versions. The ops are
+        // not applied again when the calculator is reused, so they are collected here.
+        let mut changed_elements = FxHashSet::default();
+        oplog.for_each_change_within(from, to, |change| {
+            for op in change.ops.iter() {
+                if op.container != self.container_idx {
+                    continue;
+                }
+
+                match op.content.as_list().unwrap() {
+                    crate::container::list::list_op::InnerListOp::Move { elem_id, .. }
+                    | crate::container::list::list_op::InnerListOp::Set { elem_id, .. } => {
+                        changed_elements.insert(*elem_id);
+                    }
+                    // the element of a deleted slot is deleted even if it's moved elsewhere
+                    crate::container::list::list_op::InnerListOp::Delete(del) => {
+                        for i in 0..del.atom_len() as Counter {
+                            let slot = del.id_start.inc(i);
+                            let elem =
+                                match oplog.lookup_op(slot).and_then(|op| op.content.as_list()) {
+                                    Some(crate::container::list::list_op::InnerListOp::Move {
+                                        elem_id,
+                                        ..
+                                    }) => *elem_id,
+                                    _ => slot,
+                                };
+                            changed_elements.insert(elem);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        let mut elements = FxHashMap::default();
+        for elem in changed_elements {
+            if inserted_elements.contains(&elem) {
+                continue;
+            }
+
+            let new = resolve_movable_list_elem(oplog, self.container_idx, elem, to);
+            if resolve_movable_list_elem(oplog, self.container_idx, elem, from) != new {
+                elements.insert(elem, new);
+            }
+        }
+
+        for elem in inserted_elements {
+            elements.insert(
+                elem,
+                resolve_movable_list_elem(oplog, self.container_idx, elem, to),
+            );
+        }
+
+        for elem in elements.values() {
+            if let LoroValue::Container(c) = &elem.value {
+                on_new_container(c);
+            }
+        }
+
+        InternalDiff::MovableList(MovableListInnerDelta { slots, elements })
+    }
+}
+
+/// The diff calculator of counter.
+///
+/// Counter ops are commutative, so the diff is the sum of the ops that are only
+/// included in `to`, minus the sum of the ops that are only included in `from`.
+#[derive(Debug, Default)]
+pub(crate) struct CounterDiffCalculator {
+    /// Sorted by id, so the floating point sum doesn't depend on the order the ops
+    /// are tracked in
+    ops: BTreeMap<ID, f64>,
+}
+
+impl DiffCalculatorTrait for CounterDiffCalculator {
+    fn start_tracking(&mut self, _oplog: &OpLog, _vv: &crate::VersionVector) {}
+
+    fn apply_change(
+        &mut self,
+        _oplog: &OpLog,
+        op: crate::op::RichOp,
+        _vv: Option<&crate::VersionVector>,
+    ) {
+        let x = op.raw_op().content.as_counter().unwrap();
+        self.ops.insert(op.id(), *x);
+    }
+
+    fn stop_tracking(&mut self, _oplog: &OpLog, _vv: &crate::VersionVector) {}
+
+    fn calculate_diff(
+        &mut self,
+        _oplog: &OpLog,
+        from: &crate::VersionVector,
+        to: &crate::VersionVector,
+        _on_new_container: impl FnMut(&ContainerID),
+    ) -> InternalDiff {
+        let mut diff = 0.;
+        for (id, x) in self.ops.iter() {
+            match (from.includes_id(*id), to.includes_id(*id)) {
+                (false, true) => diff += x,
+                (true, false) => diff -= x,
+                _ => {}
+            }
+        }
+
+        InternalDiff::Counter(diff)
+    }
+}
+
+/// Resolve the position and the value of the element at the given version.
+///
+/// The position of a deleted element is [ID::NONE_ID], which is not the id of any slot.
+pub(crate) fn resolve_movable_list_elem(
+    oplog: &OpLog,
+    idx: ContainerIdx,
+    elem: ID,
+    vv: &VersionVector,
+) -> MovableListElement {
+    let group = oplog.op_groups.get_movable_list(&idx);
+    let pos = if group.is_some_and(|g| g.is_deleted(elem, vv)) {
+        ID::NONE_ID
+    } else {
+        group
+            .and_then(|g| g.last_move(elem, vv))
+            .map(|m| ID::new(m.peer, m.counter))
+            .unwrap_or(elem)
+    };
+    let value = match group.and_then(|g| g.last_set(elem, vv)) {
+        Some(set) => set.value.clone().unwrap(),
+        None => oplog
+            .lookup_op(elem)
+            .and_then(|op| {
+                let slice = op.content.as_list()?.as_insert()?.0;
+                let offset = (elem.counter - op.counter) as u32;
+                oplog.arena.get_value((slice.0.start + offset) as usize)
+            })
+            .unwrap_or(LoroValue::Null),
+    };
+
+    MovableListElement { pos, value }
+}
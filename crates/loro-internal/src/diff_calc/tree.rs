@@ -5,7 +5,7 @@ use itertools::Itertools;
 use loro_common::{ContainerID, HasId, IdFull, IdSpan, Lamport, TreeID, ID};
 
 use crate::{
-    container::idx::ContainerIdx,
+    container::{idx::ContainerIdx, tree::fractional_index::FractionalIndex},
     dag::DagUtils,
     delta::{TreeDelta, TreeDeltaItem, TreeInternalDiff},
     event::InternalDiff,
@@ -87,7 +87,7 @@ impl TreeDiffCalculator {
                     break;
                 }
                 if !to.includes_id(op.id) {
-                    retreat_ops.push(*op);
+                    retreat_ops.push(op.clone());
                 }
             }
         }
@@ -118,7 +118,7 @@ impl TreeDiffCalculator {
                 if !tree_cache.current_vv.includes_id(op.id_start())
                     && to.includes_id(op.id_start())
                 {
-                    forward_ops.push((*lamport, *op));
+                    forward_ops.push((*lamport, op.clone()));
                 }
             }
         }
@@ -127,6 +127,7 @@ impl TreeDiffCalculator {
             let op = MoveLamportAndID {
                 target: op.value.target,
                 parent: op.value.parent_id(),
+                position: op.value.position.clone(),
                 id: op.id_start(),
                 lamport,
                 effected: false,
@@ -174,7 +175,7 @@ impl TreeDiffCalculator {
                     break;
                 }
                 if !lca_vv.includes_id(op.id) {
-                    retreat_ops.push(*op);
+                    retreat_ops.push(op.clone());
                 }
             }
         }
@@ -191,27 +192,33 @@ impl TreeDiffCalculator {
                 // we need to know whether old_parent is deleted
                 let is_parent_deleted = tree_cache.is_parent_deleted(op.parent);
                 let is_old_parent_deleted = tree_cache.is_parent_deleted(old_parent);
+                let old_position = tree_cache
+                    .get_last_effective_move(op.target)
+                    .and_then(|x| x.position.clone());
                 let this_diff = TreeDeltaItem::new(
                     op.target,
                     old_parent,
                     op.parent,
+                    old_position,
                     last_effective_move_op_id,
                     is_old_parent_deleted,
                     is_parent_deleted,
                 );
+                let is_create = matches!(this_diff.action, TreeInternalDiff::Create(_));
                 diffs.push(this_diff);
-                if matches!(this_diff.action, TreeInternalDiff::Create(_)) {
+                if is_create {
                     let mut s = vec![op.target];
                     while let Some(t) = s.pop() {
                         let children = tree_cache.get_children_with_id(TreeParentId::Node(t));
-                        children.iter().for_each(|c| {
-                            diffs.push(TreeDeltaItem {
-                                target: c.0,
-                                action: TreeInternalDiff::Create(TreeParentId::Node(t)),
-                                last_effective_move_op_id: c.1,
-                            })
-                        });
                         s.extend(children.iter().map(|c| c.0));
+                        diffs.extend(children.into_iter().map(|(target, id, position)| {
+                            TreeDeltaItem {
+                                target,
+                                action: TreeInternalDiff::Create(TreeParentId::Node(t)),
+                                position,
+                                last_effective_move_op_id: id,
+                            }
+                        }));
                     }
                 }
             }
@@ -233,6 +240,7 @@ impl TreeDiffCalculator {
                     let op = MoveLamportAndID {
                         target: op.value.target,
                         parent: op.value.parent_id(),
+                        position: op.value.position.clone(),
                         id: op.id_start(),
                         lamport: *lamport,
                         effected: false,
@@ -240,31 +248,34 @@ impl TreeDiffCalculator {
                     let (old_parent, _id) = tree_cache.get_parent_with_id(op.target);
                     let is_parent_deleted = tree_cache.is_parent_deleted(op.parent);
                     let is_old_parent_deleted = tree_cache.is_parent_deleted(old_parent);
-                    let effected = tree_cache.apply(op);
+                    let effected = tree_cache.apply(op.clone());
                     if effected {
                         let this_diff = TreeDeltaItem::new(
                             op.target,
                             op.parent,
                             old_parent,
+                            op.position.clone(),
                             op.id_full(),
                             is_parent_deleted,
                             is_old_parent_deleted,
                         );
+                        let is_create = matches!(this_diff.action, TreeInternalDiff::Create(_));
                         diffs.push(this_diff);
-                        if matches!(this_diff.action, TreeInternalDiff::Create(_)) {
+                        if is_create {
                             // TODO: per
                             let mut s = vec![op.target];
                             while let Some(t) = s.pop() {
                                 let children =
                                     tree_cache.get_children_with_id(TreeParentId::Node(t));
-                                children.iter().for_each(|c| {
-                                    diffs.push(TreeDeltaItem {
-                                        target: c.0,
-                                        action: TreeInternalDiff::Create(TreeParentId::Node(t)),
-                                        last_effective_move_op_id: c.1,
-                                    })
-                                });
                                 s.extend(children.iter().map(|x| x.0));
+                                diffs.extend(children.into_iter().map(|(target, id, position)| {
+                                    TreeDeltaItem {
+                                        target,
+                                        action: TreeInternalDiff::Create(TreeParentId::Node(t)),
+                                        position,
+                                        last_effective_move_op_id: id,
+                                    }
+                                }));
                             }
                         }
                     }
@@ -292,12 +303,13 @@ impl TreeDiffCalculator {
 }
 
 /// All information of an operation for diff calculating of movable tree.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MoveLamportAndID {
     pub(crate) lamport: Lamport,
     pub(crate) id: ID,
     pub(crate) target: TreeID,
     pub(crate) parent: TreeParentId,
+    pub(crate) position: Option<FractionalIndex>,
     /// Whether this action is applied in the current version.
     /// If this action will cause a circular reference, then this action will not be applied.
     pub(crate) effected: bool,
@@ -372,8 +384,8 @@ impl TreeCacheForDiff {
             effected = false;
         }
         node.effected = effected;
-        self.tree.entry(node.target).or_default().insert(node);
         self.current_vv.set_last(node.id);
+        self.tree.entry(node.target).or_default().insert(node);
         effected
     }
 
@@ -419,7 +431,10 @@ impl TreeCacheForDiff {
         ans
     }
 
-    fn get_children_with_id(&self, parent: TreeParentId) -> Vec<(TreeID, IdFull)> {
+    fn get_children_with_id(
+        &self,
+        parent: TreeParentId,
+    ) -> Vec<(TreeID, IdFull, Option<FractionalIndex>)> {
         let mut ans = vec![];
         for (tree_id, _) in self.tree.iter() {
             let Some(op) = self.get_last_effective_move(*tree_id) else {
@@ -427,7 +442,7 @@ impl TreeCacheForDiff {
             };
 
             if op.parent == parent {
-                ans.push((*tree_id, op.id_full()));
+                ans.push((*tree_id, op.id_full(), op.position.clone()));
             }
         }
 
@@ -1,13 +1,20 @@
 mod encode_reordered;
+pub mod json_schema;
 
+use crate::change::Change;
+use crate::container::idx::ContainerIdx;
 use crate::op::OpWithId;
+use crate::oplog::calc_lamports_of_new_changes;
+use crate::state::DocState;
 use crate::version::Frontiers;
-use crate::LoroDoc;
 use crate::{oplog::OpLog, LoroError, VersionVector};
-use loro_common::{IdLpSpan, LoroResult};
+use crate::{DocDiff, LoroDoc};
+use fxhash::FxHashMap;
+use loro_common::{Counter, HasCounterSpan, IdLpSpan, IdSpan, LoroResult, PeerID, ID};
 use num_traits::{FromPrimitive, ToPrimitive};
 use rle::{HasLength, Sliceable};
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
 const MAGIC_BYTES: [u8; 4] = *b"loro";
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -16,6 +23,8 @@ pub(crate) enum EncodeMode {
     Auto = 255,
     Rle = 1,
     Snapshot = 2,
+    /// The state at a version and the updates after it, without the history before it
+    ShallowSnapshot = 3,
 }
 
 impl num_traits::FromPrimitive for EncodeMode {
@@ -28,6 +37,8 @@ impl num_traits::FromPrimitive for EncodeMode {
             Some(EncodeMode::Rle)
         } else if n == EncodeMode::Snapshot as i64 {
             Some(EncodeMode::Snapshot)
+        } else if n == EncodeMode::ShallowSnapshot as i64 {
+            Some(EncodeMode::ShallowSnapshot)
         } else {
             None
         }
@@ -46,6 +57,7 @@ impl num_traits::ToPrimitive for EncodeMode {
             EncodeMode::Auto => EncodeMode::Auto as i64,
             EncodeMode::Rle => EncodeMode::Rle as i64,
             EncodeMode::Snapshot => EncodeMode::Snapshot as i64,
+            EncodeMode::ShallowSnapshot => EncodeMode::ShallowSnapshot as i64,
         })
     }
     #[inline]
@@ -61,7 +73,7 @@ impl EncodeMode {
     }
 
     pub fn is_snapshot(self) -> bool {
-        matches!(self, EncodeMode::Snapshot)
+        matches!(self, EncodeMode::Snapshot | EncodeMode::ShallowSnapshot)
     }
 }
 
@@ -149,10 +161,135 @@ pub(crate) fn decode_oplog(
     let ParsedHeaderAndBody { mode, body, .. } = parsed;
     match mode {
         EncodeMode::Rle | EncodeMode::Snapshot => encode_reordered::decode_updates(oplog, body),
+        EncodeMode::ShallowSnapshot => {
+            encode_reordered::decode_updates_in_shallow_snapshot(oplog, body)
+        }
         EncodeMode::Auto => unreachable!(),
     }
 }
 
+/// Merge the update blobs into one, without building the state of the doc.
+///
+/// The changes of all the blobs are decoded into an [OpLog] and encoded as updates again.
+/// The snapshots are accepted but only their changes are kept. The deps outside the blobs
+/// become the shallow root of the oplog, so the merged blob starts from them like the blob
+/// exported from a version. If a change depends on a change that is missing in the range
+/// the blobs cover, i.e. there is a gap inside them, it returns
+/// [LoroError::MergeWithMissingDeps].
+pub fn merge_updates(blobs: &[&[u8]]) -> LoroResult<Vec<u8>> {
+    let mut oplog = OpLog::new();
+    let mut changes_by_peer: FxHashMap<PeerID, Vec<Change>> = FxHashMap::default();
+    for blob in blobs {
+        let parsed = parse_header_and_body(blob)?;
+        if parsed.mode == EncodeMode::ShallowSnapshot {
+            return Err(LoroError::ArgErr(
+                "Cannot merge a shallow snapshot, because its history is trimmed".into(),
+            ));
+        }
+
+        for change in encode_reordered::decode_changes_of_updates(&oplog, parsed.body)? {
+            changes_by_peer
+                .entry(change.id.peer)
+                .or_default()
+                .push(change);
+        }
+    }
+
+    // the counter ranges of each peer, without the overlapped parts
+    let mut ranges: FxHashMap<PeerID, Vec<Range<Counter>>> = FxHashMap::default();
+    let mut changes = Vec::new();
+    for (peer, mut peer_changes) in changes_by_peer {
+        peer_changes.sort_by_key(|c| c.id.counter);
+        let peer_ranges = ranges.entry(peer).or_default();
+        for change in peer_changes {
+            let change = match peer_ranges.last_mut() {
+                Some(last) if change.ctr_end() <= last.end => continue,
+                Some(last) if change.id.counter <= last.end => {
+                    let change =
+                        change.slice((last.end - change.id.counter) as usize, change.atom_len());
+                    last.end = change.ctr_end();
+                    change
+                }
+                _ => {
+                    peer_ranges.push(change.id.counter..change.ctr_end());
+                    change
+                }
+            };
+            changes.push(change);
+        }
+    }
+
+    let mut missing_deps = Vec::new();
+    let mut start_frontiers: FxHashMap<PeerID, Counter> = FxHashMap::default();
+    for (&peer, peer_ranges) in ranges.iter() {
+        for pair in peer_ranges.windows(2) {
+            missing_deps.push(ID::new(peer, pair[1].start - 1));
+        }
+    }
+    for change in changes.iter() {
+        for dep in change.deps.iter() {
+            let peer_ranges = ranges.get(&dep.peer).map(|x| x.as_slice()).unwrap_or(&[]);
+            if peer_ranges.iter().any(|r| r.contains(&dep.counter)) {
+                continue;
+            }
+
+            if peer_ranges.first().is_some_and(|r| r.start <= dep.counter) {
+                missing_deps.push(*dep);
+            } else {
+                let counter = start_frontiers.entry(dep.peer).or_insert(dep.counter);
+                *counter = (*counter).max(dep.counter);
+            }
+        }
+    }
+    // the changes of a peer must start right after its deps outside the blobs
+    for (&peer, peer_ranges) in ranges.iter() {
+        let start = peer_ranges[0].start;
+        if start > 0 && start_frontiers.get(&peer) != Some(&(start - 1)) {
+            missing_deps.push(ID::new(peer, start - 1));
+        }
+    }
+
+    if !missing_deps.is_empty() {
+        missing_deps.sort();
+        missing_deps.dedup();
+        return Err(LoroError::MergeWithMissingDeps(missing_deps));
+    }
+
+    let start_vv: VersionVector = start_frontiers
+        .iter()
+        .map(|(&peer, &counter)| (peer, counter + 1))
+        .collect();
+    let start_frontiers: Frontiers = start_frontiers
+        .into_iter()
+        .map(|(peer, counter)| ID::new(peer, counter))
+        .collect();
+    oplog.init_unknown_root(start_vv, start_frontiers);
+    let lamports = calc_lamports_of_new_changes(&changes, |id| oplog.dag.get_lamport(id));
+    let mut changes = changes
+        .into_iter()
+        .zip(lamports)
+        .map(|(mut change, lamport)| {
+            change.lamport = lamport.ok_or(LoroError::DecodeDataCorruptionError)?;
+            Ok(change)
+        })
+        .collect::<LoroResult<Vec<_>>>()?;
+    // the deps of a change always have smaller lamports
+    changes.sort_by_key(|change| (change.lamport, change.id.peer));
+    for change in changes {
+        oplog.import_local_change(change)?;
+    }
+
+    Ok(encode_oplog(&oplog, &Default::default(), EncodeMode::Rle))
+}
+
+pub(crate) fn decode_json_updates(
+    oplog: &mut OpLog,
+    json: json_schema::JsonSchema,
+) -> Result<(), LoroError> {
+    let changes = json_schema::decode_json_changes(oplog, json)?;
+    encode_reordered::import_changes(oplog, changes)
+}
+
 pub(crate) struct ParsedHeaderAndBody<'a> {
     pub checksum: [u8; 16],
     pub checksum_body: &'a [u8],
@@ -222,6 +359,20 @@ pub(crate) fn export_snapshot(doc: &LoroDoc) -> Vec<u8> {
     encode_header_and_body(EncodeMode::Snapshot, body)
 }
 
+/// Export the state of `root_state` and the updates after it.
+///
+/// `root_state` should be a state at the version returned by [OpLog::calc_shallow_root].
+/// If `container_filter` is given, only the states of the containers that pass it are
+/// exported.
+pub(crate) fn export_shallow_snapshot(
+    oplog: &OpLog,
+    root_state: &DocState,
+    container_filter: Option<&dyn Fn(ContainerIdx) -> bool>,
+) -> Vec<u8> {
+    let body = encode_reordered::encode_shallow_snapshot(oplog, root_state, container_filter);
+    encode_header_and_body(EncodeMode::ShallowSnapshot, body)
+}
+
 pub(crate) fn decode_snapshot(
     doc: &LoroDoc,
     mode: EncodeMode,
@@ -229,6 +380,7 @@ pub(crate) fn decode_snapshot(
 ) -> Result<(), LoroError> {
     match mode {
         EncodeMode::Snapshot => encode_reordered::decode_snapshot(doc, body),
+        EncodeMode::ShallowSnapshot => encode_reordered::decode_shallow_snapshot(doc, body),
         _ => unreachable!(),
     }
 }
@@ -260,3 +412,46 @@ impl LoroDoc {
         encode_reordered::decode_import_blob_meta(blob)
     }
 }
+
+/// The result of an import.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportStatus {
+    /// The changes that are applied by the import.
+    ///
+    /// It includes the pending changes that become applicable after the import.
+    pub applied: Vec<IdSpan>,
+    /// The spans of the imported blob that are pending because their dependencies are
+    /// missing, including the ones that were already pending before the import.
+    pub pending: Vec<IdSpan>,
+    /// The dependencies that the pending spans of this import are waiting for.
+    ///
+    /// They are neither in the oplog nor pending. The pending changes in between are
+    /// followed, so importing these makes the pending spans applicable, unless they are
+    /// waiting for more.
+    pub missing_deps: Vec<ID>,
+}
+
+/// The pending changes of a doc, see [LoroDoc::pending_changes_summary].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PendingChangesSummary {
+    /// All the pending changes.
+    pub pending: Vec<IdSpan>,
+    /// The dependencies that the pending changes are waiting for.
+    ///
+    /// They are neither in the oplog nor pending. Importing the changes of each peer up to
+    /// these ids makes the pending changes applicable, unless they are waiting for more.
+    pub missing_deps: Vec<ID>,
+}
+
+/// The effect of importing a blob, see [LoroDoc::preview_import].
+#[derive(Debug, Clone)]
+pub struct ImportPreview {
+    /// The changes in the blob that would be pending because their dependencies are missing.
+    ///
+    /// They stay in the doc without taking effect until the missing changes are imported.
+    pub pending: Vec<IdSpan>,
+    /// The diffs the import would emit to the subscribers of the doc.
+    ///
+    /// It's empty if the doc is detached, because importing doesn't change its state then.
+    pub diff: Vec<DocDiff>,
+}
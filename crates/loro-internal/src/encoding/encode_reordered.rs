@@ -20,8 +20,9 @@ use crate::{
         StateSnapshotDecodeContext,
     },
     op::{Op, OpWithId, SliceRange},
-    state::ContainerState,
-    version::Frontiers,
+    oplog::{ShallowRoot, ShallowRootState},
+    state::{ContainerState, State},
+    version::{Frontiers, ImVersionVector},
     DocState, LoroDoc, OpLog, VersionVector,
 };
 
@@ -41,6 +42,12 @@ const MAX_DECODED_SIZE: usize = 1 << 30;
 const MAX_COLLECTION_SIZE: usize = 1 << 28;
 
 pub(crate) fn encode_updates(oplog: &OpLog, vv: &VersionVector) -> Vec<u8> {
+    // the history before the shallow root is trimmed, so we cannot export it
+    let mut vv = Cow::Borrowed(vv);
+    if oplog.is_shallow() && !vv.includes_vv(oplog.shallow_since_vv()) {
+        vv.to_mut().merge(oplog.shallow_since_vv());
+    }
+
     // skip the ops that current oplog does not have
     let actual_start_vv: VersionVector = vv
         .iter()
@@ -77,6 +84,7 @@ pub(crate) fn encode_updates(oplog: &OpLog, vv: &VersionVector) -> Vec<u8> {
     let mut dep_arena = arena::DepsArena::default();
     let mut value_writer = ValueWriter::new();
     let mut ops: Vec<TempOp> = Vec::new();
+    let mut msg_arena = Vec::new();
     let arena = &oplog.arena;
     let changes = encode_changes(
         &diff_changes,
@@ -85,6 +93,7 @@ pub(crate) fn encode_updates(oplog: &OpLog, vv: &VersionVector) -> Vec<u8> {
         &mut |op| ops.push(op),
         &mut key_register,
         &container_idx2index,
+        &mut msg_arena,
     );
 
     ops.sort_by(move |a, b| {
@@ -129,6 +138,7 @@ pub(crate) fn encode_updates(oplog: &OpLog, vv: &VersionVector) -> Vec<u8> {
             key_register.unwrap_vec(),
             dep_arena,
             &[],
+            &msg_arena,
         )),
         start_frontiers: frontiers,
     };
@@ -137,6 +147,12 @@ pub(crate) fn encode_updates(oplog: &OpLog, vv: &VersionVector) -> Vec<u8> {
 }
 
 pub(crate) fn decode_updates(oplog: &mut OpLog, bytes: &[u8]) -> LoroResult<()> {
+    let changes = decode_changes_of_updates(oplog, bytes)?;
+    import_changes(oplog, changes)
+}
+
+/// Decode the changes of an update blob without importing them
+pub(crate) fn decode_changes_of_updates(oplog: &OpLog, bytes: &[u8]) -> LoroResult<Vec<Change>> {
     let iter = serde_columnar::iter_from_bytes::<EncodedDoc>(bytes)?;
     let DecodedArenas {
         peer_ids,
@@ -144,6 +160,7 @@ pub(crate) fn decode_updates(oplog: &mut OpLog, bytes: &[u8]) -> LoroResult<()>
         keys,
         deps,
         state_blob_arena: _,
+        msg_arena,
     } = decode_arena(&iter.arenas)?;
     let ops_map = extract_ops(
         &iter.raw_values,
@@ -157,8 +174,18 @@ pub(crate) fn decode_updates(oplog: &mut OpLog, bytes: &[u8]) -> LoroResult<()>
     )?
     .ops_map;
 
-    let changes = decode_changes(iter.changes, iter.start_counters, peer_ids, deps, ops_map)?;
+    decode_changes(
+        iter.changes,
+        iter.start_counters,
+        peer_ids,
+        deps,
+        msg_arena,
+        ops_map,
+    )
+}
 
+/// Import the decoded changes. The changes whose deps are missing become pending.
+pub(crate) fn import_changes(oplog: &mut OpLog, changes: Vec<Change>) -> LoroResult<()> {
     let (latest_ids, pending_changes) = import_changes_to_oplog(changes, oplog)?;
     if oplog.try_apply_pending(latest_ids).should_update && !oplog.batch_importing {
         oplog.dag.refresh_frontiers();
@@ -171,7 +198,15 @@ pub(crate) fn decode_updates(oplog: &mut OpLog, bytes: &[u8]) -> LoroResult<()>
 pub fn decode_import_blob_meta(bytes: &[u8]) -> LoroResult<ImportBlobMetadata> {
     let parsed = parse_header_and_body(bytes)?;
     let is_snapshot = parsed.mode.is_snapshot();
-    let iterators = serde_columnar::iter_from_bytes::<EncodedDoc>(parsed.body)?;
+    let shallow_snapshot: EncodedShallowSnapshot;
+    let body = if parsed.mode == super::EncodeMode::ShallowSnapshot {
+        // only the updates after the shallow root are counted
+        shallow_snapshot = serde_columnar::from_bytes(parsed.body)?;
+        &shallow_snapshot.updates
+    } else {
+        parsed.body
+    };
+    let iterators = serde_columnar::iter_from_bytes::<EncodedDoc>(body)?;
     let DecodedArenas { peer_ids, .. } = decode_arena(&iterators.arenas)?;
     let start_vv: VersionVector = iterators
         .start_counters
@@ -228,6 +263,12 @@ fn import_changes_to_oplog(
     changes: Vec<Change>,
     oplog: &mut OpLog,
 ) -> Result<(Vec<ID>, Vec<Change>), LoroError> {
+    if oplog.is_shallow() {
+        check_changes_not_depend_on_trimmed_history(&changes, oplog)?;
+    }
+
+    oplog.validate_changes_to_import(&changes)?;
+
     let mut pending_changes = Vec::new();
     let mut latest_ids = Vec::new();
     for mut change in changes {
@@ -236,6 +277,20 @@ fn import_changes_to_oplog(
             continue;
         }
 
+        let shallow_end = oplog
+            .shallow_since_vv()
+            .get(&change.id.peer)
+            .copied()
+            .unwrap_or(0);
+        if change.id.counter < shallow_end {
+            // the part before the shallow root is trimmed in this doc
+            change = rle::Sliceable::slice(
+                &change,
+                (shallow_end - change.id.counter) as usize,
+                change.atom_len(),
+            );
+        }
+
         latest_ids.push(change.id_last());
         // calc lamport or pending if its deps are not satisfied
         match oplog.dag.get_change_lamport_from_deps(&change.deps) {
@@ -266,11 +321,37 @@ fn import_changes_to_oplog(
     Ok((latest_ids, pending_changes))
 }
 
+/// The changes after the shallow root can only depend on the last ops of the root,
+/// because the ops before them are trimmed.
+fn check_changes_not_depend_on_trimmed_history(
+    changes: &[Change],
+    oplog: &OpLog,
+) -> LoroResult<()> {
+    let root_vv = oplog.shallow_since_vv();
+    for change in changes {
+        if change.id.counter < root_vv.get(&change.id.peer).copied().unwrap_or(0) {
+            continue;
+        }
+
+        for dep in change.deps.iter() {
+            if root_vv
+                .get(&dep.peer)
+                .is_some_and(|&end| dep.counter + 1 < end)
+            {
+                return Err(LoroError::ImportUpdatesThatDependsOnTrimmedHistory);
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn decode_changes<'a>(
     encoded_changes: IterableEncodedChange<'_>,
     mut counters: Vec<i32>,
     peer_ids: arena::PeerIdArena,
     mut deps: impl Iterator<Item = Result<arena::EncodedDep, ColumnarError>> + 'a,
+    mut msg_arena: &[u8],
     mut ops_map: std::collections::HashMap<
         u64,
         Vec<Op>,
@@ -285,12 +366,25 @@ fn decode_changes<'a>(
             timestamp,
             deps_len,
             dep_on_self,
-            msg_len: _,
+            msg_len,
         } = encoded_change?;
         if peer_ids.peer_ids.len() <= peer_idx || counters.len() <= peer_idx {
             return Err(LoroError::DecodeDataCorruptionError);
         }
 
+        if msg_len < 0 || msg_arena.len() < msg_len as usize {
+            return Err(LoroError::DecodeDataCorruptionError);
+        }
+
+        let (msg, rest) = msg_arena.split_at(msg_len as usize);
+        msg_arena = rest;
+        let message = if msg.is_empty() {
+            None
+        } else {
+            let msg = std::str::from_utf8(msg).map_err(|_| LoroError::DecodeDataCorruptionError)?;
+            Some(msg.into())
+        };
+
         let counter = counters[peer_idx];
         counters[peer_idx] += len as Counter;
         let peer = peer_ids.peer_ids[peer_idx];
@@ -300,6 +394,7 @@ fn decode_changes<'a>(
             deps: Frontiers::with_capacity((deps_len + if dep_on_self { 1 } else { 0 }) as usize),
             lamport: 0,
             timestamp,
+            message,
             has_dependents: false,
         };
 
@@ -508,6 +603,7 @@ pub(crate) fn encode_snapshot(oplog: &OpLog, state: &DocState, vv: &VersionVecto
         state_bytes.extend(bytes);
     }
 
+    let mut msg_arena = Vec::new();
     let changes = encode_changes(
         &diff_changes,
         &mut dep_arena,
@@ -517,6 +613,7 @@ pub(crate) fn encode_snapshot(oplog: &OpLog, state: &DocState, vv: &VersionVecto
         },
         &mut key_register,
         &container_idx2index,
+        &mut msg_arena,
     );
 
     let ops: Vec<TempOp> = calc_sorted_ops_for_snapshot(origin_ops, pos_mapping_heap);
@@ -549,6 +646,7 @@ pub(crate) fn encode_snapshot(oplog: &OpLog, state: &DocState, vv: &VersionVecto
             key_register.unwrap_vec(),
             dep_arena,
             &state_bytes,
+            &msg_arena,
         )),
         start_frontiers: Vec::new(),
     };
@@ -712,6 +810,7 @@ pub(crate) fn decode_snapshot(doc: &LoroDoc, bytes: &[u8]) -> LoroResult<()> {
         keys,
         deps,
         state_blob_arena,
+        msg_arena,
     } = decode_arena(&iter.arenas)?;
     let ExtractedOps {
         ops_map,
@@ -728,7 +827,14 @@ pub(crate) fn decode_snapshot(doc: &LoroDoc, bytes: &[u8]) -> LoroResult<()> {
         true,
     )?;
 
-    let changes = decode_changes(iter.changes, iter.start_counters, peer_ids, deps, ops_map)?;
+    let changes = decode_changes(
+        iter.changes,
+        iter.start_counters,
+        peer_ids,
+        deps,
+        msg_arena,
+        ops_map,
+    )?;
     let (new_ids, pending_changes) = import_changes_to_oplog(changes, &mut oplog)?;
 
     for op in ops.iter_mut() {
@@ -828,7 +934,7 @@ fn decode_snapshot_states(
                 blob: state_bytes,
                 mode: crate::encoding::EncodeMode::Snapshot,
             },
-        );
+        )?;
     }
 
     let s = take(&mut state.states);
@@ -836,6 +942,384 @@ fn decode_snapshot_states(
     Ok(())
 }
 
+pub(crate) fn encode_shallow_snapshot(
+    oplog: &OpLog,
+    root_state: &DocState,
+    container_filter: Option<&dyn Fn(ContainerIdx) -> bool>,
+) -> Vec<u8> {
+    let root_vv = oplog.dag.frontiers_to_vv(&root_state.frontiers).unwrap();
+    let root = encode_shallow_root(oplog, root_state, &root_vv, container_filter);
+    let updates = encode_updates(oplog, &root_vv);
+    serde_columnar::to_vec(&EncodedShallowSnapshot {
+        root: Cow::Owned(root),
+        updates: Cow::Owned(updates),
+    })
+    .unwrap()
+}
+
+/// Encode the states at the shallow root and the ops they are made of.
+///
+/// Unlike the ops in a normal snapshot, these ops are not included in any change,
+/// so their lamports are encoded as well. If `container_filter` is given, only the
+/// containers that pass it are encoded.
+fn encode_shallow_root(
+    oplog: &OpLog,
+    state: &DocState,
+    vv: &VersionVector,
+    container_filter: Option<&dyn Fn(ContainerIdx) -> bool>,
+) -> Vec<u8> {
+    let mut peer_register: ValueRegister<PeerID> = ValueRegister::new();
+    let mut key_register: ValueRegister<InternalString> = ValueRegister::new();
+    let ExtractedContainer {
+        containers,
+        cid_idx_pairs: c_pairs,
+        idx_to_index: container_idx2index,
+    } = extract_containers_in_order(
+        // the root containers whose states are empty at the root should also be kept
+        &mut state
+            .iter()
+            .map(|x| x.container_idx())
+            .chain(oplog.arena.root_containers())
+            .filter(|&x| container_filter.is_none_or(|f| f(x))),
+        &oplog.arena,
+    );
+    let mut cid_register: ValueRegister<ContainerID> = ValueRegister::from_existing(containers);
+    let mut dep_arena = arena::DepsArena::default();
+    let mut value_writer = ValueWriter::new();
+    let mut ops: Vec<TempOp> = Vec::new();
+    let mut push_op = |op: OpWithId, container_index: u32, peer_register: &mut ValueRegister<_>| {
+        ops.push(TempOp {
+            peer_idx: peer_register.register(&op.peer) as u32,
+            peer_id: op.peer,
+            container_index,
+            prop_that_used_for_sort: 0,
+            lamport: op.lamport.unwrap(),
+            op: Cow::Owned(op.op),
+        });
+    };
+
+    let mut states = Vec::new();
+    let mut state_bytes = Vec::new();
+    for (_, c_idx) in c_pairs.iter() {
+        let container_index = *container_idx2index.get(c_idx).unwrap() as u32;
+        let state = match state.get_state(*c_idx) {
+            Some(state) if !state.is_state_empty() => state,
+            _ => {
+                states.push(EncodedStateInfo {
+                    container_index,
+                    op_len: 0,
+                    state_bytes_len: 0,
+                });
+                continue;
+            }
+        };
+
+        let mut spans = Vec::new();
+        let bytes = state.encode_snapshot(super::StateSnapshotEncoder {
+            check_idspan: &|_id_span| Ok(()),
+            encoder_by_op: &mut |_op| unreachable!(),
+            record_idspan: &mut |id_span| spans.push(id_span),
+            mode: super::EncodeMode::ShallowSnapshot,
+        });
+        let mut op_len = 0;
+        for span in spans {
+            for op in oplog.get_ops_by_idlp_span(span) {
+                op_len += op.op.atom_len();
+                push_op(op, container_index, &mut peer_register);
+            }
+        }
+
+        states.push(EncodedStateInfo {
+            container_index,
+            op_len: op_len as u32,
+            state_bytes_len: bytes.len() as u32,
+        });
+        state_bytes.extend(bytes);
+    }
+
+    // The value of a movable list element comes from its last set op or its insert op,
+    // which may not be one of the ops that form the slots.
+    for (_, c_idx) in c_pairs.iter() {
+        let Some(State::MovableListState(list)) = state.get_state(*c_idx) else {
+            continue;
+        };
+
+        let container_index = *container_idx2index.get(c_idx).unwrap() as u32;
+        let group = oplog.op_groups.get_movable_list(c_idx);
+        let slot_ids: FxHashSet<ID> = list.iter_slot_and_elem_ids().map(|(id, _)| id).collect();
+        let elem_ids: FxHashSet<ID> = list
+            .iter_slot_and_elem_ids()
+            .map(|(_, elem)| elem)
+            .collect();
+        for elem in elem_ids {
+            let id = match group.and_then(|g| g.last_set(elem, vv)) {
+                Some(set) => ID::new(set.peer, set.counter),
+                None if slot_ids.contains(&elem) => continue,
+                None => elem,
+            };
+
+            if let Some(op) = oplog.get_op_with_id(id) {
+                push_op(op, container_index, &mut peer_register);
+            }
+        }
+    }
+
+    let lamports = ops.iter().map(|x| x.lamport).collect();
+    let (encoded_ops, del_starts) = encode_ops(
+        ops,
+        &oplog.arena,
+        &mut value_writer,
+        &mut key_register,
+        &mut cid_register,
+        &mut peer_register,
+    );
+
+    let mut nodes = Vec::with_capacity(vv.len());
+    for (&peer, &end) in vv.iter() {
+        let id = ID::new(peer, end - 1);
+        let mut vv_len = 0;
+        for (&p, &c) in oplog.dag.get_vv(id).unwrap().iter() {
+            if p != peer && c > 0 {
+                dep_arena.push(peer_register.register(&p), c);
+                vv_len += 1;
+            }
+        }
+
+        nodes.push(EncodedShallowRootNode {
+            peer_idx: peer_register.register(&peer) as u32,
+            counter: id.counter,
+            lamport: oplog.dag.get_lamport(&id).unwrap(),
+            vv_len,
+        });
+    }
+
+    let frontiers = state
+        .frontiers
+        .iter()
+        .map(|x| (peer_register.register(&x.peer), x.counter))
+        .collect();
+    let container_arena = ContainerArena::from_containers(
+        cid_register.unwrap_vec(),
+        &mut peer_register,
+        &mut key_register,
+    );
+
+    let root = EncodedShallowRoot {
+        ops: encoded_ops,
+        delete_starts: del_starts,
+        lamports,
+        states,
+        nodes,
+        frontiers,
+        raw_values: Cow::Owned(value_writer.finish()),
+        arenas: Cow::Owned(encode_arena(
+            peer_register.unwrap_vec(),
+            container_arena,
+            key_register.unwrap_vec(),
+            dep_arena,
+            &state_bytes,
+            &[],
+        )),
+    };
+
+    serde_columnar::to_vec(&root).unwrap()
+}
+
+pub(crate) fn decode_shallow_snapshot(doc: &LoroDoc, bytes: &[u8]) -> LoroResult<()> {
+    let snapshot: EncodedShallowSnapshot = serde_columnar::from_bytes(bytes)?;
+    let changes = {
+        let mut state = doc.app_state().try_lock().map_err(|_| {
+            LoroError::DecodeError(
+                "decode_shallow_snapshot: failed to lock app state"
+                    .to_string()
+                    .into_boxed_str(),
+            )
+        })?;
+
+        state.check_before_decode_snapshot()?;
+        let mut oplog = doc.oplog().try_lock().map_err(|_| {
+            LoroError::DecodeError(
+                "decode_shallow_snapshot: failed to lock oplog"
+                    .to_string()
+                    .into_boxed_str(),
+            )
+        })?;
+
+        if !oplog.is_empty() {
+            unimplemented!("You can only import snapshot to a empty loro doc now");
+        }
+
+        let (root, nodes) = decode_shallow_root(&oplog, &snapshot.root)?;
+        let changes = decode_changes_of_updates(&oplog, &snapshot.updates)?;
+        // the doc must stay empty if any part of the snapshot is rejected
+        oplog.validate_shallow_snapshot_to_import(&root, &nodes, &changes)?;
+        oplog.init_shallow_root(Arc::new(root), nodes);
+        state.init_with_shallow_root(&oplog)?;
+        changes
+    };
+
+    // TODO: Fix this origin value
+    doc.update_oplog_and_apply_delta_to_state_if_needed(
+        |oplog| {
+            // the changes have been validated with the shallow root
+            let validator = oplog.import_validator.take();
+            let result = import_changes(oplog, changes);
+            oplog.import_validator = validator;
+            result
+        },
+        "".into(),
+    )
+}
+
+/// Import the updates after the shallow root into a doc that already has the history
+pub(crate) fn decode_updates_in_shallow_snapshot(
+    oplog: &mut OpLog,
+    bytes: &[u8],
+) -> LoroResult<()> {
+    let snapshot: EncodedShallowSnapshot = serde_columnar::from_bytes(bytes)?;
+    decode_updates(oplog, &snapshot.updates)
+}
+
+type ShallowRootNode = (ID, Lamport, ImVersionVector);
+
+fn decode_shallow_root(
+    oplog: &OpLog,
+    bytes: &[u8],
+) -> LoroResult<(ShallowRoot, Vec<ShallowRootNode>)> {
+    let iter = serde_columnar::iter_from_bytes::<EncodedShallowRoot>(bytes)?;
+    let DecodedArenas {
+        peer_ids,
+        containers,
+        keys,
+        mut deps,
+        state_blob_arena,
+        ..
+    } = decode_arena(&iter.arenas)?;
+    let ExtractedOps {
+        mut ops,
+        containers,
+        ..
+    } = extract_ops(
+        &iter.raw_values,
+        iter.ops,
+        iter.delete_starts,
+        &oplog.arena,
+        &containers,
+        &keys,
+        &peer_ids,
+        true,
+    )?;
+    if ops.len() != iter.lamports.len() {
+        return Err(LoroError::DecodeDataCorruptionError);
+    }
+
+    for (op, lamport) in ops.iter_mut().zip(iter.lamports.iter()) {
+        op.lamport = Some(*lamport);
+    }
+
+    // the containers with empty states don't have any op
+    for container in containers.iter() {
+        oplog.arena.register_container(container);
+    }
+
+    let mut states = Vec::new();
+    let mut ops_index = 0;
+    let mut state_blob_index = 0;
+    for encoded_state in iter.states {
+        let EncodedStateInfo {
+            container_index,
+            op_len,
+            state_bytes_len,
+        } = encoded_state?;
+        if op_len == 0 && state_bytes_len == 0 {
+            continue;
+        }
+
+        let state_blob_end = state_blob_index + state_bytes_len as usize;
+        if container_index as usize >= containers.len() || state_blob_arena.len() < state_blob_end {
+            return Err(LoroError::DecodeDataCorruptionError);
+        }
+
+        let mut state_ops = Vec::new();
+        let mut len = 0;
+        while len < op_len as usize {
+            let Some(op) = ops.get(ops_index) else {
+                return Err(LoroError::DecodeDataCorruptionError);
+            };
+
+            len += op.op.atom_len();
+            state_ops.push(op.clone());
+            ops_index += 1;
+        }
+
+        states.push(ShallowRootState {
+            container: containers[container_index as usize].clone(),
+            ops: state_ops,
+            blob: state_blob_arena[state_blob_index..state_blob_end].to_vec(),
+        });
+        state_blob_index = state_blob_end;
+    }
+
+    let mut root_ops: FxHashMap<PeerID, Vec<OpWithId>> = FxHashMap::default();
+    for op in ops {
+        root_ops.entry(op.peer).or_default().push(op);
+    }
+
+    for ops in root_ops.values_mut() {
+        ops.sort_by_key(|x| x.op.counter);
+    }
+
+    let get_peer = |peer_idx: usize| {
+        peer_ids
+            .peer_ids
+            .get(peer_idx)
+            .copied()
+            .ok_or(LoroError::DecodeDataCorruptionError)
+    };
+    let mut vv = VersionVector::default();
+    let mut nodes = Vec::new();
+    for node in iter.nodes {
+        let EncodedShallowRootNode {
+            peer_idx,
+            counter,
+            lamport,
+            vv_len,
+        } = node?;
+        let peer = get_peer(peer_idx as usize)?;
+        let mut node_vv = ImVersionVector::default();
+        for _ in 0..vv_len {
+            let Some(dep) = deps.next() else {
+                return Err(LoroError::DecodeDataCorruptionError);
+            };
+
+            let dep = dep?;
+            node_vv.insert(get_peer(dep.peer_idx)?, dep.counter);
+        }
+
+        if counter > 0 {
+            node_vv.insert(peer, counter);
+        }
+
+        vv.insert(peer, counter + 1);
+        nodes.push((ID::new(peer, counter), lamport, node_vv));
+    }
+
+    let frontiers = iter
+        .frontiers
+        .iter()
+        .map(|&(peer_idx, counter)| Ok(ID::new(get_peer(peer_idx)?, counter)))
+        .collect::<LoroResult<_>>()?;
+    Ok((
+        ShallowRoot {
+            vv,
+            frontiers,
+            ops: root_ops,
+            states,
+        },
+        nodes,
+    ))
+}
+
 mod encode {
     use fxhash::FxHashMap;
     use loro_common::{ContainerID, ContainerType, HasId, PeerID, ID};
@@ -846,7 +1330,9 @@ mod encode {
     use crate::{
         change::{Change, Lamport},
         container::idx::ContainerIdx,
-        encoding::encode_reordered::value::{EncodedTreeMove, ValueWriter},
+        encoding::encode_reordered::value::{
+            EncodedListMove, EncodedListSet, EncodedTreeMove, ValueWriter,
+        },
         op::Op,
         InternalString,
     };
@@ -979,6 +1465,7 @@ mod encode {
         push_op: &mut impl FnMut(TempOp<'a>),
         key_register: &mut ValueRegister<InternalString>,
         container_idx2index: &FxHashMap<ContainerIdx, usize>,
+        msg_arena: &mut Vec<u8>,
     ) -> Vec<EncodedChange> {
         let mut changes: Vec<EncodedChange> = Vec::with_capacity(diff_changes.len());
         for change in diff_changes.iter() {
@@ -994,13 +1481,15 @@ mod encode {
             }
 
             let peer_idx = peer_register.register(&change.id.peer);
+            let msg = change.message.as_deref().unwrap_or_default();
+            msg_arena.extend_from_slice(msg.as_bytes());
             changes.push(EncodedChange {
                 dep_on_self,
                 deps_len,
                 peer_idx,
                 len: change.atom_len(),
                 timestamp: change.timestamp,
-                msg_len: 0,
+                msg_len: msg.len() as i32,
             });
 
             for op in change.ops().iter() {
@@ -1114,12 +1603,15 @@ mod encode {
                     *start as i32
                 }
                 crate::container::list::list_op::InnerListOp::StyleEnd => 0,
+                crate::container::list::list_op::InnerListOp::Move { to, .. } => *to as i32,
+                crate::container::list::list_op::InnerListOp::Set { .. } => 0,
             },
             crate::op::InnerContent::Map(map) => {
                 let key = register_key.register(&map.key);
                 key as i32
             }
             crate::op::InnerContent::Tree(..) => 0,
+            crate::op::InnerContent::Counter(_) => 0,
         }
     }
 
@@ -1131,6 +1623,7 @@ mod encode {
                 key as i32
             }
             crate::op::InnerContent::Tree(..) => 0,
+            crate::op::InnerContent::Counter(_) => 0,
         }
     }
 
@@ -1147,7 +1640,10 @@ mod encode {
         match &op.content {
             crate::op::InnerContent::List(list) => match list {
                 crate::container::list::list_op::InnerListOp::Insert { slice, .. } => {
-                    assert_eq!(op.container.get_type(), ContainerType::List);
+                    assert!(matches!(
+                        op.container.get_type(),
+                        ContainerType::List | ContainerType::MovableList
+                    ));
                     let value = arena.get_values(slice.0.start as usize..slice.0.end as usize);
                     value_writer.write_value_content(&value.into(), register_key, register_cid);
                     ValueKind::Array
@@ -1194,6 +1690,34 @@ mod encode {
                     ValueKind::MarkStart
                 }
                 crate::container::list::list_op::InnerListOp::StyleEnd => ValueKind::Null,
+                crate::container::list::list_op::InnerListOp::Move {
+                    from,
+                    to: _,
+                    elem_id,
+                } => {
+                    value_writer.write(
+                        &Value::ListMove(EncodedListMove {
+                            from: *from as usize,
+                            elem_peer_idx: register_peer.register(&elem_id.peer),
+                            elem_cnt: elem_id.counter as usize,
+                        }),
+                        register_key,
+                        register_cid,
+                    );
+                    ValueKind::ListMove
+                }
+                crate::container::list::list_op::InnerListOp::Set { elem_id, value } => {
+                    value_writer.write(
+                        &Value::ListSet(EncodedListSet {
+                            elem_peer_idx: register_peer.register(&elem_id.peer),
+                            elem_cnt: elem_id.counter as usize,
+                            value: value.clone(),
+                        }),
+                        register_key,
+                        register_cid,
+                    );
+                    ValueKind::ListSet
+                }
             },
             crate::op::InnerContent::Map(map) => {
                 assert_eq!(op.container.get_type(), ContainerType::Map);
@@ -1208,6 +1732,11 @@ mod encode {
                 value_writer.write(&Value::TreeMove(op), register_key, register_cid);
                 ValueKind::TreeMove
             }
+            crate::op::InnerContent::Counter(x) => {
+                assert_eq!(op.container.get_type(), ContainerType::Counter);
+                value_writer.write(&Value::F64(*x), register_key, register_cid);
+                ValueKind::F64
+            }
         }
     }
 }
@@ -1293,7 +1822,7 @@ fn decode_op(
                 }
             }
         }
-        ContainerType::List => {
+        ContainerType::List | ContainerType::MovableList => {
             let pos = prop as usize;
             match kind {
                 ValueKind::Array => {
@@ -1328,6 +1857,25 @@ fn decode_op(
                         ),
                     )
                 }
+                ValueKind::ListMove => {
+                    let op = value_reader.read_list_move()?;
+                    crate::op::InnerContent::List(
+                        crate::container::list::list_op::InnerListOp::Move {
+                            from: op.from as u32,
+                            to: pos as u32,
+                            elem_id: op.elem_id(peers)?,
+                        },
+                    )
+                }
+                ValueKind::ListSet => {
+                    let op = value_reader.read_list_set(&keys.keys, id)?;
+                    crate::op::InnerContent::List(
+                        crate::container::list::list_op::InnerListOp::Set {
+                            elem_id: op.elem_id(peers)?,
+                            value: op.value,
+                        },
+                    )
+                }
                 _ => unreachable!(),
             }
         }
@@ -1338,6 +1886,17 @@ fn decode_op(
             }
             _ => unreachable!(),
         },
+        ContainerType::Counter => match kind {
+            ValueKind::F64 => {
+                let value = value_reader.read_value_content(kind, &keys.keys, id)?;
+                crate::op::InnerContent::Counter(
+                    *value
+                        .as_double()
+                        .ok_or(LoroError::DecodeDataCorruptionError)?,
+                )
+            }
+            _ => unreachable!(),
+        },
     };
 
     Ok(content)
@@ -1434,11 +1993,63 @@ struct EncodedDoc<'a> {
     /// - `key_arena`
     /// - `deps_arena`
     /// - `state_arena`
+    /// - `msg_arena`, the commit messages of the changes
     /// - `others`, left for future use
     #[columnar(borrow)]
     arenas: Cow<'a, [u8]>,
 }
 
+/// The body of a shallow snapshot
+#[columnar(ser, de)]
+struct EncodedShallowSnapshot<'a> {
+    /// The encoded [EncodedShallowRoot]
+    #[columnar(borrow)]
+    root: Cow<'a, [u8]>,
+    /// The updates after the shallow root, in the same format as [EncodedDoc]
+    #[columnar(borrow)]
+    updates: Cow<'a, [u8]>,
+}
+
+/// The states at the shallow root of a shallow snapshot
+#[columnar(ser, de)]
+struct EncodedShallowRoot<'a> {
+    /// The ops that form the states, in the order of `states`.
+    ///
+    /// They are followed by the ops that the values of the movable list elements come from.
+    #[columnar(class = "vec", iter = "EncodedOp")]
+    ops: Vec<EncodedOp>,
+    #[columnar(class = "vec", iter = "EncodedDeleteStartId")]
+    delete_starts: Vec<EncodedDeleteStartId>,
+    /// The lamport of each op in `ops`
+    lamports: Vec<Lamport>,
+    #[columnar(class = "vec", iter = "EncodedStateInfo")]
+    states: Vec<EncodedStateInfo>,
+    /// The last op of each peer in the root.
+    ///
+    /// The version vectors before them are stored in the deps arena.
+    #[columnar(class = "vec", iter = "EncodedShallowRootNode")]
+    nodes: Vec<EncodedShallowRootNode>,
+    frontiers: Vec<(PeerIdx, Counter)>,
+    #[columnar(borrow)]
+    raw_values: Cow<'a, [u8]>,
+    /// The same as the arenas in [EncodedDoc]
+    #[columnar(borrow)]
+    arenas: Cow<'a, [u8]>,
+}
+
+#[columnar(vec, ser, de, iterable)]
+#[derive(Debug, Clone)]
+struct EncodedShallowRootNode {
+    #[columnar(strategy = "DeltaRle")]
+    peer_idx: u32,
+    #[columnar(strategy = "DeltaRle")]
+    counter: i32,
+    #[columnar(strategy = "DeltaRle")]
+    lamport: u32,
+    #[columnar(strategy = "DeltaRle")]
+    vv_len: u32,
+}
+
 #[columnar(vec, ser, de, iterable)]
 #[derive(Debug, Clone)]
 struct EncodedOp {
@@ -1503,7 +2114,7 @@ mod value {
     };
 
     use super::{encode::ValueRegister, MAX_COLLECTION_SIZE};
-    use crate::container::tree::tree_op::TreeOp;
+    use crate::container::tree::{fractional_index::FractionalIndex, tree_op::TreeOp};
     use num_traits::{FromPrimitive, ToPrimitive};
 
     #[allow(unused)]
@@ -1524,6 +2135,8 @@ mod value {
         Binary(&'a [u8]),
         MarkStart(MarkStart),
         TreeMove(EncodedTreeMove),
+        ListMove(EncodedListMove),
+        ListSet(EncodedListSet),
         Unknown { kind: u8, data: &'a [u8] },
     }
 
@@ -1540,6 +2153,8 @@ mod value {
         pub is_parent_null: bool,
         pub parent_peer_idx: usize,
         pub parent_cnt: usize,
+        /// The bytes of the fractional index. It's empty if the op doesn't have a position.
+        pub position: Vec<u8>,
     }
 
     impl EncodedTreeMove {
@@ -1561,6 +2176,14 @@ mod value {
                         self.parent_cnt as Counter,
                     ))
                 },
+                position: if self.position.is_empty() {
+                    None
+                } else {
+                    Some(
+                        FractionalIndex::from_bytes(self.position.clone())
+                            .ok_or(LoroError::DecodeDataCorruptionError)?,
+                    )
+                },
             })
         }
 
@@ -1571,10 +2194,48 @@ mod value {
                 is_parent_null: op.parent.is_none(),
                 parent_peer_idx: op.parent.map_or(0, |x| register_peer_id.register(&x.peer)),
                 parent_cnt: op.parent.map_or(0, |x| x.counter as usize),
+                position: op
+                    .position
+                    .as_ref()
+                    .map_or(Vec::new(), |x| x.as_bytes().to_vec()),
             }
         }
     }
 
+    pub struct EncodedListMove {
+        pub from: usize,
+        pub elem_peer_idx: usize,
+        pub elem_cnt: usize,
+    }
+
+    impl EncodedListMove {
+        pub fn elem_id(&self, peer_ids: &[u64]) -> LoroResult<ID> {
+            Ok(ID::new(
+                *(peer_ids
+                    .get(self.elem_peer_idx)
+                    .ok_or(LoroError::DecodeDataCorruptionError)?),
+                self.elem_cnt as Counter,
+            ))
+        }
+    }
+
+    pub struct EncodedListSet {
+        pub elem_peer_idx: usize,
+        pub elem_cnt: usize,
+        pub value: LoroValue,
+    }
+
+    impl EncodedListSet {
+        pub fn elem_id(&self, peer_ids: &[u64]) -> LoroResult<ID> {
+            Ok(ID::new(
+                *(peer_ids
+                    .get(self.elem_peer_idx)
+                    .ok_or(LoroError::DecodeDataCorruptionError)?),
+                self.elem_cnt as Counter,
+            ))
+        }
+    }
+
     #[derive(Debug)]
     pub enum ValueKind {
         Null = 0,
@@ -1592,6 +2253,8 @@ mod value {
         MarkStart = 12,
         TreeMove = 13,
         Binary = 14,
+        ListMove = 15,
+        ListSet = 16,
         Unknown = 65536,
     }
 
@@ -1629,6 +2292,10 @@ mod value {
                 Some(ValueKind::TreeMove)
             } else if n == ValueKind::Binary as u8 {
                 Some(ValueKind::Binary)
+            } else if n == ValueKind::ListMove as u8 {
+                Some(ValueKind::ListMove)
+            } else if n == ValueKind::ListSet as u8 {
+                Some(ValueKind::ListSet)
             } else {
                 None
             }
@@ -1665,6 +2332,8 @@ mod value {
                 ValueKind::MarkStart => ValueKind::MarkStart as i64,
                 ValueKind::TreeMove => ValueKind::TreeMove as i64,
                 ValueKind::Binary => ValueKind::Binary as i64,
+                ValueKind::ListMove => ValueKind::ListMove as i64,
+                ValueKind::ListSet => ValueKind::ListSet as i64,
                 ValueKind::Unknown => ValueKind::Unknown as i64,
             })
         }
@@ -1692,6 +2361,8 @@ mod value {
                 ValueKind::MarkStart => ValueKind::MarkStart as u8,
                 ValueKind::TreeMove => ValueKind::TreeMove as u8,
                 ValueKind::Binary => ValueKind::Binary as u8,
+                ValueKind::ListMove => ValueKind::ListMove as u8,
+                ValueKind::ListSet => ValueKind::ListSet as u8,
                 ValueKind::Unknown => panic!("Unknown value kind"),
             })
         }
@@ -1715,6 +2386,8 @@ mod value {
                 Value::MarkStart { .. } => ValueKind::MarkStart,
                 Value::TreeMove(_) => ValueKind::TreeMove,
                 Value::Binary(_) => ValueKind::Binary,
+                Value::ListMove(_) => ValueKind::ListMove,
+                Value::ListSet(_) => ValueKind::ListSet,
                 Value::Unknown { .. } => ValueKind::Unknown,
             }
         }
@@ -1823,6 +2496,8 @@ mod value {
                 Value::Map(value) => self.write_map(value, register_key, register_cid),
                 Value::MarkStart(value) => self.write_mark(value, register_key, register_cid),
                 Value::TreeMove(op) => self.write_tree_move(op),
+                Value::ListMove(op) => self.write_list_move(op),
+                Value::ListSet(op) => self.write_list_set(op, register_key, register_cid),
                 Value::Binary(value) => self.write_binary(value),
                 Value::ContainerIdx(value) => self.write_usize(*value),
                 Value::Unknown { kind: _, data: _ } => unreachable!(),
@@ -1906,6 +2581,7 @@ mod value {
         fn write_tree_move(&mut self, op: &EncodedTreeMove) {
             self.write_usize(op.subject_peer_idx);
             self.write_usize(op.subject_cnt);
+            self.write_binary(&op.position);
             self.write_u8(op.is_parent_null as u8);
             if op.is_parent_null {
                 return;
@@ -1915,6 +2591,23 @@ mod value {
             self.write_usize(op.parent_cnt);
         }
 
+        fn write_list_move(&mut self, op: &EncodedListMove) {
+            self.write_usize(op.from);
+            self.write_usize(op.elem_peer_idx);
+            self.write_usize(op.elem_cnt);
+        }
+
+        fn write_list_set(
+            &mut self,
+            op: &EncodedListSet,
+            register_key: &mut ValueRegister<InternalString>,
+            register_cid: &mut ValueRegister<ContainerID>,
+        ) {
+            self.write_usize(op.elem_peer_idx);
+            self.write_usize(op.elem_cnt);
+            self.write_value_type_and_content(&op.value, register_key, register_cid);
+        }
+
         pub(crate) fn finish(self) -> Vec<u8> {
             self.buffer
         }
@@ -2262,6 +2955,7 @@ mod value {
         pub fn read_tree_move(&mut self) -> LoroResult<EncodedTreeMove> {
             let subject_peer_idx = self.read_usize()?;
             let subject_cnt = self.read_usize()?;
+            let position = self.read_binary()?.to_vec();
             let is_parent_null = self.read_u8()? != 0;
             let mut parent_peer_idx = 0;
             let mut parent_cnt = 0;
@@ -2276,6 +2970,33 @@ mod value {
                 is_parent_null,
                 parent_peer_idx,
                 parent_cnt,
+                position,
+            })
+        }
+
+        pub fn read_list_move(&mut self) -> LoroResult<EncodedListMove> {
+            let from = self.read_usize()?;
+            let elem_peer_idx = self.read_usize()?;
+            let elem_cnt = self.read_usize()?;
+            Ok(EncodedListMove {
+                from,
+                elem_peer_idx,
+                elem_cnt,
+            })
+        }
+
+        pub fn read_list_set(
+            &mut self,
+            keys: &[InternalString],
+            id: ID,
+        ) -> LoroResult<EncodedListSet> {
+            let elem_peer_idx = self.read_usize()?;
+            let elem_cnt = self.read_usize()?;
+            let value = self.read_value_type_and_content(keys, id)?;
+            Ok(EncodedListSet {
+                elem_peer_idx,
+                elem_cnt,
+                value,
             })
         }
     }
@@ -2295,6 +3016,7 @@ mod arena {
         keys: Vec<InternalString>,
         deps: DepsArena,
         state_blob_arena: &[u8],
+        msg_arena: &[u8],
     ) -> Vec<u8> {
         let peer_ids = PeerIdArena {
             peer_ids: peer_ids_arena,
@@ -2307,6 +3029,7 @@ mod arena {
             key_arena: &key_arena.encode(),
             deps_arena: &deps.encode(),
             state_blob_arena,
+            msg_arena,
         };
 
         encoded.encode_arenas()
@@ -2318,6 +3041,7 @@ mod arena {
         pub(super) keys: KeyArena,
         pub deps: Box<dyn Iterator<Item = Result<EncodedDep, ColumnarError>> + 'a>,
         pub state_blob_arena: &'a [u8],
+        pub msg_arena: &'a [u8],
     }
 
     pub fn decode_arena(bytes: &[u8]) -> LoroResult<DecodedArenas> {
@@ -2328,6 +3052,7 @@ mod arena {
             keys: KeyArena::decode(arenas.key_arena)?,
             deps: Box::new(DepsArena::decode_iter(arenas.deps_arena)?),
             state_blob_arena: arenas.state_blob_arena,
+            msg_arena: arenas.msg_arena,
         })
     }
 
@@ -2337,6 +3062,8 @@ mod arena {
         key_arena: &'a [u8],
         deps_arena: &'a [u8],
         state_blob_arena: &'a [u8],
+        /// The commit messages of the changes, concatenated
+        msg_arena: &'a [u8],
     }
 
     impl EncodedArenas<'_> {
@@ -2346,7 +3073,9 @@ mod arena {
                     + self.container_arena.len()
                     + self.key_arena.len()
                     + self.deps_arena.len()
-                    + 4 * 4,
+                    + self.state_blob_arena.len()
+                    + self.msg_arena.len()
+                    + 4 * 6,
             );
 
             write_arena(&mut ans, self.peer_id_arena);
@@ -2354,6 +3083,7 @@ mod arena {
             write_arena(&mut ans, self.key_arena);
             write_arena(&mut ans, self.deps_arena);
             write_arena(&mut ans, self.state_blob_arena);
+            write_arena(&mut ans, self.msg_arena);
             ans
         }
 
@@ -2362,13 +3092,20 @@ mod arena {
             let (container_arena, rest) = read_arena(rest)?;
             let (key_arena, rest) = read_arena(rest)?;
             let (deps_arena, rest) = read_arena(rest)?;
-            let (state_blob_arena, _) = read_arena(rest)?;
+            let (state_blob_arena, rest) = read_arena(rest)?;
+            // the data encoded by the older versions doesn't have the message arena
+            let msg_arena = if rest.is_empty() {
+                rest
+            } else {
+                read_arena(rest)?.0
+            };
             Ok(EncodedArenas {
                 peer_id_arena,
                 container_arena,
                 key_arena,
                 deps_arena,
                 state_blob_arena,
+                msg_arena,
             })
         }
     }
@@ -0,0 +1,627 @@
+//! A human-readable JSON format of the changes.
+//!
+//! The ids are written as `counter@peer` and the container ids as `cid:...`.
+//! The values that JSON cannot represent directly are written as
+//!
+//! - container ids: strings with the prefix `🦜:`, e.g. `"🦜:cid:0@1:Text"`
+//! - binaries: `{"🦜:binary": [1, 2, 3]}`
+//! - NaN and infinite doubles: `{"🦜:double": "NaN"}`, `"Infinity"` or `"-Infinity"`
+//!
+//! The user strings and map keys that start with `🦜:` are escaped by repeating the
+//! prefix, e.g. `"🦜:🦜:binary"`, so every value round-trips losslessly.
+
+use std::{borrow::Cow, sync::Arc};
+
+use fxhash::FxHashMap;
+use loro_common::{
+    ContainerID, Counter, HasCounterSpan, LoroError, LoroResult, LoroValue, TreeID, ID,
+};
+use rle::{HasLength, RleVec, Sliceable};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    change::{Change, Lamport, Timestamp},
+    container::{
+        list::list_op::{DeleteSpan, DeleteSpanWithId, ListOp},
+        map::MapSet,
+        richtext::TextStyleInfoFlag,
+        tree::{fractional_index::FractionalIndex, tree_op::TreeOp},
+    },
+    op::{ListSlice, RawOpContent},
+    oplog::OpLog,
+    version::Frontiers,
+    VersionVector,
+};
+
+pub const JSON_SCHEMA_VERSION: u8 = 1;
+const CONTAINER_PREFIX: &str = "🦜:";
+const BINARY_KEY: &str = "🦜:binary";
+const DOUBLE_KEY: &str = "🦜:double";
+
+/// The changes of a doc in the JSON format.
+///
+/// It can be converted from and to JSON with serde.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonSchema {
+    pub schema_version: u8,
+    /// The changes in causal order
+    pub changes: Vec<JsonChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonChange {
+    /// The id of the first op in the change
+    #[serde(with = "id_str")]
+    pub id: ID,
+    pub timestamp: Timestamp,
+    #[serde(with = "ids_str")]
+    pub deps: Vec<ID>,
+    /// It's recalculated from the deps when importing
+    pub lamport: Lamport,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub msg: Option<String>,
+    pub ops: Vec<JsonOp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonOp {
+    #[serde(with = "container_id_str")]
+    pub container: ContainerID,
+    pub counter: Counter,
+    pub content: JsonOpContent,
+}
+
+/// The content of a [JsonOp]. The positions of the text ops are unicode indexes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JsonOpContent {
+    ListInsert {
+        pos: usize,
+        #[serde(with = "values_json")]
+        values: Vec<LoroValue>,
+    },
+    TextInsert {
+        pos: usize,
+        text: String,
+    },
+    /// `len` is negative if the elements are deleted backward
+    Delete {
+        pos: isize,
+        len: isize,
+        #[serde(with = "id_str")]
+        start_id: ID,
+    },
+    Mark {
+        start: u32,
+        end: u32,
+        key: String,
+        #[serde(with = "value_json")]
+        value: LoroValue,
+        info: u8,
+    },
+    MarkEnd,
+    ListMove {
+        from: u32,
+        to: u32,
+        #[serde(with = "id_str")]
+        elem_id: ID,
+    },
+    ListSet {
+        #[serde(with = "id_str")]
+        elem_id: ID,
+        #[serde(with = "value_json")]
+        value: LoroValue,
+    },
+    MapInsert {
+        key: String,
+        #[serde(with = "value_json")]
+        value: LoroValue,
+    },
+    MapDelete {
+        key: String,
+    },
+    TreeMove {
+        #[serde(with = "tree_id_str")]
+        target: TreeID,
+        #[serde(with = "opt_id_str")]
+        parent: Option<TreeID>,
+        /// The hex string of the fractional index bytes
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fractional_index: Option<String>,
+    },
+    TreeDelete {
+        #[serde(with = "tree_id_str")]
+        target: TreeID,
+    },
+    CounterIncrement {
+        value: f64,
+    },
+}
+
+/// Export the changes that are in `end_vv` but not in `start_vv`.
+pub(crate) fn export_json(
+    oplog: &OpLog,
+    start_vv: &VersionVector,
+    end_vv: &VersionVector,
+) -> JsonSchema {
+    let mut changes: Vec<Change> = Vec::new();
+    for span in end_vv.sub_iter(start_vv) {
+        let Some(peer_changes) = oplog.changes().get(&span.peer) else {
+            continue;
+        };
+        for change in peer_changes.iter() {
+            if change.ctr_end() <= span.counter.start {
+                continue;
+            }
+            if change.id.counter >= span.counter.end {
+                break;
+            }
+
+            let start = (span.counter.start - change.id.counter).max(0) as usize;
+            let end = ((span.counter.end - change.id.counter) as usize).min(change.atom_len());
+            if start == 0 && end == change.atom_len() {
+                changes.push(change.clone());
+            } else {
+                changes.push(change.slice(start, end));
+            }
+        }
+    }
+
+    changes.sort_by_key(|c| (c.lamport, c.id.peer));
+    JsonSchema {
+        schema_version: JSON_SCHEMA_VERSION,
+        changes: changes
+            .iter()
+            .map(|change| JsonChange {
+                id: change.id,
+                timestamp: change.timestamp,
+                deps: change.deps.iter().copied().collect(),
+                lamport: change.lamport,
+                msg: change.message.as_ref().map(|x| x.to_string()),
+                ops: change
+                    .ops
+                    .iter()
+                    .flat_map(|op| oplog.local_op_to_remote(op))
+                    .map(|op| JsonOp {
+                        container: op.container,
+                        counter: op.counter,
+                        content: raw_content_to_json(op.content),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+/// Convert the JSON changes into the changes that can be imported into `oplog`.
+pub(crate) fn decode_json_changes(oplog: &OpLog, json: JsonSchema) -> LoroResult<Vec<Change>> {
+    if json.schema_version > JSON_SCHEMA_VERSION {
+        return Err(LoroError::IncompatibleFutureEncodingError(
+            json.schema_version as usize,
+        ));
+    }
+
+    let mut changes = Vec::with_capacity(json.changes.len());
+    for change in json.changes {
+        let peer = change.id.peer;
+        let mut ops = RleVec::new();
+        let mut next_counter = change.id.counter;
+        for op in change.ops {
+            if op.counter != next_counter {
+                return Err(LoroError::DecodeError(
+                    format!("The ops of change {} are not continuous", change.id).into_boxed_str(),
+                ));
+            }
+
+            let content = json_to_raw_content(op.content)?;
+            let op = oplog.arena.convert_single_op(
+                &op.container,
+                peer,
+                op.counter,
+                change.lamport + (op.counter - change.id.counter) as Lamport,
+                content,
+            );
+            next_counter += op.atom_len() as Counter;
+            ops.push(op);
+        }
+
+        if ops.is_empty() {
+            return Err(LoroError::DecodeError(
+                format!("The change {} is empty", change.id).into_boxed_str(),
+            ));
+        }
+
+        let mut ans = Change::new(
+            ops,
+            Frontiers::from(change.deps),
+            change.id,
+            change.lamport,
+            change.timestamp,
+        );
+        ans.message = change.msg.map(Arc::from);
+        changes.push(ans);
+    }
+
+    Ok(changes)
+}
+
+fn raw_content_to_json(content: RawOpContent) -> JsonOpContent {
+    match content {
+        RawOpContent::Map(MapSet { key, value }) => match value {
+            Some(value) => JsonOpContent::MapInsert {
+                key: key.to_string(),
+                value,
+            },
+            None => JsonOpContent::MapDelete {
+                key: key.to_string(),
+            },
+        },
+        RawOpContent::List(list) => match list {
+            ListOp::Insert { slice, pos } => match slice {
+                ListSlice::RawData(values) => JsonOpContent::ListInsert {
+                    pos,
+                    values: values.into_owned(),
+                },
+                ListSlice::RawStr { str, .. } => JsonOpContent::TextInsert {
+                    pos,
+                    text: str.into_owned(),
+                },
+            },
+            ListOp::Delete(DeleteSpanWithId { id_start, span }) => JsonOpContent::Delete {
+                pos: span.pos,
+                len: span.signed_len,
+                start_id: id_start,
+            },
+            ListOp::StyleStart {
+                start,
+                end,
+                key,
+                info,
+                value,
+            } => JsonOpContent::Mark {
+                start,
+                end,
+                key: key.to_string(),
+                value,
+                info: info.to_byte(),
+            },
+            ListOp::StyleEnd => JsonOpContent::MarkEnd,
+            ListOp::Move { from, to, elem_id } => JsonOpContent::ListMove { from, to, elem_id },
+            ListOp::Set { elem_id, value } => JsonOpContent::ListSet { elem_id, value },
+        },
+        RawOpContent::Tree(TreeOp {
+            target,
+            parent,
+            position,
+        }) => match parent {
+            Some(parent) if TreeID::is_deleted_root(&parent) => {
+                JsonOpContent::TreeDelete { target }
+            }
+            parent => JsonOpContent::TreeMove {
+                target,
+                parent,
+                fractional_index: position.map(|x| to_hex(x.as_bytes())),
+            },
+        },
+        RawOpContent::Counter(value) => JsonOpContent::CounterIncrement { value },
+    }
+}
+
+fn json_to_raw_content(content: JsonOpContent) -> LoroResult<RawOpContent<'static>> {
+    Ok(match content {
+        JsonOpContent::ListInsert { pos, values } => RawOpContent::List(ListOp::Insert {
+            slice: ListSlice::RawData(Cow::Owned(values)),
+            pos,
+        }),
+        JsonOpContent::TextInsert { pos, text } => RawOpContent::List(ListOp::Insert {
+            slice: ListSlice::RawStr {
+                unicode_len: text.chars().count(),
+                str: Cow::Owned(text),
+            },
+            pos,
+        }),
+        JsonOpContent::Delete { pos, len, start_id } => {
+            if len == 0 {
+                return Err(LoroError::DecodeError("Empty delete op".into()));
+            }
+
+            RawOpContent::List(ListOp::Delete(DeleteSpanWithId {
+                id_start: start_id,
+                span: DeleteSpan::new(pos, len),
+            }))
+        }
+        JsonOpContent::Mark {
+            start,
+            end,
+            key,
+            value,
+            info,
+        } => RawOpContent::List(ListOp::StyleStart {
+            start,
+            end,
+            key: key.into(),
+            info: TextStyleInfoFlag::from_byte(info),
+            value,
+        }),
+        JsonOpContent::MarkEnd => RawOpContent::List(ListOp::StyleEnd),
+        JsonOpContent::ListMove { from, to, elem_id } => {
+            RawOpContent::List(ListOp::Move { from, to, elem_id })
+        }
+        JsonOpContent::ListSet { elem_id, value } => {
+            RawOpContent::List(ListOp::Set { elem_id, value })
+        }
+        JsonOpContent::MapInsert { key, value } => RawOpContent::Map(MapSet {
+            key: key.into(),
+            value: Some(value),
+        }),
+        JsonOpContent::MapDelete { key } => RawOpContent::Map(MapSet {
+            key: key.into(),
+            value: None,
+        }),
+        JsonOpContent::TreeMove {
+            target,
+            parent,
+            fractional_index,
+        } => RawOpContent::Tree(TreeOp {
+            target,
+            parent,
+            position: match fractional_index {
+                Some(hex) => Some(
+                    from_hex(&hex)
+                        .and_then(FractionalIndex::from_bytes)
+                        .ok_or_else(|| LoroError::DecodeError("Invalid fractional index".into()))?,
+                ),
+                None => None,
+            },
+        }),
+        JsonOpContent::TreeDelete { target } => RawOpContent::Tree(TreeOp {
+            target,
+            parent: Some(TreeID::delete_root()),
+            position: None,
+        }),
+        JsonOpContent::CounterIncrement { value } => RawOpContent::Counter(value),
+    })
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    s.as_bytes()
+        .chunks(2)
+        .map(|x| {
+            if x.len() != 2 {
+                return None;
+            }
+
+            u8::from_str_radix(std::str::from_utf8(x).ok()?, 16).ok()
+        })
+        .collect()
+}
+
+/// Repeat the reserved prefix if the string starts with it
+fn escape_str(s: &str) -> String {
+    if s.starts_with(CONTAINER_PREFIX) {
+        format!("{}{}", CONTAINER_PREFIX, s)
+    } else {
+        s.to_string()
+    }
+}
+
+fn reserved_object(key: &str, value: serde_json::Value) -> serde_json::Value {
+    let mut map = serde_json::Map::new();
+    map.insert(key.to_string(), value);
+    serde_json::Value::Object(map)
+}
+
+fn value_to_json(value: &LoroValue) -> serde_json::Value {
+    match value {
+        LoroValue::Null => serde_json::Value::Null,
+        LoroValue::Bool(b) => serde_json::Value::Bool(*b),
+        LoroValue::Double(d) => match serde_json::Number::from_f64(*d) {
+            Some(n) => serde_json::Value::Number(n),
+            None => {
+                let s = if d.is_nan() {
+                    "NaN"
+                } else if *d > 0. {
+                    "Infinity"
+                } else {
+                    "-Infinity"
+                };
+                reserved_object(DOUBLE_KEY, s.into())
+            }
+        },
+        LoroValue::I64(i) => serde_json::Value::Number((*i).into()),
+        LoroValue::String(s) => serde_json::Value::String(escape_str(s)),
+        LoroValue::Binary(b) => reserved_object(
+            BINARY_KEY,
+            serde_json::Value::Array(b.iter().map(|x| (*x).into()).collect()),
+        ),
+        LoroValue::List(l) => serde_json::Value::Array(l.iter().map(value_to_json).collect()),
+        LoroValue::Map(m) => serde_json::Value::Object(
+            m.iter()
+                .map(|(k, v)| (escape_str(k), value_to_json(v)))
+                .collect(),
+        ),
+        LoroValue::Container(id) => {
+            serde_json::Value::String(format!("{}{}", CONTAINER_PREFIX, id))
+        }
+    }
+}
+
+fn json_to_value(value: serde_json::Value) -> Result<LoroValue, String> {
+    Ok(match value {
+        serde_json::Value::Null => LoroValue::Null,
+        serde_json::Value::Bool(b) => LoroValue::Bool(b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => LoroValue::I64(i),
+            None => LoroValue::Double(n.as_f64().unwrap()),
+        },
+        serde_json::Value::String(s) => match s.strip_prefix(CONTAINER_PREFIX) {
+            Some(escaped) if escaped.starts_with(CONTAINER_PREFIX) => {
+                LoroValue::String(Arc::new(escaped.to_string()))
+            }
+            Some(id) => LoroValue::Container(
+                ContainerID::try_from(id).map_err(|_| format!("Invalid container id {}", id))?,
+            ),
+            None => LoroValue::String(Arc::new(s)),
+        },
+        serde_json::Value::Array(arr) => LoroValue::List(Arc::new(
+            arr.into_iter()
+                .map(json_to_value)
+                .collect::<Result<_, _>>()?,
+        )),
+        serde_json::Value::Object(mut map) => {
+            if let Some(bytes) = map.remove(BINARY_KEY) {
+                let bytes = match bytes {
+                    serde_json::Value::Array(bytes) if map.is_empty() => bytes,
+                    _ => return Err("Invalid binary".to_string()),
+                };
+                return Ok(LoroValue::Binary(Arc::new(
+                    bytes
+                        .iter()
+                        .map(|x| x.as_u64().and_then(|x| u8::try_from(x).ok()))
+                        .collect::<Option<_>>()
+                        .ok_or("Invalid binary")?,
+                )));
+            }
+
+            if let Some(d) = map.remove(DOUBLE_KEY) {
+                return match (d.as_str(), map.is_empty()) {
+                    (Some("NaN"), true) => Ok(LoroValue::Double(f64::NAN)),
+                    (Some("Infinity"), true) => Ok(LoroValue::Double(f64::INFINITY)),
+                    (Some("-Infinity"), true) => Ok(LoroValue::Double(f64::NEG_INFINITY)),
+                    _ => Err("Invalid double".to_string()),
+                };
+            }
+
+            LoroValue::Map(Arc::new(
+                map.into_iter()
+                    .map(|(k, v)| {
+                        let k = match k.strip_prefix(CONTAINER_PREFIX) {
+                            Some(escaped) if escaped.starts_with(CONTAINER_PREFIX) => {
+                                escaped.to_string()
+                            }
+                            Some(_) => return Err(format!("Invalid key {}", k)),
+                            None => k,
+                        };
+                        Ok((k, json_to_value(v)?))
+                    })
+                    .collect::<Result<FxHashMap<_, _>, String>>()?,
+            ))
+        }
+    })
+}
+
+mod value_json {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &LoroValue, s: S) -> Result<S::Ok, S::Error> {
+        value_to_json(value).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<LoroValue, D::Error> {
+        json_to_value(serde_json::Value::deserialize(d)?).map_err(serde::de::Error::custom)
+    }
+}
+
+mod values_json {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(values: &[LoroValue], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(values.iter().map(value_to_json))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<LoroValue>, D::Error> {
+        Vec::<serde_json::Value>::deserialize(d)?
+            .into_iter()
+            .map(json_to_value)
+            .collect::<Result<_, _>>()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Ids and tree ids are written as `counter@peer`
+mod id_str {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &ID, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(id)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<ID, D::Error> {
+        let s = String::deserialize(d)?;
+        ID::try_from(s.as_str()).map_err(serde::de::Error::custom)
+    }
+}
+
+mod tree_id_str {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &TreeID, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(id)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<TreeID, D::Error> {
+        let s = String::deserialize(d)?;
+        TreeID::try_from(s.as_str()).map_err(serde::de::Error::custom)
+    }
+}
+
+mod opt_id_str {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &Option<TreeID>, s: S) -> Result<S::Ok, S::Error> {
+        match id {
+            Some(id) => s.collect_str(id),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<TreeID>, D::Error> {
+        match Option::<String>::deserialize(d)? {
+            Some(s) => TreeID::try_from(s.as_str())
+                .map(Some)
+                .map_err(serde::de::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
+
+mod ids_str {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(ids: &[ID], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(ids.iter().map(|id| id.to_string()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<ID>, D::Error> {
+        Vec::<String>::deserialize(d)?
+            .iter()
+            .map(|s| ID::try_from(s.as_str()))
+            .collect::<Result<_, _>>()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+mod container_id_str {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &ContainerID, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(id)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<ContainerID, D::Error> {
+        let s = String::deserialize(d)?;
+        ContainerID::try_from(s.as_str())
+            .map_err(|_| serde::de::Error::custom(format!("Invalid container id {}", s)))
+    }
+}
@@ -1,12 +1,14 @@
 use enum_as_inner::EnumAsInner;
-use fxhash::FxHasher64;
+use fxhash::{FxHashSet, FxHasher64};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 
 use crate::{
     container::richtext::richtext_state::RichtextStateChunk,
-    delta::{Delta, MapDelta, ResolvedMapDelta, StyleMeta, TreeDelta, TreeDiff},
+    delta::{
+        Delta, MapDelta, MovableListInnerDelta, ResolvedMapDelta, StyleMeta, TreeDelta, TreeDiff,
+    },
     handler::ValueOrHandler,
     op::SliceRanges,
     utils::string_slice::StringSlice,
@@ -208,6 +210,9 @@ pub(crate) enum InternalDiff {
     RichtextRaw(Delta<RichtextStateChunk>),
     Map(MapDelta),
     Tree(TreeDelta),
+    MovableList(MovableListInnerDelta),
+    /// The delta of a counter
+    Counter(f64),
 }
 
 impl From<InternalDiff> for DiffVariant {
@@ -236,6 +241,8 @@ pub enum Diff {
     Text(Delta<StringSlice, StyleMeta>),
     Map(ResolvedMapDelta),
     Tree(TreeDiff),
+    /// The delta of a counter
+    Counter(f64),
 }
 
 impl From<Diff> for DiffVariant {
@@ -251,6 +258,8 @@ impl InternalDiff {
             InternalDiff::RichtextRaw(t) => t.is_empty(),
             InternalDiff::Map(m) => m.updated.is_empty(),
             InternalDiff::Tree(t) => t.is_empty(),
+            InternalDiff::MovableList(t) => t.is_empty(),
+            InternalDiff::Counter(c) => *c == 0.,
         }
     }
 
@@ -265,6 +274,12 @@ impl InternalDiff {
             }
             (InternalDiff::Map(a), InternalDiff::Map(b)) => Ok(InternalDiff::Map(a.compose(b))),
             (InternalDiff::Tree(a), InternalDiff::Tree(b)) => Ok(InternalDiff::Tree(a.compose(b))),
+            (InternalDiff::MovableList(a), InternalDiff::MovableList(b)) => {
+                Ok(InternalDiff::MovableList(a.compose(b)))
+            }
+            (InternalDiff::Counter(a), InternalDiff::Counter(b)) => {
+                Ok(InternalDiff::Counter(a + b))
+            }
             (a, _) => Err(a),
         }
     }
@@ -279,16 +294,38 @@ impl Diff {
             (Diff::Map(a), Diff::Map(b)) => Ok(Diff::Map(a.compose(b))),
 
             (Diff::Tree(a), Diff::Tree(b)) => Ok(Diff::Tree(a.compose(b))),
+            (Diff::Counter(a), Diff::Counter(b)) => Ok(Diff::Counter(a + b)),
             (a, _) => Err(a),
         }
     }
 
+    /// Transform `self` so that it can be applied after `other`, given that both of
+    /// them are based on the same state.
+    ///
+    /// If `left_prior` is false, the changes of `other` win when they conflict with
+    /// `self`, i.e. the same map entries and tree nodes are left untouched by `self`.
+    pub(crate) fn transform(&mut self, other: &Diff, left_prior: bool) {
+        match (self, other) {
+            (Diff::List(a), Diff::List(b)) => *a = b.transform(a, !left_prior),
+            (Diff::Text(a), Diff::Text(b)) => *a = b.transform(a, !left_prior),
+            (Diff::Map(a), Diff::Map(b)) if !left_prior => {
+                a.updated.retain(|k, _| !b.updated.contains_key(k));
+            }
+            (Diff::Tree(a), Diff::Tree(b)) if !left_prior => {
+                let targets: FxHashSet<TreeID> = b.diff.iter().map(|x| x.target).collect();
+                a.diff.retain(|x| !targets.contains(&x.target));
+            }
+            _ => {}
+        }
+    }
+
     pub(crate) fn is_empty(&self) -> bool {
         match self {
             Diff::List(s) => s.is_empty(),
             Diff::Text(t) => t.is_empty(),
             Diff::Map(m) => m.updated.is_empty(),
             Diff::Tree(t) => t.diff.is_empty(),
+            Diff::Counter(c) => *c == 0.,
         }
     }
 
@@ -305,6 +342,7 @@ impl Diff {
             }
 
             (Diff::Tree(a), Diff::Tree(b)) => Diff::Tree(a.extend(b.diff)),
+            (Diff::Counter(a), Diff::Counter(b)) => Diff::Counter(a + b),
             _ => unreachable!(),
         }
     }
@@ -353,7 +353,7 @@ impl Actor {
             ContainerType::Text => self.text_containers.push(handler.into_text().unwrap()),
             ContainerType::Map => self.map_containers.push(handler.into_map().unwrap()),
             ContainerType::List => self.list_containers.push(handler.into_list().unwrap()),
-            ContainerType::Tree => {
+            ContainerType::Tree | ContainerType::MovableList | ContainerType::Counter => {
                 // TODO Tree
             }
         }
@@ -6,11 +6,14 @@ use std::{
 use enum_as_inner::EnumAsInner;
 use enum_dispatch::enum_dispatch;
 use fxhash::FxHashMap;
-use loro_common::{Counter, HasId, HasLamport, InternalString, LoroValue, PeerID, ID};
+use loro_common::{
+    ContainerType, Counter, HasId, HasLamport, InternalString, LoroValue, PeerID, ID,
+};
+use rle::HasLength;
 
 use crate::{
     change::{Change, Lamport},
-    container::{idx::ContainerIdx, tree::tree_op::TreeOp},
+    container::{idx::ContainerIdx, list::list_op::InnerListOp, tree::tree_op::TreeOp},
     diff_calc::tree::TreeCacheForDiff,
     op::{InnerContent, RichOp},
     VersionVector,
@@ -24,21 +27,29 @@ pub(crate) struct OpGroups {
 impl OpGroups {
     pub(crate) fn insert_by_change(&mut self, change: &Change) {
         for op in change.ops.iter() {
-            if matches!(op.content, InnerContent::List(_)) {
-                continue;
-            }
-            let container_idx = op.container;
-            let rich_op = RichOp::new_by_change(change, op);
-            let manager = self
-                .groups
-                .entry(container_idx)
-                .or_insert_with(|| match op.content {
-                    InnerContent::Map(_) => OpGroup::Map(MapOpGroup::default()),
-                    InnerContent::List(_) => unreachable!(),
-                    InnerContent::Tree(_) => OpGroup::Tree(TreeOpGroup::default()),
-                });
-            manager.insert(&rich_op)
+            self.insert_by_op(&RichOp::new_by_change(change, op));
+        }
+    }
+
+    pub(crate) fn insert_by_op(&mut self, rich_op: &RichOp) {
+        let op = rich_op.raw_op();
+        // Counter ops are commutative, so they don't need to be grouped
+        if (matches!(op.content, InnerContent::List(_))
+            && op.container.get_type() != ContainerType::MovableList)
+            || matches!(op.content, InnerContent::Counter(_))
+        {
+            return;
         }
+        let manager = self
+            .groups
+            .entry(op.container)
+            .or_insert_with(|| match op.content {
+                InnerContent::Map(_) => OpGroup::Map(MapOpGroup::default()),
+                InnerContent::List(_) => OpGroup::MovableList(MovableListOpGroup::default()),
+                InnerContent::Tree(_) => OpGroup::Tree(TreeOpGroup::default()),
+                InnerContent::Counter(_) => unreachable!(),
+            });
+        manager.insert(rich_op)
     }
 
     pub(crate) fn get(&self, container_idx: &ContainerIdx) -> Option<&OpGroup> {
@@ -54,6 +65,18 @@ impl OpGroups {
             })
     }
 
+    pub(crate) fn get_movable_list(
+        &self,
+        container_idx: &ContainerIdx,
+    ) -> Option<&MovableListOpGroup> {
+        self.groups
+            .get(container_idx)
+            .and_then(|group| match group {
+                OpGroup::MovableList(list) => Some(list),
+                _ => None,
+            })
+    }
+
     #[allow(unused)]
     pub(crate) fn get_map(&self, container_idx: &ContainerIdx) -> Option<&MapOpGroup> {
         self.groups
@@ -70,6 +93,7 @@ impl OpGroups {
 pub(crate) enum OpGroup {
     Map(MapOpGroup),
     Tree(TreeOpGroup),
+    MovableList(MovableListOpGroup),
 }
 
 #[enum_dispatch]
@@ -118,14 +142,19 @@ impl MapOpGroup {
         key: &InternalString,
         vv: &VersionVector,
     ) -> Option<&GroupedMapOpInfo> {
-        self.ops.get(key).and_then(|set| {
-            set.iter()
-                .rev()
-                .find(|op| vv.get(&op.peer).copied().unwrap_or(0) > op.counter)
-        })
+        self.ops.get(key).and_then(|set| last_op_in(set, vv))
     }
 }
 
+fn last_op_in<'a>(
+    set: &'a BTreeSet<GroupedMapOpInfo>,
+    vv: &VersionVector,
+) -> Option<&'a GroupedMapOpInfo> {
+    set.iter()
+        .rev()
+        .find(|op| vv.get(&op.peer).copied().unwrap_or(0) > op.counter)
+}
+
 impl OpGroupTrait for MapOpGroup {
     fn insert(&mut self, op: &RichOp) {
         let key = match &op.raw_op().content {
@@ -142,7 +171,7 @@ impl OpGroupTrait for MapOpGroup {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub(crate) struct GroupedTreeOpInfo {
     pub(crate) peer: PeerID,
     pub(crate) counter: Counter,
@@ -184,13 +213,78 @@ pub(crate) struct TreeOpGroup {
 impl OpGroupTrait for TreeOpGroup {
     fn insert(&mut self, op: &RichOp) {
         let tree_op = op.raw_op().content.as_tree().unwrap();
-        let target = tree_op.target;
-        let parent = tree_op.parent;
         let entry = self.ops.entry(op.lamport()).or_default();
         entry.insert(GroupedTreeOpInfo {
-            value: TreeOp { target, parent },
+            value: tree_op.clone(),
             counter: op.raw_op().counter,
             peer: op.peer,
         });
     }
 }
+
+/// The move and set ops of a movable list, grouped by the element they target.
+///
+/// The position and the value of an element are both resolved by LWW. An element is
+/// deleted once any of its positions is deleted, so a move can't revive an element
+/// that is deleted concurrently.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct MovableListOpGroup {
+    /// The id of a move op is also the id of the new position of the element.
+    moves: FxHashMap<ID, BTreeSet<GroupedMapOpInfo>>,
+    sets: FxHashMap<ID, BTreeSet<GroupedMapOpInfo>>,
+    /// The ids of the delete ops of each position
+    deletes: FxHashMap<ID, Vec<ID>>,
+}
+
+impl MovableListOpGroup {
+    /// Get the last move op of the element that is included in the given version
+    pub(crate) fn last_move(&self, elem: ID, vv: &VersionVector) -> Option<&GroupedMapOpInfo> {
+        self.moves.get(&elem).and_then(|set| last_op_in(set, vv))
+    }
+
+    /// Get the last set op of the element that is included in the given version
+    pub(crate) fn last_set(&self, elem: ID, vv: &VersionVector) -> Option<&GroupedMapOpInfo> {
+        self.sets.get(&elem).and_then(|set| last_op_in(set, vv))
+    }
+
+    /// Whether one of the positions of the element is deleted in the given version
+    pub(crate) fn is_deleted(&self, elem: ID, vv: &VersionVector) -> bool {
+        let moves = self.moves.get(&elem).into_iter().flatten();
+        std::iter::once(elem)
+            .chain(moves.map(|m| ID::new(m.peer, m.counter)))
+            .filter_map(|pos| self.deletes.get(&pos))
+            .flatten()
+            .any(|id| vv.includes_id(*id))
+    }
+}
+
+impl OpGroupTrait for MovableListOpGroup {
+    fn insert(&mut self, op: &RichOp) {
+        let (elem_id, value) = match op.raw_op().content.as_list().unwrap() {
+            InnerListOp::Move { elem_id, .. } => (*elem_id, None),
+            InnerListOp::Set { elem_id, value } => (*elem_id, Some(value.clone())),
+            InnerListOp::Delete(del) => {
+                for i in 0..del.atom_len() as Counter {
+                    self.deletes
+                        .entry(del.id_start.inc(i))
+                        .or_default()
+                        .push(op.id_start());
+                }
+                return;
+            }
+            _ => return,
+        };
+
+        let info = GroupedMapOpInfo {
+            counter: op.raw_op().counter,
+            lamport: op.lamport(),
+            peer: op.peer,
+            value,
+        };
+        if info.value.is_some() {
+            self.sets.entry(elem_id).or_default().insert(info);
+        } else {
+            self.moves.entry(elem_id).or_default().insert(info);
+        }
+    }
+}
@@ -1,31 +1,35 @@
 use super::{state::DocState, txn::Transaction};
 use crate::{
     arena::SharedArena,
+    change::Timestamp,
     container::{
         idx::ContainerIdx,
         list::list_op::{DeleteSpan, DeleteSpanWithId, ListOp},
         richtext::{richtext_state::PosType, RichtextState, StyleOp, TextStyleInfoFlag},
-        tree::tree_op::TreeOp,
+        tree::{fractional_index::FractionalIndex, tree_op::TreeOp},
     },
     cursor::{Cursor, Side},
     delta::{DeltaItem, StyleMeta, TreeDiffItem, TreeExternalDiff},
+    event::Diff,
     op::ListSlice,
+    schema::Inserted,
     state::{ContainerState, State, TreeParentId},
     txn::EventHint,
     utils::{string_slice::StringSlice, utf16::count_utf16_len},
+    OpLog,
 };
 use append_only_bytes::BytesSlice;
 use enum_as_inner::EnumAsInner;
 use fxhash::FxHashMap;
 use loro_common::{
-    ContainerID, ContainerType, Counter, IdFull, InternalString, LoroError, LoroResult,
-    LoroTreeError, LoroValue, PeerID, TreeID, ID,
+    ContainerID, ContainerType, Counter, HasIdSpan, IdFull, IdSpan, InternalString, Lamport,
+    LoroError, LoroResult, LoroTreeError, LoroValue, PeerID, TreeID, ID,
 };
 use serde::{Deserialize, Serialize};
 use std::{
     borrow::Cow,
     fmt::Debug,
-    ops::Deref,
+    ops::{Deref, Range},
     sync::{Arc, Mutex, Weak},
 };
 
@@ -176,6 +180,16 @@ impl BasicHandler {
         with_txn(&self.txn, f)
     }
 
+    /// Lock the oplog and then the state, in the same order as [crate::LoroDoc], so the
+    /// state is read at the version of the oplog.
+    fn with_oplog_and_state<R>(&self, f: impl FnOnce(&OpLog, &mut State) -> R) -> R {
+        let state = self.state.upgrade().unwrap();
+        let oplog = state.lock().unwrap().oplog.upgrade().unwrap();
+        let oplog = oplog.lock().unwrap();
+        let mut state = state.lock().unwrap();
+        state.with_state_mut(self.container_idx, |state| f(&oplog, state))
+    }
+
     fn get_parent(&self) -> Option<Handler> {
         let parent_idx = self.arena.get_parent(self.container_idx)?;
         let parent_id = self.arena.get_container_id(parent_idx).unwrap();
@@ -205,6 +219,12 @@ impl BasicHandler {
                 ContainerType::Text => Handler::Text(TextHandler {
                     inner: handler.into(),
                 }),
+                ContainerType::MovableList => Handler::MovableList(MovableListHandler {
+                    inner: handler.into(),
+                }),
+                ContainerType::Counter => Handler::Counter(CounterHandler {
+                    inner: handler.into(),
+                }),
             })
         }
     }
@@ -369,6 +389,66 @@ impl From<&DeltaItem<StringSlice, StyleMeta>> for TextDelta {
     }
 }
 
+/// The authorship of a span of content in a text or a list container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribution {
+    /// The range of the span. It's in event index for text and in element index for list.
+    pub range: Range<usize>,
+    /// The peer that created the span
+    pub peer: PeerID,
+    /// The lamport timestamp of the first op in the span
+    pub lamport: Lamport,
+    /// The timestamp of the change that created the span.
+    ///
+    /// It's 0 if the timestamp is not recorded, or if the change is not committed yet
+    /// or has been trimmed by a shallow snapshot.
+    pub timestamp: Timestamp,
+    /// The commit message of the change that created the span
+    pub message: Option<Arc<str>>,
+}
+
+/// Merge the atoms created by the same change into [Attribution]s.
+///
+/// Each atom is given by its id and its length in the index of the query.
+fn attribute_atoms(
+    oplog: &OpLog,
+    start: usize,
+    atoms: impl IntoIterator<Item = (IdFull, usize)>,
+) -> Vec<Attribution> {
+    let mut ans: Vec<Attribution> = Vec::new();
+    // the id span of the change that created the last atom, and the id next to the last atom
+    let mut last: Option<(Option<IdSpan>, ID)> = None;
+    let mut index = start;
+    for (id, len) in atoms {
+        let same_change = match &last {
+            Some((span, next)) if *next == id.id() => match span {
+                Some(span) => span.contains(id.id()),
+                None => oplog.lookup_change(id.id()).is_none(),
+            },
+            _ => false,
+        };
+
+        if same_change {
+            ans.last_mut().unwrap().range.end += len;
+            last.as_mut().unwrap().1 = id.id().inc(1);
+        } else {
+            let change = oplog.lookup_change(id.id());
+            ans.push(Attribution {
+                range: index..index + len,
+                peer: id.peer,
+                lamport: id.lamport,
+                timestamp: change.map(|c| c.timestamp).unwrap_or(0),
+                message: change.and_then(|c| c.message.clone()),
+            });
+            last = Some((change.map(|c| c.id_span()), id.id().inc(1)));
+        }
+
+        index += len;
+    }
+
+    ans
+}
+
 #[derive(Clone)]
 pub struct MapHandler {
     inner: MaybeDetached<FxHashMap<String, ValueOrHandler>>,
@@ -574,6 +654,184 @@ impl HandlerTrait for ListHandler {
     }
 }
 
+#[derive(Clone)]
+pub struct MovableListHandler {
+    inner: MaybeDetached<Vec<ValueOrHandler>>,
+}
+
+impl std::fmt::Debug for MovableListHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.inner {
+            MaybeDetached::Detached(_) => write!(f, "MovableListHandler Dettached"),
+            MaybeDetached::Attached(a) => write!(f, "MovableListHandler {}", a.id),
+        }
+    }
+}
+
+impl HandlerTrait for MovableListHandler {
+    fn is_attached(&self) -> bool {
+        self.inner.is_attached()
+    }
+
+    fn attached_handler(&self) -> Option<&BasicHandler> {
+        self.inner.attached_handler()
+    }
+
+    fn get_value(&self) -> LoroValue {
+        match &self.inner {
+            MaybeDetached::Detached(a) => {
+                let a = a.try_lock().unwrap();
+                LoroValue::List(Arc::new(a.value.iter().map(|v| v.to_value()).collect()))
+            }
+            MaybeDetached::Attached(a) => a.get_value(),
+        }
+    }
+
+    fn get_deep_value(&self) -> LoroValue {
+        match &self.inner {
+            MaybeDetached::Detached(a) => {
+                let a = a.try_lock().unwrap();
+                LoroValue::List(Arc::new(
+                    a.value.iter().map(|v| v.to_deep_value()).collect(),
+                ))
+            }
+            MaybeDetached::Attached(a) => a.get_deep_value(),
+        }
+    }
+
+    fn kind(&self) -> ContainerType {
+        ContainerType::MovableList
+    }
+
+    fn to_handler(&self) -> Handler {
+        Handler::MovableList(self.clone())
+    }
+
+    fn attach(
+        &self,
+        txn: &mut Transaction,
+        parent: &BasicHandler,
+        self_id: ContainerID,
+    ) -> LoroResult<Self> {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                let mut l = l.try_lock().unwrap();
+                let inner = create_handler(parent, self_id);
+                let list = inner.into_movable_list().unwrap();
+                for (index, v) in l.value.iter().enumerate() {
+                    match v {
+                        ValueOrHandler::Value(v) => {
+                            list.insert_with_txn(txn, index, v.clone())?;
+                        }
+                        ValueOrHandler::Handler(h) => {
+                            list.insert_container_with_txn(txn, index, h.clone())?;
+                        }
+                    }
+                }
+                l.attached = list.attached_handler().cloned();
+                Ok(list)
+            }
+            MaybeDetached::Attached(_a) => unreachable!(),
+        }
+    }
+
+    fn get_attached(&self) -> Option<Self> {
+        match &self.inner {
+            MaybeDetached::Detached(d) => d.lock().unwrap().attached.clone().map(|x| Self {
+                inner: MaybeDetached::Attached(x),
+            }),
+            MaybeDetached::Attached(_a) => Some(self.clone()),
+        }
+    }
+
+    fn from_handler(h: Handler) -> Option<Self> {
+        match h {
+            Handler::MovableList(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CounterHandler {
+    inner: MaybeDetached<f64>,
+}
+
+impl std::fmt::Debug for CounterHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.inner {
+            MaybeDetached::Detached(_) => write!(f, "CounterHandler Dettached"),
+            MaybeDetached::Attached(a) => write!(f, "CounterHandler {}", a.id),
+        }
+    }
+}
+
+impl HandlerTrait for CounterHandler {
+    fn is_attached(&self) -> bool {
+        self.inner.is_attached()
+    }
+
+    fn attached_handler(&self) -> Option<&BasicHandler> {
+        self.inner.attached_handler()
+    }
+
+    fn get_value(&self) -> LoroValue {
+        match &self.inner {
+            MaybeDetached::Detached(a) => LoroValue::Double(a.try_lock().unwrap().value),
+            MaybeDetached::Attached(a) => a.get_value(),
+        }
+    }
+
+    fn get_deep_value(&self) -> LoroValue {
+        self.get_value()
+    }
+
+    fn kind(&self) -> ContainerType {
+        ContainerType::Counter
+    }
+
+    fn to_handler(&self) -> Handler {
+        Handler::Counter(self.clone())
+    }
+
+    fn attach(
+        &self,
+        txn: &mut Transaction,
+        parent: &BasicHandler,
+        self_id: ContainerID,
+    ) -> LoroResult<Self> {
+        match &self.inner {
+            MaybeDetached::Detached(c) => {
+                let mut c = c.try_lock().unwrap();
+                let inner = create_handler(parent, self_id);
+                let counter = inner.into_counter().unwrap();
+                if c.value != 0. {
+                    counter.increment_with_txn(txn, c.value)?;
+                }
+                c.attached = counter.attached_handler().cloned();
+                Ok(counter)
+            }
+            MaybeDetached::Attached(_a) => unreachable!(),
+        }
+    }
+
+    fn get_attached(&self) -> Option<Self> {
+        match &self.inner {
+            MaybeDetached::Detached(d) => d.lock().unwrap().attached.clone().map(|x| Self {
+                inner: MaybeDetached::Attached(x),
+            }),
+            MaybeDetached::Attached(_a) => Some(self.clone()),
+        }
+    }
+
+    fn from_handler(h: Handler) -> Option<Self> {
+        match h {
+            Handler::Counter(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
 ///
 #[derive(Clone)]
 pub struct TreeHandler {
@@ -585,6 +843,7 @@ struct TreeInner {
     next_counter: Counter,
     map: FxHashMap<TreeID, MapHandler>,
     parent_links: FxHashMap<TreeID, Option<TreeID>>,
+    children_links: FxHashMap<Option<TreeID>, Vec<TreeID>>,
 }
 
 impl TreeInner {
@@ -593,10 +852,16 @@ impl TreeInner {
             next_counter: 0,
             map: FxHashMap::default(),
             parent_links: FxHashMap::default(),
+            children_links: FxHashMap::default(),
         }
     }
 
-    fn create(&mut self, parent: Option<TreeID>) -> TreeID {
+    fn create(&mut self, parent: Option<TreeID>, index: usize) -> LoroResult<TreeID> {
+        let len = self.get_children(parent).len();
+        if index > len {
+            return Err(LoroError::OutOfBound { pos: index, len });
+        }
+
         let id = TreeID::new(PeerID::MAX, self.next_counter);
         self.next_counter += 1;
         self.map.insert(
@@ -606,31 +871,58 @@ impl TreeInner {
                 .unwrap(),
         );
         self.parent_links.insert(id, parent);
-        id
+        self.children_links
+            .entry(parent)
+            .or_default()
+            .insert(index, id);
+        Ok(id)
     }
 
     fn delete(&mut self, id: TreeID) {
         self.map.remove(&id);
-        self.parent_links.remove(&id);
+        if let Some(parent) = self.parent_links.remove(&id) {
+            self.remove_from_children(parent, id);
+        }
     }
 
     fn get_parent(&self, id: TreeID) -> Option<Option<TreeID>> {
         self.parent_links.get(&id).cloned()
     }
 
-    fn mov(&mut self, target: TreeID, new_parent: Option<TreeID>) {
-        let old = self.parent_links.insert(target, new_parent);
-        assert!(old.is_some());
+    fn mov(&mut self, target: TreeID, new_parent: Option<TreeID>, index: usize) -> LoroResult<()> {
+        let old = self.parent_links.get(&target).copied();
+        let Some(old) = old else {
+            return Err(LoroTreeError::TreeNodeNotExist(target).into());
+        };
+        let len = self
+            .get_children(new_parent)
+            .iter()
+            .filter(|x| **x != target)
+            .count();
+        if index > len {
+            return Err(LoroError::OutOfBound { pos: index, len });
+        }
+
+        self.remove_from_children(old, target);
+        self.parent_links.insert(target, new_parent);
+        self.children_links
+            .entry(new_parent)
+            .or_default()
+            .insert(index, target);
+        Ok(())
     }
 
-    fn get_children(&self, id: TreeID) -> Option<Vec<TreeID>> {
-        let mut children = Vec::new();
-        for (c, p) in &self.parent_links {
-            if p.as_ref() == Some(&id) {
-                children.push(*c);
-            }
+    fn remove_from_children(&mut self, parent: Option<TreeID>, target: TreeID) {
+        if let Some(children) = self.children_links.get_mut(&parent) {
+            children.retain(|x| *x != target);
         }
-        Some(children)
+    }
+
+    fn get_children(&self, parent: Option<TreeID>) -> Vec<TreeID> {
+        self.children_links
+            .get(&parent)
+            .cloned()
+            .unwrap_or_default()
     }
 }
 
@@ -719,6 +1011,8 @@ pub enum Handler {
     Map(MapHandler),
     List(ListHandler),
     Tree(TreeHandler),
+    MovableList(MovableListHandler),
+    Counter(CounterHandler),
 }
 
 impl HandlerTrait for Handler {
@@ -728,6 +1022,8 @@ impl HandlerTrait for Handler {
             Self::Map(x) => x.is_attached(),
             Self::List(x) => x.is_attached(),
             Self::Tree(x) => x.is_attached(),
+            Self::MovableList(x) => x.is_attached(),
+            Self::Counter(x) => x.is_attached(),
         }
     }
 
@@ -737,6 +1033,8 @@ impl HandlerTrait for Handler {
             Self::Map(x) => x.attached_handler(),
             Self::List(x) => x.attached_handler(),
             Self::Tree(x) => x.attached_handler(),
+            Self::MovableList(x) => x.attached_handler(),
+            Self::Counter(x) => x.attached_handler(),
         }
     }
 
@@ -746,6 +1044,8 @@ impl HandlerTrait for Handler {
             Self::Map(x) => x.get_value(),
             Self::List(x) => x.get_value(),
             Self::Tree(x) => x.get_value(),
+            Self::MovableList(x) => x.get_value(),
+            Self::Counter(x) => x.get_value(),
         }
     }
 
@@ -755,6 +1055,8 @@ impl HandlerTrait for Handler {
             Self::Map(x) => x.get_deep_value(),
             Self::List(x) => x.get_deep_value(),
             Self::Tree(x) => x.get_deep_value(),
+            Self::MovableList(x) => x.get_deep_value(),
+            Self::Counter(x) => x.get_deep_value(),
         }
     }
 
@@ -764,6 +1066,8 @@ impl HandlerTrait for Handler {
             Self::Map(x) => x.kind(),
             Self::List(x) => x.kind(),
             Self::Tree(x) => x.kind(),
+            Self::MovableList(x) => x.kind(),
+            Self::Counter(x) => x.kind(),
         }
     }
 
@@ -773,6 +1077,8 @@ impl HandlerTrait for Handler {
             Self::Map(x) => x.to_handler(),
             Self::List(x) => x.to_handler(),
             Self::Tree(x) => x.to_handler(),
+            Self::MovableList(x) => x.to_handler(),
+            Self::Counter(x) => x.to_handler(),
         }
     }
 
@@ -787,6 +1093,8 @@ impl HandlerTrait for Handler {
             Self::Map(x) => Ok(Handler::Map(x.attach(txn, parent, self_id)?)),
             Self::List(x) => Ok(Handler::List(x.attach(txn, parent, self_id)?)),
             Self::Tree(x) => Ok(Handler::Tree(x.attach(txn, parent, self_id)?)),
+            Self::MovableList(x) => Ok(Handler::MovableList(x.attach(txn, parent, self_id)?)),
+            Self::Counter(x) => Ok(Handler::Counter(x.attach(txn, parent, self_id)?)),
         }
     }
 
@@ -796,6 +1104,8 @@ impl HandlerTrait for Handler {
             Self::Map(x) => x.get_attached().map(Handler::Map),
             Self::List(x) => x.get_attached().map(Handler::List),
             Self::Tree(x) => x.get_attached().map(Handler::Tree),
+            Self::MovableList(x) => x.get_attached().map(Handler::MovableList),
+            Self::Counter(x) => x.get_attached().map(Handler::Counter),
         }
     }
 
@@ -833,6 +1143,12 @@ impl Handler {
             ContainerType::Text => Self::Text(TextHandler {
                 inner: handler.into(),
             }),
+            ContainerType::MovableList => Self::MovableList(MovableListHandler {
+                inner: handler.into(),
+            }),
+            ContainerType::Counter => Self::Counter(CounterHandler {
+                inner: handler.into(),
+            }),
         }
     }
 
@@ -842,6 +1158,8 @@ impl Handler {
             ContainerType::Map => Self::Map(MapHandler::new_detached()),
             ContainerType::List => Self::List(ListHandler::new_detached()),
             ContainerType::Tree => Self::Tree(TreeHandler::new_detached()),
+            ContainerType::MovableList => Self::MovableList(MovableListHandler::new_detached()),
+            ContainerType::Counter => Self::Counter(CounterHandler::new_detached()),
         }
     }
 
@@ -851,6 +1169,8 @@ impl Handler {
             Self::List(x) => x.id(),
             Self::Text(x) => x.id(),
             Self::Tree(x) => x.id(),
+            Self::MovableList(x) => x.id(),
+            Self::Counter(x) => x.id(),
         }
     }
 
@@ -860,6 +1180,8 @@ impl Handler {
             Self::List(x) => x.idx(),
             Self::Text(x) => x.idx(),
             Self::Tree(x) => x.idx(),
+            Self::MovableList(x) => x.idx(),
+            Self::Counter(x) => x.idx(),
         }
     }
 
@@ -869,6 +1191,8 @@ impl Handler {
             Self::List(_) => ContainerType::List,
             Self::Text(_) => ContainerType::Text,
             Self::Tree(_) => ContainerType::Tree,
+            Self::MovableList(_) => ContainerType::MovableList,
+            Self::Counter(_) => ContainerType::Counter,
         }
     }
 
@@ -878,7 +1202,105 @@ impl Handler {
             Self::List(x) => x.get_deep_value(),
             Self::Text(x) => x.get_deep_value(),
             Self::Tree(x) => x.get_deep_value(),
+            Self::MovableList(x) => x.get_deep_value(),
+            Self::Counter(x) => x.get_deep_value(),
+        }
+    }
+
+    /// Apply the diff to the container by creating new local ops.
+    ///
+    /// The child containers inside the diff are recreated, and the mapping from
+    /// their old ids to the new ones is recorded in `container_remap`.
+    pub(crate) fn apply_diff(
+        &self,
+        diff: Diff,
+        container_remap: &mut FxHashMap<ContainerID, ContainerID>,
+    ) -> LoroResult<()> {
+        match (self, diff) {
+            (Self::Text(x), Diff::Text(delta)) => {
+                let delta: Vec<TextDelta> = delta.iter().map(TextDelta::from).collect();
+                x.apply_delta(&delta)?;
+            }
+            (Self::List(x), Diff::List(delta)) => {
+                let mut index = 0;
+                for item in delta.into_iter() {
+                    match item {
+                        DeltaItem::Retain { retain, .. } => index += retain,
+                        DeltaItem::Delete { delete, .. } => x.delete(index, delete)?,
+                        DeltaItem::Insert { insert, .. } => {
+                            for v in insert {
+                                match v {
+                                    ValueOrHandler::Value(v) => x.insert(index, v)?,
+                                    ValueOrHandler::Handler(h) => {
+                                        let new = x.insert_container(
+                                            index,
+                                            Handler::new_unattached(h.c_type()),
+                                        )?;
+                                        container_remap.insert(h.id(), new.id());
+                                    }
+                                }
+                                index += 1;
+                            }
+                        }
+                    }
+                }
+            }
+            (Self::MovableList(x), Diff::List(delta)) => {
+                let mut index = 0;
+                for item in delta.into_iter() {
+                    match item {
+                        DeltaItem::Retain { retain, .. } => index += retain,
+                        DeltaItem::Delete { delete, .. } => x.delete(index, delete)?,
+                        DeltaItem::Insert { insert, .. } => {
+                            for v in insert {
+                                match v {
+                                    ValueOrHandler::Value(v) => x.insert(index, v)?,
+                                    ValueOrHandler::Handler(h) => {
+                                        let new = x.insert_container(
+                                            index,
+                                            Handler::new_unattached(h.c_type()),
+                                        )?;
+                                        container_remap.insert(h.id(), new.id());
+                                    }
+                                }
+                                index += 1;
+                            }
+                        }
+                    }
+                }
+            }
+            (Self::Map(x), Diff::Map(delta)) => {
+                for (key, value) in delta.updated.into_iter() {
+                    match value.value {
+                        None => x.delete(&key)?,
+                        Some(ValueOrHandler::Value(v)) => x.insert(&key, v)?,
+                        Some(ValueOrHandler::Handler(h)) => {
+                            let new =
+                                x.insert_container(&key, Handler::new_unattached(h.c_type()))?;
+                            container_remap.insert(h.id(), new.id());
+                        }
+                    }
+                }
+            }
+            (Self::Tree(x), Diff::Tree(delta)) => {
+                for item in delta.diff.into_iter() {
+                    let target = item.target;
+                    match item.action {
+                        TreeExternalDiff::Create { parent, index }
+                        | TreeExternalDiff::Move { parent, index } => {
+                            // the index may be out of bound if the diff is transformed
+                            let len = x.children_num_without(parent, target);
+                            x.mov_to(target, parent, index.min(len))?;
+                        }
+                        TreeExternalDiff::Delete => x.delete(target)?,
+                    }
+                }
+            }
+            (Self::Counter(x), Diff::Counter(delta)) => x.increment(delta)?,
+            _ => unreachable!(),
         }
+
+        Ok(())
     }
 }
 
@@ -1458,7 +1880,8 @@ impl TextHandler {
                 len = start;
             }
 
-            self.mark_with_txn(txn, start, end, key.deref(), value, false)?;
+            let is_delete = value.is_null();
+            self.mark_with_txn(txn, start, end, key.deref(), value, is_delete)?;
         }
 
         Ok(())
@@ -1514,6 +1937,31 @@ impl TextHandler {
         }
     }
 
+    /// Get the authorship of the text in the given range.
+    ///
+    /// The range is in event index. The adjacent chars created by the same change
+    /// are merged into one [Attribution].
+    pub fn get_attribution(&self, range: Range<usize>) -> LoroResult<Vec<Attribution>> {
+        let inner = self.inner.try_attached_state()?;
+        inner.with_oplog_and_state(|oplog, state| {
+            let text = state.as_richtext_state_mut().unwrap();
+            let len = if cfg!(feature = "wasm") {
+                text.len_utf16()
+            } else {
+                text.len_unicode()
+            };
+            if range.start > range.end || range.end > len {
+                return Err(LoroError::OutOfBound {
+                    pos: range.end,
+                    len,
+                });
+            }
+
+            let atoms = text.get_char_ids_in_event_range(range.clone());
+            Ok(attribute_atoms(oplog, range.start, atoms))
+        })
+    }
+
     pub(crate) fn convert_entity_index_to_event_index(&self, entity_index: usize) -> usize {
         match &self.inner {
             MaybeDetached::Detached(s) => s
@@ -1578,6 +2026,11 @@ impl ListHandler {
         }
 
         let inner = self.inner.try_attached_state()?;
+        inner.with_doc_state(|state| {
+            state.check_schema(inner.container_idx, |schema| {
+                schema.check_list_insert(Inserted::Value(&v))
+            })
+        })?;
         if let Some(_container) = v.as_container() {
             return Err(LoroError::ArgErr(
                 INSERT_CONTAINER_VALUE_ARG_ERROR
@@ -1662,6 +2115,11 @@ impl ListHandler {
         }
 
         let inner = self.inner.try_attached_state()?;
+        inner.with_doc_state(|state| {
+            state.check_schema(inner.container_idx, |schema| {
+                schema.check_list_insert(Inserted::Container(child.kind()))
+            })
+        })?;
         let id = txn.next_id();
         let container_id = ContainerID::new_normal(id, child.kind());
         let v = LoroValue::Container(container_id.clone());
@@ -1887,28 +2345,51 @@ impl ListHandler {
             }
         }
     }
+
+    /// Get the authorship of the elements in the given range.
+    ///
+    /// The adjacent elements created by the same change are merged into one [Attribution].
+    pub fn get_attribution(&self, range: Range<usize>) -> LoroResult<Vec<Attribution>> {
+        let inner = self.inner.try_attached_state()?;
+        inner.with_oplog_and_state(|oplog, state| {
+            let list = state.as_list_state().unwrap();
+            let len = list.len();
+            if range.start > range.end || range.end > len {
+                return Err(LoroError::OutOfBound {
+                    pos: range.end,
+                    len,
+                });
+            }
+
+            let ids = list.get_ids_in_range(range.clone());
+            Ok(attribute_atoms(
+                oplog,
+                range.start,
+                ids.into_iter().map(|id| (id, 1)),
+            ))
+        })
+    }
 }
 
-impl MapHandler {
+impl MovableListHandler {
     /// Create a new container that is detached from the document.
     /// The edits on a detached container will not be persisted.
     /// To attach the container to the document, please insert it into an attached container.
     pub fn new_detached() -> Self {
         Self {
-            inner: MaybeDetached::new_detached(Default::default()),
+            inner: MaybeDetached::new_detached(Vec::new()),
         }
     }
 
-    pub fn insert(&self, key: &str, value: impl Into<LoroValue>) -> LoroResult<()> {
+    pub fn insert(&self, pos: usize, v: impl Into<LoroValue>) -> LoroResult<()> {
         match &self.inner {
-            MaybeDetached::Detached(m) => {
-                let mut m = m.try_lock().unwrap();
-                m.value
-                    .insert(key.into(), ValueOrHandler::Value(value.into()));
+            MaybeDetached::Detached(l) => {
+                let mut list = l.try_lock().unwrap();
+                list.value.insert(pos, ValueOrHandler::Value(v.into()));
                 Ok(())
             }
             MaybeDetached::Attached(a) => {
-                a.with_txn(|txn| self.insert_with_txn(txn, key, value.into()))
+                a.with_txn(|txn| self.insert_with_txn(txn, pos, v.into()))
             }
         }
     }
@@ -1916,11 +2397,532 @@ impl MapHandler {
     pub fn insert_with_txn(
         &self,
         txn: &mut Transaction,
-        key: &str,
-        value: LoroValue,
+        pos: usize,
+        v: LoroValue,
     ) -> LoroResult<()> {
-        if let Some(_value) = value.as_container() {
-            return Err(LoroError::ArgErr(
+        if pos > self.len() {
+            return Err(LoroError::OutOfBound {
+                pos,
+                len: self.len(),
+            });
+        }
+
+        let inner = self.inner.try_attached_state()?;
+        inner.with_doc_state(|state| {
+            state.check_schema(inner.container_idx, |schema| {
+                schema.check_list_insert(Inserted::Value(&v))
+            })
+        })?;
+        if let Some(_container) = v.as_container() {
+            return Err(LoroError::ArgErr(
+                INSERT_CONTAINER_VALUE_ARG_ERROR
+                    .to_string()
+                    .into_boxed_str(),
+            ));
+        }
+
+        let op_index = inner.with_state(|state| {
+            let list = state.as_movable_list_state().unwrap();
+            list.convert_index_to_op_index(pos).unwrap()
+        });
+
+        txn.apply_local_op(
+            inner.container_idx,
+            crate::op::RawOpContent::List(crate::container::list::list_op::ListOp::Insert {
+                slice: ListSlice::RawData(Cow::Owned(vec![v.clone()])),
+                pos: op_index,
+            }),
+            EventHint::InsertMovableList {
+                pos: pos as u32,
+                len: 1,
+            },
+            &inner.state,
+        )
+    }
+
+    pub fn push(&self, v: LoroValue) -> LoroResult<()> {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                let mut list = l.try_lock().unwrap();
+                list.value.push(ValueOrHandler::Value(v.clone()));
+                Ok(())
+            }
+            MaybeDetached::Attached(a) => a.with_txn(|txn| self.push_with_txn(txn, v)),
+        }
+    }
+
+    pub fn push_with_txn(&self, txn: &mut Transaction, v: LoroValue) -> LoroResult<()> {
+        let pos = self.len();
+        self.insert_with_txn(txn, pos, v)
+    }
+
+    pub fn pop(&self) -> LoroResult<Option<LoroValue>> {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                let mut list = l.try_lock().unwrap();
+                Ok(list.value.pop().map(|v| v.to_value()))
+            }
+            MaybeDetached::Attached(a) => a.with_txn(|txn| self.pop_with_txn(txn)),
+        }
+    }
+
+    pub fn pop_with_txn(&self, txn: &mut Transaction) -> LoroResult<Option<LoroValue>> {
+        let len = self.len();
+        if len == 0 {
+            return Ok(None);
+        }
+
+        let v = self.get(len - 1);
+        self.delete_with_txn(txn, len - 1, 1)?;
+        Ok(v)
+    }
+
+    pub fn insert_container<H: HandlerTrait>(&self, pos: usize, child: H) -> LoroResult<H> {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                let mut list = l.try_lock().unwrap();
+                list.value
+                    .insert(pos, ValueOrHandler::Handler(child.to_handler()));
+                Ok(child)
+            }
+            MaybeDetached::Attached(a) => {
+                a.with_txn(|txn| self.insert_container_with_txn(txn, pos, child))
+            }
+        }
+    }
+
+    pub fn insert_container_with_txn<H: HandlerTrait>(
+        &self,
+        txn: &mut Transaction,
+        pos: usize,
+        child: H,
+    ) -> LoroResult<H> {
+        if pos > self.len() {
+            return Err(LoroError::OutOfBound {
+                pos,
+                len: self.len(),
+            });
+        }
+
+        let inner = self.inner.try_attached_state()?;
+        inner.with_doc_state(|state| {
+            state.check_schema(inner.container_idx, |schema| {
+                schema.check_list_insert(Inserted::Container(child.kind()))
+            })
+        })?;
+        let op_index = inner.with_state(|state| {
+            let list = state.as_movable_list_state().unwrap();
+            list.convert_index_to_op_index(pos).unwrap()
+        });
+
+        let id = txn.next_id();
+        let container_id = ContainerID::new_normal(id, child.kind());
+        let v = LoroValue::Container(container_id.clone());
+        txn.apply_local_op(
+            inner.container_idx,
+            crate::op::RawOpContent::List(crate::container::list::list_op::ListOp::Insert {
+                slice: ListSlice::RawData(Cow::Owned(vec![v.clone()])),
+                pos: op_index,
+            }),
+            EventHint::InsertMovableList {
+                pos: pos as u32,
+                len: 1,
+            },
+            &inner.state,
+        )?;
+        let ans = child.attach(txn, inner, container_id)?;
+        Ok(ans)
+    }
+
+    pub fn delete(&self, pos: usize, len: usize) -> LoroResult<()> {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                let mut list = l.try_lock().unwrap();
+                list.value.drain(pos..pos + len);
+                Ok(())
+            }
+            MaybeDetached::Attached(a) => a.with_txn(|txn| self.delete_with_txn(txn, pos, len)),
+        }
+    }
+
+    pub fn delete_with_txn(&self, txn: &mut Transaction, pos: usize, len: usize) -> LoroResult<()> {
+        if len == 0 {
+            return Ok(());
+        }
+
+        if pos + len > self.len() {
+            return Err(LoroError::OutOfBound {
+                pos: pos + len,
+                len: self.len(),
+            });
+        }
+
+        let inner = self.inner.try_attached_state()?;
+        for _ in 0..len {
+            // The op index of the element may change after each deletion,
+            // so we need to query it every time.
+            let (op_index, id) = inner.with_state(|state| {
+                let list = state.as_movable_list_state().unwrap();
+                list.get_slot_at(pos).unwrap()
+            });
+
+            txn.apply_local_op(
+                inner.container_idx,
+                crate::op::RawOpContent::List(ListOp::Delete(DeleteSpanWithId::new(
+                    id.id(),
+                    op_index as isize,
+                    1,
+                ))),
+                EventHint::DeleteList(DeleteSpan::new(pos as isize, 1)),
+                &inner.state,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Move the element at `from` to `to`.
+    ///
+    /// After this op, the element will be at the index `to`.
+    pub fn mov(&self, from: usize, to: usize) -> LoroResult<()> {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                let mut list = l.try_lock().unwrap();
+                let len = list.value.len();
+                if from >= len || to >= len {
+                    return Err(LoroError::OutOfBound {
+                        pos: from.max(to),
+                        len,
+                    });
+                }
+
+                let v = list.value.remove(from);
+                list.value.insert(to, v);
+                Ok(())
+            }
+            MaybeDetached::Attached(a) => a.with_txn(|txn| self.mov_with_txn(txn, from, to)),
+        }
+    }
+
+    pub fn mov_with_txn(&self, txn: &mut Transaction, from: usize, to: usize) -> LoroResult<()> {
+        let len = self.len();
+        if from >= len || to >= len {
+            return Err(LoroError::OutOfBound {
+                pos: from.max(to),
+                len,
+            });
+        }
+
+        if from == to {
+            return Ok(());
+        }
+
+        let inner = self.inner.try_attached_state()?;
+        let (from_op_index, to_op_index, elem_id, value) = inner.with_state(|state| {
+            let list = state.as_movable_list_state().unwrap();
+            let elem_id = list.get_elem_id_at(from).unwrap();
+            let (from_op_index, _) = list.get_slot_at(from).unwrap();
+            let to_op_index = list.get_move_target_op_index(elem_id, to).unwrap();
+            (
+                from_op_index,
+                to_op_index,
+                elem_id,
+                list.get(from).cloned().unwrap(),
+            )
+        });
+        let child_diffs = match &value {
+            LoroValue::Container(c) => inner.with_doc_state(|state| {
+                let idx = state.arena.register_container(c);
+                state.get_container_state_diffs(idx)
+            }),
+            _ => Vec::new(),
+        };
+
+        txn.apply_local_op(
+            inner.container_idx,
+            crate::op::RawOpContent::List(ListOp::Move {
+                from: from_op_index as u32,
+                to: to_op_index as u32,
+                elem_id,
+            }),
+            EventHint::MoveListItem {
+                from: from as u32,
+                to: to as u32,
+                value,
+                child_diffs,
+            },
+            &inner.state,
+        )
+    }
+
+    /// Set the value of the element at `index`.
+    ///
+    /// Concurrent sets on the same element are resolved by LWW.
+    pub fn set(&self, index: usize, v: impl Into<LoroValue>) -> LoroResult<()> {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                let mut list = l.try_lock().unwrap();
+                let len = list.value.len();
+                let Some(slot) = list.value.get_mut(index) else {
+                    return Err(LoroError::OutOfBound { pos: index, len });
+                };
+                *slot = ValueOrHandler::Value(v.into());
+                Ok(())
+            }
+            MaybeDetached::Attached(a) => a.with_txn(|txn| self.set_with_txn(txn, index, v.into())),
+        }
+    }
+
+    pub fn set_with_txn(
+        &self,
+        txn: &mut Transaction,
+        index: usize,
+        v: LoroValue,
+    ) -> LoroResult<()> {
+        if index >= self.len() {
+            return Err(LoroError::OutOfBound {
+                pos: index,
+                len: self.len(),
+            });
+        }
+
+        let inner = self.inner.try_attached_state()?;
+        inner.with_doc_state(|state| {
+            state.check_schema(inner.container_idx, |schema| {
+                schema.check_list_insert(Inserted::Value(&v))
+            })
+        })?;
+        if let Some(_container) = v.as_container() {
+            return Err(LoroError::ArgErr(
+                INSERT_CONTAINER_VALUE_ARG_ERROR
+                    .to_string()
+                    .into_boxed_str(),
+            ));
+        }
+
+        let elem_id = inner.with_state(|state| {
+            let list = state.as_movable_list_state().unwrap();
+            list.get_elem_id_at(index).unwrap()
+        });
+
+        txn.apply_local_op(
+            inner.container_idx,
+            crate::op::RawOpContent::List(ListOp::Set {
+                elem_id,
+                value: v.clone(),
+            }),
+            EventHint::SetListItem {
+                index: index as u32,
+                value: v,
+            },
+            &inner.state,
+        )
+    }
+
+    pub fn get_child_handler(&self, index: usize) -> LoroResult<Handler> {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                let list = l.try_lock().unwrap();
+                let value = list.value.get(index).ok_or(LoroError::OutOfBound {
+                    pos: index,
+                    len: list.value.len(),
+                })?;
+                match value {
+                    ValueOrHandler::Handler(h) => Ok(h.clone()),
+                    _ => Err(LoroError::ArgErr(
+                        format!(
+                            "Expected container at index {}, but found {:?}",
+                            index, value
+                        )
+                        .into_boxed_str(),
+                    )),
+                }
+            }
+            MaybeDetached::Attached(a) => {
+                let Some(value) = a.with_state(|state| {
+                    state
+                        .as_movable_list_state()
+                        .as_ref()
+                        .unwrap()
+                        .get(index)
+                        .cloned()
+                }) else {
+                    return Err(LoroError::OutOfBound {
+                        pos: index,
+                        len: a.with_state(|state| state.as_movable_list_state().unwrap().len()),
+                    });
+                };
+                match value {
+                    LoroValue::Container(id) => Ok(create_handler(a, id)),
+                    _ => Err(LoroError::ArgErr(
+                        format!(
+                            "Expected container at index {}, but found {:?}",
+                            index, value
+                        )
+                        .into_boxed_str(),
+                    )),
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.inner {
+            MaybeDetached::Detached(l) => l.try_lock().unwrap().value.len(),
+            MaybeDetached::Attached(a) => {
+                a.with_state(|state| state.as_movable_list_state().unwrap().len())
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get_deep_value_with_id(&self) -> LoroResult<LoroValue> {
+        let inner = self.inner.try_attached_state()?;
+        Ok(inner.with_doc_state(|state| {
+            state.get_container_deep_value_with_id(inner.container_idx, None)
+        }))
+    }
+
+    pub fn get(&self, index: usize) -> Option<LoroValue> {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                l.try_lock().unwrap().value.get(index).map(|x| x.to_value())
+            }
+            MaybeDetached::Attached(a) => a.with_state(|state| {
+                let a = state.as_movable_list_state().unwrap();
+                a.get(index).cloned()
+            }),
+        }
+    }
+
+    /// Get value at given index, if it's a container, return a handler to the container
+    pub fn get_(&self, index: usize) -> Option<ValueOrHandler> {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                let l = l.try_lock().unwrap();
+                l.value.get(index).cloned()
+            }
+            MaybeDetached::Attached(inner) => {
+                let value = inner
+                    .with_state(|state| state.as_movable_list_state().unwrap().get(index).cloned());
+                match value {
+                    Some(LoroValue::Container(container_id)) => Some(ValueOrHandler::Handler(
+                        create_handler(inner, container_id.clone()),
+                    )),
+                    Some(value) => Some(ValueOrHandler::Value(value.clone())),
+                    None => None,
+                }
+            }
+        }
+    }
+
+    pub fn for_each<I>(&self, mut f: I)
+    where
+        I: FnMut(ValueOrHandler),
+    {
+        match &self.inner {
+            MaybeDetached::Detached(l) => {
+                let l = l.try_lock().unwrap();
+                for v in l.value.iter() {
+                    f(v.clone())
+                }
+            }
+            MaybeDetached::Attached(inner) => {
+                inner.with_state(|state| {
+                    let a = state.as_movable_list_state().unwrap();
+                    for v in a.iter() {
+                        match v {
+                            LoroValue::Container(c) => {
+                                f(ValueOrHandler::Handler(create_handler(inner, c.clone())));
+                            }
+                            value => {
+                                f(ValueOrHandler::Value(value.clone()));
+                            }
+                        }
+                    }
+                });
+            }
+        }
+    }
+
+    /// Get the cursor of the element at `pos`.
+    ///
+    /// The cursor follows the element when it's moved.
+    pub fn get_cursor(&self, pos: usize, side: Side) -> Option<Cursor> {
+        match &self.inner {
+            MaybeDetached::Detached(_) => None,
+            MaybeDetached::Attached(a) => {
+                let (id, len) = a.with_state(|s| {
+                    let l = s.as_movable_list_state().unwrap();
+                    (l.get_elem_id_at(pos), l.len())
+                });
+
+                if len == 0 {
+                    return Some(Cursor {
+                        id: None,
+                        container: self.id(),
+                        side: if side == Side::Middle {
+                            Side::Left
+                        } else {
+                            side
+                        },
+                    });
+                }
+
+                if len <= pos {
+                    return Some(Cursor {
+                        id: None,
+                        container: self.id(),
+                        side: Side::Right,
+                    });
+                }
+
+                let id = id?;
+                Some(Cursor {
+                    id: Some(id),
+                    container: self.id(),
+                    side,
+                })
+            }
+        }
+    }
+}
+
+impl MapHandler {
+    /// Create a new container that is detached from the document.
+    /// The edits on a detached container will not be persisted.
+    /// To attach the container to the document, please insert it into an attached container.
+    pub fn new_detached() -> Self {
+        Self {
+            inner: MaybeDetached::new_detached(Default::default()),
+        }
+    }
+
+    pub fn insert(&self, key: &str, value: impl Into<LoroValue>) -> LoroResult<()> {
+        match &self.inner {
+            MaybeDetached::Detached(m) => {
+                let mut m = m.try_lock().unwrap();
+                m.value
+                    .insert(key.into(), ValueOrHandler::Value(value.into()));
+                Ok(())
+            }
+            MaybeDetached::Attached(a) => {
+                a.with_txn(|txn| self.insert_with_txn(txn, key, value.into()))
+            }
+        }
+    }
+
+    pub fn insert_with_txn(
+        &self,
+        txn: &mut Transaction,
+        key: &str,
+        value: LoroValue,
+    ) -> LoroResult<()> {
+        if let Some(_value) = value.as_container() {
+            return Err(LoroError::ArgErr(
                 INSERT_CONTAINER_VALUE_ARG_ERROR
                     .to_string()
                     .into_boxed_str(),
@@ -1933,6 +2935,11 @@ impl MapHandler {
         }
 
         let inner = self.inner.try_attached_state()?;
+        inner.with_doc_state(|state| {
+            state.check_schema(inner.container_idx, |schema| {
+                schema.check_map_insert(key, Inserted::Value(&value))
+            })
+        })?;
         txn.apply_local_op(
             inner.container_idx,
             crate::op::RawOpContent::Map(crate::container::map::MapSet {
@@ -1973,6 +2980,11 @@ impl MapHandler {
         child: H,
     ) -> LoroResult<H> {
         let inner = self.inner.try_attached_state()?;
+        inner.with_doc_state(|state| {
+            state.check_schema(inner.container_idx, |schema| {
+                schema.check_map_insert(key, Inserted::Container(child.kind()))
+            })
+        })?;
         let id = txn.next_id();
         let container_id = ContainerID::new_normal(id, child.kind());
         txn.apply_local_op(
@@ -2184,6 +3196,7 @@ impl TreeHandler {
             crate::op::RawOpContent::Tree(TreeOp {
                 target,
                 parent: Some(TreeID::delete_root()),
+                position: None,
             }),
             EventHint::Tree(TreeDiffItem {
                 target,
@@ -2193,66 +3206,215 @@ impl TreeHandler {
         )
     }
 
+    /// Create a new node as the last child of `parent`.
     pub fn create<T: Into<Option<TreeID>>>(&self, parent: T) -> LoroResult<TreeID> {
+        let parent = parent.into();
+        let index = self.children(parent).len();
+        self.create_at(parent, index)
+    }
+
+    pub fn create_with_txn<T: Into<Option<TreeID>>>(
+        &self,
+        txn: &mut Transaction,
+        parent: T,
+    ) -> LoroResult<TreeID> {
+        let parent = parent.into();
+        let index = self.children(parent).len();
+        self.create_at_with_txn(txn, parent, index)
+    }
+
+    /// Create a new node as the `index`-th child of `parent`.
+    pub fn create_at<T: Into<Option<TreeID>>>(
+        &self,
+        parent: T,
+        index: usize,
+    ) -> LoroResult<TreeID> {
         match &self.inner {
             MaybeDetached::Detached(t) => {
                 let mut t = t.try_lock().unwrap();
-                Ok(t.value.create(parent.into()))
+                t.value.create(parent.into(), index)
+            }
+            MaybeDetached::Attached(a) => {
+                a.with_txn(|txn| self.create_at_with_txn(txn, parent, index))
             }
-            MaybeDetached::Attached(a) => a.with_txn(|txn| self.create_with_txn(txn, parent)),
         }
     }
 
-    pub fn create_with_txn<T: Into<Option<TreeID>>>(
+    pub fn create_at_with_txn<T: Into<Option<TreeID>>>(
         &self,
         txn: &mut Transaction,
         parent: T,
+        index: usize,
     ) -> LoroResult<TreeID> {
         let inner = self.inner.try_attached_state()?;
+        inner.with_doc_state(|state| state.check_schema(inner.container_idx, |_| Ok(())))?;
         let parent: Option<TreeID> = parent.into();
+        let position = self.generate_position_at(txn, None, parent, index)?;
+        // the id is taken after the position is generated, because it may emit move ops
         let tree_id = TreeID::from_id(txn.next_id());
-        let event_hint = TreeDiffItem {
-            target: tree_id,
-            action: TreeExternalDiff::Create(parent),
-        };
-        txn.apply_local_op(
-            inner.container_idx,
-            crate::op::RawOpContent::Tree(TreeOp {
-                target: tree_id,
-                parent,
-            }),
-            EventHint::Tree(event_hint),
-            &inner.state,
-        )?;
+        self.apply_move_with_position(txn, tree_id, parent, position, true)?;
         Ok(tree_id)
     }
 
+    /// Move `target` to be the last child of `parent`.
     pub fn mov<T: Into<Option<TreeID>>>(&self, target: TreeID, parent: T) -> LoroResult<()> {
+        let parent = parent.into();
+        let index = self.children_num_without(parent, target);
+        self.mov_to(target, parent, index)
+    }
+
+    pub fn mov_with_txn<T: Into<Option<TreeID>>>(
+        &self,
+        txn: &mut Transaction,
+        target: TreeID,
+        parent: T,
+    ) -> LoroResult<()> {
+        let parent = parent.into();
+        let index = self.children_num_without(parent, target);
+        self.mov_to_with_txn(txn, target, parent, index)
+    }
+
+    /// Move `target` to be the `index`-th child of `parent`.
+    ///
+    /// The index is the one after the move, i.e. `target` itself is not counted
+    /// if it's already a child of `parent`.
+    pub fn mov_to<T: Into<Option<TreeID>>>(
+        &self,
+        target: TreeID,
+        parent: T,
+        index: usize,
+    ) -> LoroResult<()> {
         match &self.inner {
             MaybeDetached::Detached(t) => {
                 let mut t = t.try_lock().unwrap();
-                t.value.mov(target, parent.into());
-                Ok(())
+                t.value.mov(target, parent.into(), index)
+            }
+            MaybeDetached::Attached(a) => {
+                a.with_txn(|txn| self.mov_to_with_txn(txn, target, parent, index))
             }
-            MaybeDetached::Attached(a) => a.with_txn(|txn| self.mov_with_txn(txn, target, parent)),
         }
     }
 
-    pub fn mov_with_txn<T: Into<Option<TreeID>>>(
+    pub fn mov_to_with_txn<T: Into<Option<TreeID>>>(
         &self,
         txn: &mut Transaction,
         target: TreeID,
         parent: T,
+        index: usize,
     ) -> LoroResult<()> {
         let parent = parent.into();
         let inner = self.inner.try_attached_state()?;
+        // check it before generating the position, which may emit move ops for the siblings
+        let (is_cyclic, is_deleted) = inner.with_state(|state| {
+            let a = state.as_tree_state().unwrap();
+            (
+                parent.is_some_and(|p| a.is_ancestor_of(&target, &TreeParentId::Node(p))),
+                a.parent(target).is_none(),
+            )
+        });
+        if is_cyclic {
+            return Err(LoroTreeError::CyclicMoveError.into());
+        }
+
+        let position = self.generate_position_at(txn, Some(target), parent, index)?;
+        // moving a deleted node brings it back
+        self.apply_move_with_position(txn, target, parent, position, is_deleted)
+    }
+
+    /// Move `target` to be the sibling right before `before`.
+    pub fn mov_before(&self, target: TreeID, before: TreeID) -> LoroResult<()> {
+        let (parent, index) = self.sibling_index_without(before, target)?;
+        self.mov_to(target, parent, index)
+    }
+
+    /// Move `target` to be the sibling right after `after`.
+    pub fn mov_after(&self, target: TreeID, after: TreeID) -> LoroResult<()> {
+        let (parent, index) = self.sibling_index_without(after, target)?;
+        self.mov_to(target, parent, index + 1)
+    }
+
+    /// Get the parent of `node` and the index of `node` among its siblings except `target`.
+    fn sibling_index_without(
+        &self,
+        node: TreeID,
+        target: TreeID,
+    ) -> LoroResult<(Option<TreeID>, usize)> {
+        if node == target {
+            return Err(LoroTreeError::CyclicMoveError.into());
+        }
+
+        let parent = self
+            .get_node_parent(node)
+            .ok_or(LoroTreeError::TreeNodeNotExist(node))?;
+        let index = self
+            .children(parent)
+            .into_iter()
+            .filter(|x| *x != target)
+            .position(|x| x == node)
+            .unwrap();
+        Ok((parent, index))
+    }
+
+    fn children_num_without(&self, parent: Option<TreeID>, target: TreeID) -> usize {
+        self.children(parent)
+            .into_iter()
+            .filter(|x| *x != target)
+            .count()
+    }
+
+    /// Generate the position for `target` so that it will be the `index`-th child of `parent`.
+    ///
+    /// Concurrent edits may leave some siblings with the same position. If there is no room
+    /// between the siblings around `index`, those siblings are moved to new positions first.
+    fn generate_position_at(
+        &self,
+        txn: &mut Transaction,
+        target: Option<TreeID>,
+        parent: Option<TreeID>,
+        index: usize,
+    ) -> LoroResult<FractionalIndex> {
+        let inner = self.inner.try_attached_state()?;
+        let (lower, same, upper) = inner.with_state(|state| {
+            let a = state.as_tree_state().unwrap();
+            a.get_siblings_around(&parent.into(), target, index)
+        })?;
+        let mut positions =
+            FractionalIndex::generate_n_between(lower.as_ref(), upper.as_ref(), same.len() + 1)
+                .unwrap();
+        let position = positions.remove(0);
+        for (child, child_position) in same.into_iter().zip(positions) {
+            self.apply_move_with_position(txn, child, parent, child_position, false)?;
+        }
+
+        Ok(position)
+    }
+
+    fn apply_move_with_position(
+        &self,
+        txn: &mut Transaction,
+        target: TreeID,
+        parent: Option<TreeID>,
+        position: FractionalIndex,
+        is_create: bool,
+    ) -> LoroResult<()> {
+        let inner = self.inner.try_attached_state()?;
+        let index = inner.with_state(|state| {
+            let a = state.as_tree_state().unwrap();
+            a.get_index_by_position(&target, &parent.into(), &position)
+        });
+        let action = if is_create {
+            TreeExternalDiff::Create { parent, index }
+        } else {
+            TreeExternalDiff::Move { parent, index }
+        };
         txn.apply_local_op(
             inner.container_idx,
-            crate::op::RawOpContent::Tree(TreeOp { target, parent }),
-            EventHint::Tree(TreeDiffItem {
+            crate::op::RawOpContent::Tree(TreeOp {
                 target,
-                action: TreeExternalDiff::Move(parent),
+                parent,
+                position: Some(position),
             }),
+            EventHint::Tree(TreeDiffItem { target, action }),
             &inner.state,
         )
     }
@@ -2296,15 +3458,17 @@ impl TreeHandler {
         }
     }
 
-    pub fn children(&self, target: TreeID) -> Vec<TreeID> {
+    /// Get the children of `parent` in order. If `parent` is `None`, return the roots.
+    pub fn children<T: Into<Option<TreeID>>>(&self, parent: T) -> Vec<TreeID> {
+        let parent = parent.into();
         match &self.inner {
             MaybeDetached::Detached(t) => {
                 let t = t.try_lock().unwrap();
-                t.value.get_children(target).unwrap()
+                t.value.get_children(parent)
             }
             MaybeDetached::Attached(a) => a.with_state(|state| {
                 let a = state.as_tree_state().unwrap();
-                a.get_children(&TreeParentId::Node(target))
+                a.get_children(&parent.into())
             }),
         }
     }
@@ -2349,6 +3513,65 @@ impl TreeHandler {
     }
 }
 
+impl CounterHandler {
+    /// Create a new container that is detached from the document.
+    /// The edits on a detached container will not be persisted.
+    /// To attach the container to the document, please insert it into an attached container.
+    pub fn new_detached() -> Self {
+        Self {
+            inner: MaybeDetached::new_detached(0.),
+        }
+    }
+
+    /// Increment the counter by the given value. Both integers and floats are accepted,
+    /// except NaN and infinite values.
+    pub fn increment(&self, n: impl Into<LoroValue>) -> LoroResult<()> {
+        let n = counter_delta(n.into())?;
+        match &self.inner {
+            MaybeDetached::Detached(c) => {
+                c.try_lock().unwrap().value += n;
+                Ok(())
+            }
+            MaybeDetached::Attached(a) => a.with_txn(|txn| self.increment_with_txn(txn, n)),
+        }
+    }
+
+    /// Decrement the counter by the given value. Both integers and floats are accepted.
+    pub fn decrement(&self, n: impl Into<LoroValue>) -> LoroResult<()> {
+        let n = counter_delta(n.into())?;
+        self.increment(-n)
+    }
+
+    /// Increment the counter in the given transaction. NaN and infinite values are rejected.
+    pub fn increment_with_txn(&self, txn: &mut Transaction, n: f64) -> LoroResult<()> {
+        let n = counter_delta(LoroValue::Double(n))?;
+        let inner = self.inner.try_attached_state()?;
+        txn.apply_local_op(
+            inner.container_idx,
+            crate::op::RawOpContent::Counter(n),
+            EventHint::Counter(n),
+            &inner.state,
+        )
+    }
+}
+
+fn counter_delta(n: LoroValue) -> LoroResult<f64> {
+    match n {
+        LoroValue::I64(n) => Ok(n as f64),
+        LoroValue::Double(n) if n.is_finite() => Ok(n),
+        LoroValue::Double(_) => Err(LoroError::ArgErr(
+            "Counter doesn't accept NaN or infinite values"
+                .to_string()
+                .into_boxed_str(),
+        )),
+        _ => Err(LoroError::ArgErr(
+            "Counter only accepts i64 or f64 values"
+                .to_string()
+                .into_boxed_str(),
+        )),
+    }
+}
+
 #[inline(always)]
 fn with_txn<R>(
     txn: &Weak<Mutex<Option<Transaction>>>,
@@ -2614,4 +3837,18 @@ mod test {
             ])
         )
     }
+
+    #[test]
+    fn counter_increment_with_txn() {
+        let loro = LoroDoc::new();
+        let mut txn = loro.txn().unwrap();
+        let counter = txn.get_counter("counter");
+        counter.increment_with_txn(&mut txn, 1.5).unwrap();
+        assert!(counter.increment_with_txn(&mut txn, f64::NAN).is_err());
+        assert!(counter
+            .increment_with_txn(&mut txn, f64::NEG_INFINITY)
+            .is_err());
+        txn.commit().unwrap();
+        assert_eq!(counter.get_value(), 1.5.into());
+    }
 }
@@ -11,15 +11,21 @@ pub mod diff_calc;
 pub mod handler;
 pub use event::{ContainerDiff, DiffEvent, DocDiff};
 pub use fxhash::FxHashMap;
-pub use handler::{BasicHandler, HandlerTrait, ListHandler, MapHandler, TextHandler, TreeHandler};
+pub use handler::{
+    BasicHandler, CounterHandler, HandlerTrait, ListHandler, MapHandler, MovableListHandler,
+    TextHandler, TreeHandler,
+};
 pub use loro::LoroDoc;
 pub use oplog::OpLog;
 pub use state::DocState;
+pub use undo::UndoManager;
+pub mod awareness;
 pub mod cursor;
 pub mod loro;
 pub mod obs;
 pub mod oplog;
 pub mod txn;
+pub mod undo;
 
 pub mod change;
 pub mod configure;
@@ -28,6 +34,7 @@ pub mod dag;
 pub mod encoding;
 pub mod id;
 pub mod op;
+pub mod schema;
 pub mod version;
 
 mod error;
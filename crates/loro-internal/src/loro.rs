@@ -10,12 +10,13 @@ use std::{
     },
 };
 
-use loro_common::{ContainerID, ContainerType, LoroResult, LoroValue, ID};
+use fxhash::{FxHashMap, FxHashSet};
+use loro_common::{ContainerID, ContainerType, IdSpan, LoroResult, LoroValue, ID};
 use rle::HasLength;
 
 use crate::{
     arena::SharedArena,
-    change::Timestamp,
+    change::{ChangeMeta, ChangeOp, Timestamp},
     configure::Configure,
     container::{
         idx::ContainerIdx, list::list_op::InnerListOp, richtext::config::StyleConfigMap,
@@ -24,13 +25,19 @@ use crate::{
     cursor::{AbsolutePosition, CannotFindRelativePosition, Cursor, PosQueryResult},
     dag::DagUtils,
     encoding::{
-        decode_snapshot, export_snapshot, parse_header_and_body, EncodeMode, ParsedHeaderAndBody,
+        self, decode_snapshot, export_snapshot,
+        json_schema::{self, JsonSchema},
+        parse_header_and_body, EncodeMode, ImportPreview, ImportStatus, ParsedHeaderAndBody,
+        PendingChangesSummary,
+    },
+    event::{str_to_path, ContainerDiff, EventTriggerKind, Index},
+    handler::{
+        CounterHandler, Handler, MovableListHandler, TextHandler, TreeHandler, ValueOrHandler,
     },
-    event::{str_to_path, EventTriggerKind, Index},
-    handler::{Handler, TextHandler, TreeHandler, ValueOrHandler},
     id::PeerID,
     op::InnerContent,
-    oplog::dag::FrontiersNotIncluded,
+    oplog::{dag::FrontiersNotIncluded, ImportValidator},
+    schema::Schema,
     version::Frontiers,
     HandlerTrait, InternalString, LoroError, VersionVector,
 };
@@ -38,13 +45,22 @@ use crate::{
 use super::{
     diff_calc::DiffCalculator,
     event::InternalDocDiff,
-    obs::{Observer, SubID, Subscriber},
+    obs::{LocalUpdateCallback, Observer, SubID, Subscriber},
     oplog::OpLog,
     state::DocState,
     txn::Transaction,
     ListHandler, MapHandler,
 };
 
+/// The name of the reserved root map that stores the version tags.
+pub(crate) const VERSION_TAGS_ROOT_NAME: &str = "__loro_version_tags";
+
+/// Whether the container is the reserved root map of the version tags, which is hidden
+/// from the doc value.
+pub fn is_version_tags_root(id: &ContainerID) -> bool {
+    matches!(id, ContainerID::Root { name, .. } if &**name == VERSION_TAGS_ROOT_NAME)
+}
+
 /// `LoroApp` serves as the library's primary entry point.
 /// It's constituted by an [OpLog] and an [AppState].
 ///
@@ -83,14 +99,19 @@ impl Default for LoroDoc {
 
 impl LoroDoc {
     pub fn new() -> Self {
-        let oplog = OpLog::new();
+        Self::from_oplog(OpLog::new())
+    }
+
+    fn from_oplog(oplog: OpLog) -> Self {
         let arena = oplog.arena.clone();
         let global_txn = Arc::new(Mutex::new(None));
         let config: Configure = oplog.configure.clone();
+        let oplog = Arc::new(Mutex::new(oplog));
         // share arena
         let state = DocState::new_arc(arena.clone(), Arc::downgrade(&global_txn), config.clone());
+        state.lock().unwrap().set_oplog(Arc::downgrade(&oplog));
         Self {
-            oplog: Arc::new(Mutex::new(oplog)),
+            oplog,
             state,
             config,
             detached: AtomicBool::new(false),
@@ -131,6 +152,15 @@ impl LoroDoc {
         *self.config.text_style_config.try_write().unwrap() = text_style;
     }
 
+    /// Set the schema that the local edits are checked against. `None` removes it.
+    ///
+    /// The edits that violate the schema fail with [LoroError::SchemaViolation]
+    /// before any op is recorded. The imported changes are not checked.
+    #[inline]
+    pub fn set_schema(&self, schema: Option<Schema>) {
+        self.config.set_schema(schema);
+    }
+
     /// Create a doc with auto commit enabled.
     #[inline]
     pub fn new_auto_commit() -> Self {
@@ -165,14 +195,17 @@ impl LoroDoc {
     }
 
     #[allow(unused)]
-    pub(super) fn from_existing(oplog: OpLog, state: DocState) -> Self {
+    pub(super) fn from_existing(oplog: OpLog, mut state: DocState) -> Self {
         let obs = Observer::new(oplog.arena.clone());
+        let arena = oplog.arena.clone();
+        let oplog = Arc::new(Mutex::new(oplog));
+        state.set_oplog(Arc::downgrade(&oplog));
         Self {
-            arena: oplog.arena.clone(),
+            arena,
             observer: Arc::new(obs),
             config: Default::default(),
             auto_commit: AtomicBool::new(false),
-            oplog: Arc::new(Mutex::new(oplog)),
+            oplog,
             state: Arc::new(Mutex::new(state)),
             diff_calculator: Arc::new(Mutex::new(DiffCalculator::new())),
             txn: Arc::new(Mutex::new(None)),
@@ -280,6 +313,17 @@ impl LoroDoc {
         self.commit_with(None, None, true)
     }
 
+    /// Set the commit message of the next change.
+    ///
+    /// It's stored on the change and encoded in both updates and snapshots.
+    /// This method only has effect when `auto_commit` is true.
+    pub fn set_next_commit_message(&self, message: &str) {
+        let mut txn = self.txn.try_lock().unwrap();
+        if let Some(txn) = txn.as_mut() {
+            txn.set_message(message);
+        }
+    }
+
     /// Commit the cumulative auto commit transaction.
     /// This method only has effect when `auto_commit` is true.
     /// If `immediate_renew` is true, a new transaction will be created after the old one is committed
@@ -360,6 +404,8 @@ impl LoroDoc {
         );
 
         let obs = self.observer.clone();
+        let oplog = self.oplog.clone();
+        let start_id = txn.next_id();
         txn.set_on_commit(Box::new(move |state| {
             let mut state = state.try_lock().unwrap();
             let events = state.take_events();
@@ -367,6 +413,21 @@ impl LoroDoc {
             for event in events {
                 obs.emit(event);
             }
+
+            if obs.has_local_update_subscribers() {
+                // the oplog is unlocked after the commit, but another thread may be using it
+                let oplog = oplog.lock().unwrap();
+                let mut vv = oplog.vv().clone();
+                if vv.get(&start_id.peer).copied().unwrap_or(0) <= start_id.counter {
+                    // nothing is committed
+                    return;
+                }
+
+                vv.insert(start_id.peer, start_id.counter);
+                let bytes = oplog.export_from(&vv);
+                drop(oplog);
+                obs.emit_local_update(&bytes);
+            }
         }));
 
         Ok(txn)
@@ -394,19 +455,65 @@ impl LoroDoc {
         ans
     }
 
+    /// Export the changes that are in `end_vv` but not in `start_vv` in the JSON format.
+    pub fn export_json_updates(
+        &self,
+        start_vv: &VersionVector,
+        end_vv: &VersionVector,
+    ) -> JsonSchema {
+        self.commit_then_stop();
+        let ans = json_schema::export_json(&self.oplog.lock().unwrap(), start_vv, end_vv);
+        self.renew_txn_if_auto_commit();
+        ans
+    }
+
+    /// Import the changes in the JSON format exported by [LoroDoc::export_json_updates].
+    pub fn import_json_updates(&self, json: JsonSchema) -> LoroResult<()> {
+        self.commit_then_stop();
+        let ans = self
+            .update_oplog_and_apply_delta_to_state_if_needed(
+                |oplog| encoding::decode_json_updates(oplog, json),
+                Default::default(),
+            )
+            .map(|_| self.emit_events());
+        self.renew_txn_if_auto_commit();
+        ans
+    }
+
     #[inline(always)]
-    pub fn import(&self, bytes: &[u8]) -> Result<(), LoroError> {
+    pub fn import(&self, bytes: &[u8]) -> Result<ImportStatus, LoroError> {
         self.import_with(bytes, Default::default())
     }
 
     #[inline]
-    pub fn import_with(&self, bytes: &[u8], origin: InternalString) -> Result<(), LoroError> {
+    pub fn import_with(
+        &self,
+        bytes: &[u8],
+        origin: InternalString,
+    ) -> Result<ImportStatus, LoroError> {
         self.commit_then_stop();
-        let ans = self._import_with(bytes, origin);
+        let old_vv = self.oplog_vv();
+        let ans = self
+            ._import_with(bytes, origin)
+            .and_then(|_| import_status(&self.oplog.lock().unwrap(), &old_vv, bytes));
         self.renew_txn_if_auto_commit();
         ans
     }
 
+    /// Get the pending changes and the missing dependencies they are waiting for.
+    pub fn pending_changes_summary(&self) -> PendingChangesSummary {
+        self.oplog.lock().unwrap().pending_changes_summary()
+    }
+
+    /// Set the validator that checks the changes to import. `None` removes it.
+    ///
+    /// The validator is called with each new change of an imported blob and its ops before
+    /// any of them is imported. If it rejects a change, the import fails with
+    /// [LoroError::ImportRejected] and the doc is not changed. Local changes are not checked.
+    pub fn set_import_validator(&self, validator: Option<ImportValidator>) {
+        self.oplog.lock().unwrap().import_validator = validator;
+    }
+
     fn _import_with(&self, bytes: &[u8], origin: InternalString) -> Result<(), LoroError> {
         let parsed = parse_header_and_body(bytes)?;
         match parsed.mode.is_snapshot() {
@@ -430,7 +537,10 @@ impl LoroDoc {
                 if self.can_reset_with_snapshot() {
                     tracing::info!("Init by snapshot {}", self.peer_id());
                     decode_snapshot(self, parsed.mode, parsed.body)?;
-                } else if parsed.mode == EncodeMode::Snapshot {
+                } else if matches!(
+                    parsed.mode,
+                    EncodeMode::Snapshot | EncodeMode::ShallowSnapshot
+                ) {
                     tracing::info!("Import updates to {}", self.peer_id());
                     self.update_oplog_and_apply_delta_to_state_if_needed(
                         |oplog| oplog.decode(parsed),
@@ -445,7 +555,8 @@ impl LoroDoc {
                     let updates = app.export_from(oplog.vv());
                     drop(oplog);
 
-                    return self.import_with(&updates, origin);
+                    self.import_with(&updates, origin)?;
+                    return Ok(());
                 }
             }
         };
@@ -543,13 +654,135 @@ impl LoroDoc {
         }
     }
 
+    /// Export the snapshot of the doc.
+    ///
+    /// If the doc is shallow, it's a shallow snapshot that starts from the same version.
     pub fn export_snapshot(&self) -> Vec<u8> {
         self.commit_then_stop();
-        let ans = export_snapshot(self);
+        let ans = self.export_snapshot_without_txn();
+        self.renew_txn_if_auto_commit();
+        ans
+    }
+
+    fn export_snapshot_without_txn(&self) -> Vec<u8> {
+        let root = {
+            let oplog = self.oplog.lock().unwrap();
+            if !oplog.is_shallow() {
+                drop(oplog);
+                return export_snapshot(self);
+            }
+
+            oplog.shallow_since_frontiers().clone()
+        };
+
+        self.export_shallow_snapshot_without_txn(&root).unwrap()
+    }
+
+    /// Export the state at the given version and the updates after it.
+    ///
+    /// The history before the version is trimmed, so the doc imported from it cannot
+    /// checkout to the versions before it. If there are changes after the version that
+    /// are concurrent to it, the history starts from an earlier version that all of them
+    /// depend on.
+    pub fn export_shallow_snapshot(&self, frontiers: &Frontiers) -> LoroResult<Vec<u8>> {
+        self.commit_then_stop();
+        let ans = self.export_shallow_snapshot_without_txn(frontiers);
+        self.renew_txn_if_auto_commit();
+        ans
+    }
+
+    fn export_shallow_snapshot_without_txn(&self, frontiers: &Frontiers) -> LoroResult<Vec<u8>> {
+        let oplog = self.oplog.lock().unwrap();
+        check_version_in_history(&oplog, frontiers)?;
+        let root = oplog.calc_shallow_root(frontiers);
+        if root.is_empty() {
+            drop(oplog);
+            return Ok(export_snapshot(self));
+        }
+
+        let state = self.shadow_state_at(&oplog, &root, None);
+        Ok(encoding::export_shallow_snapshot(&oplog, &state, None))
+    }
+
+    /// Export a snapshot that only contains the given containers and their descendants.
+    ///
+    /// It's a shallow snapshot of the latest version, so it carries the ops that form
+    /// the states of these containers but none of the history before. The doc imported
+    /// from it can import the later updates of this doc. The child containers that are
+    /// not roots can be accessed by their ids in that doc.
+    pub fn export_snapshot_of(&self, containers: &[ContainerID]) -> LoroResult<Vec<u8>> {
+        self.commit_then_stop();
+        let ans = self.export_snapshot_of_without_txn(containers);
         self.renew_txn_if_auto_commit();
         ans
     }
 
+    fn export_snapshot_of_without_txn(&self, containers: &[ContainerID]) -> LoroResult<Vec<u8>> {
+        let mut selected = FxHashSet::default();
+        for id in containers {
+            match self.arena.id_to_idx(id) {
+                Some(idx) => {
+                    selected.insert(idx);
+                }
+                // a root container that has never been used is empty
+                None if id.is_root() => {}
+                None => {
+                    return Err(LoroError::NotFoundError(
+                        format!("Cannot find the container {}", id).into_boxed_str(),
+                    ))
+                }
+            }
+        }
+
+        let filter = |idx: ContainerIdx| {
+            let mut current = Some(idx);
+            while let Some(c) = current {
+                if selected.contains(&c) {
+                    return true;
+                }
+                current = self.arena.get_parent(c);
+            }
+            false
+        };
+
+        let oplog = self.oplog.lock().unwrap();
+        let root = oplog.calc_shallow_root(oplog.frontiers());
+        if root.is_empty() {
+            // the doc is empty
+            drop(oplog);
+            return Ok(export_snapshot(self));
+        }
+
+        let state = self.shadow_state_at(&oplog, &root, Some(&filter));
+        Ok(encoding::export_shallow_snapshot(
+            &oplog,
+            &state,
+            Some(&filter),
+        ))
+    }
+
+    /// Whether the history before some version is trimmed
+    #[inline]
+    pub fn is_shallow(&self) -> bool {
+        self.oplog.lock().unwrap().is_shallow()
+    }
+
+    /// The version vector where the history starts.
+    ///
+    /// It's empty if the doc is not shallow.
+    #[inline]
+    pub fn shallow_since_vv(&self) -> VersionVector {
+        self.oplog.lock().unwrap().shallow_since_vv().clone()
+    }
+
+    /// The frontiers where the history starts.
+    ///
+    /// It's empty if the doc is not shallow.
+    #[inline]
+    pub fn shallow_since_frontiers(&self) -> Frontiers {
+        self.oplog.lock().unwrap().shallow_since_frontiers().clone()
+    }
+
     /// Get the version vector of the current OpLog
     #[inline]
     pub fn oplog_vv(&self) -> VersionVector {
@@ -613,6 +846,35 @@ impl LoroDoc {
         .unwrap()
     }
 
+    /// id can be a str, ContainerID, or ContainerIdRaw.
+    /// if it's str it will use Root container, which will not be None
+    #[inline]
+    pub fn get_movable_list<I: IntoContainerId>(&self, id: I) -> MovableListHandler {
+        let id = id.into_container_id(&self.arena, ContainerType::MovableList);
+        Handler::new_attached(
+            id,
+            self.arena.clone(),
+            self.get_global_txn(),
+            Arc::downgrade(&self.state),
+        )
+        .into_movable_list()
+        .unwrap()
+    }
+    /// id can be a str, ContainerID, or ContainerIdRaw.
+    /// if it's str it will use Root container, which will not be None
+    #[inline]
+    pub fn get_counter<I: IntoContainerId>(&self, id: I) -> CounterHandler {
+        let id = id.into_container_id(&self.arena, ContainerType::Counter);
+        Handler::new_attached(
+            id,
+            self.arena.clone(),
+            self.get_global_txn(),
+            Arc::downgrade(&self.state),
+        )
+        .into_counter()
+        .unwrap()
+    }
+
     /// id can be a str, ContainerID, or ContainerIdRaw.
     /// if it's str it will use Root container, which will not be None
     #[inline]
@@ -697,6 +959,15 @@ impl LoroDoc {
         self.observer.subscribe(container_id, callback)
     }
 
+    /// Subscribe the local commits.
+    ///
+    /// The callback receives an update blob for each local commit, which only contains
+    /// the new change. It can be imported by the other peers.
+    #[inline]
+    pub fn subscribe_local_update(&self, callback: LocalUpdateCallback) -> SubID {
+        self.observer.subscribe_local_update(callback)
+    }
+
     #[inline]
     pub fn unsubscribe(&self, id: SubID) {
         self.observer.unsubscribe(id);
@@ -765,16 +1036,255 @@ impl LoroDoc {
     /// This will make the current [DocState] detached from the latest version of [OpLog].
     /// Any further import will not be reflected on the [DocState], until user call [LoroDoc::attach()]
     pub fn checkout(&self, frontiers: &Frontiers) -> LoroResult<()> {
+        self.checkout_without_emitting(frontiers)?;
+        self.emit_events();
+        Ok(())
+    }
+
+    /// Get the version of the doc at the given timestamp.
+    ///
+    /// It includes all the changes whose timestamps are not greater than `timestamp`,
+    /// and the changes they depend on, even if their timestamps are greater.
+    /// The timestamps are only recorded when [LoroDoc::set_record_timestamp] is enabled
+    /// or they are set manually when committing.
+    pub fn frontiers_at_timestamp(&self, timestamp: Timestamp) -> Frontiers {
+        self.oplog.lock().unwrap().frontiers_at_timestamp(timestamp)
+    }
+
+    /// Checkout [DocState] to the version at the given timestamp.
+    ///
+    /// See [LoroDoc::frontiers_at_timestamp] and [LoroDoc::checkout].
+    pub fn checkout_at_timestamp(&self, timestamp: Timestamp) -> LoroResult<()> {
+        self.commit_then_stop();
+        let frontiers = self.frontiers_at_timestamp(timestamp);
+        self.checkout(&frontiers)
+    }
+
+    /// Get the metadata of all the changes in causal order.
+    pub fn changes(&self) -> Vec<ChangeMeta> {
+        let oplog = self.oplog.lock().unwrap();
+        oplog.change_metas_between(&Default::default(), oplog.vv())
+    }
+
+    /// Get the metadata of the changes that are in `b` but not in `a`, in causal order.
+    pub fn changes_between(&self, a: &VersionVector, b: &VersionVector) -> Vec<ChangeMeta> {
+        self.oplog.lock().unwrap().change_metas_between(a, b)
+    }
+
+    /// Get the ops of the change that contains `id`.
+    pub fn ops_in_change(&self, id: ID) -> Option<Vec<ChangeOp>> {
+        self.oplog.lock().unwrap().ops_in_change(id)
+    }
+
+    /// Fork the doc at the latest version. See [LoroDoc::fork_at].
+    pub fn fork(&self) -> LoroDoc {
+        self.commit_then_renew();
+        let frontiers = self.oplog_frontiers();
+        self.fork_at(&frontiers).unwrap()
+    }
+
+    /// Create a new independent doc with the history up to the given version.
+    ///
+    /// The new doc has a new random peer id, and its state is at the given version.
+    /// The ops are shared with this doc without encoding them.
+    pub fn fork_at(&self, frontiers: &Frontiers) -> LoroResult<LoroDoc> {
+        self.commit_then_renew();
+        let oplog = self.oplog.lock().unwrap();
+        check_version_in_history(&oplog, frontiers)?;
+        let vv = oplog.dag.frontiers_to_vv(frontiers).unwrap();
+        let forked = oplog.fork(&vv);
+        drop(oplog);
+        Ok(Self::from_forked_oplog(forked))
+    }
+
+    /// Create a doc whose state is at the latest version of the forked `oplog`.
+    fn from_forked_oplog(oplog: OpLog) -> LoroDoc {
+        let doc = LoroDoc::from_oplog(oplog);
+        {
+            let oplog = doc.oplog.lock().unwrap();
+            let mut state = doc.state.lock().unwrap();
+            if oplog.is_shallow() {
+                // the shallow root has been decoded successfully when it's imported
+                state.init_with_shallow_root(&oplog).unwrap();
+            }
+
+            let diff = DiffCalculator::new().calc_diff_internal(
+                &oplog,
+                oplog.shallow_since_vv(),
+                Some(oplog.shallow_since_frontiers()),
+                oplog.vv(),
+                Some(oplog.frontiers()),
+                None,
+            );
+            state.apply_diff(InternalDocDiff {
+                origin: Default::default(),
+                by: EventTriggerKind::Import,
+                diff: Cow::Owned(diff),
+                new_version: Cow::Owned(oplog.frontiers().clone()),
+            });
+        }
+
+        doc
+    }
+
+    /// Preview the effect of importing `bytes` without changing this doc.
+    ///
+    /// The blob is decoded like [LoroDoc::import]: a snapshot initializes an empty doc,
+    /// otherwise the changes are decoded into a fork of the oplog. The diff is calculated
+    /// on the forked oplog, and only the states of the changed containers and their
+    /// ancestors are built to convert it, so the cost doesn't grow with the unchanged
+    /// containers. The changes whose dependencies are missing in the oplog are reported
+    /// in [ImportPreview::pending].
+    ///
+    /// The containers in the returned diff may not exist in this doc and should not be
+    /// edited.
+    pub fn preview_import(&self, bytes: &[u8]) -> LoroResult<ImportPreview> {
+        let parsed = parse_header_and_body(bytes)?;
+        self.commit_then_stop();
+        let oplog = self.oplog.lock().unwrap();
+        if parsed.mode.is_snapshot() && oplog.is_empty() && self.state.lock().unwrap().is_empty() {
+            let mut new_oplog = OpLog::new();
+            new_oplog.configure = self.config.fork();
+            new_oplog.import_validator = oplog.import_validator.clone();
+            drop(oplog);
+            self.renew_txn_if_auto_commit();
+            let doc = Self::from_oplog(new_oplog);
+            if !self.is_detached() {
+                doc.state.lock().unwrap().start_recording();
+            }
+
+            decode_snapshot(&doc, parsed.mode, parsed.body)?;
+            let diff = doc.state.lock().unwrap().take_events();
+            return Ok(ImportPreview {
+                pending: Vec::new(),
+                diff,
+            });
+        }
+
+        let mut forked = oplog.fork(oplog.vv());
+        forked.pending_changes = oplog.pending_changes.clone();
+        forked.import_validator = oplog.import_validator.clone();
+        let old_vv = oplog.vv().clone();
+        drop(oplog);
+        self.renew_txn_if_auto_commit();
+
+        let old_frontiers = forked.frontiers().clone();
+        forked.decode(parsed)?;
+        let pending = import_status(&forked, &old_vv, bytes)?.pending;
+        if self.is_detached() {
+            return Ok(ImportPreview {
+                pending,
+                diff: Vec::new(),
+            });
+        }
+
+        let diff = DiffCalculator::new().calc_diff_internal(
+            &forked,
+            &old_vv,
+            Some(&old_frontiers),
+            forked.vv(),
+            Some(forked.frontiers()),
+            None,
+        );
+        let arena = &forked.arena;
+        let mut needed = FxHashSet::default();
+        let mut bring_back = FxHashSet::default();
+        for d in diff.iter() {
+            arena.with_ancestors(d.idx, |idx, _| {
+                needed.insert(idx);
+            });
+            if d.bring_back {
+                bring_back.insert(d.idx);
+            }
+        }
+        // the descendants of a brought back container are emitted with their states too
+        let filter = |idx: ContainerIdx| {
+            let mut ans = needed.contains(&idx);
+            if !ans && !bring_back.is_empty() {
+                arena.with_ancestors(idx, |idx, _| ans |= bring_back.contains(&idx));
+            }
+            ans
+        };
+        let mut state = self.shadow_state_at(&forked, &old_frontiers, Some(&filter));
+        state.start_recording();
+        state.apply_diff(InternalDocDiff {
+            origin: Default::default(),
+            by: EventTriggerKind::Import,
+            diff: Cow::Owned(diff),
+            new_version: Cow::Owned(forked.frontiers().clone()),
+        });
+        Ok(ImportPreview {
+            pending,
+            diff: state.take_events(),
+        })
+    }
+
+    /// Tag the given version with `name`.
+    ///
+    /// The tags are stored in a reserved root map, so they are synced to other peers
+    /// like any other edit. Tagging with an existing name overrides the old tag.
+    /// The tag container is hidden from [LoroDoc::get_deep_value], the events, the schema
+    /// checks and [LoroDoc::diff], so the undo manager doesn't track it either.
+    pub fn tag_version(&self, name: &str, frontiers: &Frontiers) -> LoroResult<()> {
+        check_version_in_history(&self.oplog.lock().unwrap(), frontiers)?;
+        self.get_map(VERSION_TAGS_ROOT_NAME)
+            .insert(name, frontiers.encode())
+    }
+
+    /// Get all the version tags of the latest version, sorted by name.
+    ///
+    /// The tags are read from the latest version even if the doc is detached.
+    pub fn list_tags(&self) -> Vec<(String, Frontiers)> {
+        let Some(idx) = self.arena.id_to_idx(&ContainerID::new_root(
+            VERSION_TAGS_ROOT_NAME,
+            ContainerType::Map,
+        )) else {
+            return Vec::new();
+        };
+
+        let value = if self.is_detached() {
+            let oplog = self.oplog.lock().unwrap();
+            let mut state = self.shadow_state_at(&oplog, oplog.frontiers(), Some(&|x| x == idx));
+            state.get_container_deep_value(idx)
+        } else {
+            self.state.lock().unwrap().get_container_deep_value(idx)
+        };
+
+        let LoroValue::Map(map) = value else {
+            return Vec::new();
+        };
+        let mut tags: Vec<(String, Frontiers)> = map
+            .iter()
+            .filter_map(|(name, value)| match value {
+                LoroValue::Binary(bytes) => Frontiers::decode(bytes)
+                    .ok()
+                    .map(|frontiers| (name.clone(), frontiers)),
+                _ => None,
+            })
+            .collect();
+        tags.sort_by(|a, b| a.0.cmp(&b.0));
+        tags
+    }
+
+    /// Checkout [DocState] to the version tagged with `name`.
+    ///
+    /// See [LoroDoc::tag_version] and [LoroDoc::checkout].
+    pub fn checkout_tag(&self, name: &str) -> LoroResult<()> {
+        let Some((_, frontiers)) = self.list_tags().into_iter().find(|(n, _)| n == name) else {
+            return Err(LoroError::NotFoundError(
+                format!("Cannot find the version tag {:?}", name).into_boxed_str(),
+            ));
+        };
+        self.checkout(&frontiers)
+    }
+
+    fn checkout_without_emitting(&self, frontiers: &Frontiers) -> LoroResult<()> {
         self.commit_then_stop();
         let oplog = self.oplog.lock().unwrap();
         let mut state = self.state.lock().unwrap();
         self.detached.store(true, Release);
         let mut calc = self.diff_calculator.lock().unwrap();
-        for &f in frontiers.iter() {
-            if !oplog.dag.contains(f) {
-                return Err(LoroError::InvalidFrontierIdNotFound(f));
-            }
-        }
+        check_version_in_history(&oplog, frontiers)?;
         let before = &oplog.dag.frontiers_to_vv(&state.frontiers).unwrap();
         let Some(after) = &oplog.dag.frontiers_to_vv(frontiers) else {
             return Err(LoroError::NotFoundError(
@@ -795,8 +1305,136 @@ impl LoroDoc {
             diff: Cow::Owned(diff),
             new_version: Cow::Owned(frontiers.clone()),
         });
-        drop(state);
-        self.emit_events();
+        Ok(())
+    }
+
+    /// Apply the diff to the current state by creating new local ops.
+    ///
+    /// The containers inside the diff that no longer exist are recreated, and the
+    /// mapping from the old container ids to the new ones is recorded in
+    /// `container_remap`. It requires auto commit to be enabled.
+    pub(crate) fn apply_diff(
+        &self,
+        diff: Vec<ContainerDiff>,
+        container_remap: &mut FxHashMap<ContainerID, ContainerID>,
+    ) -> LoroResult<()> {
+        for ContainerDiff { id, diff, .. } in diff {
+            let mut id = id;
+            while let Some(new_id) = container_remap.get(&id) {
+                id = new_id.clone();
+            }
+
+            let handler = Handler::new_attached(
+                id,
+                self.arena.clone(),
+                self.get_global_txn(),
+                Arc::downgrade(&self.state),
+            );
+            handler.apply_diff(diff, container_remap)?;
+        }
+
+        Ok(())
+    }
+
+    /// Calculate the diff from version `from` to version `to`.
+    ///
+    /// It's calculated on the [OpLog] alone, so the current state is not affected.
+    /// The result has the same form as the diffs in the events. If `container_filter`
+    /// is given, only the diffs of the containers that pass the filter are calculated.
+    pub fn diff(
+        &self,
+        from: &Frontiers,
+        to: &Frontiers,
+        container_filter: Option<&dyn Fn(&ContainerID) -> bool>,
+    ) -> LoroResult<Vec<ContainerDiff>> {
+        self.commit_then_renew();
+        let oplog = self.oplog.lock().unwrap();
+        check_version_in_history(&oplog, from)?;
+        check_version_in_history(&oplog, to)?;
+
+        let filter = container_filter
+            .map(|f| move |idx: ContainerIdx| f(&self.arena.get_container_id(idx).unwrap()));
+        let filter = filter.as_ref().map(|f| f as &dyn Fn(ContainerIdx) -> bool);
+        let from_vv = oplog.dag.frontiers_to_vv(from).unwrap();
+        let to_vv = oplog.dag.frontiers_to_vv(to).unwrap();
+        let mut state = self.shadow_state_at(&oplog, from, filter);
+        state.start_recording();
+        let diff = DiffCalculator::new().calc_diff_internal(
+            &oplog,
+            &from_vv,
+            Some(from),
+            &to_vv,
+            Some(to),
+            filter,
+        );
+        state.apply_diff(InternalDocDiff {
+            origin: Default::default(),
+            by: EventTriggerKind::Checkout,
+            diff: Cow::Owned(diff),
+            new_version: Cow::Owned(to.clone()),
+        });
+        Ok(state
+            .take_events()
+            .into_iter()
+            .flat_map(|e| e.diff)
+            .collect())
+    }
+
+    /// Build a state at the given version that is not attached to this doc.
+    ///
+    /// It starts from the shallow root if the doc is shallow, otherwise from the empty version.
+    fn shadow_state_at(
+        &self,
+        oplog: &OpLog,
+        frontiers: &Frontiers,
+        filter: Option<&dyn Fn(ContainerIdx) -> bool>,
+    ) -> DocState {
+        let mut state = DocState::new_shadow(
+            oplog.arena.clone(),
+            self.get_global_txn(),
+            Arc::downgrade(&self.state),
+            Arc::downgrade(&self.oplog),
+            self.config.clone(),
+        );
+        if oplog.is_shallow() {
+            // the shallow root has been decoded successfully when it's imported
+            state.init_with_shallow_root(oplog).unwrap();
+        }
+
+        let vv = oplog.dag.frontiers_to_vv(frontiers).unwrap();
+        let diff = DiffCalculator::new().calc_diff_internal(
+            oplog,
+            oplog.shallow_since_vv(),
+            Some(oplog.shallow_since_frontiers()),
+            &vv,
+            Some(frontiers),
+            filter,
+        );
+        state.apply_diff(InternalDocDiff {
+            origin: Default::default(),
+            by: EventTriggerKind::Checkout,
+            diff: Cow::Owned(diff),
+            new_version: Cow::Owned(frontiers.clone()),
+        });
+        state
+    }
+
+    /// Revert the current state to the given version by creating new local ops.
+    ///
+    /// Unlike [LoroDoc::checkout], the doc stays attached and the new ops can be synced
+    /// to other peers. It requires auto commit to be enabled.
+    ///
+    /// Returns [LoroError::AutoCommitNotStarted] if the doc is detached. Call
+    /// [LoroDoc::attach] first to revert the latest version.
+    pub fn revert_to(&self, target: &Frontiers) -> LoroResult<()> {
+        if self.is_detached() {
+            return Err(LoroError::AutoCommitNotStarted);
+        }
+
+        let current = self.state_frontiers();
+        let diff = self.diff(&current, target, None)?;
+        self.apply_diff(diff, &mut Default::default())?;
+        self.commit_then_renew();
         Ok(())
     }
 
@@ -814,7 +1452,8 @@ impl LoroDoc {
     ///
     /// After `a.merge(b)` and `b.merge(a)`, `a` and `b` will have the same content if they are in attached mode.
     pub fn merge(&self, other: &Self) -> LoroResult<()> {
-        self.import(&other.export_from(&self.oplog_vv()))
+        self.import(&other.export_from(&self.oplog_vv()))?;
+        Ok(())
     }
 
     #[cfg(feature = "test_utils")]
@@ -853,7 +1492,11 @@ impl LoroDoc {
             !self.is_detached(),
             "Cannot check consistency in detached mode"
         );
-        let bytes = self.export_from(&Default::default());
+        let bytes = if self.is_shallow() {
+            self.export_snapshot_without_txn()
+        } else {
+            self.export_from(&Default::default())
+        };
         let doc = Self::new();
         doc.import(&bytes).unwrap();
         let mut calculated_state = doc.app_state().try_lock().unwrap();
@@ -895,12 +1538,21 @@ impl LoroDoc {
             self.commit_then_renew();
             let oplog = self.oplog().lock().unwrap();
             if let Some(id) = pos.id {
+                if pos.container.container_type() == ContainerType::MovableList {
+                    // The element is deleted. A movable list element doesn't have a
+                    // fixed position in the history, so we cannot trace it back.
+                    return Err(CannotFindRelativePosition::IdNotFound);
+                }
+
                 let idx = oplog
                     .arena
                     .id_to_idx(&pos.container)
                     .ok_or(CannotFindRelativePosition::ContainerDeleted)?;
                 // We know where the target id is when we trace back to the delete_op_id.
-                let delete_op_id = find_last_delete_op(&oplog, id, idx).unwrap();
+                let Some(delete_op_id) = find_last_delete_op(&oplog, id, idx) else {
+                    // the delete op is trimmed by the shallow snapshot
+                    return Err(CannotFindRelativePosition::HistoryCleared);
+                };
                 let mut diff_calc = DiffCalculator::new();
                 let before_frontiers: Frontiers = oplog.dag.find_deps_of_id(delete_op_id);
                 let before = &oplog.dag.frontiers_to_vv(&before_frontiers).unwrap();
@@ -944,6 +1596,8 @@ impl LoroDoc {
                     }
                     crate::diff_calc::ContainerDiffCalculator::Tree(_) => unreachable!(),
                     crate::diff_calc::ContainerDiffCalculator::Map(_) => unreachable!(),
+                    crate::diff_calc::ContainerDiffCalculator::MovableList(_) => unreachable!(),
+                    crate::diff_calc::ContainerDiffCalculator::Counter(_) => unreachable!(),
                 }
             } else {
                 match pos.container.container_type() {
@@ -975,7 +1629,23 @@ impl LoroDoc {
                             },
                         })
                     }
-                    ContainerType::Map | ContainerType::Tree => unreachable!(),
+                    ContainerType::MovableList => {
+                        let list = self.get_movable_list(&pos.container);
+                        Ok(PosQueryResult {
+                            update: Some(Cursor {
+                                id: None,
+                                container: list.id(),
+                                side: pos.side,
+                            }),
+                            current: AbsolutePosition {
+                                pos: list.len(),
+                                side: pos.side,
+                            },
+                        })
+                    }
+                    ContainerType::Map | ContainerType::Tree | ContainerType::Counter => {
+                        unreachable!()
+                    }
                 }
             }
         }
@@ -983,7 +1653,12 @@ impl LoroDoc {
 }
 
 fn find_last_delete_op(oplog: &OpLog, id: ID, idx: ContainerIdx) -> Option<ID> {
-    let start_vv = oplog.dag.frontiers_to_vv(&id.into()).unwrap();
+    let start_vv = if oplog.shallow_since_vv().includes_id(id) {
+        // the history before the shallow root is trimmed
+        oplog.shallow_since_vv().clone()
+    } else {
+        oplog.dag.frontiers_to_vv(&id.into()).unwrap()
+    };
     for change in oplog.iter_changes_causally_rev(&start_vv, &oplog.dag.vv) {
         for op in change.ops.iter().rev() {
             if op.container != idx {
@@ -1001,6 +1676,45 @@ fn find_last_delete_op(oplog: &OpLog, id: ID, idx: ContainerIdx) -> Option<ID> {
     None
 }
 
+/// Get the status of importing `bytes` into the oplog at `old_vv`.
+fn import_status(oplog: &OpLog, old_vv: &VersionVector, bytes: &[u8]) -> LoroResult<ImportStatus> {
+    let mut applied: Vec<IdSpan> = oplog.vv().sub_iter(old_vv).collect();
+    applied.sort_by_key(|x| (x.peer, x.counter.start));
+    if oplog.pending_changes.is_empty() {
+        return Ok(ImportStatus {
+            applied,
+            ..Default::default()
+        });
+    }
+
+    let meta = LoroDoc::decode_import_blob_meta(bytes)?;
+    let imported: Vec<IdSpan> = meta
+        .partial_end_vv
+        .sub_iter(&meta.partial_start_vv)
+        .collect();
+    let summary = oplog.pending_changes_summary_within(&imported);
+    Ok(ImportStatus {
+        applied,
+        pending: summary.pending,
+        missing_deps: summary.missing_deps,
+    })
+}
+
+/// Check that the version exists and its history is not trimmed
+fn check_version_in_history(oplog: &OpLog, frontiers: &Frontiers) -> LoroResult<()> {
+    for &id in frontiers.iter() {
+        if !oplog.dag.contains(id) {
+            return Err(LoroError::InvalidFrontierIdNotFound(id));
+        }
+    }
+
+    if oplog.is_before_shallow_root(frontiers) {
+        return Err(LoroError::SwitchToVersionBeforeShallowRoot);
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use loro_common::ID;
@@ -281,6 +281,27 @@ impl<'a> RichOp<'a> {
         })
     }
 
+    /// Like [RichOp::new_by_cnt_range], but the op is not included in any change.
+    /// It's used for the ops kept by the shallow root.
+    pub(crate) fn new_by_cnt_range_on_op_with_id(
+        op: &'a OpWithId,
+        span: CounterSpan,
+    ) -> Option<Self> {
+        let op_slice_start = (span.start - op.op.counter).clamp(0, op.op.atom_len() as i32);
+        let op_slice_end = (span.end - op.op.counter).clamp(0, op.op.atom_len() as i32);
+        if op_slice_start == op_slice_end {
+            return None;
+        }
+        Some(RichOp {
+            op: &op.op,
+            peer: op.peer,
+            lamport: op.lamport.unwrap(),
+            timestamp: 0,
+            start: op_slice_start as usize,
+            end: op_slice_end as usize,
+        })
+    }
+
     pub fn op(&self) -> Cow<'_, Op> {
         if self.start == 0 && self.end == self.op.content_len() {
             Cow::Borrowed(self.op)
@@ -26,6 +26,8 @@ pub enum InnerContent {
     List(InnerListOp),
     Map(MapSet),
     Tree(TreeOp),
+    /// The delta of a counter
+    Counter(f64),
 }
 
 // Note: It will be encoded into binary format, so the order of its fields should not be changed.
@@ -34,6 +36,7 @@ pub enum RawOpContent<'a> {
     Map(MapSet),
     List(ListOp<'a>),
     Tree(TreeOp),
+    Counter(f64),
 }
 
 impl<'a> Clone for RawOpContent<'a> {
@@ -41,7 +44,8 @@ impl<'a> Clone for RawOpContent<'a> {
         match self {
             Self::Map(arg0) => Self::Map(arg0.clone()),
             Self::List(arg0) => Self::List(arg0.clone()),
-            Self::Tree(arg0) => Self::Tree(*arg0),
+            Self::Tree(arg0) => Self::Tree(arg0.clone()),
+            Self::Counter(x) => Self::Counter(*x),
         }
     }
 }
@@ -70,8 +74,18 @@ impl<'a> RawOpContent<'a> {
                     info: *info,
                 }),
                 ListOp::StyleEnd => RawOpContent::List(ListOp::StyleEnd),
+                ListOp::Move { from, to, elem_id } => RawOpContent::List(ListOp::Move {
+                    from: *from,
+                    to: *to,
+                    elem_id: *elem_id,
+                }),
+                ListOp::Set { elem_id, value } => RawOpContent::List(ListOp::Set {
+                    elem_id: *elem_id,
+                    value: value.clone(),
+                }),
             },
-            Self::Tree(arg0) => RawOpContent::Tree(*arg0),
+            Self::Tree(arg0) => RawOpContent::Tree(arg0.clone()),
+            Self::Counter(x) => RawOpContent::Counter(*x),
         }
     }
 }
@@ -118,6 +132,7 @@ impl<'a> HasLength for RawOpContent<'a> {
             RawOpContent::Map(x) => x.content_len(),
             RawOpContent::List(x) => x.content_len(),
             RawOpContent::Tree(x) => x.content_len(),
+            RawOpContent::Counter(_) => 1,
         }
     }
 }
@@ -128,6 +143,7 @@ impl<'a> Sliceable for RawOpContent<'a> {
             RawOpContent::Map(x) => RawOpContent::Map(x.slice(from, to)),
             RawOpContent::List(x) => RawOpContent::List(x.slice(from, to)),
             RawOpContent::Tree(x) => RawOpContent::Tree(x.slice(from, to)),
+            a @ RawOpContent::Counter(_) => a.clone(),
         }
     }
 }
@@ -161,6 +177,7 @@ impl<'a> Mergable for RawOpContent<'a> {
                 RawOpContent::Tree(y) => x.merge(y, &()),
                 _ => unreachable!(),
             },
+            RawOpContent::Counter(_) => unreachable!(),
         }
     }
 }
@@ -171,6 +188,7 @@ impl HasLength for InnerContent {
             InnerContent::List(list) => list.atom_len(),
             InnerContent::Map(_) => 1,
             InnerContent::Tree(_) => 1,
+            InnerContent::Counter(_) => 1,
         }
     }
 }
@@ -181,6 +199,7 @@ impl Sliceable for InnerContent {
             a @ InnerContent::Map(_) => a.clone(),
             InnerContent::List(x) => InnerContent::List(x.slice(from, to)),
             a @ InnerContent::Tree(_) => a.clone(),
+            a @ InnerContent::Counter(_) => a.clone(),
         }
     }
 }
@@ -207,6 +226,7 @@ impl Mergable for InnerContent {
             },
             InnerContent::Map(_) => unreachable!(),
             InnerContent::Tree(_) => unreachable!(),
+            InnerContent::Counter(_) => unreachable!(),
         }
     }
 }
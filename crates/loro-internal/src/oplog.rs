@@ -7,8 +7,9 @@ use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::mem::take;
 use std::rc::Rc;
+use std::sync::Arc;
 
-use crate::change::{get_sys_timestamp, Change, Lamport, Timestamp};
+use crate::change::{get_sys_timestamp, Change, ChangeMeta, ChangeOp, Lamport, Timestamp};
 use crate::configure::Configure;
 use crate::container::list::list_op;
 use crate::dag::{Dag, DagUtils};
@@ -16,16 +17,20 @@ use crate::encoding::ParsedHeaderAndBody;
 use crate::encoding::{decode_oplog, encode_oplog, EncodeMode};
 use crate::group::OpGroups;
 use crate::id::{Counter, PeerID, ID};
-use crate::op::{ListSlice, RawOpContent, RemoteOp, RichOp};
+use crate::op::{ListSlice, OpWithId, RawOpContent, RemoteOp, RichOp};
 use crate::span::{HasCounterSpan, HasIdSpan, HasLamportSpan};
 use crate::version::{Frontiers, ImVersionVector, VersionVector};
 use crate::LoroError;
 use fxhash::FxHashMap;
-use loro_common::{HasCounter, HasId, IdSpan};
+use loro_common::{ContainerID, HasCounter, HasId, HasLamport, IdLp, IdLpSpan, IdSpan};
 use rle::{HasLength, RleCollection, RlePush, RleVec, Sliceable};
 use smallvec::SmallVec;
 
 type ClientChanges = FxHashMap<PeerID, Vec<Change>>;
+
+/// Check a change to import with its ops. The import is rejected if it returns an error.
+pub type ImportValidator =
+    Arc<dyn Fn(&ChangeMeta, &[ChangeOp]) -> Result<(), String> + Send + Sync>;
 pub use self::dag::FrontiersNotIncluded;
 use self::iter::MergedChangeIter;
 use self::pending_changes::PendingChanges;
@@ -55,6 +60,31 @@ pub struct OpLog {
     /// If so the Dag's frontiers won't be updated until the batch is finished.
     pub(crate) batch_importing: bool,
     pub(crate) configure: Configure,
+    /// Where the history starts if the doc is loaded from a shallow snapshot
+    pub(crate) shallow_root: Arc<ShallowRoot>,
+    pub(crate) import_validator: Option<ImportValidator>,
+}
+
+/// The version where the history of a doc loaded from a shallow snapshot starts.
+///
+/// The changes before it are trimmed. Only the ops that the state at this version
+/// is made of are kept, so the state can still be restored and diffed from here.
+#[derive(Debug, Default)]
+pub(crate) struct ShallowRoot {
+    pub(crate) vv: VersionVector,
+    pub(crate) frontiers: Frontiers,
+    /// The kept ops of each peer, sorted by counter
+    pub(crate) ops: FxHashMap<PeerID, Vec<OpWithId>>,
+    /// The container states at this version
+    pub(crate) states: Vec<ShallowRootState>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ShallowRootState {
+    pub(crate) container: ContainerID,
+    /// The ops that form the state, in the order they were encoded
+    pub(crate) ops: Vec<OpWithId>,
+    pub(crate) blob: Vec<u8>,
 }
 
 /// [AppDag] maintains the causal graph of the app.
@@ -91,6 +121,8 @@ impl Clone for OpLog {
             pending_changes: Default::default(),
             batch_importing: false,
             configure: self.configure.clone(),
+            shallow_root: self.shallow_root.clone(),
+            import_validator: self.import_validator.clone(),
         }
     }
 }
@@ -102,7 +134,7 @@ impl AppDag {
             counter,
         } = id;
         self.map.get_mut(&client_id).and_then(|rle| {
-            if counter >= rle.sum_atom_len() {
+            if counter < rle.start() || counter >= rle.end() {
                 return None;
             }
 
@@ -181,6 +213,8 @@ impl OpLog {
             pending_changes: Default::default(),
             batch_importing: false,
             configure: Configure::default(),
+            shallow_root: Default::default(),
+            import_validator: None,
         }
     }
 
@@ -229,6 +263,34 @@ impl OpLog {
         timestamp
     }
 
+    /// Get the version that includes all the changes whose timestamps are not greater
+    /// than the given timestamp, together with the changes they depend on.
+    ///
+    /// The timestamps come from the clocks of different peers or are set manually, so
+    /// they are not sorted even for the changes of the same peer, and a change may
+    /// depend on a change with a greater timestamp. So all the changes are scanned, and
+    /// the version is the causal closure of the matched ones. The history before the
+    /// shallow root is always included, because it's trimmed.
+    ///
+    /// A merged change is included as a whole if its first timestamp is not greater
+    /// than the given timestamp.
+    pub fn frontiers_at_timestamp(&self, timestamp: Timestamp) -> Frontiers {
+        let mut vv = self.shallow_since_vv().clone();
+        for changes in self.changes.values() {
+            // the closure of the last matched change includes the previous ones of its peer
+            let Some(change) = changes.iter().rev().find(|c| c.timestamp <= timestamp) else {
+                continue;
+            };
+
+            let id = change.id_last();
+            if !vv.includes_id(id) {
+                vv.extend_to_include_vv(self.dag.get_vv(id).unwrap().iter());
+            }
+        }
+
+        vv.to_frontiers(&self.dag)
+    }
+
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.dag.map.is_empty() && self.arena.can_import_snapshot()
@@ -255,6 +317,7 @@ impl OpLog {
                 if !last.has_dependents
                     && change.deps_on_self()
                     && timestamp_change < self.configure.merge_interval()
+                    && change.message == last.message
                 {
                     for op in take(change.ops.vec_mut()) {
                         last.ops.push(op);
@@ -264,7 +327,13 @@ impl OpLog {
                 }
             }
             None => {
-                assert!(change.id.counter == 0, "change id is not continuous");
+                let start = self
+                    .shallow_root
+                    .vv
+                    .get(&change.id.peer)
+                    .copied()
+                    .unwrap_or(0);
+                assert!(change.id.counter == start, "change id is not continuous");
                 entry.push(change);
             }
         }
@@ -367,6 +436,11 @@ impl OpLog {
     }
 
     fn ensure_dep_on_change_end(&mut self, src: PeerID, dep: ID) {
+        if self.shallow_root.vv.includes_id(dep) {
+            // the change is trimmed by the shallow snapshot
+            return;
+        }
+
         let changes = self.changes.get_mut(&dep.peer).unwrap();
         match changes.binary_search_by(|c| c.ctr_last().cmp(&dep.counter)) {
             Ok(index) => {
@@ -451,6 +525,212 @@ impl OpLog {
         &self.dag.frontiers
     }
 
+    /// Whether the history before some version is trimmed
+    #[inline]
+    pub fn is_shallow(&self) -> bool {
+        !self.shallow_root.vv.is_empty()
+    }
+
+    /// The version vector where the history starts.
+    ///
+    /// It's empty if the doc is not shallow.
+    #[inline]
+    pub fn shallow_since_vv(&self) -> &VersionVector {
+        &self.shallow_root.vv
+    }
+
+    /// The frontiers where the history starts.
+    ///
+    /// It's empty if the doc is not shallow.
+    #[inline]
+    pub fn shallow_since_frontiers(&self) -> &Frontiers {
+        &self.shallow_root.frontiers
+    }
+
+    /// Whether the version is not reachable because its history is trimmed
+    pub(crate) fn is_before_shallow_root(&self, frontiers: &Frontiers) -> bool {
+        if !self.is_shallow() {
+            return false;
+        }
+
+        let root_vv = &self.shallow_root.vv;
+        if frontiers.iter().any(|id| {
+            root_vv
+                .get(&id.peer)
+                .is_some_and(|&end| id.counter + 1 < end)
+        }) {
+            return true;
+        }
+
+        match self.dag.frontiers_to_vv(frontiers) {
+            Some(vv) => !vv.includes_vv(root_vv),
+            None => true,
+        }
+    }
+
+    /// Find the version that a shallow snapshot exported at `frontiers` should start from.
+    ///
+    /// All the changes after the returned version need to depend on the whole of it,
+    /// so it can be the only starting point of the history. It may be earlier than
+    /// `frontiers` if there are concurrent changes.
+    pub(crate) fn calc_shallow_root(&self, frontiers: &Frontiers) -> Frontiers {
+        let mut vv = self.dag.frontiers_to_vv(frontiers).unwrap();
+        loop {
+            let mut next = vv.clone();
+            for (peer, changes) in self.changes.iter() {
+                let end = vv.get(peer).copied().unwrap_or(0);
+                for change in changes.iter().rev() {
+                    if change.ctr_end() <= end {
+                        break;
+                    }
+
+                    let deps_vv = if change.id.counter < end {
+                        self.dag
+                            .frontiers_to_vv(&ID::new(*peer, end - 1).into())
+                            .unwrap()
+                    } else {
+                        self.dag.frontiers_to_vv(&change.deps).unwrap()
+                    };
+
+                    if !deps_vv.includes_vv(&next) {
+                        next = next.intersection(&deps_vv);
+                    }
+                }
+            }
+
+            if next == vv {
+                break;
+            }
+
+            vv = next;
+        }
+
+        vv.to_frontiers(&self.dag)
+    }
+
+    /// Make the oplog start from the given shallow root.
+    ///
+    /// `nodes` are the last ids of each peer in the root with their lamports and
+    /// the version vectors before them. They are used as the dag nodes that the
+    /// following changes depend on.
+    pub(crate) fn init_shallow_root(
+        &mut self,
+        root: Arc<ShallowRoot>,
+        nodes: Vec<(ID, Lamport, ImVersionVector)>,
+    ) {
+        assert!(self.changes.is_empty(), "the oplog is not empty");
+        for (id, lamport, vv) in nodes {
+            self.dag.map.entry(id.peer).or_default().push(AppDagNode {
+                peer: id.peer,
+                cnt: id.counter,
+                lamport,
+                deps: Default::default(),
+                vv,
+                has_succ: !root.frontiers.contains(&id),
+                len: 1,
+            });
+            self.next_lamport = self.next_lamport.max(lamport + 1);
+        }
+
+        for (peer, ops) in root.ops.iter() {
+            for op in ops.iter() {
+                self.op_groups
+                    .insert_by_op(&RichOp::new(&op.op, *peer, op.lamport.unwrap(), 0));
+            }
+        }
+
+        self.dag.vv = root.vv.clone();
+        self.dag.frontiers = root.frontiers.clone();
+        self.shallow_root = root;
+    }
+
+    /// Make the oplog start from a version whose history is unknown, so the changes after
+    /// it can be imported and encoded without the changes before it.
+    ///
+    /// It becomes the shallow root of the oplog, without any kept op. Each peer of `vv`
+    /// gets a placeholder dag node that covers all its counters in `vv`, so the following
+    /// changes can depend on any id in `vv`. The lamports of the nodes are unknown, so
+    /// they start from 0. They only keep the causal order of the following changes.
+    pub(crate) fn init_unknown_root(&mut self, vv: VersionVector, frontiers: Frontiers) {
+        assert!(self.changes.is_empty(), "the oplog is not empty");
+        for (&peer, &counter) in vv.iter() {
+            if counter == 0 {
+                continue;
+            }
+
+            self.dag.map.entry(peer).or_default().push(AppDagNode {
+                peer,
+                cnt: 0,
+                lamport: 0,
+                deps: Default::default(),
+                vv: Default::default(),
+                has_succ: !frontiers.contains(&ID::new(peer, counter - 1)),
+                len: counter as usize,
+            });
+            self.next_lamport = self.next_lamport.max(counter as Lamport);
+        }
+
+        self.dag.vv = vv.clone();
+        self.dag.frontiers = frontiers.clone();
+        self.shallow_root = Arc::new(ShallowRoot {
+            vv,
+            frontiers,
+            ..Default::default()
+        });
+    }
+
+    /// Create an independent oplog with the changes included by `vv`.
+    ///
+    /// The arena is forked so the ops can be shared with this oplog as they are,
+    /// without encoding them.
+    pub(crate) fn fork(&self, vv: &VersionVector) -> OpLog {
+        let mut oplog = OpLog::new();
+        oplog.arena = self.arena.fork();
+        oplog.configure = self.configure.fork();
+        if self.is_shallow() {
+            let nodes = self
+                .shallow_root
+                .vv
+                .iter()
+                .filter(|(_, &counter)| counter > 0)
+                .map(|(&peer, &counter)| {
+                    let id = ID::new(peer, counter - 1);
+                    let node = self.dag.map[&peer].get_by_atom_index(id.counter).unwrap();
+                    (
+                        id,
+                        self.dag.get_lamport(&id).unwrap(),
+                        node.element.vv.clone(),
+                    )
+                })
+                .collect();
+            oplog.init_shallow_root(self.shallow_root.clone(), nodes);
+        }
+
+        let mut changes = Vec::new();
+        for (peer, peer_changes) in self.changes.iter() {
+            let end = vv.get(peer).copied().unwrap_or(0);
+            for change in peer_changes.iter() {
+                if change.id.counter >= end {
+                    break;
+                }
+
+                if change.ctr_end() <= end {
+                    changes.push(change.clone());
+                } else {
+                    changes.push(change.slice(0, (end - change.id.counter) as usize));
+                }
+            }
+        }
+
+        // the deps of a change always have smaller lamports
+        changes.sort_by_key(|change| (change.lamport, change.id.peer));
+        for change in changes {
+            oplog.import_local_change(change).unwrap();
+        }
+
+        oplog
+    }
+
     /// - Ordering::Less means self is less than target or parallel
     /// - Ordering::Equal means versions equal
     /// - Ordering::Greater means self's version is greater than target
@@ -480,12 +760,41 @@ impl OpLog {
     }
 
     pub(crate) fn iter_ops(&self, id_span: IdSpan) -> impl Iterator<Item = RichOp> + '_ {
+        let shallow_end = self
+            .shallow_root
+            .vv
+            .get(&id_span.peer)
+            .copied()
+            .unwrap_or(0);
+        // the ops before the shallow root can only be found in the root
+        let root_ops = if id_span.counter.start < shallow_end {
+            self.shallow_root.ops.get(&id_span.peer).map(|ops| {
+                let start = ops.partition_point(|op| op.op.ctr_end() <= id_span.counter.start);
+                ops[start..]
+                    .iter()
+                    .take_while(move |op| op.op.counter < id_span.counter.end)
+                    .filter_map(move |op| {
+                        RichOp::new_by_cnt_range_on_op_with_id(op, id_span.counter)
+                    })
+            })
+        } else {
+            None
+        };
+
+        root_ops
+            .into_iter()
+            .flatten()
+            .chain(self.iter_ops_in_changes(id_span))
+    }
+
+    fn iter_ops_in_changes(&self, id_span: IdSpan) -> impl Iterator<Item = RichOp<'_>> + '_ {
         self.changes
             .get(&id_span.peer)
             .map(move |changes| {
                 let len = changes.len();
+                // the changes of a shallow doc may start after the span start
                 let start = changes
-                    .get_by_atom_index(id_span.counter.start)
+                    .get_by_atom_index(id_span.counter.start.max(changes[0].id.counter))
                     .map(|x| x.merged_index)
                     .unwrap_or(len);
                 let mut end = changes
@@ -531,6 +840,145 @@ impl OpLog {
         Some(self.convert_change_to_remote(change))
     }
 
+    /// Get the metadata of the changes that are in `b` but not in `a`, in causal order.
+    ///
+    /// The changes are sliced if they are partially included.
+    pub fn change_metas_between(&self, a: &VersionVector, b: &VersionVector) -> Vec<ChangeMeta> {
+        let mut ans = Vec::new();
+        for span in b.sub_iter(a) {
+            let Some(changes) = self.changes.get(&span.peer) else {
+                continue;
+            };
+            let start = span.counter.start.max(changes[0].id.counter);
+            if start >= span.counter.end {
+                continue;
+            }
+
+            let index = changes.search_atom_index(start);
+            for change in changes[index..]
+                .iter()
+                .take_while(|c| c.id.counter < span.counter.end)
+            {
+                ans.push(ChangeMeta::from_change_in_range(
+                    change,
+                    span.counter.start..span.counter.end,
+                ));
+            }
+        }
+
+        ans.sort_by_key(|x| (x.lamport, x.id.peer));
+        ans
+    }
+
+    /// Get the ops of the change that contains `id`, with the decoded container ids
+    /// and contents.
+    pub fn ops_in_change(&self, id: ID) -> Option<Vec<ChangeOp>> {
+        let change = self.lookup_change(id)?;
+        let mut ans = Vec::with_capacity(change.ops.len());
+        for op in change.ops.iter() {
+            let lamport = change.lamport + (op.counter - change.id.counter) as Lamport;
+            for remote in self.local_op_to_remote(op) {
+                ans.push(ChangeOp::from_remote_op(remote, lamport, change.id.peer));
+            }
+        }
+
+        Some(ans)
+    }
+
+    /// Check the changes to import with the import validator.
+    ///
+    /// The changes that are already included by the oplog are skipped. The lamport of a
+    /// change whose deps are missing is unknown, and it's set to 0.
+    pub(crate) fn validate_changes_to_import(&self, changes: &[Change]) -> Result<(), LoroError> {
+        self.validate_changes_after(changes, self.vv(), |id| self.dag.get_lamport(id))
+    }
+
+    /// Check the content of a shallow snapshot with the import validator before it's
+    /// imported into this empty oplog.
+    ///
+    /// The ops kept in the shallow root have no change info, so each of them is checked
+    /// as a change without deps. The changes after the root are checked like normal
+    /// imported changes, with the lamports of the root `nodes`.
+    pub(crate) fn validate_shallow_snapshot_to_import(
+        &self,
+        root: &ShallowRoot,
+        nodes: &[(ID, Lamport, ImVersionVector)],
+        changes: &[Change],
+    ) -> Result<(), LoroError> {
+        let Some(validator) = self.import_validator.as_ref() else {
+            return Ok(());
+        };
+
+        for (&peer, ops) in root.ops.iter() {
+            for op in ops.iter() {
+                let lamport = op.lamport.unwrap_or(0);
+                let meta = ChangeMeta {
+                    id: op.id(),
+                    lamport,
+                    timestamp: 0,
+                    deps: Default::default(),
+                    len: op.op.atom_len(),
+                    message: None,
+                };
+                let ops: Vec<_> = self
+                    .local_op_to_remote(&op.op)
+                    .into_iter()
+                    .map(|remote| ChangeOp::from_remote_op(remote, lamport, peer))
+                    .collect();
+                validator(&meta, &ops).map_err(|reason| LoroError::ImportRejected {
+                    id: meta.id,
+                    reason: reason.into_boxed_str(),
+                })?;
+            }
+        }
+
+        self.validate_changes_after(changes, &root.vv, |id| {
+            nodes
+                .iter()
+                .find(|(node, _, _)| node == id)
+                .map(|(_, lamport, _)| *lamport)
+        })
+    }
+
+    /// Check the changes that are not included by `vv`. The lamports of the deps
+    /// outside `changes` are looked up by `lamport_of`.
+    fn validate_changes_after(
+        &self,
+        changes: &[Change],
+        vv: &VersionVector,
+        lamport_of: impl Fn(&ID) -> Option<Lamport>,
+    ) -> Result<(), LoroError> {
+        let Some(validator) = self.import_validator.as_ref() else {
+            return Ok(());
+        };
+
+        let lamports = calc_lamports_of_new_changes(changes, lamport_of);
+        for (change, lamport) in changes.iter().zip(lamports) {
+            if change.ctr_end() <= vv.get(&change.id.peer).copied().unwrap_or(0) {
+                continue;
+            }
+
+            let lamport = lamport.unwrap_or(0);
+            let mut meta =
+                ChangeMeta::from_change_in_range(change, change.id.counter..change.ctr_end());
+            meta.lamport = lamport;
+            let mut ops = Vec::with_capacity(change.ops.len());
+            for op in change.ops.iter() {
+                let op_lamport = lamport + (op.counter - change.id.counter) as Lamport;
+                for remote in self.local_op_to_remote(op) {
+                    ops.push(ChangeOp::from_remote_op(remote, op_lamport, change.id.peer));
+                }
+            }
+
+            validator(&meta, &ops).map_err(|reason| LoroError::ImportRejected {
+                id: change.id,
+                reason: reason.into_boxed_str(),
+            })?;
+        }
+
+        Ok(())
+    }
+
     fn convert_change_to_remote(&self, change: &Change) -> Change<RemoteOp> {
         let mut ops = RleVec::new();
         for op in change.ops.iter() {
@@ -545,6 +993,7 @@ impl OpLog {
             deps: change.deps.clone(),
             lamport: change.lamport,
             timestamp: change.timestamp,
+            message: change.message.clone(),
             has_dependents: false,
         }
     }
@@ -567,7 +1016,7 @@ impl OpLog {
                             pos: *pos,
                         }));
                     }
-                    loro_common::ContainerType::List => {
+                    loro_common::ContainerType::List | loro_common::ContainerType::MovableList => {
                         contents.push(RawOpContent::List(list_op::ListOp::Insert {
                             slice: ListSlice::RawData(Cow::Owned(
                                 self.arena
@@ -576,8 +1025,9 @@ impl OpLog {
                             pos: *pos,
                         }))
                     }
-                    loro_common::ContainerType::Map => unreachable!(),
-                    loro_common::ContainerType::Tree => unreachable!(),
+                    loro_common::ContainerType::Map
+                    | loro_common::ContainerType::Tree
+                    | loro_common::ContainerType::Counter => unreachable!(),
                 },
                 list_op::InnerListOp::InsertText {
                     slice,
@@ -595,8 +1045,10 @@ impl OpLog {
                         }));
                     }
                     loro_common::ContainerType::List
+                    | loro_common::ContainerType::MovableList
                     | loro_common::ContainerType::Map
-                    | loro_common::ContainerType::Tree => {
+                    | loro_common::ContainerType::Tree
+                    | loro_common::ContainerType::Counter => {
                         unreachable!()
                     }
                 },
@@ -619,6 +1071,19 @@ impl OpLog {
                 list_op::InnerListOp::StyleEnd => {
                     contents.push(RawOpContent::List(list_op::ListOp::StyleEnd))
                 }
+                list_op::InnerListOp::Move { from, to, elem_id } => {
+                    contents.push(RawOpContent::List(list_op::ListOp::Move {
+                        from: *from,
+                        to: *to,
+                        elem_id: *elem_id,
+                    }))
+                }
+                list_op::InnerListOp::Set { elem_id, value } => {
+                    contents.push(RawOpContent::List(list_op::ListOp::Set {
+                        elem_id: *elem_id,
+                        value: value.clone(),
+                    }))
+                }
             },
             crate::op::InnerContent::Map(map) => {
                 let value = map.value.clone();
@@ -627,7 +1092,8 @@ impl OpLog {
                     value,
                 }))
             }
-            crate::op::InnerContent::Tree(tree) => contents.push(RawOpContent::Tree(*tree)),
+            crate::op::InnerContent::Tree(tree) => contents.push(RawOpContent::Tree(tree.clone())),
+            crate::op::InnerContent::Counter(x) => contents.push(RawOpContent::Counter(*x)),
         };
 
         let mut ans = SmallVec::with_capacity(contents.len());
@@ -658,7 +1124,9 @@ impl OpLog {
             // Because get_by_atom_index would return Some if counter is at the end,
             // we cannot use it directly.
             // TODO: maybe we should refactor this
-            if id.counter <= changes.last().unwrap().id_last().counter {
+            if id.counter >= changes[0].id.counter
+                && id.counter <= changes.last().unwrap().id_last().counter
+            {
                 Some(changes.get_by_atom_index(id.counter).unwrap().element)
             } else {
                 None
@@ -666,10 +1134,101 @@ impl OpLog {
         })
     }
 
-    #[allow(unused)]
+    /// lookup op by id. It can be one of the ops kept by the shallow root.
     pub(crate) fn lookup_op(&self, id: ID) -> Option<&crate::op::Op> {
-        self.lookup_change(id)
-            .and_then(|change| change.ops.get_by_atom_index(id.counter).map(|x| x.element))
+        match self.lookup_change(id) {
+            Some(change) => change.ops.get_by_atom_index(id.counter).map(|x| x.element),
+            None => self.lookup_shallow_root_op(id).map(|x| &x.op),
+        }
+    }
+
+    fn lookup_shallow_root_op(&self, id: ID) -> Option<&OpWithId> {
+        let ops = self.shallow_root.ops.get(&id.peer)?;
+        let index = ops
+            .binary_search_by(|x| {
+                if x.op.counter > id.counter {
+                    Ordering::Greater
+                } else if x.op.ctr_end() <= id.counter {
+                    Ordering::Less
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .ok()?;
+        Some(&ops[index])
+    }
+
+    /// Get the atom op at the given id with its lamport.
+    pub(crate) fn get_op_with_id(&self, id: ID) -> Option<OpWithId> {
+        if let Some(change) = self.lookup_change(id) {
+            let op = change.ops.get_by_atom_index(id.counter)?.element;
+            let offset = (id.counter - op.counter) as usize;
+            return Some(OpWithId {
+                peer: id.peer,
+                op: op.slice(offset, offset + 1),
+                lamport: Some(change.lamport + (id.counter - change.id.counter) as Lamport),
+            });
+        }
+
+        let op = self.lookup_shallow_root_op(id)?;
+        let offset = (id.counter - op.op.counter) as usize;
+        Some(OpWithId {
+            peer: id.peer,
+            op: op.op.slice(offset, offset + 1),
+            lamport: op.lamport.map(|x| x + offset as Lamport),
+        })
+    }
+
+    /// Get the ops in the given span, including the ones kept by the shallow root.
+    pub(crate) fn get_ops_by_idlp_span(&self, span: IdLpSpan) -> Vec<OpWithId> {
+        let mut ans = Vec::new();
+        let mut lamport = span.lamport.start;
+        while lamport < span.lamport.end {
+            if let Some(id) = self.idlp_to_id(IdLp::new(span.peer, lamport)) {
+                let change = self.get_change_at(id).unwrap();
+                let end = change.lamport_end().min(span.lamport.end);
+                let id_span = IdSpan::new(
+                    span.peer,
+                    id.counter,
+                    id.counter + (end - lamport) as Counter,
+                );
+                for op in self.iter_ops(id_span) {
+                    ans.push(OpWithId {
+                        peer: span.peer,
+                        op: op.op().into_owned(),
+                        lamport: Some(op.lamport()),
+                    });
+                }
+                lamport = end;
+                continue;
+            }
+
+            let ops = self.shallow_root.ops.get(&span.peer).unwrap();
+            let index = ops
+                .binary_search_by(|x| {
+                    let start = x.lamport.unwrap();
+                    if start > lamport {
+                        Ordering::Greater
+                    } else if start + x.op.atom_len() as Lamport <= lamport {
+                        Ordering::Less
+                    } else {
+                        Ordering::Equal
+                    }
+                })
+                .unwrap();
+            let op = &ops[index];
+            let op_lamport = op.lamport.unwrap();
+            let start = (lamport - op_lamport) as usize;
+            let end = (span.lamport.end - op_lamport).min(op.op.atom_len() as Lamport) as usize;
+            ans.push(OpWithId {
+                peer: span.peer,
+                op: op.op.slice(start, end),
+                lamport: Some(lamport),
+            });
+            lamport = op_lamport + end as Lamport;
+        }
+
+        ans
     }
 
     #[inline(always)]
@@ -700,6 +1259,7 @@ impl OpLog {
                 std::mem::swap(&mut from_cnt, &mut to_cnt);
             }
 
+            let from_cnt = from_cnt.max(changes[0].id.counter);
             let Some(result) = changes.get_by_atom_index(from_cnt) else {
                 continue;
             };
@@ -850,7 +1410,7 @@ impl OpLog {
             let cnt = span.counter.start;
             let end_cnt = span.counter.end;
             let peer_changes = self.changes.get(&peer).unwrap();
-            let index = peer_changes.search_atom_index(cnt);
+            let index = peer_changes.search_atom_index(cnt.max(peer_changes[0].id.counter));
             peer_changes[index..]
                 .iter()
                 .take_while(move |x| x.ctr_start() < end_cnt)
@@ -906,3 +1466,66 @@ pub struct SizeInfo {
     pub total_atom_ops: usize,
     pub total_dag_node: usize,
 }
+
+/// Calculate the lamports of the changes that are not imported yet.
+///
+/// The deps of a change can be in the oplog, whose lamports are looked up by
+/// `lamport_of`, or in `changes`. It's `None` if some of the deps are missing.
+pub(crate) fn calc_lamports_of_new_changes(
+    changes: &[Change],
+    lamport_of: impl Fn(&ID) -> Option<Lamport>,
+) -> Vec<Option<Lamport>> {
+    let mut by_peer: FxHashMap<PeerID, Vec<usize>> = FxHashMap::default();
+    for (i, change) in changes.iter().enumerate() {
+        by_peer.entry(change.id.peer).or_default().push(i);
+    }
+    for indexes in by_peer.values_mut() {
+        indexes.sort_by_key(|&i| changes[i].id.counter);
+    }
+    let find = |id: &ID| -> Option<usize> {
+        let indexes = by_peer.get(&id.peer)?;
+        let pos = indexes.partition_point(|&i| changes[i].ctr_end() <= id.counter);
+        let &i = indexes.get(pos)?;
+        (changes[i].id.counter <= id.counter).then_some(i)
+    };
+
+    let mut ans: Vec<Option<Option<Lamport>>> = vec![None; changes.len()];
+    let mut visiting = vec![false; changes.len()];
+    for start in 0..changes.len() {
+        let mut stack = vec![start];
+        while let Some(&i) = stack.last() {
+            if ans[i].is_some() {
+                stack.pop();
+                continue;
+            }
+
+            visiting[i] = true;
+            let mut lamport = Some(0);
+            let mut waiting = false;
+            for dep in changes[i].deps.iter() {
+                let dep_lamport = match (lamport_of(dep), find(dep)) {
+                    (Some(x), _) => Some(x),
+                    (None, Some(j)) => match ans[j] {
+                        Some(x) => x.map(|x| x + (dep.counter - changes[j].id.counter) as Lamport),
+                        // the deps are cyclic, which only happens with invalid data
+                        None if visiting[j] => None,
+                        None => {
+                            stack.push(j);
+                            waiting = true;
+                            break;
+                        }
+                    },
+                    (None, None) => None,
+                };
+                lamport = lamport.zip(dep_lamport).map(|(a, b)| a.max(b + 1));
+            }
+
+            if !waiting {
+                ans[i] = Some(lamport);
+                stack.pop();
+            }
+        }
+    }
+
+    ans.into_iter().map(|x| x.unwrap()).collect()
+}
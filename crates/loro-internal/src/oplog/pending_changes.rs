@@ -1,11 +1,16 @@
-use std::{collections::BTreeMap, ops::Deref};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Deref,
+};
 
-use crate::{change::Change, OpLog, VersionVector};
-use fxhash::FxHashMap;
-use loro_common::{Counter, CounterSpan, HasCounterSpan, HasIdSpan, HasLamportSpan, PeerID, ID};
+use crate::{change::Change, encoding::PendingChangesSummary, OpLog, VersionVector};
+use fxhash::{FxHashMap, FxHashSet};
+use loro_common::{
+    Counter, CounterSpan, HasCounterSpan, HasIdSpan, HasLamportSpan, IdSpan, PeerID, ID,
+};
 use smallvec::SmallVec;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum PendingChange {
     // The lamport of the change decoded by `enhanced` is unknown.
     // we need calculate it when the change can be applied
@@ -25,7 +30,7 @@ impl Deref for PendingChange {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub(crate) struct PendingChanges {
     changes: FxHashMap<PeerID, BTreeMap<Counter, SmallVec<[PendingChange; 1]>>>,
 }
@@ -34,9 +39,95 @@ impl PendingChanges {
     pub fn is_empty(&self) -> bool {
         self.changes.is_empty()
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Change> {
+        self.changes
+            .values()
+            .flat_map(|x| x.values())
+            .flat_map(|x| x.iter().map(|c| &**c))
+    }
 }
 
 impl OpLog {
+    pub(crate) fn pending_changes_summary(&self) -> PendingChangesSummary {
+        self.summarize_pending(|change| Some(change.id_span()))
+    }
+
+    /// Get the pending parts of the `imported` spans and the missing deps they are waiting
+    /// for, whether or not they were already pending before the import.
+    pub(crate) fn pending_changes_summary_within(
+        &self,
+        imported: &[IdSpan],
+    ) -> PendingChangesSummary {
+        self.summarize_pending(|change| {
+            let span = imported.iter().find(|x| x.peer == change.id.peer)?;
+            let start = span.counter.start.max(change.id.counter);
+            let end = span.counter.end.min(change.ctr_end());
+            (start < end).then(|| IdSpan::new(change.id.peer, start, end))
+        })
+    }
+
+    fn summarize_pending(
+        &self,
+        mut span_of: impl FnMut(&Change) -> Option<IdSpan>,
+    ) -> PendingChangesSummary {
+        let mut pending = Vec::new();
+        let mut changes = Vec::new();
+        for change in self.pending_changes.iter() {
+            if let Some(span) = span_of(change) {
+                pending.push(span);
+                changes.push(change);
+            }
+        }
+
+        PendingChangesSummary {
+            pending: merge_overlapping_spans(pending),
+            missing_deps: self.missing_deps_of_pending(changes),
+        }
+    }
+
+    /// Get the deps that the pending `changes` are waiting for. The pending deps are
+    /// followed until the deps that are neither in the oplog nor pending.
+    fn missing_deps_of_pending<'a>(&'a self, mut changes: Vec<&'a Change>) -> Vec<ID> {
+        let mut pending_by_peer: FxHashMap<PeerID, Vec<&Change>> = FxHashMap::default();
+        for change in self.pending_changes.iter() {
+            pending_by_peer
+                .entry(change.id.peer)
+                .or_default()
+                .push(change);
+        }
+
+        let mut visited = FxHashSet::default();
+        let mut ans = BTreeSet::new();
+        while let Some(change) = changes.pop() {
+            if !visited.insert(change.id) {
+                continue;
+            }
+
+            // a change always depends on the previous op of the same peer
+            let prev = (change.id.counter > 0).then(|| change.id.inc(-1));
+            for dep in change.deps.iter().copied().chain(prev) {
+                if self.vv().includes_id(dep) {
+                    continue;
+                }
+
+                let len = changes.len();
+                changes.extend(
+                    pending_by_peer
+                        .get(&dep.peer)
+                        .into_iter()
+                        .flatten()
+                        .filter(|c| c.ctr_span().contains(dep.counter)),
+                );
+                if changes.len() == len {
+                    ans.insert(dep);
+                }
+            }
+        }
+
+        ans.into_iter().collect()
+    }
+
     pub(super) fn extend_pending_changes_with_unknown_lamport(
         &mut self,
         remote_changes: Vec<Change>,
@@ -59,6 +150,23 @@ impl OpLog {
     }
 }
 
+/// Sort the spans and merge the ones that overlap, e.g. the spans of the same change that
+/// is pending twice.
+fn merge_overlapping_spans(mut spans: Vec<IdSpan>) -> Vec<IdSpan> {
+    spans.sort_by_key(|x| (x.peer, x.counter.start));
+    let mut ans: Vec<IdSpan> = Vec::with_capacity(spans.len());
+    for span in spans {
+        match ans.last_mut() {
+            Some(last) if last.peer == span.peer && span.counter.start < last.counter.end => {
+                last.counter.end = last.counter.end.max(span.counter.end);
+            }
+            _ => ans.push(span),
+        }
+    }
+
+    ans
+}
+
 /// This struct indicates that the dag frontiers should be updated after the change is applied.
 #[must_use]
 pub(crate) struct ShouldUpdateDagFrontiers {
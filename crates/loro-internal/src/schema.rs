@@ -0,0 +1,207 @@
+//! The schema that the local edits of a doc are checked against.
+//!
+//! A [Schema] describes the root containers by their names. Each [ContainerSchema]
+//! describes the type of a container and what can be inserted into it, and the child
+//! containers are described recursively. The containers that are not described by the
+//! schema are not checked.
+//!
+//! Only local edits are checked. The imported changes are always accepted.
+
+use fxhash::FxHashMap;
+use loro_common::{ContainerType, LoroValue};
+
+use crate::event::Index;
+
+/// The schema of a doc. See [the module docs](self).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schema {
+    roots: FxHashMap<String, ContainerSchema>,
+}
+
+/// The expected type and content of a container.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContainerSchema {
+    /// A map that only allows the keys in `keys`.
+    ///
+    /// If `other_keys` is set, the other keys are allowed and their values are checked by it.
+    Map {
+        keys: FxHashMap<String, ValueSchema>,
+        other_keys: Option<Box<ValueSchema>>,
+    },
+    /// A list whose elements are checked by the inner schema
+    List(Box<ValueSchema>),
+    /// A movable list whose elements are checked by the inner schema
+    MovableList(Box<ValueSchema>),
+    Text,
+    /// A tree whose node meta maps are checked by `meta`
+    Tree {
+        meta: Box<ContainerSchema>,
+    },
+    Counter,
+}
+
+/// The expected kind of a value in a map or a list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueSchema {
+    /// Any value or container
+    Any,
+    Null,
+    Bool,
+    Double,
+    I64,
+    String,
+    Binary,
+    List,
+    Map,
+    /// A child container described by the inner schema
+    Container(ContainerSchema),
+}
+
+/// A value or a new child container that is inserted by a local edit.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Inserted<'a> {
+    Value(&'a LoroValue),
+    Container(ContainerType),
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Describe the root container with the given name.
+    pub fn with_root(mut self, name: &str, schema: ContainerSchema) -> Self {
+        self.roots.insert(name.to_string(), schema);
+        self
+    }
+
+    /// Get the schema of the root container with the given name.
+    pub fn root(&self, name: &str) -> Option<&ContainerSchema> {
+        self.roots.get(name)
+    }
+}
+
+impl ContainerSchema {
+    /// A map that only allows the given keys.
+    pub fn map<'a>(keys: impl IntoIterator<Item = (&'a str, ValueSchema)>) -> Self {
+        ContainerSchema::Map {
+            keys: keys.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+            other_keys: None,
+        }
+    }
+
+    pub fn container_type(&self) -> ContainerType {
+        match self {
+            ContainerSchema::Map { .. } => ContainerType::Map,
+            ContainerSchema::List(_) => ContainerType::List,
+            ContainerSchema::MovableList(_) => ContainerType::MovableList,
+            ContainerSchema::Text => ContainerType::Text,
+            ContainerSchema::Tree { .. } => ContainerType::Tree,
+            ContainerSchema::Counter => ContainerType::Counter,
+        }
+    }
+
+    /// Get the schema of the child container at `index`.
+    ///
+    /// It's `None` if the child is not described as a container.
+    pub(crate) fn child_container(&self, index: &Index) -> Option<&ContainerSchema> {
+        let value = match (self, index) {
+            (ContainerSchema::Map { keys, other_keys }, Index::Key(key)) => {
+                keys.get(&**key).or(other_keys.as_deref())?
+            }
+            (ContainerSchema::List(value), Index::Seq(_))
+            | (ContainerSchema::MovableList(value), Index::Seq(_)) => value,
+            (ContainerSchema::Tree { meta }, Index::Node(_)) => return Some(meta),
+            _ => return None,
+        };
+
+        match value {
+            ValueSchema::Container(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Check the value or the type of the child container to set at `key` of a map.
+    pub(crate) fn check_map_insert(&self, key: &str, value: Inserted) -> Result<(), String> {
+        let ContainerSchema::Map { keys, other_keys } = self else {
+            return Ok(());
+        };
+
+        match keys.get(key).or(other_keys.as_deref()) {
+            Some(schema) => schema
+                .check(value)
+                .map_err(|e| format!("invalid value of key \"{}\": {}", key, e)),
+            None => Err(format!("key \"{}\" is not allowed", key)),
+        }
+    }
+
+    /// Check the value or the type of the child container to insert into a list.
+    pub(crate) fn check_list_insert(&self, value: Inserted) -> Result<(), String> {
+        match self {
+            ContainerSchema::List(schema) | ContainerSchema::MovableList(schema) => schema
+                .check(value)
+                .map_err(|e| format!("invalid list element: {}", e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl ValueSchema {
+    fn check(&self, value: Inserted) -> Result<(), String> {
+        let ok = match (self, value) {
+            (ValueSchema::Any, _) => true,
+            (ValueSchema::Container(c), Inserted::Container(ty)) => c.container_type() == ty,
+            (ValueSchema::Container(_), Inserted::Value(_)) | (_, Inserted::Container(_)) => false,
+            (schema, Inserted::Value(value)) => matches!(
+                (schema, value),
+                (ValueSchema::Null, LoroValue::Null)
+                    | (ValueSchema::Bool, LoroValue::Bool(_))
+                    | (ValueSchema::Double, LoroValue::Double(_))
+                    | (ValueSchema::I64, LoroValue::I64(_))
+                    | (ValueSchema::String, LoroValue::String(_))
+                    | (ValueSchema::Binary, LoroValue::Binary(_))
+                    | (ValueSchema::List, LoroValue::List(_))
+                    | (ValueSchema::Map, LoroValue::Map(_))
+            ),
+        };
+
+        if ok {
+            return Ok(());
+        }
+
+        let found = match value {
+            Inserted::Value(value) => value_kind_name(value).to_string(),
+            Inserted::Container(ty) => format!("{} container", ty),
+        };
+        Err(format!("expected {}, found {}", self.kind_name(), found))
+    }
+
+    fn kind_name(&self) -> String {
+        match self {
+            ValueSchema::Any => "any value".to_string(),
+            ValueSchema::Null => "Null".to_string(),
+            ValueSchema::Bool => "Bool".to_string(),
+            ValueSchema::Double => "Double".to_string(),
+            ValueSchema::I64 => "I64".to_string(),
+            ValueSchema::String => "String".to_string(),
+            ValueSchema::Binary => "Binary".to_string(),
+            ValueSchema::List => "List".to_string(),
+            ValueSchema::Map => "Map".to_string(),
+            ValueSchema::Container(c) => format!("{} container", c.container_type()),
+        }
+    }
+}
+
+fn value_kind_name(value: &LoroValue) -> &'static str {
+    match value {
+        LoroValue::Null => "Null",
+        LoroValue::Bool(_) => "Bool",
+        LoroValue::Double(_) => "Double",
+        LoroValue::I64(_) => "I64",
+        LoroValue::Binary(_) => "Binary",
+        LoroValue::String(_) => "String",
+        LoroValue::List(_) => "List",
+        LoroValue::Map(_) => "Map",
+        LoroValue::Container(_) => "Container",
+    }
+}
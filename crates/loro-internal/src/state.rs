@@ -16,24 +16,30 @@ use crate::{
     },
     cursor::Cursor,
     delta::DeltaItem,
-    encoding::{StateSnapshotDecodeContext, StateSnapshotEncoder},
+    encoding::{EncodeMode, StateSnapshotDecodeContext, StateSnapshotEncoder},
     event::{Diff, EventTriggerKind, Index, InternalContainerDiff, InternalDiff},
     fx_map,
     handler::ValueOrHandler,
     id::PeerID,
+    loro::is_version_tags_root,
     op::{ListSlice, Op, RawOp, RawOpContent},
+    schema::ContainerSchema,
     txn::Transaction,
     version::Frontiers,
-    ContainerDiff, ContainerType, DocDiff, InternalString, LoroValue,
+    ContainerDiff, ContainerType, DocDiff, InternalString, LoroValue, OpLog,
 };
 
+mod counter_state;
 mod list_state;
 mod map_state;
+mod movable_list_state;
 mod richtext_state;
 mod tree_state;
 
+pub(crate) use counter_state::CounterState;
 pub(crate) use list_state::ListState;
 pub(crate) use map_state::MapState;
+pub(crate) use movable_list_state::MovableListState;
 pub(crate) use richtext_state::RichtextState;
 pub(crate) use tree_state::{get_meta_value, TreeParentId, TreeState};
 
@@ -61,6 +67,8 @@ pub struct DocState {
     // resolve event stuff
     weak_state: Weak<Mutex<DocState>>,
     global_txn: Weak<Mutex<Option<Transaction>>>,
+    // used by handlers to look up the changes of the ops in the state
+    pub(super) oplog: Weak<Mutex<OpLog>>,
     // txn related stuff
     in_txn: bool,
     changed_idx_in_txn: FxHashSet<ContainerIdx>,
@@ -118,7 +126,7 @@ pub(crate) trait ContainerState: Clone {
     fn encode_snapshot(&self, encoder: StateSnapshotEncoder) -> Vec<u8>;
 
     /// Restore the state to the state represented by the ops and the blob that exported by `get_snapshot_ops`
-    fn import_from_snapshot_ops(&mut self, ctx: StateSnapshotDecodeContext);
+    fn import_from_snapshot_ops(&mut self, ctx: StateSnapshotDecodeContext) -> LoroResult<()>;
 }
 
 impl<T: ContainerState> ContainerState for Box<T> {
@@ -194,7 +202,7 @@ impl<T: ContainerState> ContainerState for Box<T> {
     }
 
     #[doc = r" Restore the state to the state represented by the ops and the blob that exported by `get_snapshot_ops`"]
-    fn import_from_snapshot_ops(&mut self, ctx: StateSnapshotDecodeContext) {
+    fn import_from_snapshot_ops(&mut self, ctx: StateSnapshotDecodeContext) -> LoroResult<()> {
         self.as_mut().import_from_snapshot_ops(ctx)
     }
 }
@@ -207,6 +215,8 @@ pub enum State {
     MapState(Box<MapState>),
     RichtextState(Box<RichtextState>),
     TreeState(Box<TreeState>),
+    MovableListState(Box<MovableListState>),
+    CounterState(Box<CounterState>),
 }
 
 impl State {
@@ -225,9 +235,44 @@ impl State {
     pub fn new_tree(idx: ContainerIdx) -> Self {
         Self::TreeState(Box::new(TreeState::new(idx)))
     }
+
+    pub fn new_movable_list(idx: ContainerIdx) -> Self {
+        Self::MovableListState(Box::new(MovableListState::new(idx)))
+    }
+
+    pub fn new_counter(idx: ContainerIdx) -> Self {
+        Self::CounterState(Box::new(CounterState::new(idx)))
+    }
 }
 
 impl DocState {
+    /// Create a state that is used to calculate diffs aside from the doc state.
+    ///
+    /// The handlers inside the diffs it emits refer to `weak_state`, i.e. the doc state,
+    /// instead of itself.
+    #[inline]
+    pub(crate) fn new_shadow(
+        arena: SharedArena,
+        global_txn: Weak<Mutex<Option<Transaction>>>,
+        weak_state: Weak<Mutex<DocState>>,
+        oplog: Weak<Mutex<OpLog>>,
+        config: Configure,
+    ) -> Self {
+        Self {
+            peer: DefaultRandom.next_u64(),
+            arena,
+            frontiers: Frontiers::default(),
+            states: FxHashMap::default(),
+            weak_state,
+            config,
+            global_txn,
+            oplog,
+            in_txn: false,
+            changed_idx_in_txn: FxHashSet::default(),
+            event_recorder: Default::default(),
+        }
+    }
+
     #[inline]
     pub fn new_arc(
         arena: SharedArena,
@@ -245,6 +290,7 @@ impl DocState {
                 weak_state: weak.clone(),
                 config,
                 global_txn,
+                oplog: Weak::new(),
                 in_txn: false,
                 changed_idx_in_txn: FxHashSet::default(),
                 event_recorder: Default::default(),
@@ -252,6 +298,11 @@ impl DocState {
         })
     }
 
+    /// Set the oplog that the handlers look up the changes of the ops in.
+    pub(crate) fn set_oplog(&mut self, oplog: Weak<Mutex<OpLog>>) {
+        self.oplog = oplog;
+    }
+
     pub fn start_recording(&mut self) {
         if self.is_recording() {
             return;
@@ -339,6 +390,11 @@ impl DocState {
         let start = recorder.diff_start_version.take().unwrap();
         recorder.diff_start_version = Some((*diffs.last().unwrap().new_version).to_owned());
         let event = self.diffs_to_event(diffs, start);
+        if event.diff.is_empty() {
+            // e.g. the batch only changes the version tags
+            return;
+        }
+
         self.event_recorder.events.push(event);
     }
 
@@ -502,21 +558,22 @@ impl DocState {
         let container = raw_op.container;
         match &raw_op.content {
             RawOpContent::List(op) => {
-                if let ListOp::Insert {
-                    slice: ListSlice::RawData(list),
-                    ..
-                } = op
-                {
-                    let list = match list {
+                let list = match op {
+                    ListOp::Insert {
+                        slice: ListSlice::RawData(list),
+                        ..
+                    } => match list {
                         std::borrow::Cow::Borrowed(list) => list.iter(),
                         std::borrow::Cow::Owned(list) => list.iter(),
-                    };
-                    for value in list {
-                        if value.is_container() {
-                            let c = value.as_container().unwrap();
-                            let idx = self.arena.register_container(c);
-                            self.arena.set_parent(idx, Some(container));
-                        }
+                    },
+                    ListOp::Set { value, .. } => std::slice::from_ref(value).iter(),
+                    _ => return,
+                };
+                for value in list {
+                    if value.is_container() {
+                        let c = value.as_container().unwrap();
+                        let idx = self.arena.register_container(c);
+                        self.arena.set_parent(idx, Some(container));
                     }
                 }
             }
@@ -537,6 +594,7 @@ impl DocState {
                 let child_idx = self.arena.register_container(&container_id);
                 self.arena.set_parent(child_idx, Some(container));
             }
+            RawOpContent::Counter(_) => {}
         }
     }
 
@@ -574,7 +632,15 @@ impl DocState {
                     self.arena.set_parent(child_idx, Some(container));
                 }
             }
-            InternalDiff::RichtextRaw(_) => {}
+            InternalDiff::MovableList(list) => {
+                for elem in list.elements.values() {
+                    if let LoroValue::Container(c) = &elem.value {
+                        let idx = self.arena.register_container(c);
+                        self.arena.set_parent(idx, Some(container));
+                    }
+                }
+            }
+            InternalDiff::RichtextRaw(_) | InternalDiff::Counter(_) => {}
         }
     }
 
@@ -582,10 +648,10 @@ impl DocState {
         &mut self,
         cid: ContainerID,
         decode_ctx: StateSnapshotDecodeContext,
-    ) {
+    ) -> LoroResult<()> {
         let idx = self.arena.register_container(&cid);
         let state = get_or_create!(self, idx);
-        state.import_from_snapshot_ops(decode_ctx);
+        state.import_from_snapshot_ops(decode_ctx)
     }
 
     pub(crate) fn commit_txn(&mut self, new_frontiers: Frontiers, diff: Option<InternalDocDiff>) {
@@ -615,6 +681,26 @@ impl DocState {
             .unwrap_or_else(|| container_idx.get_type().default_value())
     }
 
+    /// Init the state with the container states at the shallow root of the oplog
+    pub(crate) fn init_with_shallow_root(&mut self, oplog: &OpLog) -> LoroResult<()> {
+        let root = oplog.shallow_root.clone();
+        for state in root.states.iter() {
+            self.init_container(
+                state.container.clone(),
+                StateSnapshotDecodeContext {
+                    oplog,
+                    ops: &mut state.ops.iter().cloned(),
+                    blob: &state.blob,
+                    mode: EncodeMode::ShallowSnapshot,
+                },
+            )?;
+        }
+
+        let states = std::mem::take(&mut self.states);
+        self.init_with_states_and_version(states, root.frontiers.clone());
+        Ok(())
+    }
+
     /// Set the state of the container with the given container idx.
     /// This is only used for decode.
     ///
@@ -738,6 +824,10 @@ impl DocState {
         let mut ans = FxHashMap::with_capacity_and_hasher(roots.len(), Default::default());
         for root_idx in roots {
             let id = self.arena.idx_to_id(root_idx).unwrap();
+            if is_version_tags_root(&id) {
+                continue;
+            }
+
             match id {
                 loro_common::ContainerID::Root { name, .. } => {
                     ans.insert(name.to_string(), self.get_container_deep_value(root_idx));
@@ -756,6 +846,10 @@ impl DocState {
         let mut ans = FxHashMap::with_capacity_and_hasher(roots.len(), Default::default());
         for root_idx in roots {
             let id = self.arena.idx_to_id(root_idx).unwrap();
+            if is_version_tags_root(&id) {
+                continue;
+            }
+
             match id.clone() {
                 loro_common::ContainerID::Root { name, .. } => {
                     ans.insert(
@@ -838,6 +932,35 @@ impl DocState {
         }
     }
 
+    /// Get the diffs that build the container and its descendants from empty states, parents
+    /// first. They are emitted when the container is moved, like the containers brought back
+    /// by a checkout. It's empty if the events are not recorded.
+    pub(crate) fn get_container_state_diffs(
+        &mut self,
+        container: ContainerIdx,
+    ) -> Vec<(ContainerIdx, Diff)> {
+        let mut ans = Vec::new();
+        if !self.is_recording() {
+            return ans;
+        }
+
+        let mut stack = vec![container];
+        while let Some(idx) = stack.pop() {
+            let Some(state) = self.states.get_mut(&idx) else {
+                continue;
+            };
+            let diff = state.to_diff(&self.arena, &self.global_txn, &self.weak_state);
+            for child in state.get_child_containers() {
+                stack.push(self.arena.register_container(&child));
+            }
+            if !diff.is_empty() {
+                ans.push((idx, diff));
+            }
+        }
+
+        ans
+    }
+
     pub fn get_container_deep_value(&mut self, container: ContainerIdx) -> LoroValue {
         let Some(state) = self.states.get_mut(&container) else {
             return container.get_type().default_value();
@@ -908,6 +1031,14 @@ impl DocState {
                     // omit event form deleted container
                     continue;
                 }
+                if self
+                    .arena
+                    .get_container_id(container_diff.idx)
+                    .is_some_and(|id| is_version_tags_root(&id))
+                {
+                    // the version tags are hidden from the events
+                    continue;
+                }
                 let Some((last_container_diff, _)) = containers.get_mut(&container_diff.idx) else {
                     if let Some(path) = self.get_path(container_diff.idx) {
                         containers.insert(container_diff.idx, (container_diff.diff.unwrap(), path));
@@ -955,6 +1086,54 @@ impl DocState {
         }
     }
 
+    /// Check a local edit of the container against the schema of the doc.
+    ///
+    /// `check` checks the content of the edit. It's skipped if the container is not
+    /// described by the schema.
+    pub(crate) fn check_schema(
+        &self,
+        idx: ContainerIdx,
+        check: impl FnOnce(&ContainerSchema) -> Result<(), String>,
+    ) -> LoroResult<()> {
+        let Some(schema) = self.config.schema() else {
+            return Ok(());
+        };
+        let Some(path) = self.get_path(idx) else {
+            return Ok(());
+        };
+        let mut path = path.into_iter();
+        let Some((root_id, Index::Key(root))) = path.next() else {
+            return Ok(());
+        };
+        if is_version_tags_root(&root_id) {
+            return Ok(());
+        }
+        let Some(mut container) = schema.root(&root) else {
+            return Ok(());
+        };
+        for (_, index) in path {
+            let Some(child) = container.child_container(&index) else {
+                return Ok(());
+            };
+            container = child;
+        }
+
+        let id = self.arena.idx_to_id(idx).unwrap();
+        let result = if container.container_type() != id.container_type() {
+            Err(format!(
+                "expected a {} container, found a {} container",
+                container.container_type(),
+                id.container_type()
+            ))
+        } else {
+            check(container)
+        };
+        result.map_err(|reason| LoroError::SchemaViolation {
+            container: id,
+            reason: reason.into_boxed_str(),
+        })
+    }
+
     // the container may be override, so it may return None
     fn get_path(&self, idx: ContainerIdx) -> Option<Vec<(ContainerID, Index)>> {
         let s = tracing::span!(tracing::Level::INFO, "GET PATH ", ?idx);
@@ -1080,6 +1259,10 @@ impl DocState {
                 self.config.text_style_config.clone(),
             ))),
             ContainerType::Tree => State::TreeState(Box::new(TreeState::new(idx))),
+            ContainerType::MovableList => {
+                State::MovableListState(Box::new(MovableListState::new(idx)))
+            }
+            ContainerType::Counter => State::CounterState(Box::new(CounterState::new(idx))),
         }
     }
 
@@ -1089,8 +1272,9 @@ impl DocState {
         if let Some(id) = pos.id {
             match state {
                 State::ListState(s) => s.get_index_of_id(id),
+                State::MovableListState(s) => s.get_index_of_id(id),
                 State::RichtextState(s) => s.get_index_of_id(id),
-                State::MapState(_) | State::TreeState(_) => {
+                State::MapState(_) | State::TreeState(_) | State::CounterState(_) => {
                     unreachable!()
                 }
             }
@@ -1101,8 +1285,9 @@ impl DocState {
 
             match state {
                 State::ListState(s) => Some(s.len()),
+                State::MovableListState(s) => Some(s.len()),
                 State::RichtextState(s) => Some(s.len_event()),
-                State::MapState(_) | State::TreeState(_) => {
+                State::MapState(_) | State::TreeState(_) | State::CounterState(_) => {
                     unreachable!()
                 }
             }
@@ -1133,13 +1318,19 @@ impl DocState {
                     };
                     state_idx = self.arena.register_container(c);
                 }
+                State::MovableListState(l) => {
+                    let Some(LoroValue::Container(c)) = l.get(*index.as_seq()?) else {
+                        return None;
+                    };
+                    state_idx = self.arena.register_container(c);
+                }
                 State::MapState(m) => {
                     let Some(LoroValue::Container(c)) = m.get(index.as_key()?) else {
                         return None;
                     };
                     state_idx = self.arena.register_container(c);
                 }
-                State::RichtextState(_) => return None,
+                State::RichtextState(_) | State::CounterState(_) => return None,
                 State::TreeState(_) => {
                     let id = index.as_node()?;
                     let cid = id.associated_meta_container();
@@ -1152,6 +1343,7 @@ impl DocState {
         let index = path.last().unwrap();
         let value: LoroValue = match parent_state {
             State::ListState(l) => l.get(*index.as_seq()?).cloned()?,
+            State::MovableListState(l) => l.get(*index.as_seq()?).cloned()?,
             State::MapState(m) => m.get(index.as_key()?).cloned()?,
             State::RichtextState(s) => {
                 let s = s.to_string_mut();
@@ -1164,6 +1356,7 @@ impl DocState {
                 let cid = id.associated_meta_container();
                 cid.into()
             }
+            State::CounterState(_) => return None,
         };
 
         Some(value)
@@ -0,0 +1,119 @@
+use std::sync::{Mutex, Weak};
+
+use loro_common::{ContainerID, LoroError, LoroResult, LoroValue};
+
+use super::ContainerState;
+use crate::{
+    arena::SharedArena,
+    container::idx::ContainerIdx,
+    encoding::{StateSnapshotDecodeContext, StateSnapshotEncoder},
+    event::{Diff, Index, InternalDiff},
+    op::{Op, RawOp, RawOpContent},
+    txn::Transaction,
+    DocState,
+};
+
+/// The state of a counter.
+///
+/// Counter ops are commutative, so the state is just the sum of all the applied deltas.
+#[derive(Debug, Clone)]
+pub struct CounterState {
+    idx: ContainerIdx,
+    value: f64,
+}
+
+impl CounterState {
+    pub fn new(idx: ContainerIdx) -> Self {
+        Self { idx, value: 0. }
+    }
+
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+}
+
+impl ContainerState for CounterState {
+    fn container_idx(&self) -> ContainerIdx {
+        self.idx
+    }
+
+    fn estimate_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+    }
+
+    fn is_state_empty(&self) -> bool {
+        self.value == 0.
+    }
+
+    fn apply_diff_and_convert(
+        &mut self,
+        diff: InternalDiff,
+        _arena: &SharedArena,
+        _txn: &Weak<Mutex<Option<Transaction>>>,
+        _state: &Weak<Mutex<DocState>>,
+    ) -> Diff {
+        let InternalDiff::Counter(delta) = diff else {
+            unreachable!()
+        };
+        self.value += delta;
+        Diff::Counter(delta)
+    }
+
+    fn apply_diff(
+        &mut self,
+        diff: InternalDiff,
+        _arena: &SharedArena,
+        _txn: &Weak<Mutex<Option<Transaction>>>,
+        _state: &Weak<Mutex<DocState>>,
+    ) {
+        let InternalDiff::Counter(delta) = diff else {
+            unreachable!()
+        };
+        self.value += delta;
+    }
+
+    fn apply_local_op(&mut self, raw_op: &RawOp, _op: &Op) -> LoroResult<()> {
+        let RawOpContent::Counter(delta) = raw_op.content else {
+            unreachable!()
+        };
+        self.value += delta;
+        Ok(())
+    }
+
+    #[doc = " Convert a state to a diff that when apply this diff on a empty state,"]
+    #[doc = " the state will be the same as this state."]
+    fn to_diff(
+        &mut self,
+        _arena: &SharedArena,
+        _txn: &Weak<Mutex<Option<Transaction>>>,
+        _state: &Weak<Mutex<DocState>>,
+    ) -> Diff {
+        Diff::Counter(self.value)
+    }
+
+    fn get_value(&mut self) -> LoroValue {
+        LoroValue::Double(self.value)
+    }
+
+    fn get_child_index(&self, _id: &ContainerID) -> Option<Index> {
+        None
+    }
+
+    fn get_child_containers(&self) -> Vec<ContainerID> {
+        Vec::new()
+    }
+
+    /// The counter value is stored in the blob directly, no ops are needed to restore it.
+    fn encode_snapshot(&self, _encoder: StateSnapshotEncoder) -> Vec<u8> {
+        self.value.to_le_bytes().to_vec()
+    }
+
+    fn import_from_snapshot_ops(&mut self, ctx: StateSnapshotDecodeContext) -> LoroResult<()> {
+        let bytes: [u8; 8] = ctx
+            .blob
+            .try_into()
+            .map_err(|_| LoroError::DecodeDataCorruptionError)?;
+        self.value = f64::from_le_bytes(bytes);
+        Ok(())
+    }
+}
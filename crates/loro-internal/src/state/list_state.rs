@@ -1,5 +1,5 @@
 use std::{
-    ops::RangeBounds,
+    ops::{Range, RangeBounds},
     sync::{Arc, Mutex, Weak},
 };
 
@@ -8,7 +8,7 @@ use crate::{
     arena::SharedArena,
     container::{idx::ContainerIdx, ContainerID},
     delta::Delta,
-    encoding::{EncodeMode, StateSnapshotDecodeContext, StateSnapshotEncoder},
+    encoding::{StateSnapshotDecodeContext, StateSnapshotEncoder},
     event::{Diff, Index, InternalDiff},
     handler::ValueOrHandler,
     op::{ListSlice, Op, RawOp, RawOpContent},
@@ -286,6 +286,20 @@ impl ListState {
         }
     }
 
+    /// Get the ids of the elements in the given range
+    pub(crate) fn get_ids_in_range(&self, range: Range<usize>) -> Vec<IdFull> {
+        if range.is_empty() {
+            return Vec::new();
+        }
+
+        let start = self.list.query::<LengthFinder>(&range.start).unwrap();
+        let end = self.list.query::<LengthFinder>(&range.end).unwrap();
+        self.list
+            .iter_range(start.cursor..end.cursor)
+            .map(|x| x.elem.id)
+            .collect()
+    }
+
     #[allow(unused)]
     pub(crate) fn check(&self) {
         for value in self.iter() {
@@ -408,6 +422,7 @@ impl ContainerState for ListState {
         match &op.content {
             RawOpContent::Map(_) => unreachable!(),
             RawOpContent::Tree(_) => unreachable!(),
+            RawOpContent::Counter(_) => unreachable!(),
             RawOpContent::List(list) => match list {
                 crate::container::list::list_op::ListOp::Insert { slice, pos } => match slice {
                     ListSlice::RawData(list) => match list {
@@ -425,6 +440,8 @@ impl ContainerState for ListState {
                 }
                 crate::container::list::list_op::ListOp::StyleStart { .. } => unreachable!(),
                 crate::container::list::list_op::ListOp::StyleEnd { .. } => unreachable!(),
+                crate::container::list::list_op::ListOp::Move { .. }
+                | crate::container::list::list_op::ListOp::Set { .. } => unreachable!(),
             },
         }
         Ok(())
@@ -478,8 +495,8 @@ impl ContainerState for ListState {
     }
 
     #[doc = "Restore the state to the state represented by the ops that exported by `get_snapshot_ops`"]
-    fn import_from_snapshot_ops(&mut self, ctx: StateSnapshotDecodeContext) {
-        assert_eq!(ctx.mode, EncodeMode::Snapshot);
+    fn import_from_snapshot_ops(&mut self, ctx: StateSnapshotDecodeContext) -> LoroResult<()> {
+        assert!(ctx.mode.is_snapshot());
         let mut index = 0;
         for op in ctx.ops {
             let value = op.op.content.as_list().unwrap().as_insert().unwrap().0;
@@ -491,6 +508,8 @@ impl ContainerState for ListState {
             self.insert_batch(index, list, op.id_full());
             index += len;
         }
+
+        Ok(())
     }
 }
 
@@ -11,7 +11,7 @@ use crate::{
     arena::SharedArena,
     container::{idx::ContainerIdx, map::MapSet},
     delta::{MapValue, ResolvedMapDelta, ResolvedMapValue},
-    encoding::{EncodeMode, StateSnapshotDecodeContext, StateSnapshotEncoder},
+    encoding::{StateSnapshotDecodeContext, StateSnapshotEncoder},
     event::{Diff, Index, InternalDiff},
     handler::ValueOrHandler,
     op::{Op, RawOp, RawOpContent},
@@ -104,6 +104,7 @@ impl ContainerState for MapState {
             }
             RawOpContent::List(_) => unreachable!(),
             RawOpContent::Tree(_) => unreachable!(),
+            RawOpContent::Counter(_) => unreachable!(),
         }
     }
 
@@ -169,8 +170,8 @@ impl ContainerState for MapState {
     }
 
     #[doc = " Restore the state to the state represented by the ops that exported by `get_snapshot_ops`"]
-    fn import_from_snapshot_ops(&mut self, ctx: StateSnapshotDecodeContext) {
-        assert_eq!(ctx.mode, EncodeMode::Snapshot);
+    fn import_from_snapshot_ops(&mut self, ctx: StateSnapshotDecodeContext) -> LoroResult<()> {
+        assert!(ctx.mode.is_snapshot());
         for op in ctx.ops {
             debug_assert_eq!(
                 op.op.atom_len(),
@@ -188,6 +189,8 @@ impl ContainerState for MapState {
                 },
             );
         }
+
+        Ok(())
     }
 }
 
@@ -0,0 +1,748 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Mutex, Weak},
+};
+
+use fxhash::{FxHashMap, FxHashSet};
+use generic_btree::{
+    iter,
+    rle::{HasLength, Mergeable, Sliceable},
+    BTree, BTreeTrait, Cursor, FindResult, LeafIndex, LengthFinder, PreviousCache, Query,
+    UseLengthFinder,
+};
+use loro_common::{IdFull, IdLpSpan, LoroResult, ID};
+
+use super::ContainerState;
+use crate::{
+    arena::SharedArena,
+    container::{idx::ContainerIdx, list::list_op::ListOp, ContainerID},
+    delta::{Delta, DeltaItem, MovableListElement, MovableListInnerDelta, MovableListSlot},
+    diff_calc::resolve_movable_list_elem,
+    encoding::{EncodeMode, StateSnapshotDecodeContext, StateSnapshotEncoder},
+    event::{Diff, Index, InternalDiff},
+    handler::ValueOrHandler,
+    op::{ListSlice, Op, RawOp, RawOpContent},
+    txn::Transaction,
+    DocState, LoroValue,
+};
+
+/// The state of a movable list.
+///
+/// The list is made of slots. A slot is created by an insert op or a move op, and it's
+/// removed by a delete op. Each element points to one slot, which is decided by LWW
+/// over its move ops, or to no slot if the element is deleted. A slot that no element
+/// points to is invisible to users, but it still takes a position in the op indexes.
+///
+/// The slots are stored in a B-tree that caches both the number of slots and the
+/// number of visible slots, so the conversion between user indexes and op indexes
+/// is logarithmic.
+#[derive(Debug, Clone)]
+pub struct MovableListState {
+    idx: ContainerIdx,
+    slots: BTree<SlotTree>,
+    slot_to_leaf: FxHashMap<ID, LeafIndex>,
+    elements: FxHashMap<ID, MovableListElement>,
+    child_container_to_elem: FxHashMap<ContainerID, ID>,
+}
+
+#[derive(Debug, Clone)]
+struct SlotElem {
+    slot: MovableListSlot,
+    visible: bool,
+}
+
+impl HasLength for SlotElem {
+    fn rle_len(&self) -> usize {
+        1
+    }
+}
+
+impl Sliceable for SlotElem {
+    fn _slice(&self, range: std::ops::Range<usize>) -> Self {
+        assert_eq!(range.start, 0);
+        assert_eq!(range.end, 1);
+        self.clone()
+    }
+
+    fn split(&mut self, _pos: usize) -> Self {
+        unreachable!()
+    }
+}
+
+impl Mergeable for SlotElem {
+    fn can_merge(&self, _rhs: &Self) -> bool {
+        false
+    }
+
+    fn merge_right(&mut self, _rhs: &Self) {
+        unreachable!()
+    }
+
+    fn merge_left(&mut self, _left: &Self) {
+        unreachable!()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct SlotCache {
+    /// The number of slots
+    all: isize,
+    /// The number of visible slots
+    visible: isize,
+}
+
+impl SlotCache {
+    fn add(&mut self, other: &Self) {
+        self.all += other.all;
+        self.visible += other.visible;
+    }
+}
+
+struct SlotTree;
+impl BTreeTrait for SlotTree {
+    type Elem = SlotElem;
+    type Cache = SlotCache;
+    type CacheDiff = SlotCache;
+    const USE_DIFF: bool = true;
+
+    #[inline(always)]
+    fn calc_cache_internal(
+        cache: &mut Self::Cache,
+        caches: &[generic_btree::Child<Self>],
+    ) -> Self::CacheDiff {
+        let mut new_cache = SlotCache::default();
+        for child in caches {
+            new_cache.add(&child.cache);
+        }
+
+        let diff = Self::sub_cache(&new_cache, cache);
+        *cache = new_cache;
+        diff
+    }
+
+    #[inline(always)]
+    fn apply_cache_diff(cache: &mut Self::Cache, diff: &Self::CacheDiff) {
+        cache.add(diff);
+    }
+
+    #[inline(always)]
+    fn merge_cache_diff(diff1: &mut Self::CacheDiff, diff2: &Self::CacheDiff) {
+        diff1.add(diff2);
+    }
+
+    #[inline(always)]
+    fn get_elem_cache(elem: &Self::Elem) -> Self::Cache {
+        SlotCache {
+            all: 1,
+            visible: elem.visible as isize,
+        }
+    }
+
+    #[inline(always)]
+    fn new_cache_to_diff(cache: &Self::Cache) -> Self::CacheDiff {
+        *cache
+    }
+
+    fn sub_cache(cache_lhs: &Self::Cache, cache_rhs: &Self::Cache) -> Self::CacheDiff {
+        SlotCache {
+            all: cache_lhs.all - cache_rhs.all,
+            visible: cache_lhs.visible - cache_rhs.visible,
+        }
+    }
+}
+
+/// Find the slot by its op index
+impl UseLengthFinder<SlotTree> for SlotTree {
+    fn get_len(cache: &SlotCache) -> usize {
+        cache.all as usize
+    }
+}
+
+/// Find the visible slot by its user index
+struct VisibleIndexFinder {
+    left: usize,
+}
+
+impl Query<SlotTree> for VisibleIndexFinder {
+    type QueryArg = usize;
+
+    fn init(target: &Self::QueryArg) -> Self {
+        Self { left: *target }
+    }
+
+    fn find_node(
+        &mut self,
+        _: &Self::QueryArg,
+        child_caches: &[generic_btree::Child<SlotTree>],
+    ) -> FindResult {
+        let mut last_left = self.left;
+        for (i, child) in child_caches.iter().enumerate() {
+            let len = child.cache.visible as usize;
+            if self.left >= len {
+                last_left = self.left;
+                self.left -= len;
+            } else {
+                return FindResult::new_found(i, self.left);
+            }
+        }
+
+        self.left = last_left;
+        FindResult::new_missing(child_caches.len() - 1, last_left)
+    }
+
+    fn confirm_elem(&mut self, _: &Self::QueryArg, elem: &SlotElem) -> (usize, bool) {
+        (0, elem.visible && self.left == 0)
+    }
+}
+
+impl MovableListState {
+    pub fn new(idx: ContainerIdx) -> Self {
+        Self {
+            idx,
+            slots: BTree::new(),
+            slot_to_leaf: Default::default(),
+            elements: Default::default(),
+            child_container_to_elem: Default::default(),
+        }
+    }
+
+    /// Iterate the ids of all the slots with the ids of their elements,
+    /// including the slots that are not visible
+    pub(crate) fn iter_slot_and_elem_ids(&self) -> impl Iterator<Item = (ID, ID)> + '_ {
+        self.slots
+            .iter()
+            .map(|elem| (elem.slot.id.id(), elem.slot.elem_id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.root_cache().visible as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &LoroValue> + '_ {
+        self.slots
+            .iter()
+            .filter(|elem| elem.visible)
+            .map(|elem| &self.elements.get(&elem.slot.elem_id).unwrap().value)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&LoroValue> {
+        let elem_id = self.get_elem_id_at(index)?;
+        Some(&self.elements.get(&elem_id).unwrap().value)
+    }
+
+    /// Get the id of the element at the given user index
+    pub fn get_elem_id_at(&self, index: usize) -> Option<ID> {
+        self.get_visible_slot_leaf(index)
+            .map(|leaf| self.slots.get_elem(leaf).unwrap().slot.elem_id)
+    }
+
+    /// Convert the user index to the op index.
+    ///
+    /// If `index` equals to the length of the list, the op index after the last
+    /// visible slot is returned.
+    pub fn convert_index_to_op_index(&self, index: usize) -> Option<usize> {
+        let len = self.len();
+        if index < len {
+            return self
+                .get_visible_slot_leaf(index)
+                .map(|leaf| self.get_op_index_of_leaf(leaf));
+        }
+
+        if index == len {
+            if len == 0 {
+                return Some(0);
+            }
+
+            return self
+                .get_visible_slot_leaf(len - 1)
+                .map(|leaf| self.get_op_index_of_leaf(leaf) + 1);
+        }
+
+        None
+    }
+
+    /// Get the op index and the slot id of the element that is currently at the user index
+    pub fn get_slot_at(&self, index: usize) -> Option<(usize, IdFull)> {
+        let leaf = self.get_visible_slot_leaf(index)?;
+        Some((
+            self.get_op_index_of_leaf(leaf),
+            self.slots.get_elem(leaf).unwrap().slot.id,
+        ))
+    }
+
+    /// Get the op index that an element should be moved to, so that it will be
+    /// at the user index `to` after the move.
+    pub fn get_move_target_op_index(&self, elem_id: ID, to: usize) -> Option<usize> {
+        let len = self.len();
+        let from = self.get_index_of_id(elem_id)?;
+        // the user index of the `to`-th element if the moved element is skipped
+        let skip = |i: usize| if i < from { i } else { i + 1 };
+        if to + 1 < len {
+            return self.convert_index_to_op_index(skip(to));
+        }
+
+        if to + 1 == len {
+            if len == 1 {
+                return Some(0);
+            }
+
+            return self.convert_index_to_op_index(skip(to - 1)).map(|x| x + 1);
+        }
+
+        None
+    }
+
+    pub fn get_index_of_id(&self, elem_id: ID) -> Option<usize> {
+        let elem = self.elements.get(&elem_id)?;
+        let leaf = *self.slot_to_leaf.get(&elem.pos)?;
+        if !self.slots.get_elem(leaf)?.visible {
+            return None;
+        }
+
+        Some(self.get_visible_index_of_leaf(leaf))
+    }
+
+    fn get_visible_slot_leaf(&self, index: usize) -> Option<LeafIndex> {
+        let result = self.slots.query::<VisibleIndexFinder>(&index)?;
+        if result.found {
+            Some(result.cursor.leaf)
+        } else {
+            None
+        }
+    }
+
+    /// Get the number of slots and the number of visible slots before the leaf
+    fn get_prefix_cache_of_leaf(&self, leaf: LeafIndex) -> SlotCache {
+        let mut ans = SlotCache::default();
+        self.slots
+            .visit_previous_caches(Cursor { leaf, offset: 0 }, |cache| match cache {
+                PreviousCache::NodeCache(cache) => {
+                    ans.add(cache);
+                }
+                PreviousCache::PrevSiblingElem(elem) => {
+                    ans.add(&SlotTree::get_elem_cache(elem));
+                }
+                PreviousCache::ThisElemAndOffset { .. } => {}
+            });
+        ans
+    }
+
+    fn get_op_index_of_leaf(&self, leaf: LeafIndex) -> usize {
+        self.get_prefix_cache_of_leaf(leaf).all as usize
+    }
+
+    fn get_visible_index_of_leaf(&self, leaf: LeafIndex) -> usize {
+        self.get_prefix_cache_of_leaf(leaf).visible as usize
+    }
+
+    /// Get the number of visible slots before the op index
+    fn get_visible_count_before(&self, op_index: usize) -> usize {
+        if op_index >= self.slots.root_cache().all as usize {
+            return self.len();
+        }
+
+        let result = self.slots.query::<LengthFinder>(&op_index).unwrap();
+        self.get_visible_index_of_leaf(result.cursor.leaf)
+    }
+
+    fn to_vec(&self) -> Vec<LoroValue> {
+        self.iter().cloned().collect()
+    }
+
+    fn insert_elem(&mut self, id: ID, elem: MovableListElement) -> Option<MovableListElement> {
+        if let LoroValue::Container(c) = &elem.value {
+            self.child_container_to_elem.insert(c.clone(), id);
+        }
+
+        self.elements.insert(id, elem)
+    }
+
+    fn insert_slot(&mut self, op_index: usize, slot: MovableListSlot, visible: bool) {
+        let elem = SlotElem { slot, visible };
+        if self.slots.is_empty() {
+            let cursor = self.slots.push(elem);
+            self.slot_to_leaf.insert(slot.id.id(), cursor.leaf);
+            return;
+        }
+
+        let (cursor, splitted) = self.slots.insert::<LengthFinder>(&op_index, elem);
+        self.slot_to_leaf.insert(slot.id.id(), cursor.leaf);
+        for leaf in splitted.arr {
+            let id = self.slots.get_elem(leaf).unwrap().slot.id.id();
+            self.slot_to_leaf.insert(id, leaf);
+        }
+    }
+
+    fn delete_slots(&mut self, op_index: usize, len: usize) {
+        if len == 0 {
+            return;
+        }
+
+        if len == 1 {
+            let result = self.slots.query::<LengthFinder>(&op_index).unwrap();
+            let elem = self.slots.remove_leaf(result.cursor).unwrap();
+            self.slot_to_leaf.remove(&elem.slot.id.id());
+            return;
+        }
+
+        let start = self.slots.query::<LengthFinder>(&op_index);
+        let end = self.slots.query::<LengthFinder>(&(op_index + len));
+        for elem in iter::Drain::new(&mut self.slots, start, end) {
+            self.slot_to_leaf.remove(&elem.slot.id.id());
+        }
+    }
+
+    /// Set the visibility of the slot. Return whether the slot exists and its visibility
+    /// is changed.
+    fn set_slot_visible(&mut self, slot_id: ID, visible: bool) -> bool {
+        let Some(&leaf) = self.slot_to_leaf.get(&slot_id) else {
+            return false;
+        };
+
+        let mut changed = false;
+        self.slots.update_leaf(leaf, |elem| {
+            changed = elem.visible != visible;
+            elem.visible = visible;
+            (changed, None, None)
+        });
+        changed
+    }
+
+    fn is_slot_visible(&self, slot_id: ID) -> bool {
+        self.slot_to_leaf
+            .get(&slot_id)
+            .is_some_and(|leaf| self.slots.get_elem(*leaf).unwrap().visible)
+    }
+
+    fn insert_slots(&mut self, op_index: usize, values: Vec<LoroValue>, start_id: IdFull) {
+        let mut id = start_id;
+        for (i, value) in values.into_iter().enumerate() {
+            self.insert_slot(
+                op_index + i,
+                MovableListSlot {
+                    id,
+                    elem_id: id.id(),
+                },
+                true,
+            );
+            self.insert_elem(
+                id.id(),
+                MovableListElement {
+                    pos: id.id(),
+                    value,
+                },
+            );
+            id = id.inc(1);
+        }
+    }
+
+    /// Apply the inner diff to the state.
+    ///
+    /// If `with_event` is true, it returns the user indexes of the removed elements
+    /// in the old state, and the user indexes and the values of the inserted elements
+    /// in the new state. An element whose value is changed is both removed and inserted.
+    fn apply_inner_diff(
+        &mut self,
+        diff: InternalDiff,
+        with_event: bool,
+    ) -> (BTreeSet<usize>, BTreeMap<usize, LoroValue>) {
+        let InternalDiff::MovableList(MovableListInnerDelta { slots, elements }) = diff else {
+            unreachable!()
+        };
+
+        let mut removed = BTreeSet::new();
+        if with_event {
+            let mut old_index = 0;
+            for item in slots.iter() {
+                match item {
+                    DeltaItem::Retain { retain, .. } => {
+                        old_index += retain;
+                    }
+                    DeltaItem::Insert { .. } => {}
+                    DeltaItem::Delete { delete, .. } => {
+                        let start = self.get_visible_count_before(old_index);
+                        let end = self.get_visible_count_before(old_index + delete);
+                        removed.extend(start..end);
+                        old_index += delete;
+                    }
+                }
+            }
+
+            for (id, elem) in elements.iter() {
+                let Some(old) = self.elements.get(id) else {
+                    continue;
+                };
+
+                if old == elem || !self.is_slot_visible(old.pos) {
+                    continue;
+                }
+
+                let leaf = *self.slot_to_leaf.get(&old.pos).unwrap();
+                removed.insert(self.get_visible_index_of_leaf(leaf));
+            }
+        }
+
+        // the slots that may become visible or whose values may be changed
+        let mut touched_slots = Vec::new();
+        let mut index = 0;
+        for item in slots.into_iter() {
+            match item {
+                DeltaItem::Retain { retain, .. } => {
+                    index += retain;
+                }
+                DeltaItem::Insert { insert, .. } => {
+                    for slot in insert {
+                        self.insert_slot(index, slot, false);
+                        touched_slots.push(slot.id.id());
+                        index += 1;
+                    }
+                }
+                DeltaItem::Delete { delete, .. } => {
+                    self.delete_slots(index, delete);
+                }
+            }
+        }
+
+        for (id, elem) in elements {
+            if let Some(old) = self.elements.get(&id) {
+                if *old == elem {
+                    continue;
+                }
+
+                self.set_slot_visible(old.pos, false);
+            }
+
+            touched_slots.push(elem.pos);
+            self.insert_elem(id, elem);
+        }
+
+        let mut visible_slots = Vec::new();
+        let mut visited = FxHashSet::default();
+        for slot_id in touched_slots {
+            if !visited.insert(slot_id) {
+                continue;
+            }
+
+            let Some(&leaf) = self.slot_to_leaf.get(&slot_id) else {
+                continue;
+            };
+
+            let elem_id = self.slots.get_elem(leaf).unwrap().slot.elem_id;
+            if self
+                .elements
+                .get(&elem_id)
+                .is_some_and(|e| e.pos == slot_id)
+            {
+                self.set_slot_visible(slot_id, true);
+                visible_slots.push((leaf, elem_id));
+            }
+        }
+
+        let mut inserted = BTreeMap::new();
+        if with_event {
+            // the indexes are computed after all the slots are updated
+            for (leaf, elem_id) in visible_slots {
+                inserted.insert(
+                    self.get_visible_index_of_leaf(leaf),
+                    self.elements.get(&elem_id).unwrap().value.clone(),
+                );
+            }
+        }
+
+        (removed, inserted)
+    }
+}
+
+impl ContainerState for MovableListState {
+    fn container_idx(&self) -> ContainerIdx {
+        self.idx
+    }
+
+    fn estimate_size(&self) -> usize {
+        self.slots.root_cache().all as usize * std::mem::size_of::<SlotElem>()
+            + self.elements.len() * std::mem::size_of::<(ID, MovableListElement)>()
+    }
+
+    fn is_state_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    fn apply_diff_and_convert(
+        &mut self,
+        diff: InternalDiff,
+        arena: &SharedArena,
+        txn: &Weak<Mutex<Option<Transaction>>>,
+        state: &Weak<Mutex<DocState>>,
+    ) -> Diff {
+        let (removed, inserted) = self.apply_inner_diff(diff, true);
+        let mut deletion = Delta::new();
+        let mut last = 0;
+        for index in removed {
+            deletion = deletion.retain(index - last).delete(1);
+            last = index + 1;
+        }
+
+        let mut insertion = Delta::new();
+        let mut last = 0;
+        for (index, value) in inserted {
+            insertion = insertion
+                .retain(index - last)
+                .insert(vec![ValueOrHandler::from_value(value, arena, txn, state)]);
+            last = index + 1;
+        }
+
+        Diff::List(deletion.compose(insertion))
+    }
+
+    fn apply_diff(
+        &mut self,
+        diff: InternalDiff,
+        _arena: &SharedArena,
+        _txn: &Weak<Mutex<Option<Transaction>>>,
+        _state: &Weak<Mutex<DocState>>,
+    ) {
+        self.apply_inner_diff(diff, false);
+    }
+
+    fn apply_local_op(&mut self, op: &RawOp, _: &Op) -> LoroResult<()> {
+        match &op.content {
+            RawOpContent::List(list) => match list {
+                ListOp::Insert { slice, pos } => match slice {
+                    ListSlice::RawData(list) => {
+                        self.insert_slots(*pos, list.to_vec(), op.id_full());
+                    }
+                    _ => unreachable!(),
+                },
+                ListOp::Delete(del) => {
+                    let range = del.span.to_urange();
+                    self.delete_slots(range.start, range.len());
+                }
+                ListOp::Move { to, elem_id, .. } => {
+                    self.insert_slot(
+                        *to as usize,
+                        MovableListSlot {
+                            id: op.id_full(),
+                            elem_id: *elem_id,
+                        },
+                        true,
+                    );
+                    let elem = self.elements.get_mut(elem_id).unwrap();
+                    let old_pos = std::mem::replace(&mut elem.pos, op.id);
+                    self.set_slot_visible(old_pos, false);
+                }
+                ListOp::Set { elem_id, value } => {
+                    let mut elem = self.elements.get(elem_id).unwrap().clone();
+                    elem.value = value.clone();
+                    self.insert_elem(*elem_id, elem);
+                }
+                ListOp::StyleStart { .. } | ListOp::StyleEnd => unreachable!(),
+            },
+            RawOpContent::Map(_) | RawOpContent::Tree(_) | RawOpContent::Counter(_) => {
+                unreachable!()
+            }
+        }
+
+        Ok(())
+    }
+
+    #[doc = " Convert a state to a diff that when apply this diff on a empty state,"]
+    #[doc = " the state will be the same as this state."]
+    fn to_diff(
+        &mut self,
+        arena: &SharedArena,
+        txn: &Weak<Mutex<Option<Transaction>>>,
+        state: &Weak<Mutex<DocState>>,
+    ) -> Diff {
+        Diff::List(
+            Delta::new().insert(
+                self.to_vec()
+                    .into_iter()
+                    .map(|v| ValueOrHandler::from_value(v, arena, txn, state))
+                    .collect::<Vec<_>>(),
+            ),
+        )
+    }
+
+    fn get_value(&mut self) -> LoroValue {
+        LoroValue::List(Arc::new(self.to_vec()))
+    }
+
+    fn get_child_index(&self, id: &ContainerID) -> Option<Index> {
+        let elem_id = *self.child_container_to_elem.get(id)?;
+        if self.elements.get(&elem_id)?.value.as_container() != Some(id) {
+            return None;
+        }
+
+        self.get_index_of_id(elem_id).map(Index::Seq)
+    }
+
+    fn get_child_containers(&self) -> Vec<ContainerID> {
+        self.iter()
+            .filter_map(|v| v.as_container().cloned())
+            .collect()
+    }
+
+    #[doc = "Get a list of ops that can be used to restore the state to the current state"]
+    fn encode_snapshot(&self, mut encoder: StateSnapshotEncoder) -> Vec<u8> {
+        for elem in self.slots.iter() {
+            let id_span: IdLpSpan = elem.slot.id.idlp().into();
+            encoder.encode_op(id_span, || unimplemented!());
+        }
+
+        Vec::new()
+    }
+
+    #[doc = "Restore the state to the state represented by the ops that exported by `get_snapshot_ops`"]
+    fn import_from_snapshot_ops(&mut self, ctx: StateSnapshotDecodeContext) -> LoroResult<()> {
+        assert!(ctx.mode.is_snapshot());
+        let mut slots = Vec::new();
+        for op in ctx.ops {
+            match op.op.content.as_list().unwrap() {
+                crate::container::list::list_op::InnerListOp::Insert { slice, .. } => {
+                    let mut id = op.id_full();
+                    for _ in slice.0.clone() {
+                        slots.push(MovableListSlot {
+                            id,
+                            elem_id: id.id(),
+                        });
+                        id = id.inc(1);
+                    }
+                }
+                crate::container::list::list_op::InnerListOp::Move { elem_id, .. } => {
+                    slots.push(MovableListSlot {
+                        id: op.id_full(),
+                        elem_id: *elem_id,
+                    });
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        // the ops of a shallow snapshot form the state at the shallow root
+        let vv = if ctx.mode == EncodeMode::ShallowSnapshot {
+            ctx.oplog.shallow_since_vv()
+        } else {
+            ctx.oplog.vv()
+        };
+        for slot in slots.iter() {
+            if self.elements.contains_key(&slot.elem_id) {
+                continue;
+            }
+
+            let elem = resolve_movable_list_elem(ctx.oplog, self.idx, slot.elem_id, vv);
+            self.insert_elem(slot.elem_id, elem);
+        }
+
+        for slot in slots {
+            let visible = self.elements.get(&slot.elem_id).unwrap().pos == slot.id.id();
+            let cursor = self.slots.push(SlotElem { slot, visible });
+            self.slot_to_leaf.insert(slot.id.id(), cursor.leaf);
+        }
+
+        Ok(())
+    }
+}
@@ -5,7 +5,7 @@ use std::{
 
 use fxhash::{FxHashMap, FxHashSet};
 use generic_btree::rle::HasLength;
-use loro_common::{ContainerID, InternalString, LoroResult, LoroValue, ID};
+use loro_common::{ContainerID, IdFull, InternalString, LoroResult, LoroValue, ID};
 
 use crate::{
     arena::SharedArena,
@@ -21,7 +21,7 @@ use crate::{
         },
     },
     delta::{Delta, DeltaItem, StyleMeta, StyleMetaItem},
-    encoding::{EncodeMode, StateSnapshotDecodeContext, StateSnapshotEncoder},
+    encoding::{StateSnapshotDecodeContext, StateSnapshotEncoder},
     event::{Diff, Index, InternalDiff},
     op::{Op, RawOp},
     txn::Transaction,
@@ -497,6 +497,9 @@ impl ContainerState for RichtextState {
                     );
                 }
                 list_op::InnerListOp::StyleEnd => {}
+                list_op::InnerListOp::Move { .. } | list_op::InnerListOp::Set { .. } => {
+                    unreachable!()
+                }
             },
             _ => unreachable!(),
         }
@@ -563,8 +566,8 @@ impl ContainerState for RichtextState {
     }
 
     #[doc = " Restore the state to the state represented by the ops that exported by `get_snapshot_ops`"]
-    fn import_from_snapshot_ops(&mut self, ctx: StateSnapshotDecodeContext) {
-        assert_eq!(ctx.mode, EncodeMode::Snapshot);
+    fn import_from_snapshot_ops(&mut self, ctx: StateSnapshotDecodeContext) -> LoroResult<()> {
+        assert!(ctx.mode.is_snapshot());
         let mut loader = RichtextStateLoader::default();
         let mut id_to_style = FxHashMap::default();
         for op in ctx.ops {
@@ -599,6 +602,7 @@ impl ContainerState for RichtextState {
 
         self.state = LazyLoad::Src(loader);
         // self.check_consistency_between_content_and_style_ranges();
+        Ok(())
     }
 }
 
@@ -691,6 +695,13 @@ impl RichtextState {
             .get_stable_position_at_event_index(event_index, PosType::Event)
     }
 
+    pub(crate) fn get_char_ids_in_event_range(
+        &mut self,
+        range: Range<usize>,
+    ) -> Vec<(IdFull, usize)> {
+        self.state.get_mut().get_char_ids_in_event_range(range)
+    }
+
     pub(crate) fn entity_index_to_event_index(&mut self, entity_index: usize) -> usize {
         self.state
             .get_mut()
@@ -1,15 +1,22 @@
 use enum_as_inner::EnumAsInner;
 use fxhash::FxHashMap;
+use generic_btree::{
+    rle::{HasLength as BTreeHasLength, Mergeable, Sliceable},
+    BTree, BTreeTrait, Cursor, FindResult, LeafIndex, PreviousCache, Query,
+};
 use itertools::Itertools;
-use loro_common::{ContainerID, IdFull, LoroError, LoroResult, LoroTreeError, LoroValue, TreeID};
+use loro_common::{
+    ContainerID, IdFull, IdLp, LoroError, LoroResult, LoroTreeError, LoroValue, TreeID,
+};
 use rle::HasLength;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex, Weak};
 
 use crate::container::idx::ContainerIdx;
+use crate::container::tree::fractional_index::FractionalIndex;
 use crate::delta::{TreeDiff, TreeDiffItem, TreeExternalDiff};
-use crate::encoding::{EncodeMode, StateSnapshotDecodeContext, StateSnapshotEncoder};
+use crate::encoding::{StateSnapshotDecodeContext, StateSnapshotEncoder};
 use crate::event::InternalDiff;
 use crate::txn::Transaction;
 use crate::DocState;
@@ -32,26 +39,341 @@ pub enum TreeParentId {
     None,
 }
 
+impl From<Option<TreeID>> for TreeParentId {
+    fn from(id: Option<TreeID>) -> Self {
+        match id {
+            Some(id) if TreeID::is_deleted_root(&id) => TreeParentId::Deleted,
+            Some(id) => TreeParentId::Node(id),
+            None => TreeParentId::None,
+        }
+    }
+}
+
 /// The state of movable tree.
 ///
 /// using flat representation
+///
+/// The children of a node are ordered by their positions. Nodes with the same position,
+/// which can be created by concurrent edits, are ordered by the id of their last move op.
 #[derive(Debug, Clone)]
 pub struct TreeState {
     idx: ContainerIdx,
     pub(crate) trees: FxHashMap<TreeID, TreeStateNode>,
+    children: FxHashMap<TreeParentId, NodeChildren>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct TreeStateNode {
     pub parent: TreeParentId,
+    pub position: Option<FractionalIndex>,
     pub last_move_op: IdFull,
 }
 
+impl TreeStateNode {
+    fn sort_key(&self) -> (Option<&FractionalIndex>, IdLp) {
+        (self.position.as_ref(), self.last_move_op.idlp())
+    }
+
+    fn node_position(&self, id: TreeID) -> NodePosition {
+        NodePosition {
+            position: self.position.clone(),
+            idlp: self.last_move_op.idlp(),
+            id,
+        }
+    }
+}
+
+/// The key that orders the children of a node.
+///
+/// The tree id only breaks the ties of the nodes that are not created by ops, such as
+/// the nodes in the tests.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct NodePosition {
+    position: Option<FractionalIndex>,
+    idlp: IdLp,
+    id: TreeID,
+}
+
+impl BTreeHasLength for NodePosition {
+    fn rle_len(&self) -> usize {
+        1
+    }
+}
+
+impl Sliceable for NodePosition {
+    fn _slice(&self, range: std::ops::Range<usize>) -> Self {
+        assert_eq!(range.start, 0);
+        assert_eq!(range.end, 1);
+        self.clone()
+    }
+
+    fn split(&mut self, _pos: usize) -> Self {
+        unreachable!()
+    }
+}
+
+impl Mergeable for NodePosition {
+    fn can_merge(&self, _rhs: &Self) -> bool {
+        false
+    }
+
+    fn merge_right(&mut self, _rhs: &Self) {
+        unreachable!()
+    }
+
+    fn merge_left(&mut self, _left: &Self) {
+        unreachable!()
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct ChildrenCache {
+    len: usize,
+    /// The greatest key in the subtree
+    max: Option<NodePosition>,
+}
+
+/// The children are kept sorted, so the max key of a subtree is the max key of its last child.
+///
+/// The cache can't be updated by diffs when a child is removed, so `USE_DIFF` is false.
+/// The only diff generic-btree applies in this mode is the cache of a new child.
+struct ChildrenTree;
+impl BTreeTrait for ChildrenTree {
+    type Elem = NodePosition;
+    type Cache = ChildrenCache;
+    type CacheDiff = ChildrenCache;
+    const USE_DIFF: bool = false;
+
+    fn calc_cache_internal(
+        cache: &mut Self::Cache,
+        caches: &[generic_btree::Child<Self>],
+    ) -> Self::CacheDiff {
+        *cache = ChildrenCache {
+            len: caches.iter().map(|c| c.cache.len).sum(),
+            max: caches.last().and_then(|c| c.cache.max.clone()),
+        };
+        cache.clone()
+    }
+
+    fn apply_cache_diff(cache: &mut Self::Cache, diff: &Self::CacheDiff) {
+        cache.len += diff.len;
+        if diff.max > cache.max {
+            cache.max = diff.max.clone();
+        }
+    }
+
+    fn merge_cache_diff(diff1: &mut Self::CacheDiff, diff2: &Self::CacheDiff) {
+        Self::apply_cache_diff(diff1, diff2);
+    }
+
+    fn get_elem_cache(elem: &Self::Elem) -> Self::Cache {
+        ChildrenCache {
+            len: 1,
+            max: Some(elem.clone()),
+        }
+    }
+
+    fn new_cache_to_diff(cache: &Self::Cache) -> Self::CacheDiff {
+        cache.clone()
+    }
+
+    fn sub_cache(cache_lhs: &Self::Cache, _cache_rhs: &Self::Cache) -> Self::CacheDiff {
+        cache_lhs.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ChildrenBound {
+    /// The keys that are less than the key
+    Before(NodePosition),
+    /// The keys whose positions are not greater than the position
+    NotAfter(Option<FractionalIndex>),
+}
+
+impl ChildrenBound {
+    fn contains(&self, key: &NodePosition) -> bool {
+        match self {
+            ChildrenBound::Before(bound) => key < bound,
+            ChildrenBound::NotAfter(position) => key.position <= *position,
+        }
+    }
+}
+
+/// Find the first child that is out of the bound
+struct ChildrenBoundFinder;
+
+impl Query<ChildrenTree> for ChildrenBoundFinder {
+    type QueryArg = ChildrenBound;
+
+    fn init(_target: &Self::QueryArg) -> Self {
+        Self
+    }
+
+    fn find_node(
+        &mut self,
+        bound: &Self::QueryArg,
+        child_caches: &[generic_btree::Child<ChildrenTree>],
+    ) -> FindResult {
+        for (i, child) in child_caches.iter().enumerate() {
+            if let Some(max) = &child.cache.max {
+                if !bound.contains(max) {
+                    return FindResult::new_found(i, 0);
+                }
+            }
+        }
+
+        FindResult::new_missing(child_caches.len() - 1, 0)
+    }
+
+    fn confirm_elem(&mut self, bound: &Self::QueryArg, elem: &NodePosition) -> (usize, bool) {
+        if bound.contains(elem) {
+            (1, false)
+        } else {
+            (0, true)
+        }
+    }
+}
+
+/// Find the child at the index
+struct ChildrenIndexFinder {
+    left: usize,
+}
+
+impl Query<ChildrenTree> for ChildrenIndexFinder {
+    type QueryArg = usize;
+
+    fn init(index: &Self::QueryArg) -> Self {
+        Self { left: *index }
+    }
+
+    fn find_node(
+        &mut self,
+        _index: &Self::QueryArg,
+        child_caches: &[generic_btree::Child<ChildrenTree>],
+    ) -> FindResult {
+        for (i, child) in child_caches.iter().enumerate() {
+            if self.left < child.cache.len {
+                return FindResult::new_found(i, 0);
+            }
+            self.left -= child.cache.len;
+        }
+
+        FindResult::new_missing(child_caches.len() - 1, 0)
+    }
+
+    fn confirm_elem(&mut self, _index: &Self::QueryArg, _elem: &NodePosition) -> (usize, bool) {
+        (0, self.left == 0)
+    }
+}
+
+/// The children of a node sorted by their [NodePosition]s
+#[derive(Debug, Clone, Default)]
+struct NodeChildren {
+    tree: BTree<ChildrenTree>,
+    id_to_leaf: FxHashMap<TreeID, LeafIndex>,
+}
+
+impl NodeChildren {
+    fn is_empty(&self) -> bool {
+        self.id_to_leaf.is_empty()
+    }
+
+    fn insert(&mut self, key: NodePosition) {
+        let id = key.id;
+        let cursor = if self.tree.is_empty() {
+            self.tree.push(key)
+        } else {
+            self.tree
+                .insert::<ChildrenBoundFinder>(&ChildrenBound::Before(key.clone()), key)
+                .0
+        };
+        self.id_to_leaf.insert(id, cursor.leaf);
+    }
+
+    fn remove(&mut self, id: &TreeID) {
+        if let Some(leaf) = self.id_to_leaf.remove(id) {
+            self.tree.remove_leaf(Cursor { leaf, offset: 0 });
+        }
+    }
+
+    fn get_index(&self, id: &TreeID) -> Option<usize> {
+        let leaf = *self.id_to_leaf.get(id)?;
+        let mut index = 0;
+        self.tree
+            .visit_previous_caches(Cursor { leaf, offset: 0 }, |cache| match cache {
+                PreviousCache::NodeCache(cache) => {
+                    index += cache.len;
+                }
+                PreviousCache::PrevSiblingElem(..) => {
+                    index += 1;
+                }
+                PreviousCache::ThisElemAndOffset { .. } => {}
+            });
+        Some(index)
+    }
+
+    /// Count the children in the bound
+    fn count(&self, bound: &ChildrenBound) -> usize {
+        match self.tree.query::<ChildrenBoundFinder>(bound) {
+            Some(result) if result.found => self
+                .get_index(&self.tree.get_elem(result.cursor.leaf).unwrap().id)
+                .unwrap(),
+            Some(_) => self.id_to_leaf.len(),
+            None => 0,
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &NodePosition> + '_ {
+        self.tree.iter()
+    }
+
+    /// Iterate the children from the `index`-th one
+    fn iter_from(&self, index: usize) -> impl Iterator<Item = &NodePosition> + '_ {
+        let start = if index < self.id_to_leaf.len() {
+            self.tree
+                .query::<ChildrenIndexFinder>(&index)
+                .map(|result| result.cursor)
+        } else {
+            None
+        };
+        std::iter::successors(start, |cursor| self.tree.next_elem(*cursor))
+            .map(|cursor| self.tree.get_elem(cursor.leaf).unwrap())
+    }
+}
+
 impl TreeState {
     pub fn new(idx: ContainerIdx) -> Self {
         Self {
             idx,
             trees: FxHashMap::default(),
+            children: FxHashMap::default(),
+        }
+    }
+
+    /// Update the node and keep the children of its old and new parents sorted.
+    /// The node is removed if `node` is `None`.
+    fn set_node(&mut self, target: TreeID, node: Option<TreeStateNode>) {
+        if let Some(old) = self.trees.get(&target) {
+            let old_parent = old.parent;
+            if let Some(children) = self.children.get_mut(&old_parent) {
+                children.remove(&target);
+                if children.is_empty() {
+                    self.children.remove(&old_parent);
+                }
+            }
+        }
+
+        match node {
+            Some(node) => {
+                self.children
+                    .entry(node.parent)
+                    .or_default()
+                    .insert(node.node_position(target));
+                self.trees.insert(target, node);
+            }
+            None => {
+                self.trees.remove(&target);
+            }
         }
     }
 
@@ -59,16 +381,18 @@ impl TreeState {
         &mut self,
         target: TreeID,
         parent: TreeParentId,
+        position: Option<FractionalIndex>,
         id: IdFull,
     ) -> Result<(), LoroError> {
         if parent.is_none() {
             // new root node
-            self.trees.insert(
+            self.set_node(
                 target,
-                TreeStateNode {
+                Some(TreeStateNode {
                     parent,
+                    position,
                     last_move_op: id,
-                },
+                }),
             );
             return Ok(());
         };
@@ -81,18 +405,19 @@ impl TreeState {
             return Err(LoroTreeError::CyclicMoveError.into());
         }
         // move or delete or create children node
-        self.trees.insert(
+        self.set_node(
             target,
-            TreeStateNode {
+            Some(TreeStateNode {
                 parent,
+                position,
                 last_move_op: id,
-            },
+            }),
         );
         Ok(())
     }
 
     #[inline(never)]
-    fn is_ancestor_of(&self, maybe_ancestor: &TreeID, node_id: &TreeParentId) -> bool {
+    pub(crate) fn is_ancestor_of(&self, maybe_ancestor: &TreeID, node_id: &TreeParentId) -> bool {
         if !self.trees.contains_key(maybe_ancestor) {
             return false;
         }
@@ -159,14 +484,105 @@ impl TreeState {
             .unwrap_or(0)
     }
 
+    /// Get the children of the parent in order
     pub fn get_children(&self, parent: &TreeParentId) -> Vec<TreeID> {
-        let mut ans = Vec::new();
-        for (t, p) in self.trees.iter() {
-            if &p.parent == parent {
-                ans.push(*t);
+        self.get_children_with_position(parent)
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect()
+    }
+
+    /// Get the children of the parent and their positions in order
+    pub(crate) fn get_children_with_position(
+        &self,
+        parent: &TreeParentId,
+    ) -> Vec<(TreeID, Option<FractionalIndex>)> {
+        self.children
+            .get(parent)
+            .map(|children| {
+                children
+                    .iter()
+                    .map(|key| (key.id, key.position.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the siblings around `index` among the children of the parent. `target` is not
+    /// counted as a child.
+    ///
+    /// It returns the position of the child before `index`, the children from `index` whose
+    /// positions are not greater than it, and the position of the child after them.
+    /// Only the children around `index` are visited.
+    pub(crate) fn get_siblings_around(
+        &self,
+        parent: &TreeParentId,
+        target: Option<TreeID>,
+        index: usize,
+    ) -> LoroResult<(
+        Option<FractionalIndex>,
+        Vec<TreeID>,
+        Option<FractionalIndex>,
+    )> {
+        let children = self.children.get(parent);
+        let target_index = target.and_then(|t| children?.get_index(&t));
+        let len = children.map_or(0, |c| c.id_to_leaf.len()) - target_index.is_some() as usize;
+        if index > len {
+            return Err(LoroError::OutOfBound { pos: index, len });
+        }
+        let Some(children) = children else {
+            return Ok((None, Vec::new(), None));
+        };
+
+        // skip the target if it's before the child at `index - 1`
+        let start = index.saturating_sub(1);
+        let start = start + target_index.is_some_and(|t| t <= start) as usize;
+        let mut iter = children
+            .iter_from(start)
+            .filter(|child| Some(child.id) != target);
+        let lower = match index {
+            0 => None,
+            _ => iter.next().unwrap().position.clone(),
+        };
+        let mut same = Vec::new();
+        for child in iter {
+            if child.position > lower {
+                return Ok((lower, same, child.position.clone()));
             }
+            same.push(child.id);
         }
-        ans
+
+        Ok((lower, same, None))
+    }
+
+    /// Get the index of the node among its siblings
+    pub fn get_index_by_tree_id(&self, target: &TreeID) -> Option<usize> {
+        let node = self.trees.get(target)?;
+        self.children.get(&node.parent)?.get_index(target)
+    }
+
+    /// Get the index that a node will be at if it's moved to the parent with the position
+    /// by a new local op.
+    ///
+    /// A new local op always has the greatest lamport, so it's after the siblings with the
+    /// same position.
+    pub(crate) fn get_index_by_position(
+        &self,
+        target: &TreeID,
+        parent: &TreeParentId,
+        position: &FractionalIndex,
+    ) -> usize {
+        let Some(children) = self.children.get(parent) else {
+            return 0;
+        };
+
+        let position = Some(position.clone());
+        let count = children.count(&ChildrenBound::NotAfter(position.clone()));
+        let is_target_counted = self
+            .trees
+            .get(target)
+            .is_some_and(|x| &x.parent == parent && x.position <= position);
+        count - is_target_counted as usize
     }
 }
 
@@ -190,38 +606,42 @@ impl ContainerState for TreeState {
         _txn: &Weak<Mutex<Option<Transaction>>>,
         _state: &Weak<Mutex<DocState>>,
     ) -> Diff {
-        if let InternalDiff::Tree(tree) = &diff {
+        let mut ans = vec![];
+        if let InternalDiff::Tree(tree) = diff {
             // assert never cause cycle move
-            for diff in tree.diff.iter() {
+            for diff in tree.diff.into_iter() {
                 let target = diff.target;
                 // create associated metadata container
                 let parent = match diff.action {
                     TreeInternalDiff::Create(p)
                     | TreeInternalDiff::Move(p)
                     | TreeInternalDiff::Delete(p)
-                    | TreeInternalDiff::MoveInDelete(p) => p,
-                    TreeInternalDiff::UnCreate => {
+                    | TreeInternalDiff::MoveInDelete(p) => Some(p),
+                    TreeInternalDiff::UnCreate => None,
+                };
+                match parent {
+                    Some(parent) => {
+                        self.set_node(
+                            target,
+                            Some(TreeStateNode {
+                                parent,
+                                position: diff.position.clone(),
+                                last_move_op: diff.last_effective_move_op_id,
+                            }),
+                        );
+                    }
+                    None => {
                         // delete it from state
-                        self.trees.remove(&target);
-                        continue;
+                        self.set_node(target, None);
                     }
-                };
-                self.trees.insert(
-                    target,
-                    TreeStateNode {
-                        parent,
-                        last_move_op: diff.last_effective_move_op_id,
-                    },
-                );
+                }
+
+                // the index is calculated after the diff is applied
+                ans.extend(TreeDiffItem::from_delta_item(&diff, |t| {
+                    self.get_index_by_tree_id(t).unwrap()
+                }));
             }
         }
-        let ans = diff
-            .into_tree()
-            .unwrap()
-            .diff
-            .into_iter()
-            .filter_map(TreeDiffItem::from_delta_item)
-            .collect_vec();
         Diff::Tree(TreeDiff { diff: ans })
     }
 
@@ -236,21 +656,16 @@ impl ContainerState for TreeState {
     }
 
     fn apply_local_op(&mut self, raw_op: &RawOp, _op: &crate::op::Op) -> LoroResult<()> {
-        match raw_op.content {
+        match &raw_op.content {
             crate::op::RawOpContent::Tree(tree) => {
-                let TreeOp { target, parent, .. } = tree;
+                let TreeOp {
+                    target,
+                    parent,
+                    position,
+                } = tree;
                 // TODO: use TreeParentId
-                let parent = match parent {
-                    Some(parent) => {
-                        if TreeID::is_deleted_root(&parent) {
-                            TreeParentId::Deleted
-                        } else {
-                            TreeParentId::Node(parent)
-                        }
-                    }
-                    None => TreeParentId::None,
-                };
-                self.mov(target, parent, raw_op.id_full())
+                let parent = TreeParentId::from(*parent);
+                self.mov(*target, parent, position.clone(), raw_op.id_full())
             }
             _ => unreachable!(),
         }
@@ -265,19 +680,17 @@ impl ContainerState for TreeState {
         let mut diffs = vec![];
         // TODO: perf
         let forest = Forest::from_tree_state(&self.trees);
-        let mut q = VecDeque::from(forest.roots);
-        while let Some(node) = q.pop_front() {
-            let parent = if let Some(p) = node.parent {
-                TreeParentId::Node(p)
-            } else {
-                TreeParentId::None
-            };
+        let mut q = VecDeque::from_iter(forest.roots.into_iter().enumerate());
+        while let Some((index, node)) = q.pop_front() {
             let diff = TreeDiffItem {
                 target: node.id,
-                action: TreeExternalDiff::Create(parent.into_node().ok()),
+                action: TreeExternalDiff::Create {
+                    parent: node.parent,
+                    index,
+                },
             };
             diffs.push(diff);
-            q.extend(node.children);
+            q.extend(node.children.into_iter().enumerate());
         }
 
         Diff::Tree(TreeDiff { diff: diffs })
@@ -345,13 +758,14 @@ impl ContainerState for TreeState {
     }
 
     #[doc = " Restore the state to the state represented by the ops that exported by `get_snapshot_ops`"]
-    fn import_from_snapshot_ops(&mut self, ctx: StateSnapshotDecodeContext) {
-        assert_eq!(ctx.mode, EncodeMode::Snapshot);
+    fn import_from_snapshot_ops(&mut self, ctx: StateSnapshotDecodeContext) -> LoroResult<()> {
+        assert!(ctx.mode.is_snapshot());
         for op in ctx.ops {
             assert_eq!(op.op.atom_len(), 1);
             let content = op.op.content.as_tree().unwrap();
             let target = content.target;
             let parent = content.parent;
+            let position = content.position.clone();
             // TODO: use TreeParentId
             let parent = match parent {
                 Some(parent) => {
@@ -363,14 +777,17 @@ impl ContainerState for TreeState {
                 }
                 None => TreeParentId::None,
             };
-            self.trees.insert(
+            self.set_node(
                 target,
-                TreeStateNode {
+                Some(TreeStateNode {
                     parent,
+                    position,
                     last_move_op: op.id_full(),
-                },
+                }),
             );
         }
+
+        Ok(())
     }
 }
 
@@ -402,10 +819,12 @@ impl Forest {
         let mut forest = Self::default();
         let mut parent_id_to_children = FxHashMap::default();
 
-        for id in state.keys().sorted() {
-            let parent = state.get(id).unwrap();
+        for (id, node) in state
+            .iter()
+            .sorted_by(|a, b| a.1.sort_key().cmp(&b.1.sort_key()).then(a.0.cmp(b.0)))
+        {
             parent_id_to_children
-                .entry(parent.parent)
+                .entry(node.parent)
                 .or_insert_with(Vec::new)
                 .push(*id)
         }
@@ -497,9 +916,11 @@ mod tests {
             0,
             loro_common::ContainerType::Tree,
         ));
-        state.mov(ID1, TreeParentId::None, IdFull::NONE_ID).unwrap();
         state
-            .mov(ID2, TreeParentId::Node(ID1), IdFull::NONE_ID)
+            .mov(ID1, TreeParentId::None, None, IdFull::NONE_ID)
+            .unwrap();
+        state
+            .mov(ID2, TreeParentId::Node(ID1), None, IdFull::NONE_ID)
             .unwrap();
     }
 
@@ -509,9 +930,11 @@ mod tests {
             0,
             loro_common::ContainerType::Tree,
         ));
-        state.mov(ID1, TreeParentId::None, IdFull::NONE_ID).unwrap();
         state
-            .mov(ID2, TreeParentId::Node(ID1), IdFull::NONE_ID)
+            .mov(ID1, TreeParentId::None, None, IdFull::NONE_ID)
+            .unwrap();
+        state
+            .mov(ID2, TreeParentId::Node(ID1), None, IdFull::NONE_ID)
             .unwrap();
         let roots = Forest::from_tree_state(&state.trees);
         let json = serde_json::to_string(&roots).unwrap();
@@ -527,18 +950,20 @@ mod tests {
             0,
             loro_common::ContainerType::Tree,
         ));
-        state.mov(ID1, TreeParentId::None, IdFull::NONE_ID).unwrap();
         state
-            .mov(ID2, TreeParentId::Node(ID1), IdFull::NONE_ID)
+            .mov(ID1, TreeParentId::None, None, IdFull::NONE_ID)
+            .unwrap();
+        state
+            .mov(ID2, TreeParentId::Node(ID1), None, IdFull::NONE_ID)
             .unwrap();
         state
-            .mov(ID3, TreeParentId::Node(ID2), IdFull::NONE_ID)
+            .mov(ID3, TreeParentId::Node(ID2), None, IdFull::NONE_ID)
             .unwrap();
         state
-            .mov(ID4, TreeParentId::Node(ID1), IdFull::NONE_ID)
+            .mov(ID4, TreeParentId::Node(ID1), None, IdFull::NONE_ID)
             .unwrap();
         state
-            .mov(ID2, TreeParentId::Deleted, IdFull::NONE_ID)
+            .mov(ID2, TreeParentId::Deleted, None, IdFull::NONE_ID)
             .unwrap();
         let roots = Forest::from_tree_state(&state.trees);
         let json = serde_json::to_string(&roots).unwrap();
@@ -547,4 +972,65 @@ mod tests {
             r#"{"roots":[{"id":{"peer":0,"counter":0},"meta":{"Container":{"Normal":{"peer":0,"counter":0,"container_type":"Map"}}},"parent":null,"children":[{"id":{"peer":0,"counter":3},"meta":{"Container":{"Normal":{"peer":0,"counter":3,"container_type":"Map"}}},"parent":{"peer":0,"counter":0},"children":[]}]}]}"#
         )
     }
+
+    #[test]
+    fn children_index() {
+        let mut state = TreeState::new(ContainerIdx::from_index_and_type(
+            0,
+            loro_common::ContainerType::Tree,
+        ));
+        state
+            .mov(ID1, TreeParentId::None, None, IdFull::NONE_ID)
+            .unwrap();
+        let parent = TreeParentId::Node(ID1);
+        // enough children to split the nodes of the b-tree, with repeated positions
+        for i in 1..300 {
+            let target = TreeID {
+                peer: 1,
+                counter: i,
+            };
+            let position = FractionalIndex::from_bytes(vec![(i * 37 % 50 + 1) as u8]);
+            let id = IdFull::new(i as u64 % 3, i, (i * 7 % 11) as u32);
+            state.mov(target, parent, position, id).unwrap();
+        }
+        // move some children to other positions and parents
+        for i in (1..300).step_by(7) {
+            let target = TreeID {
+                peer: 1,
+                counter: i,
+            };
+            let position = FractionalIndex::from_bytes(vec![(i * 13 % 50 + 1) as u8]);
+            let parent = if i % 2 == 0 {
+                parent
+            } else {
+                TreeParentId::Deleted
+            };
+            state
+                .mov(target, parent, position, IdFull::new(9, i, 20))
+                .unwrap();
+        }
+
+        let mut expected = state
+            .trees
+            .iter()
+            .filter(|(_, node)| node.parent == parent)
+            .map(|(id, node)| (node.sort_key(), *id))
+            .collect_vec();
+        expected.sort();
+        let children = state.get_children(&parent);
+        assert_eq!(children, expected.iter().map(|(_, id)| *id).collect_vec());
+        for (i, id) in children.iter().enumerate() {
+            assert_eq!(state.get_index_by_tree_id(id), Some(i));
+        }
+
+        let position = FractionalIndex::from_bytes(vec![20]).unwrap();
+        let target = children[5];
+        assert_eq!(
+            state.get_index_by_position(&target, &parent, &position),
+            expected
+                .iter()
+                .filter(|(key, id)| *id != target && key.0 <= Some(&position))
+                .count()
+        );
+    }
 }
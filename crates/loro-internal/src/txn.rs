@@ -34,7 +34,9 @@ use crate::{
 use super::{
     arena::SharedArena,
     event::{InternalContainerDiff, InternalDocDiff},
-    handler::{ListHandler, MapHandler, TextHandler, TreeHandler},
+    handler::{
+        CounterHandler, ListHandler, MapHandler, MovableListHandler, TextHandler, TreeHandler,
+    },
     oplog::OpLog,
     state::{DocState, State},
 };
@@ -58,6 +60,7 @@ pub struct Transaction {
     finished: bool,
     on_commit: Option<OnCommitFn>,
     timestamp: Option<Timestamp>,
+    message: Option<Arc<str>>,
 }
 
 /// We can infer local events directly from the local behavior. This enum is used to
@@ -90,12 +93,30 @@ pub(super) enum EventHint {
         len: u32,
     },
     DeleteList(DeleteSpan),
+    /// The op pos of a movable list may differ from the user index,
+    /// so the user index is recorded here.
+    InsertMovableList {
+        pos: u32,
+        len: u32,
+    },
+    MoveListItem {
+        from: u32,
+        to: u32,
+        value: LoroValue,
+        /// The states of the moved container and its descendants
+        child_diffs: Vec<(ContainerIdx, Diff)>,
+    },
+    SetListItem {
+        index: u32,
+        value: LoroValue,
+    },
     Map {
         key: InternalString,
         value: Option<LoroValue>,
     },
     Tree(TreeDiffItem),
     MarkEnd,
+    Counter(f64),
 }
 
 impl generic_btree::rle::HasLength for EventHint {
@@ -108,9 +129,13 @@ impl generic_btree::rle::HasLength for EventHint {
             EventHint::DeleteText { unicode_len, .. } => *unicode_len,
             EventHint::InsertList { len, .. } => *len as usize,
             EventHint::DeleteList(d) => d.len(),
+            EventHint::InsertMovableList { len, .. } => *len as usize,
+            EventHint::MoveListItem { .. } => 1,
+            EventHint::SetListItem { .. } => 1,
             EventHint::Map { .. } => 1,
             EventHint::Tree(_) => 1,
             EventHint::MarkEnd => 1,
+            EventHint::Counter(_) => 1,
         }
     }
 }
@@ -132,6 +157,10 @@ impl generic_btree::rle::Mergeable for EventHint {
                 },
             ) => *pos + *event_len == *r_pos && styles == r_styles,
             (EventHint::InsertList { .. }, EventHint::InsertList { .. }) => true,
+            (
+                EventHint::InsertMovableList { pos, len },
+                EventHint::InsertMovableList { pos: r_pos, .. },
+            ) => *pos + *len == *r_pos,
             // We don't merge delete text because it's hard to infer the correct pos to split:
             // `range` param is in unicode range, but the delete text event is in UTF-16 range.
             // Without the original text, it's impossible to convert the range.
@@ -161,6 +190,10 @@ impl generic_btree::rle::Mergeable for EventHint {
                 *event_len += *r_event_len;
             }
             (EventHint::InsertList { len }, EventHint::InsertList { len: r_len }) => *len += *r_len,
+            (
+                EventHint::InsertMovableList { len, .. },
+                EventHint::InsertMovableList { len: r_len, .. },
+            ) => *len += *r_len,
             (EventHint::DeleteList(l), EventHint::DeleteList(r)) => l.merge(r, &()),
             (
                 EventHint::DeleteText { span, unicode_len },
@@ -218,6 +251,7 @@ impl Transaction {
             oplog,
             frontiers,
             timestamp: None,
+            message: None,
             global_txn,
             next_counter,
             next_lamport,
@@ -243,6 +277,11 @@ impl Transaction {
         self.timestamp = Some(time);
     }
 
+    /// Set the commit message of the change created by this transaction
+    pub fn set_message(&mut self, message: &str) {
+        self.message = Some(message.into());
+    }
+
     pub(crate) fn set_on_commit(&mut self, f: OnCommitFn) {
         self.on_commit = Some(f);
     }
@@ -275,6 +314,7 @@ impl Transaction {
                 self.timestamp
                     .unwrap_or_else(|| oplog.get_timestamp_for_next_txn()),
             ),
+            message: self.message.take(),
             has_dependents: false,
         };
 
@@ -360,8 +400,13 @@ impl Transaction {
             &event,
             &op
         );
+        // the hints of different containers are not merged, even if they are adjacent
+        let same_container = self
+            .local_ops
+            .last()
+            .is_some_and(|last| last.container == container);
         match self.event_hints.last_mut() {
-            Some(last) if last.can_merge(&event) => {
+            Some(last) if same_container && last.can_merge(&event) => {
                 last.merge_right(&event);
             }
             _ => {
@@ -402,6 +447,33 @@ impl Transaction {
         .unwrap()
     }
 
+    /// id can be a str, ContainerID, or ContainerIdRaw.
+    /// if it's str it will use Root container, which will not be None
+    pub fn get_movable_list<I: IntoContainerId>(&self, id: I) -> MovableListHandler {
+        let id = id.into_container_id(&self.arena, ContainerType::MovableList);
+        Handler::new_attached(
+            id,
+            self.arena.clone(),
+            self.global_txn.clone(),
+            Arc::downgrade(&self.state),
+        )
+        .into_movable_list()
+        .unwrap()
+    }
+    /// id can be a str, ContainerID, or ContainerIdRaw.
+    /// if it's str it will use Root container, which will not be None
+    pub fn get_counter<I: IntoContainerId>(&self, id: I) -> CounterHandler {
+        let id = id.into_container_id(&self.arena, ContainerType::Counter);
+        Handler::new_attached(
+            id,
+            self.arena.clone(),
+            self.global_txn.clone(),
+            Arc::downgrade(&self.state),
+        )
+        .into_counter()
+        .unwrap()
+    }
+
     /// id can be a str, ContainerID, or ContainerIdRaw.
     /// if it's str it will use Root container, which will not be None
     pub fn get_map<I: IntoContainerId>(&self, id: I) -> MapHandler {
@@ -517,6 +589,7 @@ fn change_to_diff(
         match &hint {
             EventHint::InsertText { .. }
             | EventHint::InsertList { .. }
+            | EventHint::InsertMovableList { .. }
             | EventHint::DeleteText { .. }
             | EventHint::DeleteList(_) => {}
             _ => {
@@ -591,6 +664,63 @@ fn change_to_diff(
                         diff: Diff::List(Delta::new().retain(s.start() as usize).delete(s.len())),
                     });
                 }
+                EventHint::InsertMovableList { pos, .. } => {
+                    let mut values = Vec::new();
+                    for op in ops.iter() {
+                        let (range, _) = op.content.as_list().unwrap().as_insert().unwrap();
+                        values.extend(
+                            arena
+                                .get_values(range.to_range())
+                                .into_iter()
+                                .map(|v| ValueOrHandler::from_value(v, arena, txn, state)),
+                        );
+                    }
+                    ans.push(TxnContainerDiff {
+                        idx: op.container,
+                        diff: Diff::List(Delta::new().retain(pos as usize).insert(values)),
+                    })
+                }
+                EventHint::MoveListItem {
+                    from,
+                    to,
+                    value,
+                    child_diffs,
+                } => {
+                    let (from, to) = (from as usize, to as usize);
+                    let value = vec![ValueOrHandler::from_value(value, arena, txn, state)];
+                    let delta = if from < to {
+                        Delta::new()
+                            .retain(from)
+                            .delete(1)
+                            .retain(to - from)
+                            .insert(value)
+                    } else {
+                        Delta::new()
+                            .retain(to)
+                            .insert(value)
+                            .retain(from - to)
+                            .delete(1)
+                    };
+                    ans.push(TxnContainerDiff {
+                        idx: op.container,
+                        diff: Diff::List(delta),
+                    });
+                    // the moved container is inserted again, so its state is emitted as well
+                    ans.extend(
+                        child_diffs
+                            .into_iter()
+                            .map(|(idx, diff)| TxnContainerDiff { idx, diff }),
+                    );
+                }
+                EventHint::SetListItem { index, value } => {
+                    let value = vec![ValueOrHandler::from_value(value, arena, txn, state)];
+                    ans.push(TxnContainerDiff {
+                        idx: op.container,
+                        diff: Diff::List(
+                            Delta::new().retain(index as usize).delete(1).insert(value),
+                        ),
+                    });
+                }
                 EventHint::Map { key, value } => ans.push(TxnContainerDiff {
                     idx: op.container,
                     diff: Diff::Map(ResolvedMapDelta::new().with_entry(
@@ -613,6 +743,10 @@ fn change_to_diff(
                     // do nothing
                     break 'outer;
                 }
+                EventHint::Counter(delta) => ans.push(TxnContainerDiff {
+                    idx: op.container,
+                    diff: Diff::Counter(delta),
+                }),
             };
         }
 
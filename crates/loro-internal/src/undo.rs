@@ -0,0 +1,421 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, Weak},
+};
+
+use fxhash::FxHashMap;
+use loro_common::{ContainerID, LoroError, LoroResult, LoroValue};
+
+use crate::{
+    change::{get_sys_timestamp, Timestamp},
+    cursor::{AbsolutePosition, Cursor},
+    event::{ContainerDiff, Diff, DiffEvent},
+    obs::SubID,
+    version::Frontiers,
+    InternalString, LoroDoc,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoOrRedo {
+    Undo,
+    Redo,
+}
+
+impl UndoOrRedo {
+    fn opposite(&self) -> UndoOrRedo {
+        match self {
+            Self::Undo => Self::Redo,
+            Self::Redo => Self::Undo,
+        }
+    }
+
+    fn origin(&self) -> &'static str {
+        match self {
+            Self::Undo => "undo",
+            Self::Redo => "redo",
+        }
+    }
+}
+
+/// The metadata of an undo/redo item.
+///
+/// It's created by the `on_push` callback and passed back to the `on_pop` callback.
+/// The positions of the cursors are updated to the latest state before `on_pop` is called.
+#[derive(Debug, Clone, Default)]
+pub struct UndoItemMeta {
+    pub value: LoroValue,
+    pub cursors: Vec<CursorWithPos>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CursorWithPos {
+    pub cursor: Cursor,
+    pub pos: AbsolutePosition,
+}
+
+impl UndoItemMeta {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the cursor. Its position will be restored when the item is popped.
+    pub fn add_cursor(&mut self, cursor: &Cursor) {
+        self.cursors.push(CursorWithPos {
+            cursor: cursor.clone(),
+            pos: AbsolutePosition {
+                pos: 0,
+                side: cursor.side,
+            },
+        });
+    }
+
+    pub fn set_value(&mut self, value: LoroValue) {
+        self.value = value;
+    }
+}
+
+pub type OnPush = Box<dyn Fn(UndoOrRedo) -> UndoItemMeta + Send + Sync>;
+pub type OnPop = Box<dyn Fn(UndoOrRedo, UndoItemMeta) + Send + Sync>;
+
+#[derive(Debug)]
+struct StackItem {
+    /// The version before the local changes of this item
+    from: Frontiers,
+    /// The version after the local changes of this item
+    to: Frontiers,
+    origin: InternalString,
+    timestamp: Timestamp,
+    meta: UndoItemMeta,
+    /// The remote changes that happened after this item.
+    /// The undo of this item needs to be transformed based on them.
+    remote_diff: FxHashMap<ContainerID, Diff>,
+}
+
+#[derive(Debug, Default)]
+struct Stack {
+    items: VecDeque<StackItem>,
+}
+
+impl Stack {
+    fn compose_remote_diff(&mut self, diff: &[&ContainerDiff]) {
+        if let Some(top) = self.items.back_mut() {
+            for d in diff.iter() {
+                compose_diff(&mut top.remote_diff, d.id.clone(), d.diff.clone());
+            }
+        }
+    }
+}
+
+fn compose_diff(map: &mut FxHashMap<ContainerID, Diff>, id: ContainerID, diff: Diff) {
+    match map.remove(&id) {
+        Some(old) => {
+            let new = old.compose(diff).unwrap_or_else(|old| old);
+            map.insert(id, new);
+        }
+        None => {
+            map.insert(id, diff);
+        }
+    }
+}
+
+struct UndoManagerInner {
+    undo_stack: Stack,
+    redo_stack: Stack,
+    /// It's set when the manager is applying an undo or redo
+    processing: Option<UndoOrRedo>,
+    merge_interval: Timestamp,
+    group_by_origin: bool,
+    max_stack_size: usize,
+    exclude_origin_prefixes: Vec<Box<str>>,
+    /// The next local change will start a new undo item
+    new_checkpoint: bool,
+    container_remap: FxHashMap<ContainerID, ContainerID>,
+    on_push: Option<Arc<OnPush>>,
+    on_pop: Option<Arc<OnPop>>,
+}
+
+impl UndoManagerInner {
+    fn stack(&mut self, kind: UndoOrRedo) -> &mut Stack {
+        match kind {
+            UndoOrRedo::Undo => &mut self.undo_stack,
+            UndoOrRedo::Redo => &mut self.redo_stack,
+        }
+    }
+
+    fn is_excluded(&self, origin: &str) -> bool {
+        self.exclude_origin_prefixes
+            .iter()
+            .any(|prefix| origin.starts_with(&**prefix))
+    }
+
+    fn can_merge(&self, from: &Frontiers, origin: &str, now: Timestamp) -> bool {
+        if self.new_checkpoint {
+            return false;
+        }
+
+        let Some(top) = self.undo_stack.items.back() else {
+            return false;
+        };
+
+        // there are other changes between them
+        if &top.to != from {
+            return false;
+        }
+
+        (now - top.timestamp < self.merge_interval)
+            || (self.group_by_origin && !origin.is_empty() && *top.origin == *origin)
+    }
+
+    fn push(&mut self, kind: UndoOrRedo, item: StackItem) {
+        let max_stack_size = self.max_stack_size;
+        let stack = self.stack(kind);
+        stack.items.push_back(item);
+        while stack.items.len() > max_stack_size {
+            stack.items.pop_front();
+        }
+    }
+}
+
+/// The undo manager records the local changes of a doc and reverts them on request.
+///
+/// - Only the local changes are recorded. The changes whose origins start with one of the
+///   excluded prefixes are treated as remote changes.
+/// - The consecutive local changes are merged into one undo item if they are within the
+///   merge interval, or if they have the same origin when grouping by origin is enabled.
+/// - An undo generates the inverse ops against the latest state. They are transformed over
+///   the remote changes that happened after the undone changes, so the concurrent remote
+///   changes are preserved.
+pub struct UndoManager<'a> {
+    doc: &'a LoroDoc,
+    inner: Arc<Mutex<UndoManagerInner>>,
+    sub: SubID,
+}
+
+impl Drop for UndoManager<'_> {
+    fn drop(&mut self) {
+        self.doc.unsubscribe(self.sub);
+    }
+}
+
+impl std::fmt::Debug for UndoManager<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let inner = self.inner.lock().unwrap();
+        f.debug_struct("UndoManager")
+            .field("undo_stack", &inner.undo_stack)
+            .field("redo_stack", &inner.redo_stack)
+            .finish()
+    }
+}
+
+impl<'a> UndoManager<'a> {
+    pub fn new(doc: &'a LoroDoc) -> Self {
+        let inner = Arc::new(Mutex::new(UndoManagerInner {
+            undo_stack: Default::default(),
+            redo_stack: Default::default(),
+            processing: None,
+            merge_interval: 0,
+            group_by_origin: false,
+            max_stack_size: usize::MAX,
+            exclude_origin_prefixes: vec![],
+            new_checkpoint: false,
+            container_remap: Default::default(),
+            on_push: None,
+            on_pop: None,
+        }));
+
+        let weak = Arc::downgrade(&inner);
+        let sub = doc.subscribe_root(Arc::new(move |event| on_event(&weak, event)));
+        UndoManager { doc, inner, sub }
+    }
+
+    /// Set the interval in milliseconds. The consecutive local changes within the
+    /// interval are merged into one undo item. It's 0 by default.
+    pub fn set_merge_interval(&self, interval: Timestamp) {
+        self.inner.lock().unwrap().merge_interval = interval;
+    }
+
+    /// If enabled, the consecutive local changes with the same non-empty origin are
+    /// merged into one undo item regardless of the merge interval.
+    pub fn set_group_by_origin(&self, group: bool) {
+        self.inner.lock().unwrap().group_by_origin = group;
+    }
+
+    pub fn set_max_undo_steps(&self, size: usize) {
+        let inner = &mut *self.inner.lock().unwrap();
+        inner.max_stack_size = size;
+        for stack in [&mut inner.undo_stack, &mut inner.redo_stack] {
+            while stack.items.len() > size {
+                stack.items.pop_front();
+            }
+        }
+    }
+
+    /// The local changes whose origins start with the prefix will not be recorded.
+    pub fn add_exclude_origin_prefix(&self, prefix: &str) {
+        self.inner
+            .lock()
+            .unwrap()
+            .exclude_origin_prefixes
+            .push(prefix.into());
+    }
+
+    /// Make the next local change start a new undo item.
+    ///
+    /// The pending changes of the doc are committed first.
+    pub fn record_new_checkpoint(&self) {
+        self.doc.commit_then_renew();
+        self.inner.lock().unwrap().new_checkpoint = true;
+    }
+
+    /// Set the callback that is called when a new undo/redo item is pushed.
+    /// The returned meta will be passed to the `on_pop` callback.
+    pub fn set_on_push(&self, on_push: Option<OnPush>) {
+        self.inner.lock().unwrap().on_push = on_push.map(Arc::new);
+    }
+
+    /// Set the callback that is called when an undo/redo item is applied.
+    pub fn set_on_pop(&self, on_pop: Option<OnPop>) {
+        self.inner.lock().unwrap().on_pop = on_pop.map(Arc::new);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.inner.lock().unwrap().undo_stack.items.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.inner.lock().unwrap().redo_stack.items.is_empty()
+    }
+
+    /// Undo the last local change. Returns false if there is nothing to undo.
+    pub fn undo(&self) -> LoroResult<bool> {
+        self.perform(UndoOrRedo::Undo)
+    }
+
+    /// Redo the last undone change. Returns false if there is nothing to redo.
+    pub fn redo(&self) -> LoroResult<bool> {
+        self.perform(UndoOrRedo::Redo)
+    }
+
+    fn perform(&self, kind: UndoOrRedo) -> LoroResult<bool> {
+        let doc = self.doc;
+        // make sure the pending local changes are recorded
+        if doc.is_detached() {
+            return Err(LoroError::AutoCommitNotStarted);
+        }
+
+        doc.commit_then_renew();
+        let Some(item) = self.inner.lock().unwrap().stack(kind).items.pop_back() else {
+            return Ok(false);
+        };
+
+        let mut diff = match doc.diff(&item.to, &item.from, None) {
+            Ok(diff) => diff,
+            Err(e) => {
+                self.inner.lock().unwrap().stack(kind).items.push_back(item);
+                return Err(e);
+            }
+        };
+        for d in diff.iter_mut() {
+            if let Some(remote) = item.remote_diff.get(&d.id) {
+                d.diff.transform(remote, false);
+            }
+        }
+        diff.retain(|d| !d.diff.is_empty());
+
+        let mut container_remap = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.processing = Some(kind);
+            std::mem::take(&mut inner.container_remap)
+        };
+        let result = doc.apply_diff(diff, &mut container_remap);
+        if result.is_ok() {
+            doc.commit_with(Some(kind.origin().into()), None, true);
+        }
+        let (on_pop, mut meta) = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.processing = None;
+            inner.container_remap = container_remap;
+            if let Err(e) = result {
+                inner.stack(kind).items.push_back(item);
+                return Err(e);
+            }
+
+            // the remote changes after this item also happened after the item below it
+            let stack = inner.stack(kind);
+            if let Some(top) = stack.items.back_mut() {
+                for (id, diff) in item.remote_diff {
+                    compose_diff(&mut top.remote_diff, id, diff);
+                }
+            }
+            (inner.on_pop.clone(), item.meta)
+        };
+
+        for c in meta.cursors.iter_mut() {
+            if let Ok(ans) = doc.query_pos(&c.cursor) {
+                c.pos = ans.current;
+                if let Some(cursor) = ans.update {
+                    c.cursor = cursor;
+                }
+            }
+        }
+
+        if let Some(on_pop) = on_pop {
+            on_pop(kind, meta);
+        }
+
+        Ok(true)
+    }
+}
+
+fn on_event(inner: &Weak<Mutex<UndoManagerInner>>, event: DiffEvent) {
+    let Some(inner) = inner.upgrade() else {
+        return;
+    };
+
+    let meta = event.event_meta;
+    let mut guard = inner.lock().unwrap();
+    if meta.by.is_checkout() {
+        return;
+    }
+
+    if meta.by.is_import() || (guard.processing.is_none() && guard.is_excluded(&meta.origin)) {
+        guard.undo_stack.compose_remote_diff(event.events);
+        guard.redo_stack.compose_remote_diff(event.events);
+        return;
+    }
+
+    let now = get_sys_timestamp();
+    let kind = match guard.processing {
+        // the changes made by undo should be undone by redo, and vice versa
+        Some(kind) => kind.opposite(),
+        None => {
+            if guard.can_merge(&meta.from, &meta.origin, now) {
+                let top = guard.undo_stack.items.back_mut().unwrap();
+                top.to = meta.to.clone();
+                top.timestamp = now;
+                guard.redo_stack.items.clear();
+                return;
+            }
+
+            guard.redo_stack.items.clear();
+            guard.new_checkpoint = false;
+            UndoOrRedo::Undo
+        }
+    };
+
+    let on_push = guard.on_push.clone();
+    drop(guard);
+    let item_meta = on_push.map(|f| f(kind)).unwrap_or_default();
+    let mut guard = inner.lock().unwrap();
+    guard.push(
+        kind,
+        StackItem {
+            from: meta.from.clone(),
+            to: meta.to.clone(),
+            origin: meta.origin.clone(),
+            timestamp: now,
+            meta: item_meta,
+            remote_diff: Default::default(),
+        },
+    );
+}
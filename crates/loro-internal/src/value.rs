@@ -142,6 +142,7 @@ enum TypeHint {
     Text,
     List,
     Tree,
+    Counter,
 }
 
 pub trait ApplyDiff {
@@ -235,6 +236,11 @@ impl ApplyDiff for LoroValue {
                     }
                 }
             }
+            LoroValue::Double(value) => {
+                for item in diff.iter() {
+                    *value += item.as_counter().unwrap();
+                }
+            }
             _ => unreachable!(),
         }
     }
@@ -249,6 +255,7 @@ impl ApplyDiff for LoroValue {
             Diff::Text(_) => TypeHint::Text,
             Diff::Map(_) => TypeHint::Map,
             Diff::Tree(_) => TypeHint::Tree,
+            Diff::Counter(_) => TypeHint::Counter,
         };
         let value = {
             let mut hints = Vec::with_capacity(path.len());
@@ -272,6 +279,7 @@ impl ApplyDiff for LoroValue {
                             TypeHint::Text => LoroValue::String(Default::default()),
                             TypeHint::List => LoroValue::List(Default::default()),
                             TypeHint::Tree => LoroValue::List(Default::default()),
+                            TypeHint::Counter => LoroValue::Double(0.),
                         })
                     }
                     Index::Seq(index) => {
@@ -340,16 +348,18 @@ pub mod wasm {
                 let obj = Object::new();
                 js_sys::Reflect::set(&obj, &"target".into(), &diff.target.into()).unwrap();
                 match diff.action {
-                    TreeExternalDiff::Create(p) => {
+                    TreeExternalDiff::Create { parent, index } => {
                         js_sys::Reflect::set(&obj, &"action".into(), &"create".into()).unwrap();
-                        js_sys::Reflect::set(&obj, &"parent".into(), &p.into()).unwrap();
+                        js_sys::Reflect::set(&obj, &"parent".into(), &parent.into()).unwrap();
+                        js_sys::Reflect::set(&obj, &"index".into(), &index.into()).unwrap();
                     }
                     TreeExternalDiff::Delete => {
                         js_sys::Reflect::set(&obj, &"action".into(), &"delete".into()).unwrap();
                     }
-                    TreeExternalDiff::Move(p) => {
+                    TreeExternalDiff::Move { parent, index } => {
                         js_sys::Reflect::set(&obj, &"action".into(), &"move".into()).unwrap();
-                        js_sys::Reflect::set(&obj, &"parent".into(), &p.into()).unwrap();
+                        js_sys::Reflect::set(&obj, &"parent".into(), &parent.into()).unwrap();
+                        js_sys::Reflect::set(&obj, &"index".into(), &index.into()).unwrap();
                     }
                 }
                 array.push(&obj);
@@ -9,8 +9,8 @@ use loro_internal::{LoroDoc, LoroValue};
 use wasm_bindgen::JsValue;
 
 use crate::{
-    frontiers_to_ids, Container, JsContainer, JsImportBlobMetadata, LoroList, LoroMap, LoroText,
-    LoroTree,
+    frontiers_to_ids, Container, JsContainer, JsImportBlobMetadata, LoroCounter, LoroList, LoroMap,
+    LoroMovableList, LoroText, LoroTree,
 };
 use wasm_bindgen::__rt::IntoJsResult;
 use wasm_bindgen::convert::RefFromWasmAbi;
@@ -60,10 +60,18 @@ pub(crate) fn js_to_container(js: JsContainer) -> Result<Container, JsValue> {
             let obj = unsafe { LoroTree::ref_from_abi(ptr_u32) };
             Container::Tree(obj.clone())
         }
+        "MovableList" => {
+            let obj = unsafe { LoroMovableList::ref_from_abi(ptr_u32) };
+            Container::MovableList(obj.clone())
+        }
+        "Counter" => {
+            let obj = unsafe { LoroCounter::ref_from_abi(ptr_u32) };
+            Container::Counter(obj.clone())
+        }
         _ => {
             return Err(JsValue::from_str(
                 format!(
-                    "Value kind is {} but the valid container name is Map, List, Text or Tree",
+                    "Value kind is {} but the valid container name is Map, List, Text, Tree, MovableList or Counter",
                     kind
                 )
                 .as_str(),
@@ -117,6 +125,20 @@ pub(crate) fn resolved_diff_to_js(value: &Diff, doc: &Arc<LoroDoc>) -> JsValue {
             )
             .unwrap();
         }
+        Diff::Counter(delta) => {
+            js_sys::Reflect::set(
+                &obj,
+                &JsValue::from_str("type"),
+                &JsValue::from_str("counter"),
+            )
+            .unwrap();
+            js_sys::Reflect::set(
+                &obj,
+                &JsValue::from_str("increment"),
+                &JsValue::from_f64(*delta),
+            )
+            .unwrap();
+        }
         _ => unreachable!(),
     };
 
@@ -255,5 +277,7 @@ pub(crate) fn handler_to_js_value(handler: Handler, doc: Option<Arc<LoroDoc>>) -
         Handler::Map(m) => LoroMap { handler: m, doc }.into(),
         Handler::List(l) => LoroList { handler: l, doc }.into(),
         Handler::Tree(t) => LoroTree { handler: t, doc }.into(),
+        Handler::MovableList(l) => LoroMovableList { handler: l, doc }.into(),
+        Handler::Counter(c) => LoroCounter { handler: c, doc }.into(),
     }
 }
@@ -6,10 +6,11 @@ use loro_internal::{
     configure::{StyleConfig, StyleConfigMap},
     container::{richtext::ExpandType, ContainerID},
     cursor::{self, Side},
-    encoding::ImportBlobMetadata,
+    encoding::{json_schema::JsonSchema, ImportBlobMetadata},
     event::Index,
     handler::{
-        Handler, ListHandler, MapHandler, TextDelta, TextHandler, TreeHandler, ValueOrHandler,
+        Attribution, CounterHandler, Handler, ListHandler, MapHandler, MovableListHandler,
+        TextDelta, TextHandler, TreeHandler, ValueOrHandler,
     },
     id::{Counter, TreeID, ID},
     obs::SubID,
@@ -90,8 +91,16 @@ extern "C" {
     pub type JsChanges;
     #[wasm_bindgen(typescript_type = "Change")]
     pub type JsChange;
+    #[wasm_bindgen(typescript_type = "Attribution[]")]
+    pub type JsAttributions;
     #[wasm_bindgen(typescript_type = "Change | undefined")]
     pub type JsChangeOrUndefined;
+    #[wasm_bindgen(typescript_type = "VersionTag[]")]
+    pub type JsVersionTags;
+    #[wasm_bindgen(typescript_type = "JsonSchema")]
+    pub type JsJsonSchema;
+    #[wasm_bindgen(typescript_type = "JsonSchema | string")]
+    pub type JsJsonSchemaOrString;
     #[wasm_bindgen(
         typescript_type = "Map<PeerID, number> | Uint8Array | VersionVector | undefined | null"
     )]
@@ -114,6 +123,10 @@ extern "C" {
     pub type JsLoroListOrUndefined;
     #[wasm_bindgen(typescript_type = "LoroTree | undefined")]
     pub type JsLoroTreeOrUndefined;
+    #[wasm_bindgen(typescript_type = "LoroMovableList | undefined")]
+    pub type JsLoroMovableListOrUndefined;
+    #[wasm_bindgen(typescript_type = "LoroCounter | undefined")]
+    pub type JsLoroCounterOrUndefined;
     #[wasm_bindgen(typescript_type = "[string, Value | Container]")]
     pub type MapEntry;
     #[wasm_bindgen(typescript_type = "{[key: string]: { expand: 'before'|'after'|'none'|'both' }}")]
@@ -122,7 +135,7 @@ extern "C" {
     pub type JsDelta;
     #[wasm_bindgen(typescript_type = "-1 | 1 | 0 | undefined")]
     pub type JsPartialOrd;
-    #[wasm_bindgen(typescript_type = "'Tree'|'Map'|'List'|'Text'")]
+    #[wasm_bindgen(typescript_type = "'Tree'|'Map'|'List'|'Text'|'MovableList'|'Counter'")]
     pub type JsContainerKind;
     #[wasm_bindgen(typescript_type = "'Text'")]
     pub type JsTextStr;
@@ -132,6 +145,10 @@ extern "C" {
     pub type JsMapStr;
     #[wasm_bindgen(typescript_type = "'List'")]
     pub type JsListStr;
+    #[wasm_bindgen(typescript_type = "'MovableList'")]
+    pub type JsMovableListStr;
+    #[wasm_bindgen(typescript_type = "'Counter'")]
+    pub type JsCounterStr;
     #[wasm_bindgen(typescript_type = "ImportBlobMetadata")]
     pub type JsImportBlobMetadata;
     #[wasm_bindgen(typescript_type = "Side")]
@@ -194,6 +211,48 @@ fn id_to_js(id: &ID) -> JsValue {
     value
 }
 
+fn attributions_to_js(attributions: Vec<Attribution>) -> JsAttributions {
+    let arr = Array::new_with_length(attributions.len() as u32);
+    for (i, attribution) in attributions.into_iter().enumerate() {
+        let obj = Object::new();
+        Reflect::set(&obj, &"start".into(), &attribution.range.start.into()).unwrap();
+        Reflect::set(&obj, &"end".into(), &attribution.range.end.into()).unwrap();
+        Reflect::set(&obj, &"peer".into(), &attribution.peer.to_string().into()).unwrap();
+        Reflect::set(&obj, &"lamport".into(), &attribution.lamport.into()).unwrap();
+        Reflect::set(
+            &obj,
+            &"timestamp".into(),
+            &(attribution.timestamp as f64).into(),
+        )
+        .unwrap();
+        if let Some(message) = attribution.message {
+            Reflect::set(&obj, &"message".into(), &message.as_ref().into()).unwrap();
+        }
+        arr.set(i as u32, obj.into());
+    }
+
+    let value: JsValue = arr.into();
+    value.into()
+}
+
+fn version_tags_to_js(tags: Vec<(String, Frontiers)>) -> JsVersionTags {
+    let arr = Array::new_with_length(tags.len() as u32);
+    for (i, (name, frontiers)) in tags.into_iter().enumerate() {
+        let obj = Object::new();
+        Reflect::set(&obj, &"name".into(), &name.into()).unwrap();
+        Reflect::set(
+            &obj,
+            &"frontiers".into(),
+            &frontiers_to_ids(&frontiers).into(),
+        )
+        .unwrap();
+        arr.set(i as u32, obj.into());
+    }
+
+    let value: JsValue = arr.into();
+    value.into()
+}
+
 fn js_id_to_id(id: JsID) -> Result<ID, JsValue> {
     let peer = js_peer_to_peer(Reflect::get(&id, &"peer".into())?)?;
     let counter = Reflect::get(&id, &"counter".into())?.as_f64().unwrap() as Counter;
@@ -243,6 +302,7 @@ struct ChangeMeta {
     counter: Counter,
     deps: Vec<StringID>,
     timestamp: f64,
+    message: Option<String>,
 }
 
 impl ChangeMeta {
@@ -480,6 +540,177 @@ impl Loro {
         Ok(())
     }
 
+    /// Get the version of the doc at the given timestamp.
+    ///
+    /// It includes all the changes whose timestamps are not greater than `timestamp`.
+    /// The timestamps are only recorded when `setRecordTimestamp(true)` is called,
+    /// or when they are set by `commit`.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const text = doc.getText("text");
+    /// text.insert(0, "Hello");
+    /// doc.commit(undefined, 1_000_000);
+    /// text.insert(5, " World");
+    /// doc.commit(undefined, 3_000_000);
+    /// const frontiers = doc.frontiersAtTimestamp(2_000_000);
+    /// ```
+    #[wasm_bindgen(js_name = "frontiersAtTimestamp")]
+    pub fn frontiers_at_timestamp(&self, timestamp: f64) -> JsIDs {
+        frontiers_to_ids(&self.0.frontiers_at_timestamp(timestamp as i64))
+    }
+
+    /// Checkout the `DocState` to the version at the given timestamp.
+    ///
+    /// The document becomes detached like `checkout`.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const text = doc.getText("text");
+    /// text.insert(0, "Hello");
+    /// doc.commit(undefined, 1_000_000);
+    /// text.insert(5, " World");
+    /// doc.commit(undefined, 3_000_000);
+    /// doc.checkoutAtTimestamp(2_000_000);
+    /// console.log(doc.toJson()); // {"text": "Hello"}
+    /// ```
+    #[wasm_bindgen(js_name = "checkoutAtTimestamp")]
+    pub fn checkout_at_timestamp(&mut self, timestamp: f64) -> JsResult<()> {
+        self.0.checkout_at_timestamp(timestamp as i64)?;
+        Ok(())
+    }
+
+    /// Fork the document at the latest version.
+    ///
+    /// The new document has the whole history of this document and a new random peer id.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const text = doc.getText("text");
+    /// text.insert(0, "Hello");
+    /// const fork = doc.fork();
+    /// fork.getText("text").insert(5, " World");
+    /// console.log(doc.toJson()); // {"text": "Hello"}
+    /// console.log(fork.toJson()); // {"text": "Hello World"}
+    /// ```
+    pub fn fork(&self) -> Loro {
+        let mut doc = self.0.fork();
+        doc.start_auto_commit();
+        Loro(Arc::new(doc))
+    }
+
+    /// Fork the document at the given version.
+    ///
+    /// The new document has the history up to `frontiers` and a new random peer id.
+    /// Unlike `checkout`, the new document is editable.
+    #[wasm_bindgen(js_name = "forkAt")]
+    pub fn fork_at(&self, frontiers: Vec<JsID>) -> JsResult<Loro> {
+        let mut doc = self.0.fork_at(&ids_to_frontiers(frontiers)?)?;
+        doc.start_auto_commit();
+        Ok(Loro(Arc::new(doc)))
+    }
+
+    /// Tag the given version with `name`.
+    ///
+    /// The tags are stored in the doc and synced to other peers.
+    /// Tagging with an existing name overrides the old tag.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const text = doc.getText("text");
+    /// text.insert(0, "Hello");
+    /// doc.commit();
+    /// doc.tagVersion("Submitted draft", doc.oplogFrontiers());
+    /// text.insert(5, " World");
+    /// doc.checkoutTag("Submitted draft");
+    /// console.log(doc.toJson()); // {"text": "Hello"}
+    /// ```
+    #[wasm_bindgen(js_name = "tagVersion")]
+    pub fn tag_version(&self, name: &str, frontiers: Vec<JsID>) -> JsResult<()> {
+        self.0.tag_version(name, &ids_to_frontiers(frontiers)?)?;
+        Ok(())
+    }
+
+    /// Get all the version tags, sorted by name.
+    #[wasm_bindgen(js_name = "listTags")]
+    pub fn list_tags(&self) -> JsVersionTags {
+        version_tags_to_js(self.0.list_tags())
+    }
+
+    /// Checkout the `DocState` to the version tagged with `name`.
+    ///
+    /// The document becomes detached like `checkout`.
+    #[wasm_bindgen(js_name = "checkoutTag")]
+    pub fn checkout_tag(&mut self, name: &str) -> JsResult<()> {
+        self.0.checkout_tag(name)?;
+        Ok(())
+    }
+
+    /// Calculate the diff from version `from` to version `to` without checking out.
+    ///
+    /// The current state is not affected. It returns an array of events in the same
+    /// form as the `events` field of the subscription events.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const text = doc.getText("text");
+    /// text.insert(0, "Hello");
+    /// const v1 = doc.frontiers();
+    /// text.insert(5, " World");
+    /// const diff = doc.diff(v1, doc.frontiers());
+    /// console.log(diff[0].diff); // {type: "text", diff: [{retain: 5}, {insert: " World"}]}
+    /// ```
+    pub fn diff(&self, from: Vec<JsID>, to: Vec<JsID>) -> JsResult<JsValue> {
+        let diff = self
+            .0
+            .diff(&ids_to_frontiers(from)?, &ids_to_frontiers(to)?, None)?;
+        let arr = Array::new_with_length(diff.len() as u32);
+        for (i, d) in diff.iter().enumerate() {
+            arr.set(i as u32, container_diff_to_js_value(d, &self.0));
+        }
+
+        Ok(arr.into())
+    }
+
+    /// Revert the current state to the given version by creating new local ops.
+    ///
+    /// Unlike `checkout`, the document stays attached and editable, and the new ops
+    /// can be synced to other peers.
+    ///
+    /// @param frontiers - the target version
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const text = doc.getText("text");
+    /// const frontiers = doc.frontiers();
+    /// text.insert(0, "Hello World!");
+    /// doc.revertTo(frontiers);
+    /// console.log(doc.toJson()); // {"text": ""}
+    /// ```
+    #[wasm_bindgen(js_name = "revertTo")]
+    pub fn revert_to(&self, frontiers: Vec<JsID>) -> JsResult<()> {
+        self.0.revert_to(&ids_to_frontiers(frontiers)?)?;
+        Ok(())
+    }
+
     /// Peer ID of the current writer.
     #[wasm_bindgen(js_name = "peerId", method, getter)]
     pub fn peer_id(&self) -> u64 {
@@ -518,6 +749,26 @@ impl Loro {
             .commit_with(origin.map(|x| x.into()), timestamp.map(|x| x as i64), true);
     }
 
+    /// Set the commit message of the next change.
+    ///
+    /// Unlike the origin, the message is persisted on the change and synced to the other peers.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// doc.getText("text").insert(0, "Hello");
+    /// doc.setNextCommitMessage("Add greeting");
+    /// doc.commit();
+    /// const changes = doc.getAllChanges();
+    /// console.log(changes.get(doc.peerIdStr)?.[0].message); // "Add greeting"
+    /// ```
+    #[wasm_bindgen(js_name = "setNextCommitMessage")]
+    pub fn set_next_commit_message(&self, message: &str) {
+        self.0.set_next_commit_message(message);
+    }
+
     /// Get a LoroText by container id.
     ///
     /// The object returned is a new js object each time because it need to cross
@@ -587,6 +838,52 @@ impl Loro {
         })
     }
 
+    /// Get a LoroMovableList by container id
+    ///
+    /// The object returned is a new js object each time because it need to cross
+    /// the WASM boundary.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const list = doc.getMovableList("list");
+    /// ```
+    #[wasm_bindgen(js_name = "getMovableList", skip_typescript)]
+    pub fn get_movable_list(&self, cid: &JsIntoContainerID) -> JsResult<LoroMovableList> {
+        let list = self
+            .0
+            .get_movable_list(js_value_to_container_id(cid, ContainerType::MovableList)?);
+        Ok(LoroMovableList {
+            handler: list,
+            doc: Some(self.0.clone()),
+        })
+    }
+
+    /// Get a LoroCounter by container id
+    ///
+    /// The object returned is a new js object each time because it need to cross
+    /// the WASM boundary.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const counter = doc.getCounter("counter");
+    /// ```
+    #[wasm_bindgen(js_name = "getCounter", skip_typescript)]
+    pub fn get_counter(&self, cid: &JsIntoContainerID) -> JsResult<LoroCounter> {
+        let counter = self
+            .0
+            .get_counter(js_value_to_container_id(cid, ContainerType::Counter)?);
+        Ok(LoroCounter {
+            handler: counter,
+            doc: Some(self.0.clone()),
+        })
+    }
+
     /// Get a LoroTree by container id
     ///
     /// The object returned is a new js object each time because it need to cross
@@ -659,6 +956,22 @@ impl Loro {
                 }
                 .into()
             }
+            ContainerType::MovableList => {
+                let list = self.0.get_movable_list(container_id);
+                LoroMovableList {
+                    handler: list,
+                    doc: Some(self.0.clone()),
+                }
+                .into()
+            }
+            ContainerType::Counter => {
+                let counter = self.0.get_counter(container_id);
+                LoroCounter {
+                    handler: counter,
+                    doc: Some(self.0.clone()),
+                }
+                .into()
+            }
         })
     }
 
@@ -759,6 +1072,76 @@ impl Loro {
         Ok(self.0.export_snapshot())
     }
 
+    /// Export a shallow snapshot that only keeps the history since the given version.
+    ///
+    /// The history before the version is trimmed, so the imported doc cannot checkout
+    /// to the versions before it.
+    ///
+    /// @param frontiers - the version that the shallow snapshot starts from
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const text = doc.getText("text");
+    /// text.insert(0, "Hello");
+    /// const frontiers = doc.frontiers();
+    /// text.insert(5, " World");
+    /// const snapshot = doc.exportShallowSnapshot(frontiers);
+    /// const newDoc = new Loro();
+    /// newDoc.import(snapshot);
+    /// console.log(newDoc.isShallow()); // true
+    /// ```
+    #[wasm_bindgen(js_name = "exportShallowSnapshot")]
+    pub fn export_shallow_snapshot(&self, frontiers: Vec<JsID>) -> JsResult<Vec<u8>> {
+        Ok(self
+            .0
+            .export_shallow_snapshot(&ids_to_frontiers(frontiers)?)?)
+    }
+
+    /// Export a snapshot that only contains the given containers and their descendants.
+    ///
+    /// It's a shallow snapshot of the latest version, so it doesn't include the history.
+    /// The child containers that are not roots can be accessed by their ids in the
+    /// imported doc.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro, LoroText } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const text = doc.getMap("map").setContainer("text", new LoroText());
+    /// text.insert(0, "Hello");
+    /// doc.getText("other").insert(0, "secret");
+    /// const bytes = doc.exportSnapshotOf([text.id]);
+    /// const viewer = new Loro();
+    /// viewer.import(bytes);
+    /// console.log(viewer.getText(text.id).toString()); // "Hello"
+    /// ```
+    #[wasm_bindgen(js_name = "exportSnapshotOf")]
+    pub fn export_snapshot_of(&self, containers: Vec<JsContainerID>) -> JsResult<Vec<u8>> {
+        let containers = containers
+            .into_iter()
+            .map(|id| JsValue::from(id).try_into())
+            .collect::<Result<Vec<ContainerID>, _>>()?;
+        Ok(self.0.export_snapshot_of(&containers)?)
+    }
+
+    /// Whether the history of the document has been trimmed by a shallow snapshot.
+    #[wasm_bindgen(js_name = "isShallow")]
+    pub fn is_shallow(&self) -> bool {
+        self.0.is_shallow()
+    }
+
+    /// Get the frontiers of the shallow root.
+    ///
+    /// The history before it is not available. It's empty if the document is not shallow.
+    #[wasm_bindgen(js_name = "shallowSinceFrontiers")]
+    pub fn shallow_since_frontiers(&self) -> JsIDs {
+        frontiers_to_ids(&self.0.shallow_since_frontiers())
+    }
+
     /// Export updates from the specific version to the current version
     ///
     /// @example
@@ -785,6 +1168,51 @@ impl Loro {
         }
     }
 
+    /// Export the updates between the two versions in the JSON format.
+    ///
+    /// `start` defaults to the empty version and `end` defaults to the latest version.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// doc.getText("text").insert(0, "Hello");
+    /// const json = doc.exportJsonUpdates();
+    /// const doc2 = new Loro();
+    /// doc2.importJsonUpdates(json);
+    /// // or import the stringified JSON
+    /// doc2.importJsonUpdates(JSON.stringify(json));
+    /// ```
+    #[wasm_bindgen(js_name = "exportJsonUpdates")]
+    pub fn export_json_updates(
+        &self,
+        start: Option<VersionVector>,
+        end: Option<VersionVector>,
+    ) -> JsResult<JsJsonSchema> {
+        let start = start.map(|x| x.0).unwrap_or_default();
+        let end = end.map(|x| x.0).unwrap_or_else(|| self.0.oplog_vv());
+        let json = self.0.export_json_updates(&start, &end);
+        let s = serde_wasm_bindgen::Serializer::json_compatible();
+        let v = json.serialize(&s)?;
+        Ok(v.into())
+    }
+
+    /// Import the updates exported by `exportJsonUpdates`.
+    ///
+    /// It accepts both the JSON object and its string form.
+    #[wasm_bindgen(js_name = "importJsonUpdates")]
+    pub fn import_json_updates(&self, json: JsJsonSchemaOrString) -> JsResult<()> {
+        let json: JsValue = json.into();
+        let json = match json.as_string() {
+            Some(s) => js_sys::JSON::parse(&s)?,
+            None => json,
+        };
+        let json: JsonSchema = serde_wasm_bindgen::from_value(json)?;
+        self.0.import_json_updates(json)?;
+        Ok(())
+    }
+
     /// Import a snapshot or a update to current doc.
     ///
     /// Note:
@@ -812,6 +1240,52 @@ impl Loro {
         Ok(())
     }
 
+    /// Preview the effect of importing a snapshot or updates without changing the doc.
+    ///
+    /// It returns the spans of the changes that would be pending because of missing
+    /// dependencies, and the event batches the import would emit.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const other = new Loro();
+    /// other.getText("text").insert(0, "Hello");
+    /// const { pending, events } = doc.previewImport(other.exportFrom());
+    /// console.log(pending); // []
+    /// console.log(events[0].events[0].target); // "cid:root-text:Text"
+    /// console.log(doc.getText("text").toString()); // ""
+    /// ```
+    #[wasm_bindgen(skip_typescript, js_name = "previewImport")]
+    pub fn preview_import(&self, bytes: &[u8]) -> JsResult<JsValue> {
+        let preview = self.0.preview_import(bytes)?;
+        let pending = Array::new();
+        for span in preview.pending.iter() {
+            let obj = Object::new();
+            Reflect::set(&obj, &"peer".into(), &span.peer.to_string().into())?;
+            Reflect::set(&obj, &"counter".into(), &span.counter.start.into())?;
+            Reflect::set(&obj, &"length".into(), &span.atom_len().into())?;
+            pending.push(&obj);
+        }
+
+        let events = Array::new();
+        for diff in preview.diff.iter() {
+            let container_diffs: Vec<_> = diff.diff.iter().collect();
+            let event = DiffEvent {
+                current_target: None,
+                events: &container_diffs,
+                event_meta: diff,
+            };
+            events.push(&diff_event_to_js_value(event, &self.0));
+        }
+
+        let obj = Object::new();
+        Reflect::set(&obj, &"pending".into(), &pending)?;
+        Reflect::set(&obj, &"events".into(), &events)?;
+        Ok(obj.into())
+    }
+
     /// Import a batch of updates.
     ///
     /// It's more efficient than importing updates one by one.
@@ -897,6 +1371,38 @@ impl Loro {
             .into_u32()
     }
 
+    /// Subscribe the local commits.
+    ///
+    /// The callback is called on every local commit with an update blob that only
+    /// contains the new change. It can be sent to the other peers and imported directly.
+    ///
+    /// Returns a subscription id, which can be used to unsubscribe.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const remote = new Loro();
+    /// doc.subscribeLocalUpdates((update) => {
+    ///     remote.import(update);
+    /// });
+    /// doc.getText("text").insert(0, "Hello");
+    /// doc.commit();
+    /// console.log(remote.getText("text").toString()); // "Hello"
+    /// ```
+    #[wasm_bindgen(js_name = "subscribeLocalUpdates")]
+    pub fn subscribe_local_updates(&self, f: js_sys::Function) -> u32 {
+        let observer = observer::Observer::new(f);
+        self.0
+            .subscribe_local_update(Arc::new(move |bytes| {
+                if let Err(e) = observer.call1(&Uint8Array::from(bytes).into()) {
+                    console_error!("Error when calling the local update callback: {:#?}", e);
+                }
+            }))
+            .into_u32()
+    }
+
     /// Unsubscribe by the subscription
     ///
     /// @example
@@ -966,6 +1472,7 @@ impl Loro {
                         })
                         .collect(),
                     timestamp: change.timestamp() as f64,
+                    message: change.message().map(|x| x.to_string()),
                 };
                 row.set(i as u32, change.to_js());
             }
@@ -999,6 +1506,7 @@ impl Loro {
                 })
                 .collect(),
             timestamp: change.timestamp() as f64,
+            message: change.message().map(|x| x.to_string()),
         };
         Ok(change.to_js().into())
     }
@@ -1031,6 +1539,7 @@ impl Loro {
                 })
                 .collect(),
             timestamp: change.timestamp() as f64,
+            message: change.message().map(|x| x.to_string()),
         };
         Ok(change.to_js().into())
     }
@@ -1369,24 +1878,47 @@ impl LoroText {
         Ok(())
     }
 
-    /// Convert the state to string
-    #[allow(clippy::inherent_to_string)]
-    #[wasm_bindgen(js_name = "toString")]
-    pub fn to_string(&self) -> String {
-        self.handler.get_value().as_string().unwrap().to_string()
-    }
-
-    /// Get the text in [Delta](https://quilljs.com/docs/delta/) format.
+    /// Get the authorship of the text in the given range.
     ///
-    /// The returned value will include the rich text information.
+    /// The adjacent chars created by the same change are merged into one span.
     ///
     /// @example
     /// ```ts
     /// import { Loro } from "loro-crdt";
     ///
     /// const doc = new Loro();
+    /// doc.setPeerId("1");
     /// const text = doc.getText("text");
-    /// doc.configTextStyle({bold: {expand: "after"}});
+    /// text.insert(0, "Hello");
+    /// doc.commit();
+    /// const attribution = text.getAttribution({ start: 0, end: 5 });
+    /// console.log(attribution[0].peer); // "1"
+    /// ```
+    #[wasm_bindgen(js_name = "getAttribution")]
+    pub fn get_attribution(&self, range: JsRange) -> JsResult<JsAttributions> {
+        let range: MarkRange = serde_wasm_bindgen::from_value(range.into())?;
+        let attributions = self.handler.get_attribution(range.start..range.end)?;
+        Ok(attributions_to_js(attributions))
+    }
+
+    /// Convert the state to string
+    #[allow(clippy::inherent_to_string)]
+    #[wasm_bindgen(js_name = "toString")]
+    pub fn to_string(&self) -> String {
+        self.handler.get_value().as_string().unwrap().to_string()
+    }
+
+    /// Get the text in [Delta](https://quilljs.com/docs/delta/) format.
+    ///
+    /// The returned value will include the rich text information.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const text = doc.getText("text");
+    /// doc.configTextStyle({bold: {expand: "after"}});
     /// text.insert(0, "Hello World!");
     /// text.mark({ start: 0, end: 5 }, "bold", true);
     /// console.log(text.toDelta());  // [ { insert: 'Hello', attributes: { bold: true } } ]
@@ -2130,6 +2662,30 @@ impl LoroList {
         }
     }
 
+    /// Get the authorship of the elements in the given range.
+    ///
+    /// The adjacent elements created by the same change are merged into one span.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// doc.setPeerId("1");
+    /// const list = doc.getList("list");
+    /// list.insert(0, 1);
+    /// list.insert(1, 2);
+    /// doc.commit();
+    /// const attribution = list.getAttribution({ start: 0, end: 2 });
+    /// console.log(attribution.length); // 1
+    /// ```
+    #[wasm_bindgen(js_name = "getAttribution")]
+    pub fn get_attribution(&self, range: JsRange) -> JsResult<JsAttributions> {
+        let range: MarkRange = serde_wasm_bindgen::from_value(range.into())?;
+        let attributions = self.handler.get_attribution(range.start..range.end)?;
+        Ok(attributions_to_js(attributions))
+    }
+
     #[wasm_bindgen(skip_typescript)]
     pub fn getCursor(&self, pos: usize, side: JsSide) -> Option<Cursor> {
         let mut side_value = Side::Middle;
@@ -2149,6 +2705,349 @@ impl Default for LoroList {
     }
 }
 
+/// The handler of a movable list container.
+///
+/// Besides the list operations, it supports moving and setting elements.
+#[derive(Clone)]
+#[wasm_bindgen]
+pub struct LoroMovableList {
+    handler: MovableListHandler,
+    doc: Option<Arc<LoroDoc>>,
+}
+
+#[wasm_bindgen]
+impl LoroMovableList {
+    /// Create a new detached LoroMovableList.
+    ///
+    /// The edits on a detached container will not be persisted.
+    /// To attach the container to the document, please insert it into an attached container.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            handler: MovableListHandler::new_detached(),
+            doc: None,
+        }
+    }
+
+    /// "MovableList"
+    pub fn kind(&self) -> JsMovableListStr {
+        JsValue::from_str("MovableList").into()
+    }
+
+    /// Insert a value at index.
+    #[wasm_bindgen(skip_typescript)]
+    pub fn insert(&mut self, index: usize, value: JsLoroValue) -> JsResult<()> {
+        let v: JsValue = value.into();
+        self.handler.insert(index, v)?;
+        Ok(())
+    }
+
+    /// Delete elements from index to index + len.
+    pub fn delete(&mut self, index: usize, len: usize) -> JsResult<()> {
+        self.handler.delete(index, len)?;
+        Ok(())
+    }
+
+    /// Move the element at `from` to `to`.
+    ///
+    /// After the move, the element will be at the index `to`.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const list = doc.getMovableList("list");
+    /// list.insert(0, 1);
+    /// list.insert(1, 2);
+    /// list.move(0, 1);
+    /// console.log(list.value);  // [2, 1]
+    /// ```
+    #[wasm_bindgen(js_name = "move")]
+    pub fn mov(&mut self, from: usize, to: usize) -> JsResult<()> {
+        self.handler.mov(from, to)?;
+        Ok(())
+    }
+
+    /// Set the value at the index.
+    #[wasm_bindgen(skip_typescript)]
+    pub fn set(&mut self, index: usize, value: JsLoroValue) -> JsResult<()> {
+        let v: JsValue = value.into();
+        self.handler.set(index, v)?;
+        Ok(())
+    }
+
+    /// Get the value at the index. If the value is a container, the corresponding handler will be returned.
+    #[wasm_bindgen(skip_typescript)]
+    pub fn get(&self, index: usize) -> JsValueOrContainerOrUndefined {
+        let Some(v) = self.handler.get_(index) else {
+            return JsValue::UNDEFINED.into();
+        };
+
+        (match v {
+            ValueOrHandler::Value(v) => v.into(),
+            ValueOrHandler::Handler(h) => handler_to_js_value(h, self.doc.clone()),
+        })
+        .into()
+    }
+
+    /// Get the id of this container.
+    #[wasm_bindgen(js_name = "id", method, getter)]
+    pub fn id(&self) -> JsContainerID {
+        let value: JsValue = (&self.handler.id()).into();
+        value.into()
+    }
+
+    /// Get elements of the list. If the value is a child container, the corresponding
+    /// `Container` will be returned.
+    #[wasm_bindgen(js_name = "toArray", method, skip_typescript)]
+    pub fn to_array(&mut self) -> Vec<JsValueOrContainer> {
+        let mut arr: Vec<JsValueOrContainer> = Vec::with_capacity(self.length());
+        self.handler.for_each(|x| {
+            arr.push(match x {
+                ValueOrHandler::Value(v) => {
+                    let v: JsValue = v.into();
+                    v.into()
+                }
+                ValueOrHandler::Handler(h) => {
+                    let v: JsValue = handler_to_js_value(h, self.doc.clone());
+                    v.into()
+                }
+            });
+        });
+        arr
+    }
+
+    /// Get elements of the list. If the type of a element is a container, it will be
+    /// resolved recursively.
+    #[wasm_bindgen(js_name = "toJson")]
+    pub fn to_json(&self) -> JsValue {
+        let value = self.handler.get_deep_value();
+        value.into()
+    }
+
+    /// Insert a container at the index.
+    #[wasm_bindgen(js_name = "insertContainer", skip_typescript)]
+    pub fn insert_container(&mut self, index: usize, child: JsContainer) -> JsResult<JsContainer> {
+        let child = js_to_container(child)?;
+        let c = self.handler.insert_container(index, child.to_handler())?;
+        Ok(handler_to_js_value(c, self.doc.clone()).into())
+    }
+
+    /// Subscribe to the changes of the list.
+    ///
+    /// returns a subscription id, which can be used to unsubscribe.
+    pub fn subscribe(&self, loro: &Loro, f: js_sys::Function) -> JsResult<u32> {
+        let observer = observer::Observer::new(f);
+        let doc = loro.0.clone();
+        let ans = loro.0.subscribe(
+            &self.handler.id(),
+            Arc::new(move |e| {
+                call_after_micro_task(observer.clone(), e, &doc);
+            }),
+        );
+        Ok(ans.into_u32())
+    }
+
+    /// Unsubscribe by the subscription.
+    pub fn unsubscribe(&self, loro: &Loro, subscription: u32) -> JsResult<()> {
+        loro.0.unsubscribe(SubID::from_u32(subscription));
+        Ok(())
+    }
+
+    /// Get the length of list.
+    #[wasm_bindgen(js_name = "length", method, getter)]
+    pub fn length(&self) -> usize {
+        self.handler.len()
+    }
+
+    /// Get the parent container.
+    ///
+    /// - The parent container of the root list is `undefined`.
+    /// - The object returned is a new js object each time because it need to cross
+    ///   the WASM boundary.
+    pub fn parent(&self) -> JsContainerOrUndefined {
+        if let Some(p) = self.handler.parent() {
+            handler_to_js_value(p, self.doc.clone()).into()
+        } else {
+            JsContainerOrUndefined::from(JsValue::UNDEFINED)
+        }
+    }
+
+    /// Whether the container is attached to a docuemnt.
+    ///
+    /// If it's detached, the operations on the container will not be persisted.
+    #[wasm_bindgen(js_name = "isAttached")]
+    pub fn is_attached(&self) -> bool {
+        self.handler.is_attached()
+    }
+
+    /// Get the attached container associated with this.
+    ///
+    /// Returns an attached `Container` that equals to this or created by this, otherwise `undefined`.
+    #[wasm_bindgen(js_name = "getAttached")]
+    pub fn get_attached(&self) -> JsLoroMovableListOrUndefined {
+        if self.is_attached() {
+            let value: JsValue = self.clone().into();
+            return value.into();
+        }
+
+        if let Some(h) = self.handler.get_attached() {
+            handler_to_js_value(Handler::MovableList(h), self.doc.clone()).into()
+        } else {
+            JsValue::UNDEFINED.into()
+        }
+    }
+
+    #[wasm_bindgen(skip_typescript)]
+    pub fn getCursor(&self, pos: usize, side: JsSide) -> Option<Cursor> {
+        let mut side_value = Side::Middle;
+        if side.is_truthy() {
+            let num = side.as_f64().expect("Side must be -1 | 0 | 1");
+            side_value = Side::from_i32(num as i32).expect("Side must be -1 | 0 | 1");
+        }
+        self.handler
+            .get_cursor(pos, side_value)
+            .map(|pos| Cursor { pos })
+    }
+}
+
+impl Default for LoroMovableList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The handler of a counter container.
+///
+/// Concurrent increments are summed up.
+#[derive(Clone)]
+#[wasm_bindgen]
+pub struct LoroCounter {
+    handler: CounterHandler,
+    doc: Option<Arc<LoroDoc>>,
+}
+
+#[wasm_bindgen]
+impl LoroCounter {
+    /// Create a new detached LoroCounter.
+    ///
+    /// The edits on a detached container will not be persisted.
+    /// To attach the container to the document, please insert it into an attached container.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            handler: CounterHandler::new_detached(),
+            doc: None,
+        }
+    }
+
+    /// "Counter"
+    pub fn kind(&self) -> JsCounterStr {
+        JsValue::from_str("Counter").into()
+    }
+
+    /// Increment the counter by the given value.
+    ///
+    /// @example
+    /// ```ts
+    /// import { Loro } from "loro-crdt";
+    ///
+    /// const doc = new Loro();
+    /// const counter = doc.getCounter("counter");
+    /// counter.increment(1.5);
+    /// console.log(counter.value);  // 1.5
+    /// ```
+    pub fn increment(&self, value: f64) -> JsResult<()> {
+        self.handler.increment(value)?;
+        Ok(())
+    }
+
+    /// Decrement the counter by the given value.
+    pub fn decrement(&self, value: f64) -> JsResult<()> {
+        self.handler.decrement(value)?;
+        Ok(())
+    }
+
+    /// Get the current value of the counter.
+    #[wasm_bindgen(js_name = "value", method, getter)]
+    pub fn value(&self) -> f64 {
+        *self.handler.get_value().as_double().unwrap()
+    }
+
+    /// Get the id of this container.
+    #[wasm_bindgen(js_name = "id", method, getter)]
+    pub fn id(&self) -> JsContainerID {
+        let value: JsValue = (&self.handler.id()).into();
+        value.into()
+    }
+
+    /// Subscribe to the changes of the counter.
+    ///
+    /// returns a subscription id, which can be used to unsubscribe.
+    pub fn subscribe(&self, loro: &Loro, f: js_sys::Function) -> JsResult<u32> {
+        let observer = observer::Observer::new(f);
+        let doc = loro.0.clone();
+        let ans = loro.0.subscribe(
+            &self.handler.id(),
+            Arc::new(move |e| {
+                call_after_micro_task(observer.clone(), e, &doc);
+            }),
+        );
+        Ok(ans.into_u32())
+    }
+
+    /// Unsubscribe by the subscription.
+    pub fn unsubscribe(&self, loro: &Loro, subscription: u32) -> JsResult<()> {
+        loro.0.unsubscribe(SubID::from_u32(subscription));
+        Ok(())
+    }
+
+    /// Get the parent container.
+    ///
+    /// - The parent container of the root counter is `undefined`.
+    /// - The object returned is a new js object each time because it need to cross
+    ///   the WASM boundary.
+    pub fn parent(&self) -> JsContainerOrUndefined {
+        if let Some(p) = self.handler.parent() {
+            handler_to_js_value(p, self.doc.clone()).into()
+        } else {
+            JsContainerOrUndefined::from(JsValue::UNDEFINED)
+        }
+    }
+
+    /// Whether the container is attached to a docuemnt.
+    ///
+    /// If it's detached, the operations on the container will not be persisted.
+    #[wasm_bindgen(js_name = "isAttached")]
+    pub fn is_attached(&self) -> bool {
+        self.handler.is_attached()
+    }
+
+    /// Get the attached container associated with this.
+    ///
+    /// Returns an attached `Container` that equals to this or created by this, otherwise `undefined`.
+    #[wasm_bindgen(js_name = "getAttached")]
+    pub fn get_attached(&self) -> JsLoroCounterOrUndefined {
+        if self.is_attached() {
+            let value: JsValue = self.clone().into();
+            return value.into();
+        }
+
+        if let Some(h) = self.handler.get_attached() {
+            handler_to_js_value(Handler::Counter(h), self.doc.clone()).into()
+        } else {
+            JsValue::UNDEFINED.into()
+        }
+    }
+}
+
+impl Default for LoroCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// The handler of a tree(forest) container.
 #[derive(Clone)]
 #[wasm_bindgen]
@@ -2178,6 +3077,7 @@ impl LoroTreeNode {
     }
 
     /// Create a new tree node as the child of this node and return a LoroTreeNode instance.
+    /// If the index is undefined, the new node will be the last child.
     ///
     /// @example
     /// ```ts
@@ -2186,10 +3086,14 @@ impl LoroTreeNode {
     /// const tree = doc.getTree("tree");
     /// const root = tree.createNode();
     /// const node = root.createNode();
+    /// const first = root.createNode(0);
     /// ```
     #[wasm_bindgen(js_name = "createNode")]
-    pub fn create_node(&self) -> JsResult<LoroTreeNode> {
-        let id = self.tree.create(Some(self.id))?;
+    pub fn create_node(&self, index: Option<usize>) -> JsResult<LoroTreeNode> {
+        let id = match index {
+            Some(index) => self.tree.create_at(Some(self.id), index)?,
+            None => self.tree.create(Some(self.id))?,
+        };
         let node = LoroTreeNode::from_tree(id, self.tree.clone(), self.doc.clone());
         Ok(node)
     }
@@ -2204,7 +3108,7 @@ impl LoroTreeNode {
     }
 
     /// Move the target tree node to be a child of the parent.
-    /// If the parent is undefined, the target will be a root node.
+    /// If the index is undefined, the target will be the last child of the parent.
     ///
     /// @example
     /// ```ts
@@ -2214,10 +3118,28 @@ impl LoroTreeNode {
     /// const node = root.createNode();
     /// const node2 = node.createNode();
     /// node2.moveTo(root);
+    /// node2.moveTo(root, 0);
     /// ```
     #[wasm_bindgen(js_name = "moveTo")]
-    pub fn move_to(&self, parent: &LoroTreeNode) -> JsResult<()> {
-        self.tree.mov(self.id, parent.id)?;
+    pub fn move_to(&self, parent: &LoroTreeNode, index: Option<usize>) -> JsResult<()> {
+        match index {
+            Some(index) => self.tree.mov_to(self.id, parent.id, index)?,
+            None => self.tree.mov(self.id, parent.id)?,
+        }
+        Ok(())
+    }
+
+    /// Move this node to be the sibling right before the target node.
+    #[wasm_bindgen(js_name = "moveBefore")]
+    pub fn move_before(&self, target: &LoroTreeNode) -> JsResult<()> {
+        self.tree.mov_before(self.id, target.id)?;
+        Ok(())
+    }
+
+    /// Move this node to be the sibling right after the target node.
+    #[wasm_bindgen(js_name = "moveAfter")]
+    pub fn move_after(&self, target: &LoroTreeNode) -> JsResult<()> {
+        self.tree.mov_after(self.id, target.id)?;
         Ok(())
     }
 
@@ -2242,7 +3164,7 @@ impl LoroTreeNode {
         parent.map(|p| LoroTreeNode::from_tree(p, self.tree.clone(), self.doc.clone()))
     }
 
-    /// Get the children of this node.
+    /// Get the children of this node in order.
     ///
     /// The objects returned are new js objects each time because they need to cross
     /// the WASM boundary.
@@ -2277,6 +3199,7 @@ impl LoroTree {
 
     /// Create a new tree node as the child of parent and return an unique tree id.
     /// If the parent is undefined, the tree node will be a root node.
+    /// If the index is undefined, the tree node will be the last child of the parent.
     ///
     /// @example
     /// ```ts
@@ -2303,13 +3226,20 @@ impl LoroTree {
     ///  *\/
     /// ```
     #[wasm_bindgen(js_name = "createNode")]
-    pub fn create_node(&mut self, parent: Option<JsTreeID>) -> JsResult<LoroTreeNode> {
-        let id = if let Some(p) = parent {
+    pub fn create_node(
+        &mut self,
+        parent: Option<JsTreeID>,
+        index: Option<usize>,
+    ) -> JsResult<LoroTreeNode> {
+        let parent = if let Some(p) = parent {
             let p: JsValue = p.into();
-            let p = TreeID::try_from(p).unwrap();
-            self.handler.create(p)?
+            Some(TreeID::try_from(p).unwrap())
         } else {
-            self.handler.create(None)?
+            None
+        };
+        let id = match index {
+            Some(index) => self.handler.create_at(parent, index)?,
+            None => self.handler.create(parent)?,
         };
         let node = LoroTreeNode::from_tree(id, self.handler.clone(), self.doc.clone());
         Ok(node)
@@ -2318,6 +3248,7 @@ impl LoroTree {
     /// Move the target tree node to be a child of the parent.
     /// It's not allowed that the target is an ancestor of the parent
     /// or the target and the parent are the same node.
+    /// If the index is undefined, the target will be the last child of the parent.
     ///
     /// @example
     /// ```ts
@@ -2333,7 +3264,12 @@ impl LoroTree {
     /// tree.move(root.id, node.id);
     /// ```
     #[wasm_bindgen(js_name = "move")]
-    pub fn mov(&mut self, target: JsTreeID, parent: Option<JsTreeID>) -> JsResult<()> {
+    pub fn mov(
+        &mut self,
+        target: JsTreeID,
+        parent: Option<JsTreeID>,
+        index: Option<usize>,
+    ) -> JsResult<()> {
         let target: JsValue = target.into();
         let target = TreeID::try_from(target).unwrap();
         let parent = if let Some(parent) = parent {
@@ -2343,10 +3279,23 @@ impl LoroTree {
         } else {
             None
         };
-        self.handler.mov(target, parent)?;
+        match index {
+            Some(index) => self.handler.mov_to(target, parent, index)?,
+            None => self.handler.mov(target, parent)?,
+        }
         Ok(())
     }
 
+    /// Get the root nodes of the forest in order.
+    pub fn roots(&self) -> Array {
+        let roots = self.handler.children(None);
+        let roots = roots.into_iter().map(|c| {
+            let node = LoroTreeNode::from_tree(c, self.handler.clone(), self.doc.clone());
+            JsValue::from(node)
+        });
+        Array::from_iter(roots)
+    }
+
     /// Delete a tree node from the forest.
     ///
     /// @example
@@ -2733,6 +3682,8 @@ pub enum Container {
     Map(LoroMap),
     List(LoroList),
     Tree(LoroTree),
+    MovableList(LoroMovableList),
+    Counter(LoroCounter),
 }
 
 impl Container {
@@ -2742,6 +3693,8 @@ impl Container {
             Container::Map(m) => Handler::Map(m.handler.clone()),
             Container::List(l) => Handler::List(l.handler.clone()),
             Container::Tree(t) => Handler::Tree(t.handler.clone()),
+            Container::MovableList(l) => Handler::MovableList(l.handler.clone()),
+            Container::Counter(c) => Handler::Counter(c.handler.clone()),
         }
     }
 }
@@ -2762,6 +3715,34 @@ pub fn decode_import_blob_meta(blob: &[u8]) -> JsResult<JsImportBlobMetadata> {
     Ok(meta.into())
 }
 
+/// Merge the update blobs into one without loading the document.
+///
+/// Only the history is merged. The deps outside the blobs become the start version of
+/// the merged blob. It throws if there is a gap inside the blobs, or if any of the blobs
+/// is a shallow snapshot.
+///
+/// @example
+/// ```ts
+/// import { Loro, mergeUpdates } from "loro-crdt";
+///
+/// const doc = new Loro();
+/// const text = doc.getText("text");
+/// text.insert(0, "Hello");
+/// const a = doc.exportFrom();
+/// const v = doc.version();
+/// text.insert(5, " world");
+/// const b = doc.exportFrom(v);
+/// const newDoc = new Loro();
+/// newDoc.import(mergeUpdates([a, b]));
+/// console.log(newDoc.getText("text").toString()); // "Hello world"
+/// ```
+#[wasm_bindgen(js_name = "mergeUpdates")]
+pub fn merge_updates(blobs: Vec<Uint8Array>) -> JsResult<Vec<u8>> {
+    let blobs: Vec<Vec<u8>> = blobs.iter().map(|b| b.to_vec()).collect();
+    let blobs: Vec<&[u8]> = blobs.iter().map(|b| b.as_slice()).collect();
+    Ok(loro_internal::encoding::merge_updates(&blobs)?)
+}
+
 #[wasm_bindgen(typescript_custom_section)]
 const TYPES: &'static str = r#"
 /**
@@ -2779,7 +3760,7 @@ const TYPES: &'static str = r#"
 * const text = list.insertContainer(1, containerType);
 * ```
 */
-export type ContainerType = "Text" | "Map" | "List"| "Tree";
+export type ContainerType = "Text" | "Map" | "List"| "Tree" | "MovableList" | "Counter";
 
 export type PeerID = `${number}`;
 /**
@@ -2894,6 +3875,57 @@ export interface Change {
     length: number,
     timestamp: number,
     deps: OpId[],
+    /**
+     * The commit message of the change
+     */
+    message?: string,
+}
+
+/**
+ * The updates exported by `exportJsonUpdates`
+ */
+export interface JsonSchema {
+    schema_version: number,
+    changes: JsonChange[],
+}
+
+export interface JsonChange {
+    /** "{counter}@{peer}" */
+    id: string,
+    timestamp: number,
+    deps: string[],
+    lamport: number,
+    msg?: string,
+    ops: JsonOp[],
+}
+
+export interface JsonOp {
+    container: ContainerID,
+    counter: number,
+    content: { type: string, [key: string]: unknown },
+}
+
+/**
+ * A named version of the document
+ */
+export interface VersionTag {
+    name: string,
+    frontiers: OpId[],
+}
+
+/**
+ * The authorship of a span of content in a text or a list
+ */
+export interface Attribution {
+    start: number,
+    end: number,
+    peer: PeerID,
+    lamport: number,
+    /**
+     * It's 0 if the timestamp is not recorded or the change is not committed yet
+     */
+    timestamp: number,
+    message?: string,
 }
 
 
@@ -2910,7 +3942,7 @@ export type Value =
   | Uint8Array
   | Value[];
 
-export type Container = LoroList | LoroMap | LoroText | LoroTree;
+export type Container = LoroList | LoroMap | LoroText | LoroTree | LoroMovableList | LoroCounter;
 
 export interface ImportBlobMetadata {
     /**
@@ -3013,5 +4045,14 @@ interface LoroList {
     getCursor(pos: number, side?: Side): Cursor | undefined;
 }
 
+interface LoroMovableList {
+    /**
+     * Get the cursor position at the given pos.
+     *
+     * The cursor is bound to the element, so it follows the element when it's moved.
+     */
+    getCursor(pos: number, side?: Side): Cursor | undefined;
+}
+
 export type Side = -1 | 0 | 1;
 "#;
@@ -6,7 +6,7 @@ use loro_internal::handler::{TextDelta, ValueOrHandler};
 use loro_internal::FxHashMap;
 use loro_internal::{
     event::{Diff as DiffInner, Index},
-    ContainerDiff as ContainerDiffInner, DiffEvent as DiffEventInner,
+    ContainerDiff as ContainerDiffInner, DiffEvent as DiffEventInner, DocDiff,
 };
 use std::sync::Arc;
 
@@ -35,6 +35,8 @@ pub enum Diff<'a> {
     Text(Vec<TextDelta>),
     Map(MapDelta<'a>),
     Tree(&'a TreeDiff),
+    /// The delta of a counter
+    Counter(f64),
 }
 
 #[derive(Debug)]
@@ -49,6 +51,31 @@ pub struct MapDelta<'a> {
     pub updated: FxHashMap<&'a str, Option<ValueOrContainer>>,
 }
 
+/// The diffs between two versions calculated by [`crate::LoroDoc::diff`].
+///
+/// It owns the diffs. They are borrowed as [`ContainerDiff`]s by [`DiffBatch::iter`].
+#[derive(Debug)]
+pub struct DiffBatch(Vec<ContainerDiffInner>);
+
+impl DiffBatch {
+    pub(crate) fn new(diffs: Vec<ContainerDiffInner>) -> Self {
+        Self(diffs)
+    }
+
+    /// Iterate the diffs of the containers, sorted by the length of their paths
+    pub fn iter(&self) -> impl Iterator<Item = ContainerDiff<'_>> {
+        self.0.iter().map(ContainerDiff::from)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
 impl<'a> From<DiffEventInner<'a>> for DiffEvent<'a> {
     fn from(value: DiffEventInner<'a>) -> Self {
         DiffEvent {
@@ -60,6 +87,17 @@ impl<'a> From<DiffEventInner<'a>> for DiffEvent<'a> {
     }
 }
 
+impl<'a> From<&'a DocDiff> for DiffEvent<'a> {
+    fn from(value: &'a DocDiff) -> Self {
+        DiffEvent {
+            triggered_by: value.by,
+            origin: &value.origin,
+            current_target: None,
+            events: value.diff.iter().map(|diff| diff.into()).collect(),
+        }
+    }
+}
+
 impl<'a> From<&'a ContainerDiffInner> for ContainerDiff<'a> {
     fn from(value: &'a ContainerDiffInner) -> Self {
         ContainerDiff {
@@ -105,6 +143,7 @@ impl<'a> From<&'a DiffInner> for Diff<'a> {
                 Diff::Text(text)
             }
             DiffInner::Tree(t) => Diff::Tree(t),
+            DiffInner::Counter(c) => Diff::Counter(*c),
             _ => todo!(),
         }
     }
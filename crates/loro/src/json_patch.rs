@@ -0,0 +1,418 @@
+//! Convert the events of a doc into [JSON Patch](https://datatracker.ietf.org/doc/html/rfc6902)
+//! operations against the JSON shape of [LoroDoc::get_deep_value].
+//!
+//! The deltas of lists and maps are converted into `add` and `remove` operations on the
+//! elements. A map event may delete a key that doesn't exist in the old value, e.g. it's
+//! inserted and deleted in the same transaction, so the adapter keeps the patched value to
+//! only remove the keys that exist in it.
+//!
+//! Texts, counters and trees are not diffed: the whole value of a changed one is replaced
+//! by its new value. JSON Patch cannot splice a string, a counter is a single number, and
+//! the nodes of a tree are not ordered in its JSON value. A container inserted into a list
+//! or a map is added with its deep value, and the events of its descendants in the same
+//! [DiffEvent] are skipped.
+//!
+//! Applying the patches of every event to the deep value of the doc, in order, produces the
+//! new deep value of the doc. The only exception is a root container that is empty and
+//! never changed, e.g. it's only accessed by [LoroDoc::get_text]. It's in the deep value
+//! without any event, so it's only added to the patched value when it's changed.
+
+use std::{collections::HashSet, sync::Arc};
+
+use loro_internal::{handler::HandlerTrait, FxHashMap};
+
+use crate::{
+    event::{ContainerDiff, Diff, DiffEvent, ListDiffItem},
+    ContainerID, ContainerTrait, ContainerType, Index, LoroDoc, LoroError, LoroResult, LoroValue,
+    ValueOrContainer,
+};
+
+/// A JSON Patch operation. The path is a JSON Pointer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonPatchOp {
+    Add { path: String, value: LoroValue },
+    Remove { path: String },
+    Replace { path: String, value: LoroValue },
+}
+
+impl JsonPatchOp {
+    pub fn path(&self) -> &str {
+        match self {
+            JsonPatchOp::Add { path, .. }
+            | JsonPatchOp::Remove { path }
+            | JsonPatchOp::Replace { path, .. } => path,
+        }
+    }
+
+    /// Convert the operation into the JSON object of RFC 6902, e.g.
+    /// `{"op": "add", "path": "/list/0", "value": 1}`, so it can be serialized by
+    /// [ToJson](crate::ToJson).
+    pub fn to_value(&self) -> LoroValue {
+        let mut map = FxHashMap::default();
+        let op = match self {
+            JsonPatchOp::Add { value, .. } => {
+                map.insert("value".to_string(), value.clone());
+                "add"
+            }
+            JsonPatchOp::Remove { .. } => "remove",
+            JsonPatchOp::Replace { value, .. } => {
+                map.insert("value".to_string(), value.clone());
+                "replace"
+            }
+        };
+        map.insert("op".to_string(), op.into());
+        map.insert("path".to_string(), self.path().into());
+        map.into()
+    }
+}
+
+/// Converts the [DiffEvent]s of a doc into JSON Patch operations.
+///
+/// The patches apply to the deep value of the doc when the adapter is created, followed by
+/// the patches of the previous events.
+///
+/// # Example
+///
+/// ```
+/// # use std::sync::{Arc, Mutex};
+/// # use loro::{json_patch::{apply_json_patch, JsonPatchAdapter}, LoroDoc};
+/// let doc = Arc::new(LoroDoc::new());
+/// let mut value = doc.get_deep_value();
+/// let adapter = Mutex::new(JsonPatchAdapter::new(&doc));
+/// let patches = Arc::new(Mutex::new(Vec::new()));
+/// let patches_clone = patches.clone();
+/// let weak = Arc::downgrade(&doc);
+/// doc.subscribe_root(Arc::new(move |event| {
+///     let doc = weak.upgrade().unwrap();
+///     let ops = adapter.lock().unwrap().convert(&doc, &event);
+///     patches_clone.lock().unwrap().extend(ops);
+/// }));
+///
+/// let list = doc.get_list("list");
+/// list.insert(0, 1).unwrap();
+/// list.insert(1, "two").unwrap();
+/// doc.get_text("text").insert(0, "Hello").unwrap();
+/// doc.commit();
+///
+/// apply_json_patch(&mut value, &patches.lock().unwrap()).unwrap();
+/// assert_eq!(value, doc.get_deep_value());
+/// ```
+#[derive(Debug)]
+pub struct JsonPatchAdapter {
+    /// The value with the patches of the converted events applied
+    value: LoroValue,
+}
+
+impl JsonPatchAdapter {
+    pub fn new(doc: &LoroDoc) -> Self {
+        Self {
+            value: doc.get_deep_value(),
+        }
+    }
+
+    /// Convert the event into JSON Patch operations.
+    ///
+    /// The doc is used to read the new values of the texts, counters and trees, so it
+    /// should be called inside the subscriber of the event.
+    ///
+    /// If the patches cannot be applied to the patched value of the adapter, e.g. the
+    /// adapter is created from another doc, the value is rebuilt from the deep value of
+    /// the doc, and a single `replace` of the whole document is returned instead.
+    pub fn convert(&mut self, doc: &LoroDoc, event: &DiffEvent) -> Vec<JsonPatchOp> {
+        let mut ans = Vec::new();
+        // the containers whose new deep values are already in the patches
+        let mut covered: HashSet<ContainerID> = HashSet::new();
+        for diff in event.events.iter() {
+            let Some((root, _)) = diff.path.first() else {
+                continue;
+            };
+            let ContainerID::Root {
+                name,
+                container_type,
+            } = root
+            else {
+                continue;
+            };
+            if diff.path.iter().any(|(id, _)| covered.contains(id)) {
+                continue;
+            }
+
+            let Some(pointer) = to_pointer(doc, diff.path) else {
+                continue;
+            };
+
+            let start = ans.len();
+            if !self.value.as_map().unwrap().contains_key(&**name) {
+                ans.push(JsonPatchOp::Add {
+                    path: to_pointer(doc, &diff.path[..1]).unwrap(),
+                    value: empty_value(*container_type),
+                });
+            }
+
+            convert_diff(doc, &self.value, diff, pointer, &mut covered, &mut ans);
+            if apply_json_patch(&mut self.value, &ans[start..]).is_err() {
+                // the patched value is out of sync with the doc
+                self.value = doc.get_deep_value();
+                return vec![JsonPatchOp::Replace {
+                    path: String::new(),
+                    value: self.value.clone(),
+                }];
+            }
+        }
+
+        ans
+    }
+}
+
+fn convert_diff(
+    doc: &LoroDoc,
+    old: &LoroValue,
+    diff: &ContainerDiff,
+    pointer: String,
+    covered: &mut HashSet<ContainerID>,
+    ans: &mut Vec<JsonPatchOp>,
+) {
+    match &diff.diff {
+        Diff::List(items) => {
+            let mut index = 0;
+            for item in items {
+                match item {
+                    ListDiffItem::Retain { retain } => index += retain,
+                    ListDiffItem::Insert { insert } => {
+                        for v in insert {
+                            ans.push(JsonPatchOp::Add {
+                                path: join(&pointer, &index.to_string()),
+                                value: deep_value(v, covered),
+                            });
+                            index += 1;
+                        }
+                    }
+                    ListDiffItem::Delete { delete } => {
+                        for _ in 0..*delete {
+                            ans.push(JsonPatchOp::Remove {
+                                path: join(&pointer, &index.to_string()),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        Diff::Map(map) => {
+            // sorted so that the patches are deterministic
+            let mut updated: Vec<_> = map.updated.iter().collect();
+            updated.sort_unstable_by_key(|(k, _)| **k);
+            for (key, v) in updated {
+                let path = join(&pointer, key);
+                match v {
+                    Some(v) => ans.push(JsonPatchOp::Add {
+                        path,
+                        value: deep_value(v, covered),
+                    }),
+                    None => {
+                        if get_by_pointer(old, &path).is_some() {
+                            ans.push(JsonPatchOp::Remove { path });
+                        }
+                    }
+                }
+            }
+        }
+        Diff::Text(_) | Diff::Counter(_) | Diff::Tree(_) => {
+            let value = match diff.target.container_type() {
+                ContainerType::Text => doc
+                    .get_text(diff.target.clone())
+                    .to_handler()
+                    .get_deep_value(),
+                ContainerType::Counter => doc
+                    .get_counter(diff.target.clone())
+                    .to_handler()
+                    .get_deep_value(),
+                ContainerType::Tree => doc
+                    .get_tree(diff.target.clone())
+                    .to_handler()
+                    .get_deep_value(),
+                _ => unreachable!(),
+            };
+            let in_list = matches!(diff.path.last(), Some((_, Index::Seq(_))));
+            ans.push(if in_list {
+                JsonPatchOp::Replace {
+                    path: pointer,
+                    value,
+                }
+            } else {
+                JsonPatchOp::Add {
+                    path: pointer,
+                    value,
+                }
+            });
+            covered.insert(diff.target.clone());
+        }
+    }
+}
+
+/// Apply the JSON Patch operations to the value in order.
+///
+/// If an operation fails, the value is left with the operations before it applied.
+pub fn apply_json_patch(value: &mut LoroValue, ops: &[JsonPatchOp]) -> LoroResult<()> {
+    for op in ops {
+        let tokens = parse_pointer(op.path())?;
+        let Some((last, parents)) = tokens.split_last() else {
+            // the whole value is the target
+            match op {
+                JsonPatchOp::Add { value: v, .. } | JsonPatchOp::Replace { value: v, .. } => {
+                    *value = v.clone();
+                }
+                JsonPatchOp::Remove { .. } => *value = LoroValue::Null,
+            }
+            continue;
+        };
+
+        let mut parent = &mut *value;
+        for token in parents {
+            parent = get_child_mut(parent, token)
+                .ok_or_else(|| LoroError::NotFoundError(op.path().into()))?;
+        }
+
+        match parent {
+            LoroValue::Map(map) => {
+                let map = Arc::make_mut(map);
+                match op {
+                    JsonPatchOp::Add { value: v, .. } => {
+                        map.insert(last.clone(), v.clone());
+                    }
+                    JsonPatchOp::Remove { .. } => {
+                        map.remove(last)
+                            .ok_or_else(|| LoroError::NotFoundError(op.path().into()))?;
+                    }
+                    JsonPatchOp::Replace { value: v, .. } => {
+                        *map.get_mut(last)
+                            .ok_or_else(|| LoroError::NotFoundError(op.path().into()))? = v.clone();
+                    }
+                }
+            }
+            LoroValue::List(list) => {
+                let list = Arc::make_mut(list);
+                let len = list.len();
+                let pos = if last == "-" {
+                    len
+                } else {
+                    last.parse::<usize>()
+                        .map_err(|_| LoroError::ArgErr(op.path().into()))?
+                };
+                match op {
+                    JsonPatchOp::Add { value: v, .. } if pos <= len => {
+                        list.insert(pos, v.clone());
+                    }
+                    JsonPatchOp::Remove { .. } if pos < len => {
+                        list.remove(pos);
+                    }
+                    JsonPatchOp::Replace { value: v, .. } if pos < len => {
+                        list[pos] = v.clone();
+                    }
+                    _ => return Err(LoroError::OutOfBound { pos, len }),
+                }
+            }
+            _ => return Err(LoroError::NotFoundError(op.path().into())),
+        }
+    }
+
+    Ok(())
+}
+
+fn get_by_pointer<'a>(value: &'a LoroValue, pointer: &str) -> Option<&'a LoroValue> {
+    let mut ans = value;
+    for token in parse_pointer(pointer).ok()? {
+        ans = match ans {
+            LoroValue::Map(map) => map.get(&token)?,
+            LoroValue::List(list) => list.get(token.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(ans)
+}
+
+fn get_child_mut<'a>(value: &'a mut LoroValue, token: &str) -> Option<&'a mut LoroValue> {
+    match value {
+        LoroValue::Map(map) => Arc::make_mut(map).get_mut(token),
+        LoroValue::List(list) => Arc::make_mut(list).get_mut(token.parse::<usize>().ok()?),
+        _ => None,
+    }
+}
+
+/// Get the deep value of the inserted value. An inserted container is added to `covered`.
+fn deep_value(v: &ValueOrContainer, covered: &mut HashSet<ContainerID>) -> LoroValue {
+    match v {
+        ValueOrContainer::Value(v) => v.clone(),
+        ValueOrContainer::Container(c) => {
+            covered.insert(c.id());
+            c.to_handler().get_deep_value()
+        }
+    }
+}
+
+fn empty_value(kind: ContainerType) -> LoroValue {
+    match kind {
+        ContainerType::Text => LoroValue::String(Default::default()),
+        ContainerType::Map => LoroValue::Map(Default::default()),
+        ContainerType::List | ContainerType::MovableList | ContainerType::Tree => {
+            LoroValue::List(Default::default())
+        }
+        ContainerType::Counter => LoroValue::Double(0.),
+    }
+}
+
+/// Convert the path of an event into a JSON Pointer.
+///
+/// It returns `None` if a tree node on the path doesn't exist.
+fn to_pointer(doc: &LoroDoc, path: &[(ContainerID, Index)]) -> Option<String> {
+    let mut ans = String::new();
+    for (i, (_, index)) in path.iter().enumerate() {
+        match index {
+            Index::Key(key) => ans = join(&ans, key),
+            Index::Seq(pos) => ans = join(&ans, &pos.to_string()),
+            Index::Node(node) => {
+                // the nodes are in the JSON value of the tree in the order of its state
+                let tree = doc.get_tree(path[i.checked_sub(1)?].0.clone());
+                let LoroValue::List(nodes) = tree.to_handler().get_value() else {
+                    return None;
+                };
+                let id: LoroValue = node.id().to_string().into();
+                let pos = nodes
+                    .iter()
+                    .position(|n| n.as_map().and_then(|n| n.get("id")) == Some(&id))?;
+                ans = join(&join(&ans, &pos.to_string()), "meta");
+            }
+        }
+    }
+
+    Some(ans)
+}
+
+fn join(pointer: &str, token: &str) -> String {
+    let mut ans = String::with_capacity(pointer.len() + token.len() + 1);
+    ans.push_str(pointer);
+    ans.push('/');
+    for c in token.chars() {
+        match c {
+            '~' => ans.push_str("~0"),
+            '/' => ans.push_str("~1"),
+            c => ans.push(c),
+        }
+    }
+    ans
+}
+
+fn parse_pointer(pointer: &str) -> LoroResult<Vec<String>> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(LoroError::ArgErr(
+            format!("Invalid JSON Pointer {}", pointer).into(),
+        ));
+    };
+    Ok(rest
+        .split('/')
+        .map(|t| t.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
@@ -1,6 +1,6 @@
 #![doc = include_str!("../README.md")]
 use either::Either;
-use event::{DiffEvent, Subscriber};
+use event::{DiffBatch, DiffEvent, Subscriber};
 use loro_internal::change::Timestamp;
 use loro_internal::container::IntoContainerId;
 use loro_internal::cursor::CannotFindRelativePosition;
@@ -14,8 +14,9 @@ use loro_internal::LoroDoc as InnerLoroDoc;
 use loro_internal::OpLog;
 
 use loro_internal::{
-    handler::Handler as InnerHandler, ListHandler as InnerListHandler,
-    MapHandler as InnerMapHandler, TextHandler as InnerTextHandler,
+    handler::Handler as InnerHandler, CounterHandler as InnerCounterHandler,
+    ListHandler as InnerListHandler, MapHandler as InnerMapHandler,
+    MovableListHandler as InnerMovableListHandler, TextHandler as InnerTextHandler,
     TreeHandler as InnerTreeHandler,
 };
 use std::cmp::Ordering;
@@ -23,21 +24,66 @@ use std::ops::Range;
 use std::sync::Arc;
 
 pub mod event;
-
+pub mod json_patch;
+pub mod storage;
+pub mod sync;
+mod undo;
+pub use undo::{CursorWithPos, OnPop, OnPush, UndoItemMeta, UndoManager, UndoOrRedo};
+
+pub use loro_internal::awareness::{
+    Awareness, AwarenessEvent, AwarenessEventTrigger, AwarenessSubscriber, PeerInfo,
+};
+pub use loro_internal::change::{ChangeMeta, ChangeOp, ChangeOpContent};
 pub use loro_internal::configure::Configure;
 pub use loro_internal::configure::StyleConfigMap;
 pub use loro_internal::container::richtext::ExpandType;
 pub use loro_internal::container::{ContainerID, ContainerType};
 pub use loro_internal::delta::{TreeDeltaItem, TreeDiff, TreeExternalDiff};
+pub use loro_internal::encoding::json_schema::{JsonChange, JsonOp, JsonOpContent, JsonSchema};
+pub use loro_internal::encoding::{ImportPreview, ImportStatus, PendingChangesSummary};
 pub use loro_internal::event::Index;
-pub use loro_internal::handler::TextDelta;
-pub use loro_internal::id::{PeerID, TreeID, ID};
-pub use loro_internal::obs::SubID;
-pub use loro_internal::oplog::FrontiersNotIncluded;
+pub use loro_internal::handler::{Attribution, TextDelta};
+pub use loro_internal::id::{IdSpan, PeerID, TreeID, ID};
+pub use loro_internal::obs::{LocalUpdateCallback, SubID};
+pub use loro_internal::oplog::{FrontiersNotIncluded, ImportValidator};
+pub use loro_internal::schema::{ContainerSchema, Schema, ValueSchema};
 pub use loro_internal::version::{Frontiers, VersionVector};
 pub use loro_internal::{loro_value, to_value};
 pub use loro_internal::{LoroError, LoroResult, LoroValue, ToJson};
 
+/// Merge the update blobs into one without loading the doc.
+///
+/// Only the changes are merged, so it doesn't build the state of the doc. The snapshots
+/// are accepted, but only their history is kept. The deps outside the blobs become the
+/// start version of the merged blob, so it can be imported wherever all the blobs can.
+/// It returns [`LoroError::MergeWithMissingDeps`] if there is a gap inside the blobs, i.e.
+/// a change depends on a missing change in the range they cover. The shallow snapshots
+/// cannot be merged.
+///
+/// # Example
+///
+/// ```
+/// # use loro::{merge_updates, LoroDoc};
+/// let doc = LoroDoc::new();
+/// let text = doc.get_text("text");
+/// let mut blobs = Vec::new();
+/// for s in ["Hello", " ", "world"] {
+///     let v = doc.oplog_vv();
+///     text.insert(text.len_unicode(), s).unwrap();
+///     doc.commit();
+///     blobs.push(doc.export_from(&v));
+/// }
+///
+/// let blobs: Vec<&[u8]> = blobs.iter().map(|b| b.as_slice()).collect();
+/// let merged = merge_updates(&blobs).unwrap();
+/// let new_doc = LoroDoc::new();
+/// new_doc.import(&merged).unwrap();
+/// assert_eq!(new_doc.get_text("text").to_string(), "Hello world");
+/// ```
+pub fn merge_updates(blobs: &[&[u8]]) -> LoroResult<Vec<u8>> {
+    loro_internal::encoding::merge_updates(blobs)
+}
+
 /// `LoroDoc` is the entry for the whole document.
 /// When it's dropped, all the associated [`Handler`]s will be invalidated.
 pub struct LoroDoc {
@@ -102,6 +148,35 @@ impl LoroDoc {
         self.doc.config_text_style(text_style)
     }
 
+    /// Set the schema that the local edits are checked against. `None` removes it.
+    ///
+    /// The schema describes the types of the root containers and their children, the allowed
+    /// map keys and the kinds of the values. The edits that violate it fail with
+    /// [`LoroError::SchemaViolation`] before any op is recorded. The imported changes
+    /// are not checked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::{ContainerSchema, LoroDoc, LoroError, Schema, ValueSchema};
+    ///
+    /// let doc = LoroDoc::new();
+    /// doc.set_schema(Some(Schema::new().with_root(
+    ///     "settings",
+    ///     ContainerSchema::map([("theme", ValueSchema::String)]),
+    /// )));
+    /// let settings = doc.get_map("settings");
+    /// settings.insert("theme", "dark").unwrap();
+    /// assert!(matches!(
+    ///     settings.insert("theme", 1),
+    ///     Err(LoroError::SchemaViolation { .. })
+    /// ));
+    /// assert!(settings.insert("font", "mono").is_err());
+    /// ```
+    pub fn set_schema(&self, schema: Option<Schema>) {
+        self.doc.set_schema(schema)
+    }
+
     /// Attach the document state to the latest known version.
     ///
     /// > The document becomes detached during a `checkout` operation.
@@ -124,6 +199,202 @@ impl LoroDoc {
         self.doc.checkout(frontiers)
     }
 
+    /// Get the version of the doc at the given timestamp.
+    ///
+    /// It includes all the changes whose timestamps are not greater than `timestamp`,
+    /// and the changes they depend on. The timestamps come from the clocks of the peers,
+    /// so a change may depend on a change with a greater timestamp, which is included
+    /// too. The timestamps are only recorded when [`LoroDoc::set_record_timestamp`] is enabled,
+    /// or when they are set by [`LoroDoc::commit_with`].
+    ///
+    /// The local changes committed within the merge interval are merged into one change,
+    /// which keeps the first timestamp. See [`LoroDoc::set_change_merge_interval`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::LoroDoc;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let text = doc.get_text("text");
+    /// text.insert(0, "Hello").unwrap();
+    /// doc.commit_with(None, Some(1_000_000), true);
+    /// let v = doc.oplog_frontiers();
+    /// text.insert(5, " World").unwrap();
+    /// doc.commit_with(None, Some(3_000_000), true);
+    /// assert_eq!(doc.frontiers_at_timestamp(2_000_000), v);
+    /// ```
+    pub fn frontiers_at_timestamp(&self, timestamp: Timestamp) -> Frontiers {
+        self.doc.frontiers_at_timestamp(timestamp)
+    }
+
+    /// Checkout the `DocState` to the version at the given timestamp.
+    ///
+    /// The document becomes detached like [`LoroDoc::checkout`].
+    /// See [`LoroDoc::frontiers_at_timestamp`] for how the version is decided.
+    pub fn checkout_at_timestamp(&self, timestamp: Timestamp) -> LoroResult<()> {
+        self.doc.checkout_at_timestamp(timestamp)
+    }
+
+    /// Fork the document at the latest version.
+    ///
+    /// The new document has the whole history of this document and a new random peer id.
+    /// The edits on the two documents don't affect each other until they are synced.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::LoroDoc;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let text = doc.get_text("text");
+    /// text.insert(0, "Hello").unwrap();
+    /// let fork = doc.fork();
+    /// assert_ne!(fork.peer_id(), doc.peer_id());
+    /// fork.get_text("text").insert(5, " World").unwrap();
+    /// assert_eq!(fork.get_text("text").to_string(), "Hello World");
+    /// assert_eq!(text.to_string(), "Hello");
+    /// ```
+    pub fn fork(&self) -> LoroDoc {
+        let mut doc = self.doc.fork();
+        doc.start_auto_commit();
+        LoroDoc { doc }
+    }
+
+    /// Fork the document at the given version.
+    ///
+    /// The new document has the history up to `frontiers` and a new random peer id.
+    /// It's attached and editable, unlike a document checked out to the version.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::LoroDoc;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let text = doc.get_text("text");
+    /// text.insert(0, "Hello").unwrap();
+    /// doc.commit();
+    /// let v = doc.oplog_frontiers();
+    /// text.insert(5, " World").unwrap();
+    /// let fork = doc.fork_at(&v).unwrap();
+    /// assert_eq!(fork.get_text("text").to_string(), "Hello");
+    /// assert_eq!(fork.oplog_frontiers(), v);
+    /// ```
+    pub fn fork_at(&self, frontiers: &Frontiers) -> LoroResult<LoroDoc> {
+        let mut doc = self.doc.fork_at(frontiers)?;
+        doc.start_auto_commit();
+        Ok(LoroDoc { doc })
+    }
+
+    /// Tag the given version with `name`, e.g. "Submitted draft".
+    ///
+    /// The tags are stored in a reserved root map, so they are synced to other peers
+    /// with the updates and snapshots. Tagging with an existing name overrides the old tag.
+    /// The tag container is not included in [`LoroDoc::get_deep_value`], the events,
+    /// the schema checks or [`LoroDoc::diff`], so the [`UndoManager`] doesn't track it.
+    ///
+    /// The version must be included in the doc.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::LoroDoc;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let text = doc.get_text("text");
+    /// text.insert(0, "Hello").unwrap();
+    /// doc.commit();
+    /// doc.tag_version("draft", &doc.oplog_frontiers()).unwrap();
+    /// text.insert(5, " World").unwrap();
+    /// doc.commit();
+    ///
+    /// let other = LoroDoc::new();
+    /// other.import(&doc.export_snapshot()).unwrap();
+    /// assert_eq!(other.list_tags()[0].0, "draft");
+    /// other.checkout_tag("draft").unwrap();
+    /// assert_eq!(other.get_text("text").to_string(), "Hello");
+    /// ```
+    pub fn tag_version(&self, name: &str, frontiers: &Frontiers) -> LoroResult<()> {
+        self.doc.tag_version(name, frontiers)
+    }
+
+    /// Get all the version tags, sorted by name.
+    ///
+    /// The tags are read from the latest version even if the doc is detached.
+    pub fn list_tags(&self) -> Vec<(String, Frontiers)> {
+        self.doc.list_tags()
+    }
+
+    /// Checkout the `DocState` to the version tagged with `name`.
+    ///
+    /// The document becomes detached like [`LoroDoc::checkout`].
+    /// It returns [`LoroError::NotFoundError`] if the tag doesn't exist.
+    pub fn checkout_tag(&self, name: &str) -> LoroResult<()> {
+        self.doc.checkout_tag(name)
+    }
+
+    /// Calculate the diff from version `from` to version `to` without checking out.
+    ///
+    /// The current state is not affected. The result has the same form as the diffs
+    /// in the events, and [`event::DiffBatch::iter`] gives them as [`event::ContainerDiff`]s.
+    /// If `container_filter` is given, only the diffs of the containers that pass
+    /// the filter are calculated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use loro::LoroDoc;
+    /// let doc = LoroDoc::new();
+    /// let text = doc.get_text("text");
+    /// text.insert(0, "Hello").unwrap();
+    /// doc.commit();
+    /// let v1 = doc.oplog_frontiers();
+    /// text.insert(5, " World").unwrap();
+    /// doc.commit();
+    /// let diff = doc.diff(&v1, &doc.oplog_frontiers(), None).unwrap();
+    /// assert_eq!(diff.len(), 1);
+    /// let container_diff = diff.iter().next().unwrap();
+    /// assert_eq!(container_diff.target, &text.id());
+    /// assert!(container_diff.diff.is_text());
+    /// ```
+    pub fn diff(
+        &self,
+        from: &Frontiers,
+        to: &Frontiers,
+        container_filter: Option<&dyn Fn(&ContainerID) -> bool>,
+    ) -> LoroResult<DiffBatch> {
+        self.doc
+            .diff(from, to, container_filter)
+            .map(DiffBatch::new)
+    }
+
+    /// Revert the current state to the given version by creating new local ops.
+    ///
+    /// Unlike `checkout`, the document stays attached and editable, and the new ops
+    /// can be synced to other peers like any other local change.
+    ///
+    /// It returns [LoroError::AutoCommitNotStarted] if the document is detached.
+    /// Call [LoroDoc::attach] first in that case.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use loro::LoroDoc;
+    /// let doc = LoroDoc::new();
+    /// let text = doc.get_text("text");
+    /// text.insert(0, "Hello").unwrap();
+    /// doc.commit();
+    /// let frontiers = doc.oplog_frontiers();
+    /// text.insert(5, " World").unwrap();
+    /// doc.commit();
+    /// doc.revert_to(&frontiers).unwrap();
+    /// assert_eq!(text.to_string(), "Hello");
+    /// ```
+    pub fn revert_to(&self, frontiers: &Frontiers) -> LoroResult<()> {
+        self.doc.revert_to(frontiers)
+    }
+
     pub fn cmp_with_frontiers(&self, other: &Frontiers) -> Ordering {
         self.doc.cmp_with_frontiers(other)
     }
@@ -164,6 +435,24 @@ impl LoroDoc {
         }
     }
 
+    /// Get a [LoroMovableList] by container id.
+    ///
+    /// If the provided id is string, it will be converted into a root container id with the name of the string.
+    pub fn get_movable_list<I: IntoContainerId>(&self, id: I) -> LoroMovableList {
+        LoroMovableList {
+            handler: self.doc.get_movable_list(id),
+        }
+    }
+
+    /// Get a [LoroCounter] by container id.
+    ///
+    /// If the provided id is string, it will be converted into a root container id with the name of the string.
+    pub fn get_counter<I: IntoContainerId>(&self, id: I) -> LoroCounter {
+        LoroCounter {
+            handler: self.doc.get_counter(id),
+        }
+    }
+
     /// Get a [MapHandler] by container id.
     ///
     /// If the provided id is string, it will be converted into a root container id with the name of the string.
@@ -215,27 +504,271 @@ impl LoroDoc {
             .commit_with(origin.map(|x| x.into()), timestamp, immediate_renew)
     }
 
+    /// Set the commit message of the next change.
+    ///
+    /// Unlike the origin, the message is persisted on the change, so it can be
+    /// read by the other peers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::{LoroDoc, ID};
+    ///
+    /// let doc = LoroDoc::new();
+    /// doc.set_peer_id(1).unwrap();
+    /// doc.get_text("text").insert(0, "Hello").unwrap();
+    /// doc.set_next_commit_message("Add greeting");
+    /// doc.commit();
+    /// let new_doc = LoroDoc::new();
+    /// new_doc.import(&doc.export_snapshot()).unwrap();
+    /// let msg = new_doc.with_oplog(|oplog| {
+    ///     oplog
+    ///         .get_change_at(ID::new(1, 0))
+    ///         .and_then(|c| c.message().cloned())
+    /// });
+    /// assert_eq!(msg.as_deref(), Some("Add greeting"));
+    /// ```
+    pub fn set_next_commit_message(&self, message: &str) {
+        self.doc.set_next_commit_message(message)
+    }
+
     pub fn is_detached(&self) -> bool {
         self.doc.is_detached()
     }
 
-    pub fn import(&self, bytes: &[u8]) -> Result<(), LoroError> {
+    /// Import a snapshot or updates.
+    ///
+    /// The returned [`ImportStatus`] lists the applied changes, and the changes that are
+    /// pending because their dependencies are missing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::{IdSpan, LoroDoc, ID};
+    ///
+    /// let doc = LoroDoc::new();
+    /// doc.set_peer_id(1).unwrap();
+    /// let text = doc.get_text("text");
+    /// text.insert(0, "Hello").unwrap();
+    /// doc.commit();
+    /// let v = doc.oplog_vv();
+    /// text.insert(5, " world").unwrap();
+    /// doc.commit();
+    ///
+    /// let new_doc = LoroDoc::new();
+    /// // the second change depends on the first one
+    /// let status = new_doc.import(&doc.export_from(&v)).unwrap();
+    /// assert!(status.applied.is_empty());
+    /// assert_eq!(status.pending, vec![IdSpan::new(1, 5, 11)]);
+    /// assert_eq!(status.missing_deps, vec![ID::new(1, 4)]);
+    /// assert_eq!(new_doc.pending_changes_summary().missing_deps, vec![ID::new(1, 4)]);
+    ///
+    /// let status = new_doc.import(&doc.export_from(&Default::default())).unwrap();
+    /// assert_eq!(status.applied, vec![IdSpan::new(1, 0, 11)]);
+    /// assert!(new_doc.pending_changes_summary().pending.is_empty());
+    /// ```
+    pub fn import(&self, bytes: &[u8]) -> Result<ImportStatus, LoroError> {
         self.doc.import_with(bytes, "".into())
     }
 
-    pub fn import_with(&self, bytes: &[u8], origin: &str) -> Result<(), LoroError> {
+    pub fn import_with(&self, bytes: &[u8], origin: &str) -> Result<ImportStatus, LoroError> {
         self.doc.import_with(bytes, origin.into())
     }
 
+    /// Get the pending changes and the missing dependencies they are waiting for.
+    ///
+    /// The changes of each peer up to the missing dependencies should be requested
+    /// to apply the pending changes.
+    pub fn pending_changes_summary(&self) -> PendingChangesSummary {
+        self.doc.pending_changes_summary()
+    }
+
+    /// Set the validator that checks the changes to import. `None` removes it.
+    ///
+    /// The validator is called with each new change of an imported blob and its ops before
+    /// any of them is imported. If it rejects a change, the import fails with
+    /// [`LoroError::ImportRejected`] and the doc is not changed. Local changes are not checked.
+    ///
+    /// The ops kept in the root of a shallow snapshot have no change info, so each of them
+    /// is passed as a single-op change without deps or timestamp.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use loro::{ContainerID, ContainerType, LoroDoc, LoroError};
+    ///
+    /// let doc = LoroDoc::new();
+    /// let text = ContainerID::new_root("text", ContainerType::Text);
+    /// doc.set_import_validator(Some(Arc::new(move |change, ops| {
+    ///     if change.id.peer != 1 {
+    ///         return Err(format!("unknown peer {}", change.id.peer));
+    ///     }
+    ///     if ops.iter().any(|op| op.container != text) {
+    ///         return Err("only the text can be edited".into());
+    ///     }
+    ///     Ok(())
+    /// })));
+    ///
+    /// let other = LoroDoc::new();
+    /// other.set_peer_id(2).unwrap();
+    /// other.get_text("text").insert(0, "Hello").unwrap();
+    /// other.commit();
+    /// let err = doc.import(&other.export_from(&Default::default())).unwrap_err();
+    /// assert!(matches!(err, LoroError::ImportRejected { .. }));
+    /// assert!(doc.oplog_vv().is_empty());
+    /// ```
+    pub fn set_import_validator(&self, validator: Option<ImportValidator>) {
+        self.doc.set_import_validator(validator)
+    }
+
+    /// Preview the effect of importing `bytes` without changing the doc.
+    ///
+    /// It reports the changes that would be pending because of missing dependencies
+    /// and the diffs the import would emit. Use [`DiffEvent::from`] to read the diffs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::{event::DiffEvent, LoroDoc};
+    ///
+    /// let doc = LoroDoc::new();
+    /// let other = LoroDoc::new();
+    /// other.get_text("text").insert(0, "Hello").unwrap();
+    /// other.commit();
+    ///
+    /// let preview = doc.preview_import(&other.export_from(&Default::default())).unwrap();
+    /// assert!(preview.pending.is_empty());
+    /// let event = DiffEvent::from(&preview.diff[0]);
+    /// assert_eq!(event.events.len(), 1);
+    /// // the doc is not changed
+    /// assert_eq!(doc.get_text("text").to_string(), "");
+    /// ```
+    pub fn preview_import(&self, bytes: &[u8]) -> LoroResult<ImportPreview> {
+        self.doc.preview_import(bytes)
+    }
+
     /// Export all the ops not included in the given `VersionVector`
     pub fn export_from(&self, vv: &VersionVector) -> Vec<u8> {
         self.doc.export_from(vv)
     }
 
+    /// Export the changes that are in `end_vv` but not in `start_vv` in a human-readable
+    /// JSON format.
+    ///
+    /// Each change lists its id, lamport, deps, timestamp, message and ops. The result can be
+    /// converted to a JSON string with serde and imported by [`LoroDoc::import_json_updates`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::{JsonSchema, LoroDoc};
+    ///
+    /// let doc = LoroDoc::new();
+    /// doc.set_peer_id(1).unwrap();
+    /// doc.get_text("text").insert(0, "Hello").unwrap();
+    /// doc.commit();
+    /// let json = doc.export_json_updates(&Default::default(), &doc.oplog_vv());
+    /// let s = serde_json::to_string(&json).unwrap();
+    /// assert!(s.contains(r#""id":"0@1""#));
+    ///
+    /// let new_doc = LoroDoc::new();
+    /// let json: JsonSchema = serde_json::from_str(&s).unwrap();
+    /// new_doc.import_json_updates(json).unwrap();
+    /// assert_eq!(new_doc.get_text("text").to_string(), "Hello");
+    /// ```
+    pub fn export_json_updates(
+        &self,
+        start_vv: &VersionVector,
+        end_vv: &VersionVector,
+    ) -> JsonSchema {
+        self.doc.export_json_updates(start_vv, end_vv)
+    }
+
+    /// Import the changes in the JSON format exported by [`LoroDoc::export_json_updates`].
+    pub fn import_json_updates(&self, json: JsonSchema) -> LoroResult<()> {
+        self.doc.import_json_updates(json)
+    }
+
     pub fn export_snapshot(&self) -> Vec<u8> {
         self.doc.export_snapshot()
     }
 
+    /// Export the state at the given version and the updates after it.
+    ///
+    /// The history before the version is trimmed. The doc imported from it cannot
+    /// checkout to the versions before it, but it can still sync with other peers.
+    /// If there are changes after the version that are concurrent to it, the history
+    /// starts from an earlier version that all of them depend on.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use loro::{LoroDoc, LoroError};
+    /// let doc = LoroDoc::new();
+    /// let text = doc.get_text("text");
+    /// text.insert(0, "Hello").unwrap();
+    /// doc.commit();
+    /// let v1 = doc.oplog_frontiers();
+    /// text.insert(5, " World").unwrap();
+    /// doc.commit();
+    /// let bytes = doc.export_shallow_snapshot(&v1).unwrap();
+    ///
+    /// let shallow = LoroDoc::new();
+    /// shallow.import(&bytes).unwrap();
+    /// assert_eq!(shallow.get_text("text").to_string(), "Hello World");
+    /// assert_eq!(shallow.shallow_since_frontiers(), v1);
+    /// assert!(matches!(
+    ///     shallow.checkout(&Default::default()),
+    ///     Err(LoroError::SwitchToVersionBeforeShallowRoot)
+    /// ));
+    /// ```
+    pub fn export_shallow_snapshot(&self, frontiers: &Frontiers) -> LoroResult<Vec<u8>> {
+        self.doc.export_shallow_snapshot(frontiers)
+    }
+
+    /// Export a snapshot that only contains the given containers and their descendants.
+    ///
+    /// It's a shallow snapshot of the latest version. It carries the ops that form the
+    /// states of these containers, but not the history before. The child containers that
+    /// are not roots can be accessed by their ids in the imported doc.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use loro::{LoroDoc, LoroMap, LoroText};
+    /// let doc = LoroDoc::new();
+    /// let map = doc.get_map("map");
+    /// let text = map.insert_container("text", LoroText::new()).unwrap();
+    /// text.insert(0, "Hello").unwrap();
+    /// doc.get_text("other").insert(0, "secret").unwrap();
+    /// doc.commit();
+    ///
+    /// let bytes = doc.export_snapshot_of(&[text.id()]).unwrap();
+    /// let viewer = LoroDoc::new();
+    /// viewer.import(&bytes).unwrap();
+    /// assert_eq!(viewer.get_text(text.id()).to_string(), "Hello");
+    /// assert_eq!(viewer.get_text("other").to_string(), "");
+    /// ```
+    pub fn export_snapshot_of(&self, containers: &[ContainerID]) -> LoroResult<Vec<u8>> {
+        self.doc.export_snapshot_of(containers)
+    }
+
+    /// Whether the history before some version is trimmed
+    pub fn is_shallow(&self) -> bool {
+        self.doc.is_shallow()
+    }
+
+    /// The version vector where the history starts. It's empty if the doc is not shallow.
+    pub fn shallow_since_vv(&self) -> VersionVector {
+        self.doc.shallow_since_vv()
+    }
+
+    /// The frontiers where the history starts. It's empty if the doc is not shallow.
+    pub fn shallow_since_frontiers(&self) -> Frontiers {
+        self.doc.shallow_since_frontiers()
+    }
+
     /// Convert `Frontiers` into `VersionVector`
     pub fn frontiers_to_vv(&self, frontiers: &Frontiers) -> Option<VersionVector> {
         self.doc.frontiers_to_vv(frontiers)
@@ -251,6 +784,54 @@ impl LoroDoc {
         f(&oplog)
     }
 
+    /// Get the metadata of all the committed changes in causal order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::{LoroDoc, ChangeOpContent, ID};
+    ///
+    /// let doc = LoroDoc::new();
+    /// doc.set_peer_id(1).unwrap();
+    /// doc.get_text("text").insert(0, "Hello").unwrap();
+    /// doc.set_next_commit_message("Say hello");
+    /// doc.commit();
+    /// let changes = doc.changes();
+    /// assert_eq!(changes.len(), 1);
+    /// assert_eq!(changes[0].id, ID::new(1, 0));
+    /// assert_eq!(changes[0].len, 5);
+    /// assert_eq!(changes[0].message.as_deref(), Some("Say hello"));
+    ///
+    /// let ops = doc.ops_in_change(changes[0].id).unwrap();
+    /// assert_eq!(
+    ///     ops[0].content,
+    ///     ChangeOpContent::TextInsert {
+    ///         pos: 0,
+    ///         text: "Hello".into()
+    ///     }
+    /// );
+    /// ```
+    pub fn changes(&self) -> Vec<ChangeMeta> {
+        self.doc.changes()
+    }
+
+    /// Get the metadata of the changes that are in `b` but not in `a`, in causal order.
+    ///
+    /// The changes that are partially included are sliced.
+    pub fn changes_between(&self, a: &VersionVector, b: &VersionVector) -> Vec<ChangeMeta> {
+        self.doc.changes_between(a, b)
+    }
+
+    /// Get the ops of the change that contains `id`.
+    ///
+    /// The positions of the text ops are entity indexes. See [ChangeOpContent] for how to
+    /// map them to unicode indexes.
+    ///
+    /// It returns `None` if the change is not found.
+    pub fn ops_in_change(&self, id: ID) -> Option<Vec<ChangeOp>> {
+        self.doc.ops_in_change(id)
+    }
+
     /// Get the `VersionVector` version of `OpLog`
     pub fn oplog_vv(&self) -> VersionVector {
         self.doc.oplog_vv()
@@ -355,6 +936,35 @@ impl LoroDoc {
         }))
     }
 
+    /// Subscribe the local commits.
+    ///
+    /// The callback is invoked on every local commit with an update blob that only
+    /// contains the new change, so it can be sent to the other peers directly.
+    /// Returns a subscription id that can be used to unsubscribe.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use loro::LoroDoc;
+    /// # use std::sync::{Arc, Mutex};
+    /// let doc = LoroDoc::new();
+    /// let updates = Arc::new(Mutex::new(Vec::new()));
+    /// let updates_clone = updates.clone();
+    /// let sub = doc.subscribe_local_update(Arc::new(move |bytes: &[u8]| {
+    ///     updates_clone.lock().unwrap().push(bytes.to_vec());
+    /// }));
+    /// doc.get_text("text").insert(0, "Hello").unwrap();
+    /// doc.commit();
+    ///
+    /// let remote = LoroDoc::new();
+    /// remote.import(&updates.lock().unwrap()[0]).unwrap();
+    /// assert_eq!(remote.get_text("text").to_string(), "Hello");
+    /// doc.unsubscribe(sub);
+    /// ```
+    pub fn subscribe_local_update(&self, callback: LocalUpdateCallback) -> SubID {
+        self.doc.subscribe_local_update(callback)
+    }
+
     /// Remove a subscription.
     pub fn unsubscribe(&self, id: SubID) {
         self.doc.unsubscribe(id)
@@ -369,84 +979,315 @@ impl LoroDoc {
         self.doc.get_by_path(path).map(ValueOrContainer::from)
     }
 
-    /// Get the handler by the string path.
-    pub fn get_by_str_path(&self, path: &str) -> Option<ValueOrContainer> {
-        self.doc.get_by_str_path(path).map(ValueOrContainer::from)
+    /// Get the handler by the string path.
+    pub fn get_by_str_path(&self, path: &str) -> Option<ValueOrContainer> {
+        self.doc.get_by_str_path(path).map(ValueOrContainer::from)
+    }
+
+    /// Get the absolute position of the given cursor.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use loro::{LoroDoc, ToJson};
+    /// let doc = LoroDoc::new();
+    /// let text = &doc.get_text("text");
+    /// text.insert(0, "01234").unwrap();
+    /// let pos = text.get_cursor(5, Default::default()).unwrap();
+    /// assert_eq!(doc.get_cursor_pos(&pos).unwrap().current.pos, 5);
+    /// text.insert(0, "01234").unwrap();
+    /// assert_eq!(doc.get_cursor_pos(&pos).unwrap().current.pos, 10);
+    /// text.delete(0, 10).unwrap();
+    /// assert_eq!(doc.get_cursor_pos(&pos).unwrap().current.pos, 0);
+    /// text.insert(0, "01234").unwrap();
+    /// assert_eq!(doc.get_cursor_pos(&pos).unwrap().current.pos, 5);
+    /// ```
+    pub fn get_cursor_pos(
+        &self,
+        cursor: &Cursor,
+    ) -> Result<PosQueryResult, CannotFindRelativePosition> {
+        self.doc.query_pos(cursor)
+    }
+}
+
+/// It's used to prevent the user from implementing the trait directly.
+#[allow(private_bounds)]
+trait SealedTrait {}
+#[allow(private_bounds)]
+pub trait ContainerTrait: SealedTrait {
+    type Handler: HandlerTrait;
+    fn to_container(&self) -> Container;
+    fn to_handler(&self) -> Self::Handler;
+    fn from_handler(handler: Self::Handler) -> Self;
+    fn try_from_container(container: Container) -> Option<Self>
+    where
+        Self: Sized;
+    fn is_attached(&self) -> bool;
+    /// If a detached container is attached, this method will return its corresponding attached handler.
+    fn get_attached(&self) -> Option<Self>
+    where
+        Self: Sized;
+}
+
+/// LoroList container. It's used to model array.
+///
+/// It can have sub containers.
+///
+/// ```
+/// # use loro::{LoroDoc, ContainerType, ToJson};
+/// # use serde_json::json;
+/// let doc = LoroDoc::new();
+/// let list = doc.get_list("list");
+/// list.insert(0, 123).unwrap();
+/// list.insert(1, "h").unwrap();
+/// assert_eq!(
+///     doc.get_deep_value().to_json_value(),
+///     json!({
+///         "list": [123, "h"]
+///     })
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct LoroList {
+    handler: InnerListHandler,
+}
+
+impl SealedTrait for LoroList {}
+impl ContainerTrait for LoroList {
+    type Handler = InnerListHandler;
+    fn to_container(&self) -> Container {
+        Container::List(self.clone())
+    }
+
+    fn to_handler(&self) -> Self::Handler {
+        self.handler.clone()
+    }
+
+    fn from_handler(handler: Self::Handler) -> Self {
+        Self { handler }
+    }
+
+    fn is_attached(&self) -> bool {
+        self.handler.is_attached()
+    }
+
+    fn get_attached(&self) -> Option<Self> {
+        self.handler.get_attached().map(Self::from_handler)
+    }
+
+    fn try_from_container(container: Container) -> Option<Self> {
+        container.into_list().ok()
+    }
+}
+
+impl LoroList {
+    /// Create a new container that is detached from the document.
+    ///
+    /// The edits on a detached container will not be persisted.
+    /// To attach the container to the document, please insert it into an attached container.
+    pub fn new() -> Self {
+        Self {
+            handler: InnerListHandler::new_detached(),
+        }
+    }
+
+    /// Whether the container is attached to a document
+    ///
+    /// The edits on a detached container will not be persisted.
+    /// To attach the container to the document, please insert it into an attached container.
+    pub fn is_attached(&self) -> bool {
+        self.handler.is_attached()
+    }
+
+    pub fn insert(&self, pos: usize, v: impl Into<LoroValue>) -> LoroResult<()> {
+        self.handler.insert(pos, v)
+    }
+
+    #[inline]
+    pub fn delete(&self, pos: usize, len: usize) -> LoroResult<()> {
+        self.handler.delete(pos, len)
+    }
+
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<Either<LoroValue, Container>> {
+        match self.handler.get_(index) {
+            Some(ValueOrHandler::Handler(c)) => Some(Either::Right(c.into())),
+            Some(ValueOrHandler::Value(v)) => Some(Either::Left(v)),
+            None => None,
+        }
+    }
+
+    #[inline]
+    pub fn get_deep_value(&self) -> LoroValue {
+        self.handler.get_deep_value()
+    }
+
+    #[inline]
+    pub fn get_value(&self) -> LoroValue {
+        self.handler.get_value()
+    }
+
+    #[inline]
+    pub fn id(&self) -> ContainerID {
+        self.handler.id().clone()
+    }
+
+    #[inline]
+    pub fn pop(&self) -> LoroResult<Option<LoroValue>> {
+        self.handler.pop()
+    }
+
+    #[inline]
+    pub fn push(&self, v: impl Into<LoroValue>) -> LoroResult<()> {
+        self.handler.push(v.into())
+    }
+
+    #[inline]
+    pub fn push_container<C: ContainerTrait>(&self, child: C) -> LoroResult<C> {
+        let pos = self.handler.len();
+        Ok(C::from_handler(
+            self.handler.insert_container(pos, child.to_handler())?,
+        ))
+    }
+
+    pub fn for_each<I>(&self, f: I)
+    where
+        I: FnMut(ValueOrHandler),
+    {
+        self.handler.for_each(f)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.handler.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.handler.is_empty()
+    }
+
+    /// Insert a container with the given type at the given index.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use loro::{LoroDoc, ContainerType, LoroText, ToJson};
+    /// # use serde_json::json;
+    /// let doc = LoroDoc::new();
+    /// let list = doc.get_list("m");
+    /// let text = list.insert_container(0, LoroText::new()).unwrap();
+    /// text.insert(0, "12");
+    /// text.insert(0, "0");
+    /// assert_eq!(doc.get_deep_value().to_json_value(), json!({"m": ["012"]}));
+    /// ```
+    #[inline]
+    pub fn insert_container<C: ContainerTrait>(&self, pos: usize, child: C) -> LoroResult<C> {
+        Ok(C::from_handler(
+            self.handler.insert_container(pos, child.to_handler())?,
+        ))
+    }
+
+    /// Get the cursor at the given position.
+    ///
+    /// Using "index" to denote cursor positions can be unstable, as positions may
+    /// shift with document edits. To reliably represent a position or range within
+    /// a document, it is more effective to leverage the unique ID of each item/character
+    /// in a List CRDT or Text CRDT.
+    ///
+    /// Loro optimizes State metadata by not storing the IDs of deleted elements. This
+    /// approach complicates tracking cursors since they rely on these IDs. The solution
+    /// recalculates position by replaying relevant history to update stable positions
+    /// accurately. To minimize the performance impact of history replay, the system
+    /// updates cursor info to reference only the IDs of currently present elements,
+    /// thereby reducing the need for replay.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::LoroDoc;
+    /// use loro_internal::cursor::Side;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let list = doc.get_list("list");
+    /// list.insert(0, 0).unwrap();
+    /// let cursor = list.get_cursor(0, Side::Middle).unwrap();
+    /// assert_eq!(doc.get_cursor_pos(&cursor).unwrap().current.pos, 0);
+    /// list.insert(0, 0).unwrap();
+    /// assert_eq!(doc.get_cursor_pos(&cursor).unwrap().current.pos, 1);
+    /// list.insert(0, 0).unwrap();
+    /// list.insert(0, 0).unwrap();
+    /// assert_eq!(doc.get_cursor_pos(&cursor).unwrap().current.pos, 3);
+    /// list.insert(4, 0).unwrap();
+    /// assert_eq!(doc.get_cursor_pos(&cursor).unwrap().current.pos, 3);
+    /// ```
+    pub fn get_cursor(&self, pos: usize, side: Side) -> Option<Cursor> {
+        self.handler.get_cursor(pos, side)
     }
 
-    /// Get the absolute position of the given cursor.
+    /// Get the authorship of the elements in the given range.
+    ///
+    /// The adjacent elements created by the same change are merged into one [Attribution].
     ///
     /// # Example
     ///
     /// ```
-    /// # use loro::{LoroDoc, ToJson};
+    /// use loro::LoroDoc;
+    ///
     /// let doc = LoroDoc::new();
-    /// let text = &doc.get_text("text");
-    /// text.insert(0, "01234").unwrap();
-    /// let pos = text.get_cursor(5, Default::default()).unwrap();
-    /// assert_eq!(doc.get_cursor_pos(&pos).unwrap().current.pos, 5);
-    /// text.insert(0, "01234").unwrap();
-    /// assert_eq!(doc.get_cursor_pos(&pos).unwrap().current.pos, 10);
-    /// text.delete(0, 10).unwrap();
-    /// assert_eq!(doc.get_cursor_pos(&pos).unwrap().current.pos, 0);
-    /// text.insert(0, "01234").unwrap();
-    /// assert_eq!(doc.get_cursor_pos(&pos).unwrap().current.pos, 5);
+    /// doc.set_peer_id(1).unwrap();
+    /// let list = doc.get_list("list");
+    /// list.push(0).unwrap();
+    /// list.push(1).unwrap();
+    /// doc.commit();
+    /// let attribution = list.get_attribution(0..2).unwrap();
+    /// assert_eq!(attribution.len(), 1);
+    /// assert_eq!(attribution[0].peer, 1);
+    /// assert_eq!(attribution[0].range, 0..2);
     /// ```
-    pub fn get_cursor_pos(
-        &self,
-        cursor: &Cursor,
-    ) -> Result<PosQueryResult, CannotFindRelativePosition> {
-        self.doc.query_pos(cursor)
+    pub fn get_attribution(&self, range: Range<usize>) -> LoroResult<Vec<Attribution>> {
+        self.handler.get_attribution(range)
     }
 }
 
-/// It's used to prevent the user from implementing the trait directly.
-#[allow(private_bounds)]
-trait SealedTrait {}
-#[allow(private_bounds)]
-pub trait ContainerTrait: SealedTrait {
-    type Handler: HandlerTrait;
-    fn to_container(&self) -> Container;
-    fn to_handler(&self) -> Self::Handler;
-    fn from_handler(handler: Self::Handler) -> Self;
-    fn try_from_container(container: Container) -> Option<Self>
-    where
-        Self: Sized;
-    fn is_attached(&self) -> bool;
-    /// If a detached container is attached, this method will return its corresponding attached handler.
-    fn get_attached(&self) -> Option<Self>
-    where
-        Self: Sized;
+impl Default for LoroList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-/// LoroList container. It's used to model array.
+/// LoroMovableList container. It's a list that supports moving and setting elements.
 ///
-/// It can have sub containers.
+/// Concurrent moves of the same element are resolved by LWW, so the element
+/// always ends up in exactly one place. Concurrent sets are resolved by LWW too.
 ///
 /// ```
-/// # use loro::{LoroDoc, ContainerType, ToJson};
+/// # use loro::{LoroDoc, ToJson};
 /// # use serde_json::json;
 /// let doc = LoroDoc::new();
-/// let list = doc.get_list("list");
-/// list.insert(0, 123).unwrap();
-/// list.insert(1, "h").unwrap();
+/// let list = doc.get_movable_list("list");
+/// list.insert(0, "a").unwrap();
+/// list.insert(1, "b").unwrap();
+/// list.insert(2, "c").unwrap();
+/// list.mov(0, 2).unwrap();
+/// list.set(0, "B").unwrap();
 /// assert_eq!(
 ///     doc.get_deep_value().to_json_value(),
 ///     json!({
-///         "list": [123, "h"]
+///         "list": ["B", "c", "a"]
 ///     })
 /// );
 /// ```
 #[derive(Clone, Debug)]
-pub struct LoroList {
-    handler: InnerListHandler,
+pub struct LoroMovableList {
+    handler: InnerMovableListHandler,
 }
 
-impl SealedTrait for LoroList {}
-impl ContainerTrait for LoroList {
-    type Handler = InnerListHandler;
+impl SealedTrait for LoroMovableList {}
+impl ContainerTrait for LoroMovableList {
+    type Handler = InnerMovableListHandler;
     fn to_container(&self) -> Container {
-        Container::List(self.clone())
+        Container::MovableList(self.clone())
     }
 
     fn to_handler(&self) -> Self::Handler {
@@ -466,18 +1307,18 @@ impl ContainerTrait for LoroList {
     }
 
     fn try_from_container(container: Container) -> Option<Self> {
-        container.into_list().ok()
+        container.into_movable_list().ok()
     }
 }
 
-impl LoroList {
+impl LoroMovableList {
     /// Create a new container that is detached from the document.
     ///
     /// The edits on a detached container will not be persisted.
     /// To attach the container to the document, please insert it into an attached container.
     pub fn new() -> Self {
         Self {
-            handler: InnerListHandler::new_detached(),
+            handler: InnerMovableListHandler::new_detached(),
         }
     }
 
@@ -498,6 +1339,20 @@ impl LoroList {
         self.handler.delete(pos, len)
     }
 
+    /// Move the element at `from` to `to`.
+    ///
+    /// After the move, the element will be at the index `to`.
+    #[inline]
+    pub fn mov(&self, from: usize, to: usize) -> LoroResult<()> {
+        self.handler.mov(from, to)
+    }
+
+    /// Set the value of the element at `pos`.
+    #[inline]
+    pub fn set(&self, pos: usize, v: impl Into<LoroValue>) -> LoroResult<()> {
+        self.handler.set(pos, v)
+    }
+
     #[inline]
     pub fn get(&self, index: usize) -> Option<Either<LoroValue, Container>> {
         match self.handler.get_(index) {
@@ -558,19 +1413,6 @@ impl LoroList {
     }
 
     /// Insert a container with the given type at the given index.
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// # use loro::{LoroDoc, ContainerType, LoroText, ToJson};
-    /// # use serde_json::json;
-    /// let doc = LoroDoc::new();
-    /// let list = doc.get_list("m");
-    /// let text = list.insert_container(0, LoroText::new()).unwrap();
-    /// text.insert(0, "12");
-    /// text.insert(0, "0");
-    /// assert_eq!(doc.get_deep_value().to_json_value(), json!({"m": ["012"]}));
-    /// ```
     #[inline]
     pub fn insert_container<C: ContainerTrait>(&self, pos: usize, child: C) -> LoroResult<C> {
         Ok(C::from_handler(
@@ -580,17 +1422,7 @@ impl LoroList {
 
     /// Get the cursor at the given position.
     ///
-    /// Using "index" to denote cursor positions can be unstable, as positions may
-    /// shift with document edits. To reliably represent a position or range within
-    /// a document, it is more effective to leverage the unique ID of each item/character
-    /// in a List CRDT or Text CRDT.
-    ///
-    /// Loro optimizes State metadata by not storing the IDs of deleted elements. This
-    /// approach complicates tracking cursors since they rely on these IDs. The solution
-    /// recalculates position by replaying relevant history to update stable positions
-    /// accurately. To minimize the performance impact of history replay, the system
-    /// updates cursor info to reference only the IDs of currently present elements,
-    /// thereby reducing the need for replay.
+    /// The cursor is bound to the element, so it follows the element when it's moved.
     ///
     /// # Example
     ///
@@ -599,24 +1431,19 @@ impl LoroList {
     /// use loro_internal::cursor::Side;
     ///
     /// let doc = LoroDoc::new();
-    /// let list = doc.get_list("list");
+    /// let list = doc.get_movable_list("list");
     /// list.insert(0, 0).unwrap();
+    /// list.insert(1, 1).unwrap();
     /// let cursor = list.get_cursor(0, Side::Middle).unwrap();
-    /// assert_eq!(doc.get_cursor_pos(&cursor).unwrap().current.pos, 0);
-    /// list.insert(0, 0).unwrap();
+    /// list.mov(0, 1).unwrap();
     /// assert_eq!(doc.get_cursor_pos(&cursor).unwrap().current.pos, 1);
-    /// list.insert(0, 0).unwrap();
-    /// list.insert(0, 0).unwrap();
-    /// assert_eq!(doc.get_cursor_pos(&cursor).unwrap().current.pos, 3);
-    /// list.insert(4, 0).unwrap();
-    /// assert_eq!(doc.get_cursor_pos(&cursor).unwrap().current.pos, 3);
     /// ```
     pub fn get_cursor(&self, pos: usize, side: Side) -> Option<Cursor> {
         self.handler.get_cursor(pos, side)
     }
 }
 
-impl Default for LoroList {
+impl Default for LoroMovableList {
     fn default() -> Self {
         Self::new()
     }
@@ -981,6 +1808,35 @@ impl LoroText {
     pub fn get_cursor(&self, pos: usize, side: Side) -> Option<Cursor> {
         self.handler.get_cursor(pos, side)
     }
+
+    /// Get the authorship of the text in the given range.
+    ///
+    /// The range is in Unicode index. The adjacent chars created by the same change
+    /// are merged into one [Attribution].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use loro::LoroDoc;
+    ///
+    /// let doc = LoroDoc::new();
+    /// doc.set_peer_id(1).unwrap();
+    /// let text = doc.get_text("text");
+    /// text.insert(0, "Hello").unwrap();
+    /// doc.commit();
+    /// doc.set_peer_id(2).unwrap();
+    /// text.insert(5, " World").unwrap();
+    /// doc.commit();
+    /// let attribution = text.get_attribution(0..11).unwrap();
+    /// assert_eq!(attribution.len(), 2);
+    /// assert_eq!(attribution[0].peer, 1);
+    /// assert_eq!(attribution[0].range, 0..5);
+    /// assert_eq!(attribution[1].peer, 2);
+    /// assert_eq!(attribution[1].range, 5..11);
+    /// ```
+    pub fn get_attribution(&self, range: Range<usize>) -> LoroResult<Vec<Attribution>> {
+        self.handler.get_attribution(range)
+    }
 }
 
 impl Default for LoroText {
@@ -1066,6 +1922,30 @@ impl LoroTree {
         self.handler.create(parent)
     }
 
+    /// Create a new tree node at the given index among the children of `parent`,
+    /// and return the [`TreeID`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use loro::LoroDoc;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let tree = doc.get_tree("tree");
+    /// let root = tree.create(None).unwrap();
+    /// let b = tree.create(root).unwrap();
+    /// // insert `a` before `b`
+    /// let a = tree.create_at(root, 0).unwrap();
+    /// assert_eq!(tree.children(root), vec![a, b]);
+    /// ```
+    pub fn create_at<T: Into<Option<TreeID>>>(
+        &self,
+        parent: T,
+        index: usize,
+    ) -> LoroResult<TreeID> {
+        self.handler.create_at(parent, index)
+    }
+
     /// Move the `target` node to be a child of the `parent` node.
     ///
     /// If the `parent` is `None`, the `target` node will be a root.
@@ -1086,6 +1966,42 @@ impl LoroTree {
         self.handler.mov(target, parent.into())
     }
 
+    /// Move the `target` node to be the `index`-th child of the `parent` node.
+    ///
+    /// The index is counted without the `target` node itself.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use loro::LoroDoc;
+    ///
+    /// let doc = LoroDoc::new();
+    /// let tree = doc.get_tree("tree");
+    /// let root = tree.create(None).unwrap();
+    /// let a = tree.create(root).unwrap();
+    /// let b = tree.create(root).unwrap();
+    /// tree.mov_to(b, root, 0).unwrap();
+    /// assert_eq!(tree.children(root), vec![b, a]);
+    /// ```
+    pub fn mov_to<T: Into<Option<TreeID>>>(
+        &self,
+        target: TreeID,
+        parent: T,
+        index: usize,
+    ) -> LoroResult<()> {
+        self.handler.mov_to(target, parent, index)
+    }
+
+    /// Move the `target` node to be the sibling right before the `before` node.
+    pub fn mov_before(&self, target: TreeID, before: TreeID) -> LoroResult<()> {
+        self.handler.mov_before(target, before)
+    }
+
+    /// Move the `target` node to be the sibling right after the `after` node.
+    pub fn mov_after(&self, target: TreeID, after: TreeID) -> LoroResult<()> {
+        self.handler.mov_after(target, after)
+    }
+
     /// Delete a tree node.
     ///
     /// Note: If the deleted node has children, the children do not appear in the state
@@ -1131,6 +2047,13 @@ impl LoroTree {
         self.handler.get_node_parent(target)
     }
 
+    /// Return the children of the `parent` node in order.
+    ///
+    /// If the `parent` is `None`, return the roots.
+    pub fn children<T: Into<Option<TreeID>>>(&self, parent: T) -> Vec<TreeID> {
+        self.handler.children(parent)
+    }
+
     /// Return whether target node exists.
     pub fn contains(&self, target: TreeID) -> bool {
         self.handler.contains(target)
@@ -1171,6 +2094,102 @@ impl Default for LoroTree {
     }
 }
 
+/// LoroCounter container.
+///
+/// Concurrent increments and decrements are summed up.
+///
+/// ```
+/// # use loro::LoroDoc;
+/// let doc_a = LoroDoc::new();
+/// let doc_b = LoroDoc::new();
+/// doc_a.get_counter("counter").increment(1).unwrap();
+/// doc_b.get_counter("counter").increment(2.5).unwrap();
+/// doc_a.import(&doc_b.export_from(&Default::default())).unwrap();
+/// assert_eq!(doc_a.get_counter("counter").get_value(), 3.5);
+/// ```
+#[derive(Clone, Debug)]
+pub struct LoroCounter {
+    handler: InnerCounterHandler,
+}
+
+impl SealedTrait for LoroCounter {}
+impl ContainerTrait for LoroCounter {
+    type Handler = InnerCounterHandler;
+    fn to_container(&self) -> Container {
+        Container::Counter(self.clone())
+    }
+
+    fn to_handler(&self) -> Self::Handler {
+        self.handler.clone()
+    }
+
+    fn from_handler(handler: Self::Handler) -> Self {
+        Self { handler }
+    }
+
+    fn is_attached(&self) -> bool {
+        self.handler.is_attached()
+    }
+
+    fn get_attached(&self) -> Option<Self> {
+        self.handler.get_attached().map(Self::from_handler)
+    }
+
+    fn try_from_container(container: Container) -> Option<Self> {
+        container.into_counter().ok()
+    }
+}
+
+impl LoroCounter {
+    /// Create a new container that is detached from the document.
+    ///
+    /// The edits on a detached container will not be persisted.
+    /// To attach the container to the document, please insert it into an attached container.
+    pub fn new() -> Self {
+        Self {
+            handler: InnerCounterHandler::new_detached(),
+        }
+    }
+
+    /// Whether the container is attached to a document
+    pub fn is_attached(&self) -> bool {
+        self.handler.is_attached()
+    }
+
+    /// Increment the counter by the given value. It accepts both integers and floats.
+    ///
+    /// NaN and infinite values are rejected.
+    #[inline]
+    pub fn increment(&self, value: impl Into<LoroValue>) -> LoroResult<()> {
+        self.handler.increment(value)
+    }
+
+    /// Decrement the counter by the given value. It accepts both integers and floats.
+    ///
+    /// NaN and infinite values are rejected.
+    #[inline]
+    pub fn decrement(&self, value: impl Into<LoroValue>) -> LoroResult<()> {
+        self.handler.decrement(value)
+    }
+
+    /// Get the current value of the counter.
+    #[inline]
+    pub fn get_value(&self) -> f64 {
+        *self.handler.get_value().as_double().unwrap()
+    }
+
+    #[inline]
+    pub fn id(&self) -> ContainerID {
+        self.handler.id()
+    }
+}
+
+impl Default for LoroCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 use enum_as_inner::EnumAsInner;
 
 /// All the CRDT containers supported by loro.
@@ -1180,6 +2199,8 @@ pub enum Container {
     Map(LoroMap),
     Text(LoroText),
     Tree(LoroTree),
+    MovableList(LoroMovableList),
+    Counter(LoroCounter),
 }
 
 impl SealedTrait for Container {}
@@ -1196,6 +2217,8 @@ impl ContainerTrait for Container {
             Container::Map(x) => Self::Handler::Map(x.to_handler()),
             Container::Text(x) => Self::Handler::Text(x.to_handler()),
             Container::Tree(x) => Self::Handler::Tree(x.to_handler()),
+            Container::MovableList(x) => Self::Handler::MovableList(x.to_handler()),
+            Container::Counter(x) => Self::Handler::Counter(x.to_handler()),
         }
     }
 
@@ -1205,6 +2228,8 @@ impl ContainerTrait for Container {
             InnerHandler::Map(x) => Container::Map(LoroMap { handler: x }),
             InnerHandler::List(x) => Container::List(LoroList { handler: x }),
             InnerHandler::Tree(x) => Container::Tree(LoroTree { handler: x }),
+            InnerHandler::MovableList(x) => Container::MovableList(LoroMovableList { handler: x }),
+            InnerHandler::Counter(x) => Container::Counter(LoroCounter { handler: x }),
         }
     }
 
@@ -1214,6 +2239,8 @@ impl ContainerTrait for Container {
             Container::Map(x) => x.is_attached(),
             Container::Text(x) => x.is_attached(),
             Container::Tree(x) => x.is_attached(),
+            Container::MovableList(x) => x.is_attached(),
+            Container::Counter(x) => x.is_attached(),
         }
     }
 
@@ -1223,6 +2250,8 @@ impl ContainerTrait for Container {
             Container::Map(x) => x.get_attached().map(Container::Map),
             Container::Text(x) => x.get_attached().map(Container::Text),
             Container::Tree(x) => x.get_attached().map(Container::Tree),
+            Container::MovableList(x) => x.get_attached().map(Container::MovableList),
+            Container::Counter(x) => x.get_attached().map(Container::Counter),
         }
     }
 
@@ -1246,6 +2275,8 @@ impl Container {
             ContainerType::Map => Container::Map(LoroMap::new()),
             ContainerType::Text => Container::Text(LoroText::new()),
             ContainerType::Tree => Container::Tree(LoroTree::new()),
+            ContainerType::MovableList => Container::MovableList(LoroMovableList::new()),
+            ContainerType::Counter => Container::Counter(LoroCounter::new()),
         }
     }
 
@@ -1255,6 +2286,8 @@ impl Container {
             Container::Map(_) => ContainerType::Map,
             Container::Text(_) => ContainerType::Text,
             Container::Tree(_) => ContainerType::Tree,
+            Container::MovableList(_) => ContainerType::MovableList,
+            Container::Counter(_) => ContainerType::Counter,
         }
     }
 
@@ -1264,6 +2297,8 @@ impl Container {
             Container::Map(x) => x.id(),
             Container::Text(x) => x.id(),
             Container::Tree(x) => x.id(),
+            Container::MovableList(x) => x.id(),
+            Container::Counter(x) => x.id(),
         }
     }
 }
@@ -1275,6 +2310,8 @@ impl From<InnerHandler> for Container {
             InnerHandler::Map(x) => Container::Map(LoroMap { handler: x }),
             InnerHandler::List(x) => Container::List(LoroList { handler: x }),
             InnerHandler::Tree(x) => Container::Tree(LoroTree { handler: x }),
+            InnerHandler::MovableList(x) => Container::MovableList(LoroMovableList { handler: x }),
+            InnerHandler::Counter(x) => Container::Counter(LoroCounter { handler: x }),
         }
     }
 }
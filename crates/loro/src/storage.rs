@@ -0,0 +1,301 @@
+//! An append-only file storage of a doc.
+//!
+//! Instead of rewriting the whole snapshot on every save, each update is appended to a
+//! log file as a record. A record is framed by the length and the md5 checksum of its
+//! payload, followed by a checksum of the two, so a corrupted length is never mistaken
+//! for a torn record:
+//!
+//! ```text
+//! | payload length: u32 (LE) | md5 checksum: [u8; 16] | header checksum: [u8; 4] | payload |
+//! ```
+//!
+//! The header checksum is the first 4 bytes of the md5 checksum of the length and the
+//! payload checksum.
+//!
+//! The payload is a blob that can be imported by [LoroDoc::import]. When the updates
+//! appended since the last compaction exceed the threshold, the log is replaced by a
+//! single snapshot record. If the app crashes while writing, the final record may be
+//! torn. It's ignored and removed when the storage is opened again. A corrupted record
+//! followed by more data cannot be caused by a crash, so opening such a log fails.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::{ImportStatus, LoroDoc, LoroError, SubID};
+
+/// The updates appended since the last compaction that trigger a compaction, in bytes
+pub const DEFAULT_COMPACTION_THRESHOLD: u64 = 1 << 20;
+
+const RECORD_HEADER_SIZE: usize = 4 + 16 + 4;
+
+/// A [LoroDoc] that is persisted in an append-only log file.
+///
+/// The local commits of the doc are appended to the log automatically. The updates
+/// imported from other peers should be imported by [FileStorage::import], so that they
+/// are appended as well.
+///
+/// # Example
+///
+/// ```
+/// # use loro::storage::FileStorage;
+/// let path = std::env::temp_dir().join(format!("loro-doc-example-{}", std::process::id()));
+/// let storage = FileStorage::open(&path).unwrap();
+/// storage.doc().get_text("text").insert(0, "Hello").unwrap();
+/// storage.commit().unwrap();
+/// drop(storage);
+///
+/// let storage = FileStorage::open(&path).unwrap();
+/// assert_eq!(storage.doc().get_text("text").to_string(), "Hello");
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct FileStorage {
+    doc: LoroDoc,
+    log: Arc<Mutex<LogFile>>,
+    sub: SubID,
+    compaction_threshold: u64,
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    /// The bytes of the updates appended since the last compaction
+    appended: u64,
+    /// The error of appending a local update. It's returned by the next operation of
+    /// the storage, because the update is appended in the commit callback.
+    error: Option<io::Error>,
+}
+
+impl std::fmt::Debug for FileStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileStorage")
+            .field("path", &self.log.lock().unwrap().path)
+            .field("compaction_threshold", &self.compaction_threshold)
+            .finish()
+    }
+}
+
+impl FileStorage {
+    /// Open the log file at `path` and recover the doc from it.
+    ///
+    /// The file is created if it doesn't exist. A torn record at the end of the file is
+    /// ignored and removed. It returns an [io::ErrorKind::InvalidData] error if a record
+    /// before the end is corrupted.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        let doc = LoroDoc::new();
+        let mut valid_len = 0;
+        // the snapshot written by the last compaction is not counted as appended updates
+        let mut snapshot_len = 0;
+        while valid_len < bytes.len() {
+            let (payload, record_len) = match read_record(&bytes[valid_len..]) {
+                Record::Valid(payload, record_len) => (payload, record_len),
+                Record::Torn => break,
+                Record::Corrupted => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("The record at byte {} of the log is corrupted", valid_len),
+                    ));
+                }
+            };
+            if valid_len == 0
+                && LoroDoc::decode_import_blob_meta(payload).is_ok_and(|meta| meta.is_snapshot)
+            {
+                snapshot_len = record_len;
+            }
+
+            doc.import(payload).map_err(to_io_error)?;
+            valid_len += record_len;
+        }
+
+        if valid_len < bytes.len() {
+            file.set_len(valid_len as u64)?;
+            file.sync_all()?;
+        }
+
+        let log = Arc::new(Mutex::new(LogFile {
+            path,
+            file,
+            appended: (valid_len - snapshot_len) as u64,
+            error: None,
+        }));
+        let log_clone = log.clone();
+        let sub = doc.subscribe_local_update(Arc::new(move |update| {
+            let mut log = log_clone.lock().unwrap();
+            if let Err(e) = log.append(update) {
+                log.error.get_or_insert(e);
+            }
+        }));
+
+        Ok(Self {
+            doc,
+            log,
+            sub,
+            compaction_threshold: DEFAULT_COMPACTION_THRESHOLD,
+        })
+    }
+
+    pub fn doc(&self) -> &LoroDoc {
+        &self.doc
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.log.lock().unwrap().path.clone()
+    }
+
+    /// Set how many bytes of updates can be appended before the log is compacted.
+    pub fn set_compaction_threshold(&mut self, bytes: u64) {
+        self.compaction_threshold = bytes;
+    }
+
+    /// Commit the pending local ops of the doc, which are appended to the log.
+    ///
+    /// The log is compacted if it exceeds the threshold.
+    pub fn commit(&self) -> io::Result<()> {
+        self.doc.commit();
+        self.take_error()?;
+        self.compact_if_needed()
+    }
+
+    /// Import the updates or the snapshot from other peers, and append it to the log.
+    ///
+    /// The log is compacted if it exceeds the threshold.
+    pub fn import(&self, bytes: &[u8]) -> io::Result<ImportStatus> {
+        let status = self.doc.import(bytes).map_err(to_io_error)?;
+        self.take_error()?;
+        self.log.lock().unwrap().append(bytes)?;
+        self.compact_if_needed()?;
+        Ok(status)
+    }
+
+    /// Replace the log by a snapshot of the doc.
+    ///
+    /// The pending changes, whose dependencies are missing, are not kept in the snapshot.
+    pub fn compact(&self) -> io::Result<()> {
+        // the pending local ops are committed and appended first
+        self.doc.commit();
+        self.take_error()?;
+        let snapshot = self.doc.export_snapshot();
+        self.log.lock().unwrap().replace_with(&snapshot)
+    }
+
+    fn compact_if_needed(&self) -> io::Result<()> {
+        if self.log.lock().unwrap().appended > self.compaction_threshold {
+            self.compact()?;
+        }
+
+        Ok(())
+    }
+
+    fn take_error(&self) -> io::Result<()> {
+        match self.log.lock().unwrap().error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for FileStorage {
+    fn drop(&mut self) {
+        // the pending local ops are appended before the subscription is removed
+        self.doc.commit();
+        self.doc.unsubscribe(self.sub);
+    }
+}
+
+impl LogFile {
+    fn append(&mut self, payload: &[u8]) -> io::Result<()> {
+        self.file.write_all(&encode_record(payload))?;
+        self.file.sync_data()?;
+        self.appended += (RECORD_HEADER_SIZE + payload.len()) as u64;
+        Ok(())
+    }
+
+    /// Atomically replace the log file by a new one that only has the record of `payload`.
+    fn replace_with(&mut self, payload: &[u8]) -> io::Result<()> {
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        let mut tmp = File::create(&tmp_path)?;
+        tmp.write_all(&encode_record(payload))?;
+        tmp.sync_all()?;
+        drop(tmp);
+        std::fs::rename(&tmp_path, &self.path)?;
+
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.appended = 0;
+        Ok(())
+    }
+}
+
+fn encode_record(payload: &[u8]) -> Vec<u8> {
+    let mut ans = Vec::with_capacity(RECORD_HEADER_SIZE + payload.len());
+    ans.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    ans.extend_from_slice(&md5::compute(payload).0);
+    ans.extend_from_slice(&header_checksum(&ans));
+    ans.extend_from_slice(payload);
+    ans
+}
+
+/// The checksum of the payload length and the payload checksum
+fn header_checksum(header: &[u8]) -> [u8; 4] {
+    md5::compute(&header[..RECORD_HEADER_SIZE - 4]).0[..4]
+        .try_into()
+        .unwrap()
+}
+
+enum Record<'a> {
+    /// The payload and the length of the record
+    Valid(&'a [u8], usize),
+    /// The final record is incomplete or its checksum doesn't match
+    Torn,
+    /// The header checksum doesn't match, or the checksum of a record that is followed
+    /// by more data doesn't match
+    Corrupted,
+}
+
+/// Read the record at the start of `bytes`, which are the rest of the log.
+fn read_record(bytes: &[u8]) -> Record<'_> {
+    if bytes.len() < RECORD_HEADER_SIZE {
+        return Record::Torn;
+    }
+
+    if header_checksum(bytes) != bytes[RECORD_HEADER_SIZE - 4..RECORD_HEADER_SIZE] {
+        // the length may be wrong, so the data after it may be valid records
+        return Record::Corrupted;
+    }
+
+    let len = u32::from_le_bytes(bytes[..4].try_into().unwrap()) as usize;
+    let Some(payload) = RECORD_HEADER_SIZE
+        .checked_add(len)
+        .and_then(|end| bytes.get(RECORD_HEADER_SIZE..end))
+    else {
+        return Record::Torn;
+    };
+
+    let end = RECORD_HEADER_SIZE + len;
+    if md5::compute(payload).0 != bytes[4..RECORD_HEADER_SIZE - 4] {
+        return if end == bytes.len() {
+            Record::Torn
+        } else {
+            Record::Corrupted
+        };
+    }
+
+    Record::Valid(payload, end)
+}
+
+fn to_io_error(e: LoroError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
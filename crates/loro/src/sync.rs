@@ -0,0 +1,174 @@
+//! A transport-agnostic sync protocol between two peers.
+//!
+//! Each side of a connection owns a [SyncSession]. The peers exchange their version
+//! vectors, and each of them answers with the updates that the other one lacks. The
+//! messages returned by the session should be sent to the remote peer in order, and the
+//! messages from the remote peer are passed to [SyncSession::receive].
+
+use crate::{LoroDoc, LoroError, LoroResult, VersionVector};
+
+/// How many times a session asks again in a row when the received updates cannot be
+/// applied because of the missing dependencies. After that the session is stalled, see
+/// [SyncSession::receive].
+pub const MAX_RETRIES: usize = 3;
+
+const REQUEST_TAG: u8 = 0;
+const UPDATES_TAG: u8 = 1;
+
+/// A message of the sync protocol.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncMessage {
+    /// The version of the sender. The receiver replies with the updates that the
+    /// sender lacks.
+    Request(VersionVector),
+    /// The updates exported by [LoroDoc::export_from]
+    Updates(Vec<u8>),
+}
+
+impl SyncMessage {
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            SyncMessage::Request(vv) => {
+                let mut ans = vec![REQUEST_TAG];
+                ans.extend(vv.encode());
+                ans
+            }
+            SyncMessage::Updates(updates) => {
+                let mut ans = Vec::with_capacity(updates.len() + 1);
+                ans.push(UPDATES_TAG);
+                ans.extend_from_slice(updates);
+                ans
+            }
+        }
+    }
+
+    pub fn decode(bytes: &[u8]) -> LoroResult<Self> {
+        match bytes.split_first() {
+            Some((&REQUEST_TAG, vv)) => Ok(SyncMessage::Request(VersionVector::decode(vv)?)),
+            Some((&UPDATES_TAG, updates)) => Ok(SyncMessage::Updates(updates.to_vec())),
+            _ => Err(LoroError::DecodeError("Invalid sync message".into())),
+        }
+    }
+}
+
+/// The sync state of a doc with one remote peer.
+///
+/// # Example
+///
+/// ```
+/// # use loro::{sync::SyncSession, LoroDoc};
+/// let a = LoroDoc::new();
+/// let b = LoroDoc::new();
+/// a.get_text("text").insert(0, "Hello").unwrap();
+/// b.get_text("text").insert(0, "Hi").unwrap();
+/// a.commit();
+/// b.commit();
+///
+/// let mut session_a = SyncSession::new();
+/// let mut session_b = SyncSession::new();
+/// let mut to_b = vec![session_a.start(&a)];
+/// while !to_b.is_empty() {
+///     let mut to_a = Vec::new();
+///     for msg in to_b {
+///         to_a.extend(session_b.receive(&b, msg).unwrap());
+///     }
+///     to_b = Vec::new();
+///     for msg in to_a {
+///         to_b.extend(session_a.receive(&a, msg).unwrap());
+///     }
+/// }
+///
+/// assert_eq!(a.get_deep_value(), b.get_deep_value());
+/// assert!(session_a.is_synced(&a) && session_b.is_synced(&b));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SyncSession {
+    /// The version that the remote peer is known to have
+    remote_vv: VersionVector,
+    retries: usize,
+}
+
+impl SyncSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The first message of the handshake. It asks for the updates that the doc lacks.
+    pub fn start(&self, doc: &LoroDoc) -> SyncMessage {
+        SyncMessage::Request(doc.oplog_vv())
+    }
+
+    /// Handle a message from the remote peer, and return the messages to reply.
+    ///
+    /// If the received updates still cannot be applied after [MAX_RETRIES] requests in a
+    /// row, the session is stalled and it returns [LoroError::SyncStalled] with the missing
+    /// changes. The updates are imported and kept pending anyway, and the session recovers
+    /// once the updates that can be applied arrive.
+    pub fn receive(&mut self, doc: &LoroDoc, msg: SyncMessage) -> LoroResult<Vec<SyncMessage>> {
+        let mut ans = Vec::new();
+        match msg {
+            SyncMessage::Request(vv) => {
+                // the updates in flight are sent again, because they may have been lost
+                self.remote_vv = vv.clone();
+                let local_vv = doc.oplog_vv();
+                if !vv.includes_vv(&local_vv) {
+                    ans.push(SyncMessage::Updates(doc.export_from(&vv)));
+                    self.remote_vv.merge(&local_vv);
+                }
+
+                if !local_vv.includes_vv(&vv) {
+                    ans.push(SyncMessage::Request(local_vv));
+                }
+            }
+            SyncMessage::Updates(updates) => {
+                let status = doc.import(&updates)?;
+                for span in status.applied.iter().chain(status.pending.iter()) {
+                    self.remote_vv.extend_to_include(*span);
+                }
+
+                // only the changes of this message are considered, the pending changes
+                // imported from elsewhere are not this session's business
+                if status.pending.is_empty() {
+                    self.retries = 0;
+                } else if self.retries < MAX_RETRIES {
+                    // the remote peer sent the updates based on a stale version of this doc
+                    self.retries += 1;
+                    ans.push(SyncMessage::Request(doc.oplog_vv()));
+                } else {
+                    return Err(LoroError::SyncStalled(status.missing_deps));
+                }
+            }
+        }
+
+        Ok(ans)
+    }
+
+    /// Get the updates of the doc that the remote peer doesn't have yet.
+    ///
+    /// It should be called after the local changes are committed.
+    pub fn local_updates(&mut self, doc: &LoroDoc) -> Option<SyncMessage> {
+        let local_vv = doc.oplog_vv();
+        if self.remote_vv.includes_vv(&local_vv) {
+            return None;
+        }
+
+        let updates = doc.export_from(&self.remote_vv);
+        self.remote_vv.merge(&local_vv);
+        Some(SyncMessage::Updates(updates))
+    }
+
+    /// The version that the remote peer is known to have.
+    pub fn remote_vv(&self) -> &VersionVector {
+        &self.remote_vv
+    }
+
+    /// Whether the doc and the remote peer have the same changes.
+    ///
+    /// The changes received from the remote peer that are still pending in the doc are
+    /// included by [SyncSession::remote_vv] but not by the doc, so the session is not
+    /// synced until they are applied. The pending changes imported from elsewhere don't
+    /// matter.
+    pub fn is_synced(&self, doc: &LoroDoc) -> bool {
+        self.remote_vv == doc.oplog_vv()
+    }
+}
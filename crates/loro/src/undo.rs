@@ -0,0 +1,97 @@
+use loro_internal::undo::UndoManager as InnerUndoManager;
+pub use loro_internal::undo::{CursorWithPos, OnPop, OnPush, UndoItemMeta, UndoOrRedo};
+
+use crate::{LoroDoc, LoroResult};
+
+/// The undo manager of a [LoroDoc].
+///
+/// It only records the local changes. Each undo generates the inverse ops against the
+/// latest state, which are transformed over the concurrent remote changes, so undoing
+/// never reverts the changes of other peers.
+///
+/// # Example
+///
+/// ```
+/// # use loro::{LoroDoc, UndoManager};
+/// let doc = LoroDoc::new();
+/// let mut undo = UndoManager::new(&doc);
+/// let text = doc.get_text("text");
+/// text.insert(0, "hello").unwrap();
+/// doc.commit();
+/// text.insert(5, " world").unwrap();
+/// doc.commit();
+/// assert_eq!(text.to_string(), "hello world");
+/// undo.undo().unwrap();
+/// assert_eq!(text.to_string(), "hello");
+/// undo.redo().unwrap();
+/// assert_eq!(text.to_string(), "hello world");
+/// ```
+#[derive(Debug)]
+pub struct UndoManager<'a>(InnerUndoManager<'a>);
+
+impl<'a> UndoManager<'a> {
+    /// Create a new undo manager that records the local changes of the doc
+    /// from now on.
+    pub fn new(doc: &'a LoroDoc) -> Self {
+        Self(InnerUndoManager::new(&doc.doc))
+    }
+
+    /// Undo the last local change. Returns `false` if there is nothing to undo.
+    pub fn undo(&mut self) -> LoroResult<bool> {
+        self.0.undo()
+    }
+
+    /// Redo the last undone change. Returns `false` if there is nothing to redo.
+    pub fn redo(&mut self) -> LoroResult<bool> {
+        self.0.redo()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.0.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.0.can_redo()
+    }
+
+    /// Make the next local change start a new undo step.
+    pub fn record_new_checkpoint(&mut self) {
+        self.0.record_new_checkpoint()
+    }
+
+    /// Set the interval in milliseconds. The consecutive local changes within the
+    /// interval are merged into one undo step. It's 0 by default.
+    pub fn set_merge_interval(&mut self, interval: i64) {
+        self.0.set_merge_interval(interval)
+    }
+
+    /// If enabled, the consecutive local changes with the same non-empty origin are
+    /// merged into one undo step regardless of the merge interval.
+    pub fn set_group_by_origin(&mut self, group: bool) {
+        self.0.set_group_by_origin(group)
+    }
+
+    /// Set the max number of undo and redo steps. The oldest steps are dropped when
+    /// it's exceeded.
+    pub fn set_max_undo_steps(&mut self, size: usize) {
+        self.0.set_max_undo_steps(size)
+    }
+
+    /// The local changes whose origins start with the prefix will not be recorded.
+    pub fn add_exclude_origin_prefix(&mut self, prefix: &str) {
+        self.0.add_exclude_origin_prefix(prefix)
+    }
+
+    /// Set the callback that is called when a new undo/redo step is pushed.
+    ///
+    /// The returned meta is passed to the `on_pop` callback. The cursors added to it
+    /// are resolved against the latest state when the step is popped.
+    pub fn set_on_push(&mut self, on_push: Option<OnPush>) {
+        self.0.set_on_push(on_push)
+    }
+
+    /// Set the callback that is called after an undo/redo step is applied.
+    pub fn set_on_pop(&mut self, on_pop: Option<OnPop>) {
+        self.0.set_on_pop(on_pop)
+    }
+}
@@ -1,7 +1,27 @@
-use std::{cmp::Ordering, sync::Arc};
+use std::{
+    cmp::Ordering,
+    collections::VecDeque,
+    io::Write,
+    sync::{Arc, Mutex},
+};
 
-use loro::{FrontiersNotIncluded, LoroDoc, LoroError, LoroList, LoroMap, LoroText, ToJson};
-use loro_internal::{handler::TextDelta, id::ID, vv, LoroResult};
+use loro::{
+    event::{Diff, DiffEvent, ListDiffItem},
+    json_patch::{apply_json_patch, JsonPatchAdapter, JsonPatchOp},
+    loro_value, merge_updates,
+    storage::FileStorage,
+    sync::{SyncMessage, SyncSession, MAX_RETRIES},
+    Awareness, AwarenessEvent, AwarenessEventTrigger, ChangeOpContent, ContainerID,
+    ContainerSchema, ContainerType, Frontiers, FrontiersNotIncluded, IdSpan, ImportValidator,
+    JsonSchema, LoroCounter, LoroDoc, LoroError, LoroList, LoroMap, LoroText, Schema, ToJson,
+    UndoItemMeta, UndoManager, UndoOrRedo, ValueSchema,
+};
+use loro_internal::{
+    cursor::{Cursor, Side},
+    handler::TextDelta,
+    id::ID,
+    vv, LoroResult, LoroValue,
+};
 use serde_json::json;
 
 #[test]
@@ -396,6 +416,49 @@ fn subscribe() {
     assert!(ran.load(std::sync::atomic::Ordering::Relaxed));
 }
 
+#[test]
+fn adjacent_edits_of_different_texts_in_one_txn() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let text = doc
+        .get_map("map")
+        .insert_container("text", LoroText::new())?;
+    let root_text = doc.get_text("text");
+    text.insert(0, "Hello")?;
+    root_text.insert(0, "Hello world")?;
+    doc.commit();
+
+    let deltas = Arc::new(Mutex::new(Vec::new()));
+    let deltas_clone = deltas.clone();
+    doc.subscribe_root(Arc::new(move |event| {
+        for e in event.events {
+            let delta = e.diff.as_text().unwrap().clone();
+            deltas_clone.lock().unwrap().push((e.target.clone(), delta));
+        }
+    }));
+    // the insert hints are adjacent, but they must not be merged
+    text.insert(5, " world")?;
+    root_text.insert(11, "!")?;
+    doc.commit();
+
+    let delta = |pos, s: &str| {
+        vec![
+            TextDelta::Retain {
+                retain: pos,
+                attributes: None,
+            },
+            TextDelta::Insert {
+                insert: s.into(),
+                attributes: None,
+            },
+        ]
+    };
+    let deltas = deltas.lock().unwrap();
+    assert_eq!(deltas.len(), 2);
+    assert!(deltas.contains(&(text.id(), delta(5, " world"))));
+    assert!(deltas.contains(&(root_text.id(), delta(11, "!"))));
+    Ok(())
+}
+
 #[test]
 fn prelim_support() -> LoroResult<()> {
     let map = LoroMap::new();
@@ -654,7 +717,6 @@ fn get_cursor_at_the_end() {
     assert_eq!(doc.get_cursor_pos(&pos).unwrap().current.pos, 5);
 }
 
-
 #[test]
 fn get_cursor_for_list() {
     let doc = LoroDoc::new();
@@ -715,3 +777,2833 @@ fn get_cursor_for_list() {
         assert_eq!(result.current.pos, 4);
     }
 }
+
+#[test]
+fn movable_list() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let list = doc.get_movable_list("list");
+    list.insert(0, 0)?;
+    list.insert(1, 1)?;
+    list.insert(2, 2)?;
+    list.mov(0, 2)?;
+    assert_eq!(list.get_value().to_json_value(), json!([1, 2, 0]));
+    list.mov(2, 1)?;
+    assert_eq!(list.get_value().to_json_value(), json!([1, 0, 2]));
+    list.set(0, "a")?;
+    assert_eq!(list.get_value().to_json_value(), json!(["a", 0, 2]));
+    list.delete(1, 1)?;
+    assert_eq!(list.get_value().to_json_value(), json!(["a", 2]));
+    list.push(3)?;
+    assert_eq!(list.pop()?, Some(3.into()));
+    assert!(list.mov(0, 2).is_err());
+    assert!(list.set(2, 0).is_err());
+
+    let doc_b = LoroDoc::new();
+    doc_b.import(&doc.export_from(&Default::default()))?;
+    assert_eq!(doc_b.get_deep_value(), doc.get_deep_value());
+    let doc_c = LoroDoc::new();
+    doc_c.import(&doc.export_snapshot())?;
+    assert_eq!(doc_c.get_deep_value(), doc.get_deep_value());
+    // the imported docs can keep editing
+    doc_c.get_movable_list("list").mov(1, 0)?;
+    doc_c.get_movable_list("list").insert(1, "b")?;
+    doc.import(&doc_c.export_from(&doc.oplog_vv()))?;
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({"list": [2, "b", "a"]})
+    );
+    Ok(())
+}
+
+#[test]
+fn movable_list_concurrent_moves() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let list = doc_a.get_movable_list("list");
+    for i in 0..5 {
+        list.push(i)?;
+    }
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    doc_b.import(&doc_a.export_snapshot())?;
+
+    doc_a.get_movable_list("list").mov(0, 4)?;
+    doc_b.get_movable_list("list").mov(0, 2)?;
+    doc_a.import(&doc_b.export_from(&Default::default()))?;
+    doc_b.import(&doc_a.export_from(&Default::default()))?;
+    assert_eq!(doc_a.get_deep_value(), doc_b.get_deep_value());
+    let value = doc_a.get_movable_list("list").get_value().to_json_value();
+    let arr = value.as_array().unwrap();
+    assert_eq!(arr.len(), 5);
+    assert_eq!(arr.iter().filter(|x| **x == json!(0)).count(), 1);
+    // peer 2 wins because it has the greater peer id
+    assert_eq!(value, json!([1, 2, 0, 3, 4]));
+    Ok(())
+}
+
+#[test]
+fn movable_list_concurrent_move_and_delete() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let list = doc_a.get_movable_list("list");
+    for i in 0..3 {
+        list.push(i)?;
+    }
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    doc_b.import(&doc_a.export_snapshot())?;
+
+    doc_a.get_movable_list("list").delete(0, 1)?;
+    doc_b.get_movable_list("list").mov(0, 2)?;
+    doc_b.get_movable_list("list").mov(2, 1)?;
+    // another peer moves 0 again after seeing the moves but not the delete
+    let doc_c = LoroDoc::new();
+    doc_c.set_peer_id(3)?;
+    doc_c.import(&doc_b.export_snapshot())?;
+    doc_c.get_movable_list("list").mov(1, 0)?;
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events_clone = events.clone();
+    let _sub = doc_b.subscribe(
+        &ContainerID::new_root("list", ContainerType::MovableList),
+        Arc::new(move |e| {
+            for e in e.events {
+                events_clone.lock().unwrap().push(format!("{:?}", e.diff));
+            }
+        }),
+    );
+    doc_a.import(&doc_b.export_from(&Default::default()))?;
+    doc_b.import(&doc_a.export_from(&Default::default()))?;
+    // the delete wins over the concurrent moves, so 0 is removed on both sides
+    assert_eq!(
+        doc_a.get_movable_list("list").get_value().to_json_value(),
+        json!([1, 2])
+    );
+    assert_eq!(
+        doc_b.get_movable_list("list").get_value().to_json_value(),
+        json!([1, 2])
+    );
+    // the mover sees 0 removed from where it moved it to
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec!["List([Retain { retain: 1 }, Delete { delete: 1 }])"]
+    );
+
+    for doc in [&doc_a, &doc_b, &doc_c] {
+        doc.import(&doc_a.export_from(&Default::default()))?;
+        doc.import(&doc_c.export_from(&Default::default()))?;
+        assert_eq!(
+            doc.get_movable_list("list").get_value().to_json_value(),
+            json!([1, 2])
+        );
+    }
+    assert_eq!(doc_a.get_deep_value(), {
+        let doc = LoroDoc::new();
+        doc.import(&doc_a.export_snapshot())?;
+        doc.get_deep_value()
+    });
+    Ok(())
+}
+
+#[test]
+fn movable_list_concurrent_set() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    doc_a.get_movable_list("list").push(0)?;
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    doc_b.import(&doc_a.export_snapshot())?;
+
+    doc_a.get_movable_list("list").set(0, "a")?;
+    doc_b.get_movable_list("list").set(0, "b")?;
+    doc_b.get_movable_list("list").mov(0, 0)?;
+    doc_a.import(&doc_b.export_from(&Default::default()))?;
+    doc_b.import(&doc_a.export_from(&Default::default()))?;
+    assert_eq!(doc_a.get_deep_value(), doc_b.get_deep_value());
+    assert_eq!(
+        doc_a.get_deep_value().to_json_value(),
+        json!({"list": ["b"]})
+    );
+    Ok(())
+}
+
+#[test]
+fn movable_list_checkout() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let list = doc.get_movable_list("list");
+    list.push(0)?;
+    list.push(1)?;
+    list.push(2)?;
+    doc.commit();
+    let f0 = doc.oplog_frontiers();
+    list.mov(2, 0)?;
+    list.set(1, "x")?;
+    doc.commit();
+    let f1 = doc.oplog_frontiers();
+    assert_eq!(list.get_value().to_json_value(), json!([2, "x", 1]));
+    doc.checkout(&f0)?;
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({"list": [0, 1, 2]})
+    );
+    doc.checkout(&f1)?;
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({"list": [2, "x", 1]})
+    );
+    Ok(())
+}
+
+#[test]
+fn movable_list_events() -> LoroResult<()> {
+    use loro::event::{Diff, ListDiffItem};
+    use std::sync::Mutex;
+
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    let mirror = Arc::new(Mutex::new(Vec::<loro::LoroValue>::new()));
+    let mirror_clone = mirror.clone();
+    doc_a.subscribe_root(Arc::new(move |event| {
+        let mut mirror = mirror_clone.lock().unwrap();
+        for e in event.events {
+            let Diff::List(items) = &e.diff else {
+                unreachable!()
+            };
+            let mut index = 0;
+            for item in items {
+                match item {
+                    ListDiffItem::Retain { retain } => index += retain,
+                    ListDiffItem::Delete { delete } => {
+                        mirror.drain(index..index + delete);
+                    }
+                    ListDiffItem::Insert { insert } => {
+                        for v in insert {
+                            mirror.insert(index, v.as_value().unwrap().clone());
+                            index += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }));
+
+    let check = |doc: &LoroDoc| {
+        doc.commit();
+        assert_eq!(
+            loro::LoroValue::List(Arc::new(mirror.lock().unwrap().clone())),
+            doc.get_movable_list("list").get_value()
+        );
+    };
+
+    let list_a = doc_a.get_movable_list("list");
+    for i in 0..6 {
+        list_a.push(i)?;
+    }
+    check(&doc_a);
+    list_a.mov(0, 5)?;
+    list_a.mov(3, 1)?;
+    list_a.set(2, "a")?;
+    list_a.delete(4, 1)?;
+    check(&doc_a);
+
+    doc_b.import(&doc_a.export_snapshot())?;
+    let list_b = doc_b.get_movable_list("list");
+    list_b.mov(1, 3)?;
+    list_b.set(0, "b")?;
+    list_b.delete(2, 2)?;
+    list_b.insert(1, 100)?;
+    list_a.mov(4, 0)?;
+    list_a.set(1, "c")?;
+    check(&doc_a);
+    doc_a.import(&doc_b.export_from(&Default::default()))?;
+    check(&doc_a);
+    doc_b.import(&doc_a.export_from(&Default::default()))?;
+    assert_eq!(doc_a.get_deep_value(), doc_b.get_deep_value());
+    Ok(())
+}
+
+#[test]
+fn movable_list_move_container_events() -> LoroResult<()> {
+    use std::sync::Mutex;
+
+    let doc = LoroDoc::new();
+    let list = doc.get_movable_list("list");
+    let child = list.insert_container(0, loro::LoroMovableList::new())?;
+    child.insert(0, 5)?;
+    list.insert(1, 0)?;
+    doc.commit();
+    let targets = Arc::new(Mutex::new(Vec::new()));
+    let targets_clone = targets.clone();
+    let _sub = doc.subscribe_root(Arc::new(move |event| {
+        for e in event.events {
+            targets_clone.lock().unwrap().push(e.target.clone());
+        }
+    }));
+    list.mov(0, 1)?;
+    doc.commit();
+    // The moved container is inserted again, so its content is emitted as well
+    assert_eq!(*targets.lock().unwrap(), vec![list.id(), child.id()]);
+    Ok(())
+}
+
+#[test]
+fn movable_list_cursor() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let list = doc.get_movable_list("list");
+    list.push(0)?;
+    list.push(1)?;
+    list.push(2)?;
+    let cursor = list.get_cursor(0, Default::default()).unwrap();
+    list.mov(0, 2)?;
+    assert_eq!(doc.get_cursor_pos(&cursor).unwrap().current.pos, 2);
+    list.insert(0, 3)?;
+    assert_eq!(doc.get_cursor_pos(&cursor).unwrap().current.pos, 3);
+    Ok(())
+}
+
+#[test]
+fn counter() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let counter = doc.get_counter("counter");
+    counter.increment(1)?;
+    counter.increment(2.5)?;
+    counter.decrement(1)?;
+    assert_eq!(counter.get_value(), 2.5);
+    assert!(counter.increment("1").is_err());
+    assert!(counter.increment(f64::NAN).is_err());
+    assert!(counter.decrement(f64::INFINITY).is_err());
+    assert_eq!(counter.get_value(), 2.5);
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({"counter": 2.5})
+    );
+
+    let doc_b = LoroDoc::new();
+    doc_b.import(&doc.export_from(&Default::default()))?;
+    assert_eq!(doc_b.get_deep_value(), doc.get_deep_value());
+    let doc_c = LoroDoc::new();
+    doc_c.import(&doc.export_snapshot())?;
+    assert_eq!(doc_c.get_deep_value(), doc.get_deep_value());
+    doc_c.get_counter("counter").increment(1)?;
+    doc.import(&doc_c.export_from(&doc.oplog_vv()))?;
+    assert_eq!(doc.get_counter("counter").get_value(), 3.5);
+    Ok(())
+}
+
+#[test]
+fn counter_concurrent_increments_are_summed() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    doc_a.get_counter("counter").increment(1)?;
+    doc_b.get_counter("counter").increment(2)?;
+    doc_b.get_counter("counter").decrement(0.5)?;
+    doc_a.import(&doc_b.export_from(&Default::default()))?;
+    doc_b.import(&doc_a.export_from(&Default::default()))?;
+    assert_eq!(doc_a.get_counter("counter").get_value(), 2.5);
+    assert_eq!(doc_a.get_deep_value(), doc_b.get_deep_value());
+    Ok(())
+}
+
+#[test]
+fn counter_checkout() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let counter = doc.get_counter("counter");
+    counter.increment(1)?;
+    doc.commit();
+    let f0 = doc.state_frontiers();
+    counter.increment(2)?;
+    doc.commit();
+    let f1 = doc.state_frontiers();
+    counter.decrement(5)?;
+    doc.commit();
+    doc.checkout(&f0)?;
+    assert_eq!(doc.get_counter("counter").get_value(), 1.);
+    doc.checkout(&f1)?;
+    assert_eq!(doc.get_counter("counter").get_value(), 3.);
+    doc.checkout(&f0)?;
+    assert_eq!(doc.get_counter("counter").get_value(), 1.);
+    doc.attach();
+    assert_eq!(doc.get_counter("counter").get_value(), -2.);
+    Ok(())
+}
+
+#[test]
+fn counter_in_map() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let map = doc.get_map("map");
+    let counter = loro::LoroCounter::new();
+    counter.increment(3)?;
+    let counter = map.insert_container("counter", counter)?;
+    counter.increment(1)?;
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({"map": {"counter": 4.0}})
+    );
+    let doc_b = LoroDoc::new();
+    doc_b.import(&doc.export_snapshot())?;
+    assert_eq!(doc_b.get_deep_value(), doc.get_deep_value());
+    Ok(())
+}
+
+#[test]
+fn counter_events() -> LoroResult<()> {
+    use loro::event::Diff;
+    use std::sync::Mutex;
+
+    let doc_a = LoroDoc::new();
+    let doc_b = LoroDoc::new();
+    let sum = Arc::new(Mutex::new(0.));
+    let sum_clone = sum.clone();
+    doc_a.subscribe_root(Arc::new(move |event| {
+        for e in event.events {
+            let Diff::Counter(delta) = &e.diff else {
+                unreachable!()
+            };
+            *sum_clone.lock().unwrap() += delta;
+        }
+    }));
+    doc_a.get_counter("counter").increment(1)?;
+    doc_a.get_counter("counter").increment(2)?;
+    doc_a.commit();
+    assert_eq!(*sum.lock().unwrap(), 3.);
+    doc_b.get_counter("counter").decrement(10)?;
+    doc_a.import(&doc_b.export_from(&Default::default()))?;
+    assert_eq!(*sum.lock().unwrap(), -7.);
+    let f = doc_a.state_frontiers();
+    doc_a.get_counter("counter").increment(4)?;
+    doc_a.commit();
+    assert_eq!(*sum.lock().unwrap(), -3.);
+    doc_a.checkout(&f)?;
+    assert_eq!(*sum.lock().unwrap(), -7.);
+    Ok(())
+}
+
+#[test]
+fn tree_children_order() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let tree = doc.get_tree("tree");
+    let root = tree.create(None)?;
+    let a = tree.create(root)?;
+    let b = tree.create(root)?;
+    let c = tree.create(root)?;
+    assert_eq!(tree.children(root), vec![a, b, c]);
+    let d = tree.create_at(root, 1)?;
+    assert_eq!(tree.children(root), vec![a, d, b, c]);
+    tree.mov_to(c, root, 0)?;
+    assert_eq!(tree.children(root), vec![c, a, d, b]);
+    tree.mov_to(c, root, 3)?;
+    assert_eq!(tree.children(root), vec![a, d, b, c]);
+    tree.mov_before(b, a)?;
+    assert_eq!(tree.children(root), vec![b, a, d, c]);
+    tree.mov_after(b, d)?;
+    assert_eq!(tree.children(root), vec![a, d, b, c]);
+    let root2 = tree.create_at(None, 0)?;
+    assert_eq!(tree.children(None), vec![root2, root]);
+    tree.mov_after(a, root2)?;
+    assert_eq!(tree.children(None), vec![root2, a, root]);
+    assert_eq!(tree.children(root), vec![d, b, c]);
+    assert!(tree.create_at(root, 4).is_err());
+    assert!(tree.mov_to(a, root, 4).is_err());
+    assert!(tree.mov_before(root, d).is_err());
+
+    // the siblings are spread over many B-tree nodes
+    let parent = tree.create(None)?;
+    let mut expected = Vec::new();
+    for i in 0..40 {
+        expected.insert(i / 2, tree.create_at(parent, i / 2)?);
+    }
+    assert_eq!(tree.children(parent), expected);
+    for (from, to) in [
+        (0, 39),
+        (39, 0),
+        (5, 30),
+        (30, 5),
+        (17, 18),
+        (18, 17),
+        (20, 20),
+    ] {
+        let node = expected.remove(from);
+        expected.insert(to, node);
+        tree.mov_to(node, parent, to)?;
+        assert_eq!(tree.children(parent), expected);
+    }
+    Ok(())
+}
+
+#[test]
+fn tree_children_order_detached() -> LoroResult<()> {
+    let tree = loro::LoroTree::new();
+    let a = tree.create(None)?;
+    let b = tree.create(None)?;
+    let c = tree.create_at(None, 1)?;
+    assert_eq!(tree.children(None), vec![a, c, b]);
+    tree.mov_to(a, None, 2)?;
+    assert_eq!(tree.children(None), vec![c, b, a]);
+    tree.mov_before(a, c)?;
+    assert_eq!(tree.children(None), vec![a, c, b]);
+    Ok(())
+}
+
+#[test]
+fn tree_concurrent_insertions_at_same_index() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    let tree_a = doc_a.get_tree("tree");
+    let root = tree_a.create(None)?;
+    let first = tree_a.create(root)?;
+    let last = tree_a.create(root)?;
+    doc_a.commit();
+    doc_b.import(&doc_a.export_snapshot())?;
+    let tree_b = doc_b.get_tree("tree");
+    let x = tree_a.create_at(root, 1)?;
+    let y = tree_b.create_at(root, 1)?;
+    doc_a.commit();
+    doc_b.commit();
+    doc_a.import(&doc_b.export_from(&doc_a.oplog_vv()))?;
+    doc_b.import(&doc_a.export_from(&doc_b.oplog_vv()))?;
+    let children = tree_a.children(root);
+    assert_eq!(children, tree_b.children(root));
+    assert_eq!(children.len(), 4);
+    assert_eq!(children[0], first);
+    assert_eq!(children[3], last);
+    assert!(children.contains(&x) && children.contains(&y));
+
+    // x and y have the same position, inserting between them still works
+    let z = tree_a.create_at(root, 2)?;
+    assert_eq!(
+        tree_a.children(root),
+        vec![first, children[1], z, children[2], last]
+    );
+    doc_a.commit();
+    doc_b.import(&doc_a.export_from(&doc_b.oplog_vv()))?;
+    assert_eq!(tree_a.children(root), tree_b.children(root));
+
+    let doc_c = LoroDoc::new();
+    doc_c.import(&doc_a.export_snapshot())?;
+    assert_eq!(doc_c.get_tree("tree").children(root), tree_a.children(root));
+    Ok(())
+}
+
+#[test]
+fn tree_events_with_index() -> LoroResult<()> {
+    use loro::{event::Diff, TreeExternalDiff, TreeID};
+    use std::{collections::HashMap, sync::Mutex};
+
+    #[derive(Default)]
+    struct Mirror {
+        children: HashMap<Option<TreeID>, Vec<TreeID>>,
+        parent: HashMap<TreeID, Option<TreeID>>,
+    }
+
+    impl Mirror {
+        fn remove(&mut self, target: TreeID) {
+            let parent = self.parent.remove(&target).unwrap();
+            self.children
+                .get_mut(&parent)
+                .unwrap()
+                .retain(|x| *x != target);
+        }
+
+        fn remove_subtree(&mut self, target: TreeID) {
+            self.remove(target);
+            for child in self.children.remove(&Some(target)).unwrap_or_default() {
+                self.parent.insert(child, Some(target));
+                self.children.entry(Some(target)).or_default().push(child);
+                self.remove_subtree(child);
+            }
+        }
+
+        fn insert(&mut self, target: TreeID, parent: Option<TreeID>, index: usize) {
+            self.parent.insert(target, parent);
+            self.children
+                .entry(parent)
+                .or_default()
+                .insert(index, target);
+        }
+
+        fn check(&self, tree: &loro::LoroTree) {
+            let mut stack = vec![None];
+            while let Some(parent) = stack.pop() {
+                let children = self.children.get(&parent).cloned().unwrap_or_default();
+                assert_eq!(children, tree.children(parent));
+                stack.extend(children.into_iter().map(Some));
+            }
+        }
+    }
+
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    let mirror = Arc::new(Mutex::new(Mirror::default()));
+    let mirror_clone = mirror.clone();
+    doc_a.subscribe_root(Arc::new(move |event| {
+        let mut mirror = mirror_clone.lock().unwrap();
+        for e in event.events {
+            let Diff::Tree(diff) = &e.diff else {
+                continue;
+            };
+            for item in diff.diff.iter() {
+                match item.action {
+                    TreeExternalDiff::Create { parent, index } => {
+                        mirror.insert(item.target, parent, index)
+                    }
+                    TreeExternalDiff::Move { parent, index } => {
+                        mirror.remove(item.target);
+                        mirror.insert(item.target, parent, index);
+                    }
+                    TreeExternalDiff::Delete => mirror.remove_subtree(item.target),
+                }
+            }
+        }
+    }));
+
+    let tree_a = doc_a.get_tree("tree");
+    let check = |doc: &LoroDoc| {
+        doc.commit();
+        mirror.lock().unwrap().check(&tree_a);
+    };
+
+    let root = tree_a.create(None)?;
+    let a = tree_a.create(root)?;
+    let b = tree_a.create_at(root, 0)?;
+    let c = tree_a.create_at(a, 0)?;
+    check(&doc_a);
+    tree_a.mov_to(a, root, 0)?;
+    tree_a.mov_after(c, b)?;
+    check(&doc_a);
+    let f = doc_a.state_frontiers();
+
+    doc_b.import(&doc_a.export_snapshot())?;
+    let tree_b = doc_b.get_tree("tree");
+    tree_b.create_at(root, 1)?;
+    tree_b.mov_before(b, a)?;
+    tree_b.delete(c)?;
+    tree_a.create_at(root, 1)?;
+    tree_a.mov_to(b, a, 0)?;
+    check(&doc_a);
+    doc_a.import(&doc_b.export_from(&Default::default()))?;
+    check(&doc_a);
+    // the nodes created concurrently at index 1 have the same position
+    tree_a.create_at(root, 2)?;
+    check(&doc_a);
+    doc_a.checkout(&f)?;
+    check(&doc_a);
+    doc_a.attach();
+    check(&doc_a);
+    Ok(())
+}
+
+#[test]
+fn undo_redo_local_changes() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let mut undo = UndoManager::new(&doc);
+    let text = doc.get_text("text");
+    let map = doc.get_map("map");
+    let list = doc.get_list("list");
+    let counter = doc.get_counter("counter");
+    text.insert(0, "hello")?;
+    doc.commit();
+    map.insert("a", 1)?;
+    list.push("x")?;
+    counter.increment(2)?;
+    doc.commit();
+    map.insert("a", 2)?;
+    list.delete(0, 1)?;
+    text.delete(0, 1)?;
+    doc.commit();
+    let json = doc.get_deep_value().to_json_value();
+    assert_eq!(
+        json,
+        json!({"text": "ello", "map": {"a": 2}, "list": [], "counter": 2.0})
+    );
+
+    assert!(undo.undo()?);
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({"text": "hello", "map": {"a": 1}, "list": ["x"], "counter": 2.0})
+    );
+    assert!(undo.undo()?);
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({"text": "hello", "map": {}, "list": [], "counter": 0.0})
+    );
+    assert!(undo.undo()?);
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({"text": "", "map": {}, "list": [], "counter": 0.0})
+    );
+    assert!(!undo.can_undo());
+    assert!(!undo.undo()?);
+
+    assert!(undo.redo()?);
+    assert!(undo.redo()?);
+    assert!(undo.redo()?);
+    assert!(!undo.can_redo());
+    assert_eq!(doc.get_deep_value().to_json_value(), json);
+
+    // a new local change clears the redo stack
+    undo.undo()?;
+    assert!(undo.can_redo());
+    text.insert(0, "!")?;
+    doc.commit();
+    assert!(!undo.can_redo());
+
+    // the max steps limit both stacks
+    undo.undo()?;
+    undo.undo()?;
+    undo.set_max_undo_steps(1);
+    assert!(undo.redo()?);
+    assert!(!undo.redo()?);
+    assert!(undo.undo()?);
+    assert!(!undo.undo()?);
+    Ok(())
+}
+
+#[test]
+fn undo_merge_interval_and_origin() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let mut undo = UndoManager::new(&doc);
+    undo.set_merge_interval(60_000);
+    let text = doc.get_text("text");
+    text.insert(0, "a")?;
+    doc.commit();
+    text.insert(1, "b")?;
+    doc.commit();
+    undo.record_new_checkpoint();
+    text.insert(2, "c")?;
+    doc.commit();
+    undo.undo()?;
+    assert_eq!(text.to_string(), "ab");
+    undo.undo()?;
+    assert_eq!(text.to_string(), "");
+
+    let doc = LoroDoc::new();
+    let mut undo = UndoManager::new(&doc);
+    undo.set_group_by_origin(true);
+    undo.add_exclude_origin_prefix("sys:");
+    let text = doc.get_text("text");
+    text.insert(0, "a")?;
+    doc.commit_with(Some("typing"), None, true);
+    text.insert(1, "b")?;
+    doc.commit_with(Some("typing"), None, true);
+    text.insert(2, "c")?;
+    doc.commit_with(Some("paste"), None, true);
+    text.insert(0, "0")?;
+    doc.commit_with(Some("sys:init"), None, true);
+    undo.undo()?;
+    assert_eq!(text.to_string(), "0ab");
+    undo.undo()?;
+    assert_eq!(text.to_string(), "0");
+    assert!(!undo.can_undo());
+    Ok(())
+}
+
+#[test]
+fn undo_keeps_concurrent_remote_changes() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    let mut undo = UndoManager::new(&doc_a);
+    let text_a = doc_a.get_text("text");
+    let map_a = doc_a.get_map("map");
+    text_a.insert(0, "hello")?;
+    map_a.insert("a", 1)?;
+    map_a.insert("b", 1)?;
+    doc_a.commit();
+    doc_b.import(&doc_a.export_from(&Default::default()))?;
+
+    let text_b = doc_b.get_text("text");
+    text_b.insert(0, "world ")?;
+    text_b.insert(11, "!")?;
+    doc_b.get_map("map").insert("a", 2)?;
+    doc_b.commit();
+    doc_a.import(&doc_b.export_from(&doc_a.oplog_vv()))?;
+    assert_eq!(text_a.to_string(), "world hello!");
+
+    undo.undo()?;
+    assert_eq!(text_a.to_string(), "world !");
+    // the remote change of the same key wins
+    assert_eq!(map_a.get_deep_value().to_json_value(), json!({"a": 2}));
+    undo.redo()?;
+    assert_eq!(text_a.to_string(), "world hello!");
+    assert_eq!(
+        map_a.get_deep_value().to_json_value(),
+        json!({"a": 2, "b": 1})
+    );
+    Ok(())
+}
+
+#[test]
+fn undo_restores_deleted_containers_and_tree_nodes() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let mut undo = UndoManager::new(&doc);
+    let map = doc.get_map("map");
+    let sub = map.insert_container("text", LoroText::new())?;
+    sub.insert(0, "abc")?;
+    let tree = doc.get_tree("tree");
+    let root = tree.create(None)?;
+    let child = tree.create(root)?;
+    let child2 = tree.create_at(root, 0)?;
+    doc.commit();
+
+    map.delete("text")?;
+    tree.delete(root)?;
+    doc.commit();
+    assert_eq!(map.get_deep_value().to_json_value(), json!({}));
+    assert!(tree.children(None).is_empty());
+
+    undo.undo()?;
+    assert_eq!(map.get_deep_value().to_json_value(), json!({"text": "abc"}));
+    assert_eq!(tree.children(None), vec![root]);
+    assert_eq!(tree.children(root), vec![child2, child]);
+    undo.redo()?;
+    assert!(tree.children(None).is_empty());
+    undo.undo()?;
+    assert_eq!(tree.children(root), vec![child2, child]);
+
+    // the restored container keeps working with later undos
+    undo.undo()?;
+    assert_eq!(
+        doc.get_deep_value().to_json_value(),
+        json!({"map": {}, "tree": []})
+    );
+    undo.redo()?;
+    assert_eq!(map.get_deep_value().to_json_value(), json!({"text": "abc"}));
+    assert_eq!(tree.children(root), vec![child2, child]);
+    Ok(())
+}
+
+#[test]
+fn undo_restores_cursors() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let mut undo = UndoManager::new(&doc);
+    let text = doc.get_text("text");
+    let text_clone = text.clone();
+    undo.set_on_push(Some(Box::new(move |_| {
+        let mut meta = UndoItemMeta::new();
+        let cursor = text_clone.get_cursor(2, Default::default()).unwrap();
+        meta.add_cursor(&cursor);
+        meta
+    })));
+    let popped = Arc::new(std::sync::Mutex::new(vec![]));
+    let popped_clone = popped.clone();
+    undo.set_on_pop(Some(Box::new(move |kind, meta| {
+        popped_clone
+            .lock()
+            .unwrap()
+            .push((kind, meta.cursors[0].pos.pos));
+    })));
+
+    text.insert(0, "abcd")?;
+    doc.commit();
+    // the cursor recorded for this step points to "a"
+    text.insert(0, "xy")?;
+    doc.commit();
+    undo.undo()?;
+    assert_eq!(text.to_string(), "abcd");
+    assert_eq!(popped.lock().unwrap().as_slice(), &[(UndoOrRedo::Undo, 0)]);
+    undo.redo()?;
+    assert_eq!(
+        popped.lock().unwrap().as_slice(),
+        &[(UndoOrRedo::Undo, 0), (UndoOrRedo::Redo, 4)]
+    );
+    Ok(())
+}
+
+#[test]
+fn revert_to_earlier_version() -> LoroResult<()> {
+    let mut doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let text = doc.get_text("text");
+    let map = doc.get_map("map");
+    let list = doc.get_movable_list("list");
+    let tree = doc.get_tree("tree");
+    let counter = doc.get_counter("counter");
+    text.insert(0, "hello")?;
+    map.insert_container("sub", LoroList::new())?.push(1)?;
+    list.push("a")?;
+    list.push("b")?;
+    let root = tree.create(None)?;
+    let child = tree.create(root)?;
+    counter.increment(1)?;
+    doc.commit();
+    let v1 = doc.oplog_frontiers();
+    let value = doc.get_deep_value().to_json_value();
+
+    text.delete(0, 2)?;
+    text.insert(0, "world ")?;
+    map.delete("sub")?;
+    map.insert("x", 1)?;
+    list.mov(0, 1)?;
+    list.set(0, "c")?;
+    tree.mov(child, None)?;
+    tree.delete(root)?;
+    counter.increment(3)?;
+    doc.commit();
+    let other = LoroDoc::new();
+    other.import(&doc.export_from(&Default::default()))?;
+
+    doc.revert_to(&v1)?;
+    assert!(!doc.is_detached());
+    assert_eq!(doc.get_deep_value().to_json_value(), value);
+    assert_eq!(tree.children(None), vec![root]);
+    assert_eq!(tree.children(root), vec![child]);
+
+    // the revert is synced as new ops
+    other.import(&doc.export_from(&other.oplog_vv()))?;
+    assert_eq!(other.get_deep_value().to_json_value(), value);
+    text.insert(0, "!")?;
+    doc.commit();
+    assert_eq!(text.to_string(), "!hello");
+
+    // a detached doc cannot be reverted
+    doc.detach();
+    assert!(matches!(
+        doc.revert_to(&v1),
+        Err(LoroError::AutoCommitNotStarted)
+    ));
+    Ok(())
+}
+
+#[test]
+fn diff_between_versions() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let text = doc.get_text("text");
+    let map = doc.get_map("map");
+    let list = doc.get_list("list");
+    text.insert(0, "hello")?;
+    map.insert("a", 1)?;
+    doc.commit();
+    let v1 = doc.oplog_frontiers();
+    text.insert(5, " world")?;
+    map.insert("b", 2)?;
+    list.push("x")?;
+    doc.commit();
+    text.delete(0, 1)?;
+    let child = list.insert_container(0, LoroMap::new())?;
+    child.insert("c", 3)?;
+    doc.commit();
+    let v3 = doc.oplog_frontiers();
+    let value_v3 = doc.get_deep_value();
+
+    let text_delta = |diff: &Diff| diff.as_text().unwrap().clone();
+    let map_delta = |diff: &Diff| {
+        let mut updated = diff
+            .as_map()
+            .unwrap()
+            .updated
+            .iter()
+            .map(|(k, v)| {
+                (
+                    k.to_string(),
+                    v.as_ref().map(|v| v.as_value().unwrap().clone()),
+                )
+            })
+            .collect::<Vec<_>>();
+        updated.sort_by(|a, b| a.0.cmp(&b.0));
+        updated
+    };
+
+    let diff = doc.diff(&v1, &v3, None)?;
+    let diffs = diff.iter().collect::<Vec<_>>();
+    assert_eq!(diffs.len(), 4);
+    // the parents come first
+    assert_eq!(diffs[3].target, &child.id());
+    for d in diffs.iter() {
+        if d.target == &text.id() {
+            assert_eq!(
+                text_delta(&d.diff),
+                vec![
+                    TextDelta::Delete { delete: 1 },
+                    TextDelta::Retain {
+                        retain: 4,
+                        attributes: None
+                    },
+                    TextDelta::Insert {
+                        insert: " world".into(),
+                        attributes: None
+                    }
+                ]
+            );
+        } else if d.target == &map.id() {
+            assert_eq!(map_delta(&d.diff), vec![("b".into(), Some(2.into()))]);
+        } else if d.target == &child.id() {
+            assert_eq!(d.path.len(), 2);
+            assert_eq!(map_delta(&d.diff), vec![("c".into(), Some(3.into()))]);
+        } else {
+            assert_eq!(d.target, &list.id());
+            let items = d.diff.as_list().unwrap();
+            assert_eq!(items.len(), 1);
+            let ListDiffItem::Insert { insert } = &items[0] else {
+                unreachable!()
+            };
+            assert_eq!(insert[0].as_container().unwrap().id(), child.id());
+            assert_eq!(insert[1].as_value().unwrap(), &LoroValue::from("x"));
+        }
+    }
+    // the state is untouched
+    assert!(!doc.is_detached());
+    assert_eq!(doc.state_frontiers(), v3);
+    assert_eq!(doc.get_deep_value(), value_v3);
+
+    // the reversed diff
+    let diff = doc.diff(&v3, &v1, None)?;
+    // the deleted child is omitted
+    assert_eq!(diff.len(), 3);
+    for d in diff.iter() {
+        if d.target == &text.id() {
+            assert_eq!(
+                text_delta(&d.diff),
+                vec![
+                    TextDelta::Insert {
+                        insert: "h".into(),
+                        attributes: None
+                    },
+                    TextDelta::Retain {
+                        retain: 4,
+                        attributes: None
+                    },
+                    TextDelta::Delete { delete: 6 }
+                ]
+            );
+        } else if d.target == &map.id() {
+            assert_eq!(map_delta(&d.diff), vec![("b".into(), None)]);
+        } else {
+            assert_eq!(d.target, &list.id());
+            assert!(matches!(
+                d.diff.as_list().unwrap()[..],
+                [ListDiffItem::Delete { delete: 2 }]
+            ));
+        }
+    }
+
+    let text_id = text.id();
+    let diff = doc.diff(&v1, &v3, Some(&|id| id == &text_id))?;
+    assert_eq!(diff.len(), 1);
+    assert_eq!(diff.iter().next().unwrap().target, &text_id);
+
+    assert!(matches!(
+        doc.diff(&v1, &ID::new(100, 0).into(), None),
+        Err(LoroError::InvalidFrontierIdNotFound(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn shallow_snapshot() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let text = doc.get_text("text");
+    let map = doc.get_map("map");
+    let list = doc.get_list("list");
+    let tree = doc.get_tree("tree");
+    let movable_list = doc.get_movable_list("movable_list");
+    let counter = doc.get_counter("counter");
+    text.insert(0, "hello")?;
+    map.insert("a", 1)?;
+    list.push("x")?;
+    let root = tree.create(None)?;
+    let child = tree.create(root)?;
+    movable_list.push("a")?;
+    movable_list.push("b")?;
+    movable_list.push("c")?;
+    counter.increment(1.)?;
+    doc.commit();
+    text.insert(5, " world")?;
+    map.insert("a", 2)?;
+    list.delete(0, 1)?;
+    tree.mov(child, None)?;
+    movable_list.mov(0, 2)?;
+    movable_list.set(1, "B")?;
+    counter.increment(2.)?;
+    doc.commit();
+    let v = doc.oplog_frontiers();
+    let value_at_v = doc.get_deep_value();
+    text.delete(0, 1)?;
+    map.insert("b", 3)?;
+    tree.mov(root, child)?;
+    movable_list.mov(2, 0)?;
+    counter.decrement(1.)?;
+    doc.commit();
+
+    let bytes = doc.export_shallow_snapshot(&v)?;
+    let shallow = LoroDoc::new();
+    shallow.set_peer_id(2)?;
+    shallow.import(&bytes)?;
+    assert!(shallow.is_shallow());
+    assert!(!doc.is_shallow());
+    assert_eq!(shallow.shallow_since_frontiers(), v);
+    assert_eq!(shallow.get_deep_value(), doc.get_deep_value());
+    // only the history after the shallow root is kept
+    assert!(
+        shallow.export_from(&Default::default()).len() < doc.export_from(&Default::default()).len()
+    );
+
+    // the versions after the shallow root are still reachable
+    shallow.checkout(&v)?;
+    assert_eq!(shallow.get_deep_value(), value_at_v);
+    shallow.attach();
+    assert_eq!(shallow.get_deep_value(), doc.get_deep_value());
+    assert!(matches!(
+        shallow.checkout(&Default::default()),
+        Err(LoroError::SwitchToVersionBeforeShallowRoot)
+    ));
+    assert!(matches!(
+        shallow.checkout(&ID::new(1, 0).into()),
+        Err(LoroError::SwitchToVersionBeforeShallowRoot)
+    ));
+    shallow.attach();
+
+    // sync both ways
+    shallow.get_text("text").insert(0, "H")?;
+    shallow.get_movable_list("movable_list").push("d")?;
+    shallow.commit();
+    text.insert(0, "Oh, ")?;
+    movable_list.set(0, "A")?;
+    doc.commit();
+    doc.import(&shallow.export_from(&doc.oplog_vv()))?;
+    shallow.import(&doc.export_from(&shallow.oplog_vv()))?;
+    assert_eq!(shallow.get_deep_value(), doc.get_deep_value());
+
+    // a shallow doc exports shallow snapshots from the same root
+    let new_doc = LoroDoc::new();
+    new_doc.import(&shallow.export_snapshot())?;
+    assert_eq!(new_doc.shallow_since_frontiers(), v);
+    assert_eq!(new_doc.get_deep_value(), doc.get_deep_value());
+    Ok(())
+}
+
+#[test]
+fn shallow_snapshot_with_concurrent_changes() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    doc_a.get_text("text").insert(0, "a")?;
+    doc_a.commit();
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    doc_b.import(&doc_a.export_snapshot())?;
+    doc_b.get_text("text").insert(1, "b")?;
+    doc_b.commit();
+    doc_a.get_text("text").insert(1, "c")?;
+    doc_a.commit();
+    let v = doc_a.oplog_frontiers();
+    doc_a.import(&doc_b.export_from(&Default::default()))?;
+
+    // the change of doc_b is concurrent to `v`, so the history starts before it
+    let bytes = doc_a.export_shallow_snapshot(&v)?;
+    let shallow = LoroDoc::new();
+    shallow.import(&bytes)?;
+    assert_eq!(shallow.shallow_since_frontiers(), ID::new(1, 0).into());
+    assert_eq!(shallow.get_deep_value(), doc_a.get_deep_value());
+    shallow.checkout(&v)?;
+    assert_eq!(shallow.get_text("text").to_string(), "ac");
+
+    // the updates that depend on the trimmed history cannot be imported
+    let doc_c = LoroDoc::new();
+    doc_c.set_peer_id(3)?;
+    doc_c.import(&doc_a.export_snapshot())?;
+    doc_a.get_text("text").insert(0, "x")?;
+    doc_a.commit();
+    doc_c.get_text("text").insert(0, "y")?;
+    doc_c.commit();
+    let shallow = LoroDoc::new();
+    shallow.import(&doc_a.export_shallow_snapshot(&doc_a.oplog_frontiers())?)?;
+    assert!(matches!(
+        shallow.import(&doc_c.export_from(&shallow.oplog_vv())),
+        Err(LoroError::ImportUpdatesThatDependsOnTrimmedHistory)
+    ));
+    Ok(())
+}
+
+#[test]
+fn attribution() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let text_a = doc_a.get_text("text");
+    text_a.insert(0, "Hello")?;
+    doc_a.commit_with(None, Some(100), true);
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    doc_b.import(&doc_a.export_snapshot())?;
+    let text_b = doc_b.get_text("text");
+    text_b.insert(2, "--")?;
+    text_b.insert(7, "!")?;
+    doc_b.commit_with(None, Some(200), true);
+    doc_a.import(&doc_b.export_from(&doc_a.oplog_vv()))?;
+    assert_eq!(text_a.to_string(), "He--llo!");
+
+    let attribution = text_a.get_attribution(0..8)?;
+    let spans: Vec<_> = attribution
+        .iter()
+        .map(|a| (a.range.clone(), a.peer, a.timestamp))
+        .collect();
+    assert_eq!(
+        spans,
+        vec![
+            (0..2, 1, 100),
+            (2..4, 2, 200),
+            (4..7, 1, 100),
+            (7..8, 2, 200)
+        ]
+    );
+    assert_eq!(attribution[2].lamport, 2);
+    assert_eq!(text_a.get_attribution(3..5)?.len(), 2);
+    assert!(text_a.get_attribution(3..3)?.is_empty());
+    assert!(matches!(
+        text_a.get_attribution(0..9),
+        Err(LoroError::OutOfBound { .. })
+    ));
+
+    // the ops in the pending transaction are not committed yet
+    text_a.insert(0, "Oh, ")?;
+    let attribution = text_a.get_attribution(0..6)?;
+    assert_eq!(attribution[0].range, 0..4);
+    assert_eq!(attribution[0].timestamp, 0);
+    doc_a.commit_with(None, Some(300), true);
+    assert_eq!(text_a.get_attribution(0..6)?[0].timestamp, 300);
+
+    let list = doc_a.get_list("list");
+    list.push(0)?;
+    list.push(1)?;
+    doc_a.commit_with(None, Some(400), true);
+    doc_b.import(&doc_a.export_from(&doc_b.oplog_vv()))?;
+    doc_b.get_list("list").insert(1, 2)?;
+    doc_b.commit();
+    doc_a.import(&doc_b.export_from(&doc_a.oplog_vv()))?;
+    let spans: Vec<_> = list
+        .get_attribution(0..3)?
+        .iter()
+        .map(|a| (a.range.clone(), a.peer))
+        .collect();
+    assert_eq!(spans, vec![(0..1, 1), (1..2, 2), (2..3, 1)]);
+    Ok(())
+}
+
+#[test]
+fn attribution_while_importing() -> LoroResult<()> {
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    let mut updates = Vec::new();
+    for _ in 0..200 {
+        let vv = doc_b.oplog_vv();
+        doc_b.get_text("text").insert(0, "ab")?;
+        doc_b.commit();
+        updates.push(doc_b.export_from(&vv));
+    }
+
+    let doc_a = LoroDoc::new();
+    let text = doc_a.get_text("text");
+    std::thread::scope(|s| {
+        let importer = s.spawn(|| {
+            for update in updates.iter() {
+                doc_a.import(update).unwrap();
+            }
+        });
+        while !importer.is_finished() {
+            // the text only grows, so the range is always valid
+            let len = text.len_unicode();
+            let attribution = text.get_attribution(0..len).unwrap();
+            assert_eq!(attribution.last().map_or(0, |a| a.range.end), len);
+            assert!(attribution.iter().all(|a| a.peer == 2));
+        }
+    });
+    assert_eq!(text.get_attribution(0..400)?.len(), 200);
+    Ok(())
+}
+
+#[test]
+fn commit_message() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let text = doc.get_text("text");
+    text.insert(0, "Hello")?;
+    doc.set_next_commit_message("Add greeting");
+    doc.commit();
+    text.insert(5, " World")?;
+    doc.commit();
+    text.insert(11, "!")?;
+    doc.set_next_commit_message("Add mark");
+    doc.commit();
+
+    let get_messages = |doc: &LoroDoc| {
+        doc.with_oplog(|oplog| {
+            [0, 5, 11].map(|counter| {
+                oplog
+                    .get_change_at(ID::new(1, counter))
+                    .and_then(|c| c.message().map(|m| m.to_string()))
+            })
+        })
+    };
+    let expected = [
+        Some("Add greeting".to_string()),
+        None,
+        Some("Add mark".to_string()),
+    ];
+    // the changes with different messages are not merged
+    assert_eq!(get_messages(&doc), expected);
+
+    let from_snapshot = LoroDoc::new();
+    from_snapshot.import(&doc.export_snapshot())?;
+    assert_eq!(get_messages(&from_snapshot), expected);
+    let from_updates = LoroDoc::new();
+    from_updates.import(&doc.export_from(&Default::default()))?;
+    assert_eq!(get_messages(&from_updates), expected);
+
+    let attribution = from_updates.get_text("text").get_attribution(0..12)?;
+    assert_eq!(attribution[0].message.as_deref(), Some("Add greeting"));
+    assert_eq!(attribution[1].message, None);
+    assert_eq!(attribution[2].message.as_deref(), Some("Add mark"));
+    Ok(())
+}
+
+#[test]
+fn checkout_at_timestamp() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    // a merged change is included as a whole, so don't merge them in this test
+    doc_a.set_change_merge_interval(0);
+    doc_b.set_change_merge_interval(0);
+    doc_a.get_text("text").insert(0, "a")?;
+    doc_a.commit_with(None, Some(100), true);
+    doc_b.import(&doc_a.export_from(&Default::default()))?;
+    doc_b.get_text("text").insert(1, "b")?;
+    doc_b.commit_with(None, Some(200), true);
+    doc_a.get_text("text").insert(1, "c")?;
+    doc_a.commit_with(None, Some(300), true);
+    doc_a.import(&doc_b.export_from(&Default::default()))?;
+    doc_b.import(&doc_a.export_from(&Default::default()))?;
+    doc_b.get_text("text").insert(0, "d")?;
+    doc_b.commit_with(None, Some(400), true);
+    doc_a.import(&doc_b.export_from(&doc_a.oplog_vv()))?;
+
+    assert!(doc_a.frontiers_at_timestamp(50).is_empty());
+    assert_eq!(doc_a.frontiers_at_timestamp(100), ID::new(1, 0).into());
+    assert_eq!(doc_a.frontiers_at_timestamp(250), ID::new(2, 0).into());
+    assert_eq!(doc_a.frontiers_at_timestamp(400), doc_a.oplog_frontiers());
+
+    let expected = [
+        (50, ""),
+        (150, "a"),
+        (250, "ab"),
+        (350, "acb"),
+        (450, "dacb"),
+    ];
+    for (timestamp, text) in expected {
+        doc_a.checkout_at_timestamp(timestamp)?;
+        assert!(doc_a.is_detached());
+        assert_eq!(doc_a.get_text("text").to_string(), text);
+    }
+
+    doc_a.attach();
+
+    // the clock of a remote peer may be behind, but the changes its change depends on
+    // are included, even if their timestamps are greater
+    let doc_c = LoroDoc::new();
+    doc_c.set_peer_id(3)?;
+    doc_c.import(&doc_a.export_snapshot())?;
+    for i in 0..3 {
+        doc_c.get_text("text").insert(0, "e")?;
+        doc_c.commit();
+        // so the changes of doc_c are not merged
+        doc_b.get_text("text").insert(0, "f")?;
+        doc_b.commit_with(None, Some(500 + i), true);
+        doc_c.import(&doc_b.export_from(&doc_c.oplog_vv()))?;
+    }
+    let mut json = doc_c.export_json_updates(&doc_a.oplog_vv(), &doc_c.oplog_vv());
+    for change in json.changes.iter_mut() {
+        if change.id.peer == 3 {
+            change.timestamp = [350, 360, 150][change.id.counter as usize];
+        }
+    }
+    doc_a.import_json_updates(json)?;
+    assert_eq!(doc_a.frontiers_at_timestamp(160), ID::new(3, 2).into());
+    assert_eq!(doc_a.frontiers_at_timestamp(140), ID::new(1, 0).into());
+    Ok(())
+}
+
+#[test]
+fn version_tags() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let text = doc_a.get_text("text");
+    text.insert(0, "Hello")?;
+    doc_a.commit();
+    let draft = doc_a.oplog_frontiers();
+    doc_a.tag_version("Submitted draft", &draft)?;
+    text.insert(5, " World")?;
+    doc_a.commit();
+    let unknown: Frontiers = ID::new(3, 0).into();
+    assert!(doc_a.tag_version("unknown", &unknown).is_err());
+    assert_eq!(
+        doc_a.get_deep_value().to_json_value(),
+        json!({"text": "Hello World"})
+    );
+
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    doc_b.import(&doc_a.export_from(&Default::default()))?;
+    doc_b.tag_version("Final", &doc_b.oplog_frontiers())?;
+    doc_b.commit();
+    doc_a.import(&doc_b.export_from(&doc_a.oplog_vv()))?;
+    let tags = doc_a.list_tags();
+    assert_eq!(
+        tags.iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["Final", "Submitted draft"]
+    );
+    assert_eq!(tags[1].1, draft);
+
+    doc_a.checkout_tag("Submitted draft")?;
+    assert!(doc_a.is_detached());
+    assert_eq!(text.to_string(), "Hello");
+    // the tags are still read from the latest version
+    assert_eq!(doc_a.list_tags(), tags);
+    doc_a.checkout_tag("Final")?;
+    assert_eq!(text.to_string(), "Hello World");
+    assert!(matches!(
+        doc_a.checkout_tag("missing"),
+        Err(LoroError::NotFoundError(_))
+    ));
+    doc_a.attach();
+
+    // the tags are hidden from the schema checks, the events, the diffs and the undo stack
+    let doc = LoroDoc::new();
+    doc.set_schema(Some(Schema::new().with_root(
+        "__loro_version_tags",
+        ContainerSchema::map(std::iter::empty()),
+    )));
+    let mut undo = UndoManager::new(&doc);
+    let events = Arc::new(Mutex::new(0));
+    let events_clone = events.clone();
+    let _sub = doc.subscribe_root(Arc::new(move |_| {
+        *events_clone.lock().unwrap() += 1;
+    }));
+    let text = doc.get_text("text");
+    text.insert(0, "a")?;
+    doc.commit();
+    let v = doc.oplog_frontiers();
+    doc.tag_version("v", &v)?;
+    doc.commit();
+    assert_eq!(*events.lock().unwrap(), 1);
+    assert!(doc.diff(&v, &doc.oplog_frontiers(), None)?.is_empty());
+    assert!(undo.undo()?);
+    assert_eq!(text.to_string(), "");
+    assert!(!undo.can_undo());
+    assert_eq!(doc.list_tags().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn fork() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    let text = doc_a.get_text("text");
+    text.insert(0, "Hello")?;
+    text.mark(0..5, "bold", true)?;
+    let list = doc_a
+        .get_map("map")
+        .insert_container("list", LoroList::new())?;
+    list.push("a")?;
+    doc_a.commit();
+    doc_b.import(&doc_a.export_snapshot())?;
+    doc_b.get_text("text").insert(5, "!")?;
+    doc_b.commit();
+    text.insert(0, "Oh, ")?;
+    doc_a.commit();
+    doc_a.import(&doc_b.export_from(&doc_a.oplog_vv()))?;
+    let v = doc_a.oplog_frontiers();
+    let value_at_v = doc_a.get_deep_value();
+    list.push("b")?;
+    doc_a.commit();
+
+    let fork = doc_a.fork();
+    assert_ne!(fork.peer_id(), doc_a.peer_id());
+    assert_eq!(fork.oplog_vv(), doc_a.oplog_vv());
+    assert_eq!(fork.get_deep_value(), doc_a.get_deep_value());
+    assert_eq!(
+        fork.get_text("text").to_delta(),
+        doc_a.get_text("text").to_delta()
+    );
+
+    let fork = doc_a.fork_at(&v)?;
+    assert_eq!(fork.oplog_frontiers(), v);
+    assert_eq!(fork.get_deep_value(), value_at_v);
+    assert!(doc_a.fork_at(&ID::new(3, 0).into()).is_err());
+
+    // the fork is independent of the original doc
+    fork.get_text("text").insert(0, "Well, ")?;
+    fork.commit();
+    assert_eq!(text.to_string(), "Oh, Hello!");
+    assert_eq!(fork.get_text("text").to_string(), "Well, Oh, Hello!");
+    doc_a.import(&fork.export_from(&doc_a.oplog_vv()))?;
+    fork.import(&doc_a.export_from(&fork.oplog_vv()))?;
+    assert_eq!(fork.get_deep_value(), doc_a.get_deep_value());
+    assert_eq!(
+        fork.get_deep_value().to_json_value(),
+        json!({"text": "Well, Oh, Hello!", "map": {"list": ["a", "b"]}})
+    );
+
+    // fork a doc loaded from a shallow snapshot
+    let shallow = LoroDoc::new();
+    shallow.import(&doc_a.export_shallow_snapshot(&v)?)?;
+    let fork = shallow.fork();
+    assert!(fork.is_shallow());
+    assert_eq!(fork.get_deep_value(), doc_a.get_deep_value());
+    assert_eq!(fork.fork_at(&v)?.get_deep_value(), value_at_v);
+    Ok(())
+}
+
+#[test]
+fn change_meta_and_ops() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    doc_a.set_change_merge_interval(0);
+    let list = doc_a.get_list("list");
+    list.insert(0, 1)?;
+    let map = list.insert_container(1, LoroMap::new())?;
+    map.insert("key", "value")?;
+    doc_a.commit_with(None, Some(10), true);
+    let v = doc_a.oplog_vv();
+    doc_b.import(&doc_a.export_snapshot())?;
+    doc_b.get_text("text").insert(0, "hi")?;
+    doc_b.set_next_commit_message("greet");
+    doc_b.commit();
+    doc_a.import(&doc_b.export_from(&doc_a.oplog_vv()))?;
+    list.delete(0, 1)?;
+    doc_a.get_counter("counter").increment(2.)?;
+    doc_a.commit_with(None, Some(20), true);
+
+    let changes = doc_a.changes();
+    assert_eq!(changes.len(), 3);
+    assert_eq!(changes[0].id, ID::new(1, 0));
+    assert_eq!(changes[0].len, 3);
+    assert_eq!(changes[0].timestamp, 10);
+    assert!(changes[0].deps.is_empty());
+    assert_eq!(changes[1].id, ID::new(2, 0));
+    assert_eq!(changes[1].lamport, 3);
+    assert_eq!(changes[1].message.as_deref(), Some("greet"));
+    assert_eq!(changes[1].deps, ID::new(1, 2).into());
+    assert_eq!(changes[2].id, ID::new(1, 3));
+    assert_eq!(changes[2].lamport, 5);
+    assert_eq!(changes[2].deps, ID::new(2, 1).into());
+
+    // the partially included change is sliced
+    let between = doc_a.changes_between(&vv!(1 => 1), &v);
+    assert_eq!(between.len(), 1);
+    assert_eq!(between[0].id, ID::new(1, 1));
+    assert_eq!(between[0].lamport, 1);
+    assert_eq!(between[0].len, 2);
+    assert_eq!(between[0].deps, ID::new(1, 0).into());
+    assert_eq!(
+        doc_a
+            .changes_between(&v, &doc_a.oplog_vv())
+            .iter()
+            .map(|c| c.id)
+            .collect::<Vec<_>>(),
+        vec![ID::new(2, 0), ID::new(1, 3)]
+    );
+
+    let ops = doc_a.ops_in_change(ID::new(1, 1)).unwrap();
+    let list_id = ContainerID::new_root("list", ContainerType::List);
+    // the continuous insertions are merged into one op
+    assert_eq!(ops.len(), 2);
+    assert_eq!(ops[0].id, ID::new(1, 0));
+    assert_eq!(ops[0].container, list_id);
+    assert_eq!(
+        ops[0].content,
+        ChangeOpContent::ListInsert {
+            pos: 0,
+            values: vec![1.into(), LoroValue::Container(map.id())]
+        }
+    );
+    assert_eq!(ops[1].id, ID::new(1, 2));
+    assert_eq!(ops[1].container, map.id());
+    assert_eq!(
+        ops[1].content,
+        ChangeOpContent::MapSet {
+            key: "key".into(),
+            value: Some("value".into())
+        }
+    );
+
+    let ops = doc_a.ops_in_change(ID::new(1, 3)).unwrap();
+    assert_eq!(ops[0].lamport, 5);
+    assert_eq!(ops[0].content, ChangeOpContent::Delete { pos: 0, len: 1 });
+    assert_eq!(ops[1].content, ChangeOpContent::CounterIncrement(2.));
+    assert!(doc_a.ops_in_change(ID::new(1, 5)).is_none());
+
+    // the text positions are entity indexes, which include the style anchors
+    let doc = LoroDoc::new();
+    doc.set_peer_id(3)?;
+    let text = doc.get_text("text");
+    text.insert(0, "abcdef")?;
+    text.mark(1..3, "bold", true)?;
+    text.insert(5, "X")?;
+    text.delete(4, 1)?;
+    doc.commit();
+    let ops = doc.ops_in_change(ID::new(3, 0)).unwrap();
+    let contents = ops.into_iter().map(|op| op.content).collect::<Vec<_>>();
+    assert_eq!(
+        contents,
+        vec![
+            ChangeOpContent::TextInsert {
+                pos: 0,
+                text: "abcdef".into()
+            },
+            ChangeOpContent::Mark {
+                start: 1,
+                end: 3,
+                key: "bold".into(),
+                value: true.into()
+            },
+            ChangeOpContent::MarkEnd,
+            ChangeOpContent::TextInsert {
+                pos: 7,
+                text: "X".into()
+            },
+            ChangeOpContent::Delete { pos: 6, len: 1 },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn json_updates() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let text = doc.get_text("text");
+    text.insert(0, "Hello 😀 world")?;
+    text.mark(0..5, "bold", true)?;
+    text.delete(6, 2)?;
+    let map = doc.get_map("map");
+    map.insert("binary", vec![1u8, 2, 3])?;
+    map.insert("float", 1.0)?;
+    map.insert("int", 1)?;
+    map.insert("nested", loro_value!({"a": [1, "b", null]}))?;
+    let list = map.insert_container("list", LoroList::new())?;
+    list.insert(0, "x")?;
+    map.delete("int")?;
+    doc.set_next_commit_message("first");
+    doc.commit();
+    let v = doc.oplog_vv();
+    let movable_list = doc.get_movable_list("movable_list");
+    movable_list.push("a")?;
+    movable_list.push("b")?;
+    movable_list.mov(0, 1)?;
+    movable_list.set(0, "c")?;
+    let tree = doc.get_tree("tree");
+    let root = tree.create(None)?;
+    let child = tree.create(root)?;
+    tree.mov(child, None)?;
+    tree.delete(root)?;
+    doc.get_counter("counter").increment(1.5)?;
+    doc.commit();
+
+    let json = doc.export_json_updates(&Default::default(), &doc.oplog_vv());
+    let s = serde_json::to_string_pretty(&json).unwrap();
+    assert!(s.contains(r#""msg": "first""#));
+    assert!(s.contains(r#""container": "cid:root-text:Text""#));
+    assert!(s.contains(r#""🦜:cid:"#));
+    let parsed: JsonSchema = serde_json::from_str(&s).unwrap();
+    assert_eq!(parsed, json);
+
+    let new_doc = LoroDoc::new();
+    new_doc.import_json_updates(parsed)?;
+    assert_eq!(new_doc.get_deep_value(), doc.get_deep_value());
+    assert_eq!(
+        new_doc.get_text("text").to_delta(),
+        doc.get_text("text").to_delta()
+    );
+    assert_eq!(new_doc.changes(), doc.changes());
+    assert_eq!(
+        new_doc.export_json_updates(&Default::default(), &new_doc.oplog_vv()),
+        json
+    );
+    // the doc imported from JSON is the same as the one imported from the binary format
+    let binary_doc = LoroDoc::new();
+    binary_doc.import(&doc.export_from(&Default::default()))?;
+    assert_eq!(
+        new_doc.export_from(&Default::default()),
+        binary_doc.export_from(&Default::default())
+    );
+
+    // export a part of the history
+    let part = doc.export_json_updates(&v, &doc.oplog_vv());
+    assert_eq!(part.changes.len(), 1);
+    let part_doc = LoroDoc::new();
+    part_doc.import_json_updates(part.clone())?;
+    assert!(part_doc.get_deep_value().to_json_value()["tree"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+    part_doc.import(&doc.export_from(&Default::default()))?;
+    assert_eq!(part_doc.get_deep_value(), doc.get_deep_value());
+
+    let mut invalid = part;
+    invalid.changes[0].ops[0].counter += 1;
+    assert!(LoroDoc::new().import_json_updates(invalid).is_err());
+    Ok(())
+}
+
+#[test]
+fn json_updates_round_trip_reserved_values() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let map = doc.get_map("map");
+    map.insert("🦜:binary", vec![1u8, 2, 3])?;
+    map.insert("🦜:double", "🦜:cid:root-map:Map")?;
+    map.insert("str", "🦜:🦜:")?;
+    map.insert("escaped", loro_value!({"🦜:binary": [1, 2, 3]}))?;
+    map.insert("double", loro_value!({"🦜:double": "NaN"}))?;
+    map.insert("inf", f64::INFINITY)?;
+    map.insert("neg_inf", f64::NEG_INFINITY)?;
+    map.insert("float", 2.0)?;
+    let list = doc.get_list("list");
+    list.push(f64::NAN)?;
+    doc.commit();
+
+    let json = doc.export_json_updates(&Default::default(), &doc.oplog_vv());
+    let s = serde_json::to_string(&json).unwrap();
+    let new_doc = LoroDoc::new();
+    new_doc.import_json_updates(serde_json::from_str::<JsonSchema>(&s).unwrap())?;
+    assert_eq!(
+        new_doc.get_map("map").get_deep_value(),
+        map.get_deep_value()
+    );
+    let list = new_doc.get_list("list").get_value().into_list().unwrap();
+    assert!(matches!(&list[0], LoroValue::Double(d) if d.is_nan()));
+    Ok(())
+}
+
+#[test]
+fn preview_import() -> LoroResult<()> {
+    let mut doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    doc.get_text("text").insert(0, "Hello")?;
+    doc.commit();
+    let other = LoroDoc::new();
+    other.set_peer_id(2)?;
+    other.import(&doc.export_from(&Default::default()))?;
+    other.get_text("text").insert(5, " world")?;
+    other.get_map("map").insert("key", 1)?;
+    other.commit();
+    let v = other.oplog_vv();
+    other.get_text("text").insert(0, "Hi, ")?;
+    other.commit();
+
+    let updates = other.export_from(&doc.oplog_vv());
+    let preview = doc.preview_import(&updates)?;
+    assert!(preview.pending.is_empty());
+    let targets: Vec<_> = preview
+        .diff
+        .iter()
+        .flat_map(|d| DiffEvent::from(d).events)
+        .map(|e| e.target.clone())
+        .collect();
+    assert!(targets.contains(&ContainerID::new_root("text", ContainerType::Text)));
+    assert!(targets.contains(&ContainerID::new_root("map", ContainerType::Map)));
+    assert_eq!(doc.get_text("text").to_string(), "Hello");
+    assert_eq!(doc.oplog_vv().get(&2), None);
+
+    // the changes after `v` depend on the missing changes
+    let preview = doc.preview_import(&other.export_from(&v))?;
+    assert_eq!(preview.pending, vec![IdSpan::new(2, 7, 11)]);
+    assert!(preview.diff.is_empty());
+
+    // the diff is empty when the doc is detached
+    doc.detach();
+    let preview = doc.preview_import(&updates)?;
+    assert!(preview.pending.is_empty());
+    assert!(preview.diff.is_empty());
+    doc.attach();
+
+    // snapshots are previewed the same way they are imported
+    for snapshot in [
+        other.export_snapshot(),
+        other.export_shallow_snapshot(&other.oplog_frontiers())?,
+    ] {
+        let new_doc = LoroDoc::new();
+        let preview = new_doc.preview_import(&snapshot)?;
+        assert!(preview.pending.is_empty());
+        assert!(!preview.diff.is_empty());
+        assert!(new_doc.oplog_vv().is_empty());
+        let expected: Vec<_> = preview
+            .diff
+            .iter()
+            .flat_map(|d| DiffEvent::from(d).events)
+            .map(|e| e.target.clone())
+            .collect();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = events.clone();
+        let _sub = new_doc.subscribe_root(Arc::new(move |e| {
+            let mut events = events_clone.lock().unwrap();
+            events.extend(e.events.iter().map(|e| e.target.clone()));
+        }));
+        new_doc.import(&snapshot)?;
+        assert_eq!(*events.lock().unwrap(), expected);
+    }
+
+    doc.import(&updates)?;
+    assert_eq!(doc.get_text("text").to_string(), "Hi, Hello world");
+    assert!(doc.preview_import(&[1, 2, 3]).is_err());
+    Ok(())
+}
+
+#[test]
+fn import_validator() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_text("text").insert(0, "Hello")?;
+    a.commit();
+    b.import(&a.export_from(&Default::default()))?;
+    b.get_text("text").insert(5, " world")?;
+    b.commit();
+    a.import(&b.export_from(&Default::default()))?;
+    a.get_text("text").insert(0, "> ")?;
+    a.commit();
+
+    let doc = LoroDoc::new();
+    let checked = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let checked_clone = checked.clone();
+    let text = ContainerID::new_root("text", ContainerType::Text);
+    let validator: ImportValidator = Arc::new(move |change, ops| {
+        checked_clone.lock().unwrap().push(change.clone());
+        if ops.iter().any(|op| op.container != text) {
+            return Err("only the text can be edited".into());
+        }
+        Ok(())
+    });
+    doc.set_import_validator(Some(validator.clone()));
+    doc.import(&a.export_from(&Default::default()))?;
+    assert_eq!(doc.get_text("text").to_string(), "> Hello world");
+    // the metadata is the same as the one in the oplog, including the lamports
+    let mut checked_changes = std::mem::take(&mut *checked.lock().unwrap());
+    checked_changes.sort_by_key(|x| x.lamport);
+    assert_eq!(checked_changes, a.changes());
+
+    // the changes that are already imported are not checked again
+    doc.import(&a.export_from(&Default::default()))?;
+    assert!(checked.lock().unwrap().is_empty());
+
+    // a rejected import doesn't change the doc
+    let v = a.oplog_vv();
+    a.get_text("text").insert(0, "!")?;
+    a.commit();
+    b.import(&a.export_from(&Default::default()))?;
+    b.get_map("map").insert("key", 1)?;
+    b.commit();
+    let err = doc.import(&b.export_from(&doc.oplog_vv())).unwrap_err();
+    assert!(matches!(err, LoroError::ImportRejected { id, .. } if id.peer == 2));
+    assert_eq!(doc.oplog_vv(), v);
+    assert_eq!(doc.get_text("text").to_string(), "> Hello world");
+    let json = b.export_json_updates(&doc.oplog_vv(), &b.oplog_vv());
+    assert!(doc.import_json_updates(json).is_err());
+    assert_eq!(doc.oplog_vv(), v);
+
+    // the snapshot is checked too
+    let new_doc = LoroDoc::new();
+    new_doc.set_import_validator(Some(validator.clone()));
+    assert!(new_doc.import(&b.export_snapshot()).is_err());
+    assert!(new_doc.oplog_vv().is_empty());
+    assert_eq!(new_doc.get_text("text").to_string(), "");
+
+    // so are the shallow root and the changes after it of a shallow snapshot
+    let before_map = b.oplog_frontiers();
+    b.get_text("text").insert(0, "#")?;
+    b.commit();
+    for v in [b.oplog_frontiers(), before_map] {
+        let bytes = b.export_shallow_snapshot(&v)?;
+        let new_doc = LoroDoc::new();
+        new_doc.set_import_validator(Some(validator.clone()));
+        let err = new_doc.import(&bytes).unwrap_err();
+        assert!(matches!(err, LoroError::ImportRejected { id, .. } if id.peer == 2));
+        assert!(new_doc.oplog_vv().is_empty());
+        assert!(!new_doc.is_shallow());
+        assert!(new_doc.state_frontiers().is_empty());
+        assert_eq!(new_doc.get_text("text").to_string(), "");
+        assert!(new_doc.get_map("map").is_empty());
+    }
+
+    doc.set_import_validator(None);
+    doc.import(&b.export_from(&doc.oplog_vv()))?;
+    assert_eq!(
+        doc.get_deep_value().to_json_value()["map"],
+        json!({"key": 1})
+    );
+    Ok(())
+}
+
+#[test]
+fn schema_violation() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    let item = ContainerSchema::map([("title", ValueSchema::String), ("done", ValueSchema::Bool)]);
+    doc.set_schema(Some(
+        Schema::new()
+            .with_root(
+                "settings",
+                ContainerSchema::map([
+                    ("theme", ValueSchema::String),
+                    ("size", ValueSchema::I64),
+                    (
+                        "tags",
+                        ValueSchema::Container(ContainerSchema::List(Box::new(
+                            ValueSchema::String,
+                        ))),
+                    ),
+                ]),
+            )
+            .with_root(
+                "todos",
+                ContainerSchema::MovableList(Box::new(ValueSchema::Container(item))),
+            ),
+    ));
+
+    let settings = doc.get_map("settings");
+    settings.insert("theme", "dark")?;
+    settings.insert("size", 12)?;
+    let tags = settings.insert_container("tags", LoroList::new())?;
+    tags.push("a")?;
+    doc.commit();
+    let vv = doc.oplog_vv();
+
+    let is_violation = |r: LoroResult<()>| matches!(r, Err(LoroError::SchemaViolation { .. }));
+    assert!(is_violation(settings.insert("theme", 1)));
+    assert!(is_violation(settings.insert("unknown", "x")));
+    assert!(settings.insert_container("theme", LoroText::new()).is_err());
+    assert!(is_violation(tags.insert(0, 1.5)));
+    assert!(tags.insert_container(0, LoroMap::new()).is_err());
+    let todos = doc.get_movable_list("todos");
+    assert!(is_violation(todos.insert(0, "not a map")));
+    let todo = todos.insert_container(0, LoroMap::new())?;
+    todo.insert("title", "write tests")?;
+    assert!(is_violation(todo.insert("done", "yes")));
+    assert!(is_violation(todos.set(0, "x")));
+    // the root "settings" is a map
+    assert!(matches!(
+        doc.get_tree("settings").create(None),
+        Err(LoroError::SchemaViolation { .. })
+    ));
+    assert!(is_violation(doc.get_list("settings").insert(0, 1)));
+    // the containers that are not described are not checked
+    doc.get_map("other").insert("any", 1)?;
+    doc.commit();
+    // only the valid edits are recorded
+    assert_eq!(
+        settings.get_deep_value().to_json_value(),
+        json!({"theme": "dark", "size": 12, "tags": ["a"]})
+    );
+    assert_eq!(
+        todos.get_deep_value().to_json_value(),
+        json!([{"title": "write tests"}])
+    );
+    assert_eq!(doc.get_tree("settings").nodes().len(), 0);
+    assert_eq!(
+        doc.oplog_vv().get(&doc.peer_id()),
+        Some(&(vv.get(&doc.peer_id()).unwrap() + 3))
+    );
+
+    // the imported changes are not checked
+    let other = LoroDoc::new();
+    other.get_map("settings").insert("theme", 1)?;
+    other.commit();
+    doc.import(&other.export_from(&Default::default()))?;
+
+    doc.set_schema(None);
+    settings.insert("unknown", "x")?;
+    Ok(())
+}
+
+#[test]
+fn import_status() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let text = a.get_text("text");
+    text.insert(0, "12")?;
+    a.commit();
+    let v0 = a.oplog_vv();
+    text.insert(2, "345")?;
+    a.commit();
+    let v1 = a.oplog_vv();
+    text.insert(5, "67")?;
+    a.commit();
+    let first = a.export_from(&Default::default());
+    let second = a.export_from(&v0);
+    let third = a.export_from(&v1);
+
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    let status = b.import(&third)?;
+    assert!(status.applied.is_empty());
+    assert_eq!(status.pending, vec![IdSpan::new(1, 5, 7)]);
+    assert_eq!(status.missing_deps, vec![ID::new(1, 4)]);
+
+    // the changes that were already pending are reported again, but listed once
+    let status = b.import(&third)?;
+    assert_eq!(status.pending, vec![IdSpan::new(1, 5, 7)]);
+    assert_eq!(status.missing_deps, vec![ID::new(1, 4)]);
+    assert_eq!(
+        b.pending_changes_summary().pending,
+        vec![IdSpan::new(1, 5, 7)]
+    );
+
+    // the change of another peer waits for the missing deps through the pending changes
+    let c = LoroDoc::new();
+    c.set_peer_id(3)?;
+    c.import(&first)?;
+    c.get_text("text").insert(0, "0")?;
+    c.commit();
+    let status = b.import(&c.export_from(&a.oplog_vv()))?;
+    assert_eq!(status.pending, vec![IdSpan::new(3, 0, 1)]);
+    assert_eq!(status.missing_deps, vec![ID::new(1, 4)]);
+
+    // `second` is still missing its dependency, so it becomes pending too
+    let status = b.import(&second)?;
+    assert!(status.applied.is_empty());
+    assert_eq!(status.pending, vec![IdSpan::new(1, 2, 7)]);
+    assert_eq!(status.missing_deps, vec![ID::new(1, 1)]);
+    let summary = b.pending_changes_summary();
+    assert_eq!(summary.missing_deps, vec![ID::new(1, 1)]);
+    assert_eq!(
+        summary.pending,
+        vec![IdSpan::new(1, 2, 7), IdSpan::new(3, 0, 1)]
+    );
+
+    let status = b.import(&first[..])?;
+    assert_eq!(
+        status.applied,
+        vec![IdSpan::new(1, 0, 7), IdSpan::new(3, 0, 1)]
+    );
+    assert!(status.pending.is_empty());
+    assert!(status.missing_deps.is_empty());
+    assert_eq!(b.pending_changes_summary(), Default::default());
+    assert_eq!(b.get_text("text").to_string(), "01234567");
+
+    // importing the same updates again applies nothing
+    let status = b.import(&first)?;
+    assert!(status.applied.is_empty());
+    assert!(status.pending.is_empty());
+    Ok(())
+}
+
+#[test]
+fn export_snapshot_of() -> LoroResult<()> {
+    let doc = LoroDoc::new();
+    doc.set_peer_id(1)?;
+    let root = doc.get_map("root");
+    let page = root.insert_container("page", LoroMap::new())?;
+    let text = page.insert_container("body", LoroText::new())?;
+    text.insert(0, "Hello")?;
+    let list = page.insert_container("tags", LoroList::new())?;
+    list.push("a")?;
+    root.insert("title", "secret")?;
+    doc.get_text("notes").insert(0, "secret")?;
+    doc.commit();
+    text.insert(5, " world")?;
+    doc.commit();
+
+    let viewer = LoroDoc::new();
+    viewer.import(&doc.export_snapshot_of(&[page.id()])?)?;
+    assert_eq!(
+        viewer.get_map(page.id()).get_deep_value().to_json_value(),
+        json!({"body": "Hello world", "tags": ["a"]})
+    );
+    assert_eq!(viewer.get_text(text.id()).to_string(), "Hello world");
+    assert_eq!(viewer.get_text("notes").to_string(), "");
+    assert_eq!(viewer.oplog_vv(), doc.oplog_vv());
+
+    // the later updates of the source doc can be imported
+    let v = doc.oplog_vv();
+    text.insert(0, "> ")?;
+    doc.commit();
+    viewer.import(&doc.export_from(&v))?;
+    assert_eq!(viewer.get_text(text.id()).to_string(), "> Hello world");
+
+    let missing = ContainerID::new_normal(ID::new(9, 9), ContainerType::Text);
+    assert!(matches!(
+        doc.export_snapshot_of(&[missing]),
+        Err(LoroError::NotFoundError(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn merge_updates_offline() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_text("text").insert(0, "Hello")?;
+    a.commit();
+    let snapshot = a.export_snapshot();
+    let v = a.oplog_vv();
+    b.import(&snapshot)?;
+    b.get_list("list").push(1)?;
+    b.commit();
+    let from_b = b.export_from(&v);
+    let v = a.oplog_vv();
+    a.get_text("text").insert(5, " world")?;
+    a.commit();
+    let from_a = a.export_from(&v);
+
+    // the order of the blobs doesn't matter
+    let merged = merge_updates(&[&from_b, &from_a, &snapshot])?;
+    a.import(&from_b)?;
+    let doc = LoroDoc::new();
+    doc.import(&merged)?;
+    assert_eq!(doc.get_deep_value(), a.get_deep_value());
+    assert_eq!(doc.oplog_vv(), a.oplog_vv());
+
+    // the deps outside the blobs become the start version of the merged blob
+    let merged = merge_updates(&[&from_b, &from_a])?;
+    let meta = LoroDoc::decode_import_blob_meta(&merged)?;
+    assert_eq!(meta.start_frontiers, ID::new(1, 4).into());
+    let doc = LoroDoc::new();
+    doc.import(&snapshot)?;
+    doc.import(&merged)?;
+    assert_eq!(doc.get_deep_value(), a.get_deep_value());
+    assert_eq!(doc.oplog_vv(), a.oplog_vv());
+
+    // but a gap inside the blobs cannot be encoded
+    let v = a.oplog_vv();
+    a.get_text("text").insert(0, "> ")?;
+    a.commit();
+    let after_gap = a.export_from(&v);
+    match merge_updates(&[&snapshot, &after_gap]) {
+        Err(LoroError::MergeWithMissingDeps(deps)) => assert_eq!(deps, vec![ID::new(1, 10)]),
+        _ => unreachable!(),
+    }
+    let shallow = a.export_shallow_snapshot(&a.oplog_frontiers())?;
+    assert!(matches!(
+        merge_updates(&[&shallow]),
+        Err(LoroError::ArgErr(_))
+    ));
+    assert!(matches!(
+        merge_updates(&[b"invalid blob"]),
+        Err(LoroError::DecodeError(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn merge_updates_exported_from_a_version() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_text("text").insert(0, "Hello")?;
+    a.commit();
+    let v0 = a.oplog_vv();
+    let snapshot = a.export_snapshot();
+    b.import(&snapshot)?;
+    // it's concurrent to the edits of a below, so it depends on an earlier id of a
+    b.get_list("list").push(1)?;
+    b.commit();
+    let mut blobs = Vec::new();
+    for i in 0..3 {
+        let v = a.oplog_vv();
+        a.get_text("text").insert(0, &i.to_string())?;
+        a.commit();
+        blobs.push(a.export_from(&v));
+    }
+    let v = a.oplog_vv();
+    a.import(&b.export_from(&v0))?;
+    a.get_map("map").insert("key", "value")?;
+    a.commit();
+    blobs.push(a.export_from(&v));
+    let all = a.export_from(&v0);
+
+    let check = |merged: &[u8], start: &[&[u8]]| -> LoroResult<()> {
+        let doc = LoroDoc::new();
+        doc.import(&snapshot)?;
+        for blob in start {
+            doc.import(blob)?;
+        }
+        doc.import(merged)?;
+        assert_eq!(doc.get_deep_value(), a.get_deep_value());
+        assert_eq!(doc.oplog_vv(), a.oplog_vv());
+        Ok(())
+    };
+    check(&merge_updates(&[&all])?, &[])?;
+    check(
+        &merge_updates(&blobs.iter().map(|x| x.as_slice()).collect::<Vec<_>>())?,
+        &[],
+    )?;
+    check(&merge_updates(&[&all, &blobs[3]])?, &[])?;
+    check(&merge_updates(&[&blobs[3], &all])?, &[])?;
+    check(
+        &merge_updates(&[&blobs[3]])?,
+        &[&blobs[0], &blobs[1], &blobs[2]],
+    )?;
+    Ok(())
+}
+
+/// An in-memory transport that delivers the encoded messages between two sync sessions
+/// in order. It returns the number of delivered messages.
+fn deliver_sync_messages(
+    a: (&LoroDoc, &mut SyncSession),
+    b: (&LoroDoc, &mut SyncSession),
+    to_b: Vec<SyncMessage>,
+) -> LoroResult<usize> {
+    let (doc_a, session_a) = a;
+    let (doc_b, session_b) = b;
+    let mut to_b: VecDeque<Vec<u8>> = to_b.iter().map(|m| m.encode()).collect();
+    let mut to_a: VecDeque<Vec<u8>> = VecDeque::new();
+    let mut delivered = 0;
+    while !to_a.is_empty() || !to_b.is_empty() {
+        if let Some(msg) = to_b.pop_front() {
+            let replies = session_b.receive(doc_b, SyncMessage::decode(&msg)?)?;
+            to_a.extend(replies.iter().map(|m| m.encode()));
+            delivered += 1;
+        }
+        if let Some(msg) = to_a.pop_front() {
+            let replies = session_a.receive(doc_a, SyncMessage::decode(&msg)?)?;
+            to_b.extend(replies.iter().map(|m| m.encode()));
+            delivered += 1;
+        }
+        assert!(delivered < 100, "the sync doesn't stop");
+    }
+
+    Ok(delivered)
+}
+
+#[test]
+fn sync_session() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    a.get_text("text").insert(0, "Hello")?;
+    a.commit();
+    b.get_list("list").push(1)?;
+    b.commit();
+
+    let mut session_a = SyncSession::new();
+    let mut session_b = SyncSession::new();
+    let start = session_a.start(&a);
+    deliver_sync_messages((&a, &mut session_a), (&b, &mut session_b), vec![start])?;
+    assert_eq!(a.get_deep_value(), b.get_deep_value());
+    assert!(session_a.is_synced(&a));
+    assert!(session_b.is_synced(&b));
+    assert!(session_a.local_updates(&a).is_none());
+
+    // the local updates are sent to the remote peer
+    a.get_text("text").insert(5, " world")?;
+    a.commit();
+    let msg = session_a.local_updates(&a).unwrap();
+    deliver_sync_messages((&a, &mut session_a), (&b, &mut session_b), vec![msg])?;
+    assert_eq!(b.get_text("text").to_string(), "Hello world");
+
+    // the first message is lost, so the second one cannot be applied
+    a.get_text("text").insert(0, "1")?;
+    a.commit();
+    assert!(session_a.local_updates(&a).is_some());
+    a.get_text("text").insert(0, "2")?;
+    a.commit();
+    let msg = session_a.local_updates(&a).unwrap();
+    deliver_sync_messages((&a, &mut session_a), (&b, &mut session_b), vec![msg])?;
+    assert_eq!(b.get_text("text").to_string(), "21Hello world");
+    assert!(b.pending_changes_summary().pending.is_empty());
+    assert!(session_a.is_synced(&a));
+    assert!(session_b.is_synced(&b));
+
+    // the synced peers stop after the handshake
+    let start = session_b.start(&b);
+    let delivered = deliver_sync_messages((&b, &mut session_b), (&a, &mut session_a), vec![start])?;
+    assert_eq!(delivered, 1);
+
+    // the session stalls if the remote peer never sends the missing changes
+    let v = a.oplog_vv();
+    a.get_text("text").insert(0, "3")?;
+    a.commit();
+    let missing = a.oplog_vv();
+    a.get_text("text").insert(0, "4")?;
+    a.commit();
+    let updates = SyncMessage::Updates(a.export_from(&missing));
+    for _ in 0..MAX_RETRIES {
+        let replies = session_b.receive(&b, updates.clone())?;
+        assert_eq!(replies, vec![SyncMessage::Request(b.oplog_vv())]);
+    }
+    match session_b.receive(&b, updates) {
+        Err(LoroError::SyncStalled(deps)) => assert_eq!(deps, vec![ID::new(1, 13)]),
+        _ => unreachable!(),
+    }
+    // it recovers when the missing changes arrive
+    let updates = SyncMessage::Updates(a.export_from(&v));
+    assert!(session_b.receive(&b, updates)?.is_empty());
+    assert_eq!(b.get_deep_value(), a.get_deep_value());
+
+    // the pending changes imported from a third peer don't affect the session
+    let c = LoroDoc::new();
+    c.set_peer_id(3)?;
+    c.get_text("text").insert(0, "c")?;
+    c.commit();
+    let c_vv = c.oplog_vv();
+    c.get_text("text").insert(0, "c")?;
+    c.commit();
+    b.import(&c.export_from(&c_vv))?;
+    assert!(!b.pending_changes_summary().pending.is_empty());
+    a.get_text("text").insert(0, "5")?;
+    a.commit();
+    let msg = session_a.local_updates(&a).unwrap();
+    let delivered = deliver_sync_messages((&a, &mut session_a), (&b, &mut session_b), vec![msg])?;
+    assert_eq!(delivered, 1);
+    assert!(session_a.is_synced(&a));
+    assert!(session_b.is_synced(&b));
+    Ok(())
+}
+
+#[test]
+fn sync_message_encoding() -> LoroResult<()> {
+    let vv = vv!(1 => 3, 2 => 5);
+    let msg = SyncMessage::Request(vv);
+    assert_eq!(SyncMessage::decode(&msg.encode())?, msg);
+    let msg = SyncMessage::Updates(vec![1, 2, 3]);
+    assert_eq!(SyncMessage::decode(&msg.encode())?, msg);
+    assert!(matches!(
+        SyncMessage::decode(&[]),
+        Err(LoroError::DecodeError(_))
+    ));
+    assert!(matches!(
+        SyncMessage::decode(&[9, 1]),
+        Err(LoroError::DecodeError(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn awareness_shares_cursors() -> LoroResult<()> {
+    let doc_a = LoroDoc::new();
+    doc_a.set_peer_id(1)?;
+    let doc_b = LoroDoc::new();
+    doc_b.set_peer_id(2)?;
+    let text = doc_a.get_text("text");
+    text.insert(0, "Hello world")?;
+    doc_a.commit();
+    doc_b.import(&doc_a.export_snapshot())?;
+
+    let mut a = Awareness::new(1, 30_000);
+    let mut b = Awareness::new(2, 30_000);
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events_clone = events.clone();
+    b.subscribe(Box::new(move |e: &AwarenessEvent| {
+        events_clone.lock().unwrap().push(e.clone());
+    }));
+
+    // share the selection of "world"
+    let anchor = text.get_cursor(6, Side::Middle).unwrap();
+    let focus = text.get_cursor(11, Side::Middle).unwrap();
+    a.set_local_state(loro_value!({
+        "name": "Alice",
+        "anchor": LoroValue::from(&anchor),
+        "focus": LoroValue::from(&focus),
+    }));
+    b.apply(&a.encode(&[1]))?;
+    assert_eq!(
+        events.lock().unwrap()[0],
+        AwarenessEvent {
+            by: AwarenessEventTrigger::Import,
+            added: vec![1],
+            updated: vec![],
+            removed: vec![],
+        }
+    );
+
+    // the positions are stable after concurrent edits
+    doc_b.get_text("text").insert(0, ">> ")?;
+    doc_b.commit();
+    let LoroValue::Map(state) = b.get_all_states()[&1].state.clone() else {
+        unreachable!()
+    };
+    let anchor = Cursor::try_from(&state["anchor"])?;
+    let focus = Cursor::try_from(&state["focus"])?;
+    assert_eq!(doc_b.get_cursor_pos(&anchor).unwrap().current.pos, 9);
+    assert_eq!(doc_b.get_cursor_pos(&focus).unwrap().current.pos, 14);
+    assert!(Cursor::try_from(&state["name"]).is_err());
+
+    // the stale state is ignored
+    let stale = a.encode(&[1]);
+    a.set_local_state(loro_value!({"name": "Alice A."}));
+    b.apply(&a.encode_all())?;
+    b.apply(&stale)?;
+    assert_eq!(
+        b.get_all_states()[&1].state,
+        loro_value!({"name": "Alice A."})
+    );
+    assert_eq!(events.lock().unwrap().len(), 2);
+    assert_eq!(events.lock().unwrap()[1].updated, vec![1]);
+
+    a.remove_local_state();
+    assert_eq!(a.get_local_state(), None);
+    b.apply(&a.encode_all())?;
+    assert!(b.get_all_states().is_empty());
+    assert_eq!(events.lock().unwrap()[2].removed, vec![1]);
+    assert!(b.apply(b"invalid").is_err());
+    Ok(())
+}
+
+#[test]
+fn awareness_timeout() -> LoroResult<()> {
+    let mut a = Awareness::new(1, 30_000);
+    let mut b = Awareness::new(2, 1);
+    a.set_local_state("a");
+    b.set_local_state("b");
+    b.apply(&a.encode_all())?;
+    assert_eq!(b.get_all_states().len(), 2);
+
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events_clone = events.clone();
+    b.subscribe(Box::new(move |e: &AwarenessEvent| {
+        events_clone.lock().unwrap().push(e.clone());
+    }));
+    // the local state never expires
+    assert_eq!(b.remove_outdated(), vec![1]);
+    assert_eq!(events.lock().unwrap()[0].by, AwarenessEventTrigger::Timeout);
+    assert_eq!(events.lock().unwrap()[0].removed, vec![1]);
+    assert_eq!(b.get_all_states().len(), 1);
+    assert_eq!(b.get_local_state(), Some("b".into()));
+
+    // the state is received again after it expires
+    a.set_local_state("a2");
+    b.apply(&a.encode_all())?;
+    assert_eq!(b.get_all_states()[&1].state, "a2".into());
+    assert_eq!(events.lock().unwrap()[1].added, vec![1]);
+
+    // the received state of the local peer is ignored, even if its clock is greater
+    let mut stale_b = Awareness::new(2, 30_000);
+    stale_b.set_local_state("stale");
+    stale_b.set_local_state("stale");
+    b.apply(&stale_b.encode_all())?;
+    assert_eq!(b.get_local_state(), Some("b".into()));
+    // no event is emitted
+    assert_eq!(events.lock().unwrap().len(), 2);
+    Ok(())
+}
+
+#[test]
+fn subscribe_local_update() -> LoroResult<()> {
+    let a = LoroDoc::new();
+    a.set_peer_id(1)?;
+    let b = LoroDoc::new();
+    b.set_peer_id(2)?;
+    let updates = Arc::new(Mutex::new(Vec::new()));
+    let updates_clone = updates.clone();
+    let sub = a.subscribe_local_update(Arc::new(move |bytes: &[u8]| {
+        updates_clone.lock().unwrap().push(bytes.to_vec());
+    }));
+
+    a.get_text("text").insert(0, "Hello")?;
+    a.commit();
+    // an empty commit has no update
+    a.commit();
+    b.get_list("list").push(1)?;
+    b.commit();
+    // the imported changes are not local updates
+    a.import(&b.export_snapshot())?;
+    a.get_text("text").insert(5, " world")?;
+    a.commit();
+    assert_eq!(updates.lock().unwrap().len(), 2);
+
+    // each update only contains the new change
+    let meta = LoroDoc::decode_import_blob_meta(&updates.lock().unwrap()[1])?;
+    assert_eq!(meta.change_num, 1);
+    assert_eq!(meta.partial_start_vv, vv!(1 => 5));
+    assert_eq!(meta.partial_end_vv, vv!(1 => 11));
+    for update in updates.lock().unwrap().iter() {
+        b.import(update)?;
+    }
+    assert_eq!(a.get_deep_value(), b.get_deep_value());
+
+    a.unsubscribe(sub);
+    a.get_text("text").insert(0, "!")?;
+    a.commit();
+    assert_eq!(updates.lock().unwrap().len(), 2);
+    Ok(())
+}
+
+fn temp_storage_path(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("loro-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn file_storage_recovers_doc() -> std::io::Result<()> {
+    let path = temp_storage_path("recover");
+    let storage = FileStorage::open(&path)?;
+    storage.doc().set_peer_id(1).unwrap();
+    let text = storage.doc().get_text("text");
+    text.insert(0, "Hello").unwrap();
+    storage.commit()?;
+    text.insert(5, " world").unwrap();
+    // the ops committed by the doc directly are appended too
+    storage.doc().commit();
+
+    let remote = LoroDoc::new();
+    remote.set_peer_id(2).unwrap();
+    remote.get_list("list").push(1).unwrap();
+    remote.commit();
+    storage.import(&remote.export_snapshot())?;
+    let value = storage.doc().get_deep_value();
+    drop(storage);
+
+    let storage = FileStorage::open(&path)?;
+    assert_eq!(storage.doc().get_deep_value(), value);
+    drop(storage);
+
+    // a torn final record is ignored and removed
+    let len = std::fs::metadata(&path)?.len();
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path)?;
+    file.write_all(&[100, 0, 0, 0, 1, 2, 3])?;
+    drop(file);
+    let storage = FileStorage::open(&path)?;
+    assert_eq!(storage.doc().get_deep_value(), value);
+    assert_eq!(std::fs::metadata(&path)?.len(), len);
+    storage.doc().get_text("text").insert(0, "!").unwrap();
+    storage.commit()?;
+    drop(storage);
+
+    let storage = FileStorage::open(&path)?;
+    assert_eq!(storage.doc().get_text("text").to_string(), "!Hello world");
+    drop(storage);
+
+    // a corrupted record in the middle is not a torn write, so the log is kept as is
+    let mut bytes = std::fs::read(&path)?;
+    let record_len = u32::from_le_bytes(bytes[..4].try_into().unwrap()) as usize;
+    bytes[24 + record_len / 2] ^= 0xff;
+    std::fs::write(&path, &bytes)?;
+    let err = FileStorage::open(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(std::fs::read(&path)?, bytes);
+    std::fs::remove_file(&path)
+}
+
+#[test]
+fn file_storage_rejects_corrupted_length() -> std::io::Result<()> {
+    let path = temp_storage_path("corrupted-length");
+    let storage = FileStorage::open(&path)?;
+    let text = storage.doc().get_text("text");
+    for i in 0..3 {
+        text.insert(0, &i.to_string()).unwrap();
+        storage.commit()?;
+    }
+    let value = storage.doc().get_deep_value();
+    drop(storage);
+
+    // the length of a torn final record is intact, so only the final record is removed
+    let mut bytes = std::fs::read(&path)?;
+    let record_len = u32::from_le_bytes(bytes[..4].try_into().unwrap()) as usize;
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path)?;
+    file.write_all(&bytes[..24 + record_len / 2])?;
+    drop(file);
+    let storage = FileStorage::open(&path)?;
+    assert_eq!(storage.doc().get_deep_value(), value);
+    assert_eq!(std::fs::read(&path)?, bytes);
+    drop(storage);
+
+    // a corrupted length may point past the end of the log, but the records after it
+    // are not removed
+    bytes[3] ^= 1;
+    std::fs::write(&path, &bytes)?;
+    let err = FileStorage::open(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(std::fs::read(&path)?, bytes);
+    std::fs::remove_file(&path)
+}
+
+#[test]
+fn file_storage_compaction() -> std::io::Result<()> {
+    let path = temp_storage_path("compaction");
+    let mut storage = FileStorage::open(&path)?;
+    storage.set_compaction_threshold(1024);
+    let text = storage.doc().get_text("text");
+    let mut max_len = 0;
+    for i in 0..200 {
+        text.insert(0, &i.to_string()).unwrap();
+        storage.commit()?;
+        max_len = max_len.max(std::fs::metadata(&path)?.len());
+    }
+
+    let value = storage.doc().get_deep_value();
+    // the log is compacted into a snapshot, so it doesn't keep growing with the updates
+    assert!(max_len < 200 * 64);
+    storage.compact()?;
+    let compacted_len = std::fs::metadata(&path)?.len();
+    text.insert(0, "a").unwrap();
+    storage.commit()?;
+    assert!(std::fs::metadata(&path)?.len() > compacted_len);
+    drop(storage);
+
+    let storage = FileStorage::open(&path)?;
+    assert_eq!(
+        storage.doc().get_text("text").to_string(),
+        format!("a{}", value.as_map().unwrap()["text"].as_string().unwrap())
+    );
+    drop(storage);
+    std::fs::remove_file(&path)
+}
+
+/// Subscribe the doc, and apply the JSON patches of its events to a copy of its deep value.
+fn patched_value(doc: &Arc<LoroDoc>) -> Arc<Mutex<LoroValue>> {
+    let value = Arc::new(Mutex::new(doc.get_deep_value()));
+    let adapter = Mutex::new(JsonPatchAdapter::new(doc));
+    let value_clone = value.clone();
+    let weak = Arc::downgrade(doc);
+    doc.subscribe_root(Arc::new(move |event| {
+        let doc = weak.upgrade().unwrap();
+        let patches = adapter.lock().unwrap().convert(&doc, &event);
+        apply_json_patch(&mut value_clone.lock().unwrap(), &patches).unwrap();
+    }));
+    value
+}
+
+#[test]
+fn json_patch_reproduces_deep_value() -> LoroResult<()> {
+    let doc = Arc::new(LoroDoc::new());
+    doc.set_peer_id(1)?;
+    let value = patched_value(&doc);
+    let check = || assert_eq!(*value.lock().unwrap(), doc.get_deep_value());
+
+    let map = doc.get_map("map");
+    map.insert("a/b~c", 1)?;
+    let list = map.insert_container("list", LoroList::new())?;
+    list.insert(0, "x")?;
+    let text = list.insert_container(1, LoroText::new())?;
+    text.insert(0, "hello")?;
+    let root_text = doc.get_text("text");
+    root_text.insert(0, "hello world")?;
+    doc.commit();
+    check();
+
+    list.insert(0, 0)?;
+    list.delete(1, 1)?;
+    text.insert(5, " world")?;
+    // it's adjacent to the insertion above, but it's in another text
+    root_text.insert(11, "!")?;
+    text.delete(0, 1)?;
+    map.delete("a/b~c")?;
+    doc.commit();
+    check();
+
+    let counter = map.insert_container("counter", LoroCounter::new())?;
+    counter.increment(2)?;
+    doc.commit();
+    counter.increment(-0.5)?;
+    doc.commit();
+    check();
+
+    let tree = doc.get_tree("tree");
+    let root = tree.create(None)?;
+    let child = tree.create(root)?;
+    tree.get_meta(child)?.insert("name", "child")?;
+    doc.commit();
+    check();
+    tree.get_meta(root)?.insert("name", "root")?;
+    let tags = tree
+        .get_meta(child)?
+        .insert_container("tags", LoroList::new())?;
+    doc.commit();
+    check();
+    tags.push("leaf")?;
+    doc.commit();
+    check();
+    tree.mov(child, None)?;
+    doc.commit();
+    tree.delete(root)?;
+    doc.commit();
+    check();
+
+    let movable = doc.get_movable_list("movable");
+    for i in 0..4 {
+        movable.push(i)?;
+    }
+    doc.commit();
+    movable.mov(0, 3)?;
+    movable.set(1, "set")?;
+    doc.commit();
+    check();
+
+    // the events of the imports and the checkouts are converted as well
+    let remote = LoroDoc::new();
+    remote.set_peer_id(2)?;
+    remote.import(&doc.export_snapshot())?;
+    let frontiers = doc.oplog_frontiers();
+    remote.get_text("remote").insert(0, "from remote")?;
+    remote
+        .get_map("map")
+        .insert_container("nested", LoroMap::new())?
+        .insert("key", "value")?;
+    remote.get_list("list").push(1)?;
+    remote.commit();
+    doc.import(&remote.export_from(&doc.oplog_vv()))?;
+    check();
+    doc.tag_version("v1", &frontiers)?;
+    doc.checkout(&frontiers)?;
+    check();
+    doc.attach();
+    check();
+    Ok(())
+}
+
+#[test]
+fn json_patch_ops() {
+    let doc = Arc::new(LoroDoc::new());
+    let list = doc.get_list("list");
+    let adapter = Mutex::new(JsonPatchAdapter::new(&doc));
+    let patches = Arc::new(Mutex::new(Vec::new()));
+    let patches_clone = patches.clone();
+    let weak = Arc::downgrade(&doc);
+    doc.subscribe_root(Arc::new(move |event| {
+        let doc = weak.upgrade().unwrap();
+        let ops = adapter.lock().unwrap().convert(&doc, &event);
+        patches_clone.lock().unwrap().extend(ops);
+    }));
+    let take = || std::mem::take(&mut *patches.lock().unwrap());
+    list.insert(0, 1).unwrap();
+    doc.commit();
+    let ops = take();
+    assert_eq!(
+        ops,
+        vec![JsonPatchOp::Add {
+            path: "/list/0".into(),
+            value: 1.into(),
+        }]
+    );
+    assert_eq!(
+        ops[0].to_value().to_json_value(),
+        json!({"op": "add", "path": "/list/0", "value": 1})
+    );
+
+    let map = doc.get_map("map");
+    map.insert("a", 1).unwrap();
+    doc.commit();
+    take();
+    // a key inserted and deleted in the same transaction has no patch
+    map.insert("b", 1).unwrap();
+    map.delete("b").unwrap();
+    map.delete("a").unwrap();
+    doc.commit();
+    assert_eq!(
+        take(),
+        vec![JsonPatchOp::Remove {
+            path: "/map/a".into()
+        }]
+    );
+
+    // texts, counters and trees are replaced as a whole
+    let text = list.insert_container(1, LoroText::new()).unwrap();
+    doc.commit();
+    take();
+    text.insert(0, "abc").unwrap();
+    doc.commit();
+    text.delete(1, 1).unwrap();
+    doc.commit();
+    assert_eq!(
+        take(),
+        vec![
+            JsonPatchOp::Replace {
+                path: "/list/1".into(),
+                value: "abc".into(),
+            },
+            JsonPatchOp::Replace {
+                path: "/list/1".into(),
+                value: "ac".into(),
+            }
+        ]
+    );
+    doc.get_counter("counter").increment(2.).unwrap();
+    doc.commit();
+    assert_eq!(
+        take(),
+        vec![
+            JsonPatchOp::Add {
+                path: "/counter".into(),
+                value: 0.0.into(),
+            },
+            JsonPatchOp::Add {
+                path: "/counter".into(),
+                value: 2.0.into(),
+            }
+        ]
+    );
+    let tree = doc.get_tree("tree");
+    tree.create(None).unwrap();
+    doc.commit();
+    let ops = take();
+    assert_eq!(
+        ops.last(),
+        Some(&JsonPatchOp::Add {
+            path: "/tree".into(),
+            value: tree.get_value_with_meta(),
+        })
+    );
+
+    let mut value = loro_value!({"a~b": [1, 2]});
+    let err = apply_json_patch(
+        &mut value,
+        &[JsonPatchOp::Remove {
+            path: "/a~0b/2".into(),
+        }],
+    );
+    assert!(matches!(err, Err(LoroError::OutOfBound { pos: 2, len: 2 })));
+    apply_json_patch(
+        &mut value,
+        &[JsonPatchOp::Replace {
+            path: "/a~0b/1".into(),
+            value: 3.into(),
+        }],
+    )
+    .unwrap();
+    assert_eq!(value, loro_value!({"a~b": [1, 3]}));
+
+    // an adapter that is out of sync with the doc replaces the whole document
+    let doc = Arc::new(LoroDoc::new());
+    let list = doc.get_list("list");
+    list.insert(0, 1).unwrap();
+    list.insert(1, 2).unwrap();
+    doc.commit();
+    let adapter = Mutex::new(JsonPatchAdapter::new(&LoroDoc::new()));
+    let patches = Arc::new(Mutex::new(Vec::new()));
+    let patches_clone = patches.clone();
+    let weak = Arc::downgrade(&doc);
+    let _sub = doc.subscribe_root(Arc::new(move |event| {
+        let doc = weak.upgrade().unwrap();
+        let ops = adapter.lock().unwrap().convert(&doc, &event);
+        patches_clone.lock().unwrap().extend(ops);
+    }));
+    list.delete(0, 1).unwrap();
+    doc.commit();
+    assert_eq!(
+        std::mem::take(&mut *patches.lock().unwrap()),
+        vec![JsonPatchOp::Replace {
+            path: "".into(),
+            value: loro_value!({"list": [2]}),
+        }]
+    );
+    // the adapter is in sync again
+    list.push(3).unwrap();
+    doc.commit();
+    assert_eq!(
+        *patches.lock().unwrap(),
+        vec![JsonPatchOp::Add {
+            path: "/list/1".into(),
+            value: 3.into(),
+        }]
+    );
+}
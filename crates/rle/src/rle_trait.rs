@@ -170,7 +170,7 @@ impl<T: Mergable + HasIndex + HasLength> RleCollection<T> for Vec<T> {
         &self,
         index: <T as HasIndex>::Int,
     ) -> Option<SearchResult<'_, T, <T as HasIndex>::Int>> {
-        if index > self.end() {
+        if index < self.start() || index > self.end() {
             return None;
         }
 
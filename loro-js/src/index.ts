@@ -4,11 +4,14 @@ import {
   ContainerID,
   Delta,
   Loro,
+  LoroCounter,
   LoroList,
   LoroMap,
+  LoroMovableList,
   LoroText,
   LoroTree,
   OpId,
+  PeerID,
   TreeID,
   Value,
 } from "loro-wasm";
@@ -81,22 +84,57 @@ export type MapDiff = {
 };
 
 export type TreeDiffItem =
-  | { target: TreeID; action: "create"; parent: TreeID | undefined }
+  | {
+    target: TreeID;
+    action: "create";
+    parent: TreeID | undefined;
+    index: number;
+  }
   | { target: TreeID; action: "delete" }
-  | { target: TreeID; action: "move"; parent: TreeID | undefined };
+  | {
+    target: TreeID;
+    action: "move";
+    parent: TreeID | undefined;
+    index: number;
+  };
 
 export type TreeDiff = {
   type: "tree";
   diff: TreeDiffItem[];
 };
 
-export type Diff = ListDiff | TextDiff | MapDiff | TreeDiff;
+export type CounterDiff = {
+  type: "counter";
+  increment: number;
+};
+
+export type Diff = ListDiff | TextDiff | MapDiff | TreeDiff | CounterDiff;
 
 interface Listener {
   (event: LoroEventBatch): void;
 }
 
-const CONTAINER_TYPES = ["Map", "Text", "List", "Tree"];
+/**
+ * A span of ops by the same peer, from `counter` to `counter + length`.
+ */
+export interface IdSpan {
+  peer: PeerID;
+  counter: number;
+  length: number;
+}
+
+/**
+ * The effect of importing a blob, returned by `Loro.previewImport`.
+ *
+ * @prop pending - The changes that would be pending because of missing dependencies.
+ * @prop events - The event batches that the import would emit.
+ */
+export interface ImportPreview {
+  pending: IdSpan[];
+  events: LoroEventBatch[];
+}
+
+const CONTAINER_TYPES = ["Map", "Text", "List", "Tree", "MovableList", "Counter"];
 
 export function isContainerId(s: string): s is ContainerID {
   return s.startsWith("cid:");
@@ -156,6 +194,8 @@ export function getType<T>(
   : T extends LoroMap<any> ? "Map"
   : T extends LoroTree<any> ? "Tree"
   : T extends LoroList<any> ? "List"
+  : T extends LoroMovableList<any> ? "MovableList"
+  : T extends LoroCounter ? "Counter"
   : "Json" {
   if (isContainer(value)) {
     return value.kind() as unknown as any;
@@ -167,6 +207,7 @@ export function getType<T>(
 declare module "loro-wasm" {
   interface Loro {
     subscribe(listener: Listener): number;
+    previewImport(bytes: Uint8Array): ImportPreview;
   }
 
   interface Loro<
@@ -223,6 +264,40 @@ declare module "loro-wasm" {
     getTree<Key extends keyof T>(
       name: Key,
     ): T[Key] extends LoroTree ? T[Key] : LoroTree;
+    /**
+     * Get a LoroMovableList by container id
+     *
+     * The object returned is a new js object each time because it need to cross
+     * the WASM boundary.
+     *
+     * @example
+     * ```ts
+     * import { Loro } from "loro-crdt";
+     *
+     * const doc = new Loro();
+     * const list = doc.getMovableList("list");
+     * ```
+     */
+    getMovableList<Key extends keyof T>(
+      name: Key,
+    ): T[Key] extends LoroMovableList ? T[Key] : LoroMovableList;
+    /**
+     * Get a LoroCounter by container id
+     *
+     * The object returned is a new js object each time because it need to cross
+     * the WASM boundary.
+     *
+     * @example
+     * ```ts
+     * import { Loro } from "loro-crdt";
+     *
+     * const doc = new Loro();
+     * const counter = doc.getCounter("counter");
+     * ```
+     */
+    getCounter<Key extends keyof T>(
+      name: Key,
+    ): T[Key] extends LoroCounter ? T[Key] : LoroCounter;
     getText(key: string | ContainerID): LoroText;
   }
 
@@ -301,6 +376,48 @@ declare module "loro-wasm" {
     getAttached(): undefined | LoroList<T>;
   }
 
+  interface LoroMovableList<T = unknown> {
+    new (): LoroMovableList<T>;
+    /**
+     *  Get elements of the list. If the value is a child container, the corresponding
+     *  `Container` will be returned.
+     */
+    toArray(): T[];
+    /**
+     * Insert a container at the index.
+     */
+    insertContainer<C extends Container>(
+      pos: number,
+      child: C,
+    ): T extends C ? T : C;
+    /**
+     * Get the value at the index. If the value is a container, the corresponding handler will be returned.
+     */
+    get(index: number): T;
+    /**
+     *  Insert a value at index.
+     */
+    insert(pos: number, value: Exclude<T, Container>): void;
+    /**
+     *  Set the value at index.
+     *
+     *  @example
+     *  ```ts
+     *  import { Loro } from "loro-crdt";
+     *
+     *  const doc = new Loro();
+     *  const list = doc.getMovableList("list");
+     *  list.insert(0, 100);
+     *  list.set(0, 200);
+     *  console.log(list.value);  // [200];
+     *  ```
+     */
+    set(pos: number, value: Exclude<T, Container>): void;
+    delete(pos: number, len: number): void;
+    subscribe(txn: Loro, listener: Listener): number;
+    getAttached(): undefined | LoroMovableList<T>;
+  }
+
   interface LoroMap<
     T extends Record<string, unknown> = Record<string, unknown>,
   > {
@@ -389,9 +506,10 @@ declare module "loro-wasm" {
     T extends Record<string, unknown> = Record<string, unknown>,
   > {
     new (): LoroTree<T>;
-    createNode(parent: TreeID | undefined): LoroTreeNode<T>;
-    move(target: TreeID, parent: TreeID | undefined): void;
+    createNode(parent?: TreeID, index?: number): LoroTreeNode<T>;
+    move(target: TreeID, parent?: TreeID, index?: number): void;
     delete(target: TreeID): void;
+    roots(): Array<LoroTreeNode<T>>;
     has(target: TreeID): boolean;
     getNodeByID(target: TreeID): LoroTreeNode;
     subscribe(txn: Loro, listener: Listener): number;
@@ -404,9 +522,11 @@ declare module "loro-wasm" {
      * Get the associated metadata map container of a tree node.
      */
     readonly data: LoroMap<T>;
-    createNode(): LoroTreeNode<T>;
+    createNode(index?: number): LoroTreeNode<T>;
     setAsRoot(): void;
-    moveTo(parent: LoroTreeNode<T>): void;
+    moveTo(parent: LoroTreeNode<T>, index?: number): void;
+    moveBefore(target: LoroTreeNode<T>): void;
+    moveAfter(target: LoroTreeNode<T>): void;
     parent(): LoroTreeNode<T> | undefined;
     children(): Array<LoroTreeNode<T>>;
   }